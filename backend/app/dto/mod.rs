@@ -1,24 +1,100 @@
+use crate::domain::{ContentType, SearchField};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[cfg(feature = "schema")]
+mod schema;
+#[cfg(feature = "schema")]
+pub use schema::*;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CreateSnippetRequest {
     pub trigger: String,
     pub replacement: String,
     pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub content_type: Option<ContentType>,
+    #[serde(default)]
+    pub confirm_before_expand: Option<bool>,
+    #[serde(default)]
+    pub keep_trigger: Option<bool>,
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+    /// Milliseconds between typed characters; `None` uses the engine default
+    #[serde(default)]
+    pub type_delay_ms: Option<u64>,
+    /// Also add the trigger's Title and UPPER case forms as aliases that
+    /// expand in the same case, see `Snippet::generate_case_variants`
+    #[serde(default)]
+    pub generate_case_variants: bool,
+    /// Store the replacement encrypted, which needs a passphrase
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+impl CreateSnippetRequest {
+    /// A request for `trigger` with every other setting left at its default
+    pub fn new(trigger: impl Into<String>, replacement: impl Into<String>) -> Self {
+        Self {
+            trigger: trigger.into(),
+            replacement: replacement.into(),
+            tags: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            generate_case_variants: false,
+            encrypted: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UpdateSnippetRequest {
     pub id: Uuid,
     pub trigger: Option<String>,
     pub replacement: Option<String>,
     pub tags: Option<Vec<String>>,
     pub is_active: Option<bool>,
+    #[serde(default)]
+    pub content_type: Option<ContentType>,
+    #[serde(default)]
+    pub confirm_before_expand: Option<bool>,
+    #[serde(default)]
+    pub keep_trigger: Option<bool>,
+    /// Replaces all aliases when set; an empty list removes them
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+    /// `Some(Some(ms))` sets the typing delay, `Some(None)` goes back to the
+    /// engine default
+    #[serde(default)]
+    pub type_delay_ms: Option<Option<u64>>,
+    /// Pin the snippet to the top of listings, or unpin it
+    #[serde(default)]
+    pub pinned: Option<bool>,
+    /// `Some(Some(combo))` binds a hotkey such as `Ctrl+Alt+1`, `Some(None)`
+    /// removes it
+    #[serde(default)]
+    pub hotkey: Option<Option<String>>,
+    /// `Some(Some(time))` sets when the snippet starts expanding, `Some(None)`
+    /// removes the limit
+    #[serde(default)]
+    pub active_from: Option<Option<DateTime<Utc>>>,
+    /// `Some(Some(time))` sets when the snippet stops expanding, `Some(None)`
+    /// removes the limit
+    #[serde(default)]
+    pub active_until: Option<Option<DateTime<Utc>>>,
+    /// Start or stop storing the replacement encrypted
+    #[serde(default)]
+    pub encrypted: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SnippetDto {
     pub id: Uuid,
     pub trigger: String,
@@ -28,85 +104,564 @@ pub struct SnippetDto {
     pub is_active: bool,
     pub usage_count: u64,
     pub tags: Vec<String>,
+    pub content_type: ContentType,
+    pub confirm_before_expand: bool,
+    pub keep_trigger: bool,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub type_delay_ms: Option<u64>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Canonical form of the bound hotkey, e.g. `Ctrl+Alt+1`
+    #[serde(default)]
+    pub hotkey: Option<String>,
+    /// The snippet only expands from `active_from` until `active_until`
+    #[serde(default)]
+    pub active_from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub active_until: Option<DateTime<Utc>>,
+    /// Title and UPPER case aliases expand in their case
+    #[serde(default)]
+    pub case_variants: bool,
+    /// Place in the hand-arranged order, see `TypelyService::reorder_snippets`
+    #[serde(default)]
+    pub position: Option<u32>,
+    /// Replacement stored encrypted at rest
+    #[serde(default)]
+    pub encrypted: bool,
+    /// The replacement couldn't be decrypted, so `replacement` only says so
+    #[serde(default)]
+    pub locked: bool,
+    /// The trigger as it should be shown, see `TriggerDisplay`. Equal to
+    /// `trigger` unless the prefix is hidden.
+    #[serde(default)]
+    pub display_trigger: String,
+}
+
+impl SnippetDto {
+    /// Set `display_trigger` for `display`, leaving `trigger` alone
+    pub fn with_trigger_display(mut self, display: &TriggerDisplay) -> Self {
+        self.display_trigger = display.render(&self.trigger).to_string();
+        self
+    }
 }
 
+/// Characters of a replacement shown in a `SnippetSummaryDto::preview`
+pub const DEFAULT_PREVIEW_LENGTH: usize = 50;
+
+/// A snippet as shown in list views: its replacement cut down to a one-line
+/// preview instead of being sent in full
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnippetSummaryDto {
+    pub id: Uuid,
+    pub trigger: String,
+    /// See `SnippetDto::display_trigger`
+    pub display_trigger: String,
+    /// The first line of the replacement, see `replacement_preview`
+    pub preview: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub is_active: bool,
+    pub usage_count: u64,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+}
+
+impl SnippetSummaryDto {
+    /// Set `display_trigger` for `display`, leaving `trigger` alone
+    pub fn with_trigger_display(mut self, display: &TriggerDisplay) -> Self {
+        self.display_trigger = display.render(&self.trigger).to_string();
+        self
+    }
+}
+
+/// The first line of `replacement`, cut to `max_chars` characters with
+/// `...` when longer. Replacements with more lines end in `[+N lines]`.
+pub fn replacement_preview(replacement: &str, max_chars: usize) -> String {
+    let mut lines = replacement.lines();
+    let first_line = lines.next().unwrap_or_default();
+    let more_lines = lines.count();
+
+    let mut preview = if first_line.chars().count() > max_chars {
+        let kept: String = first_line
+            .chars()
+            .take(max_chars.saturating_sub(3))
+            .collect();
+        format!("{}...", kept)
+    } else {
+        first_line.to_string()
+    };
+    if more_lines > 0 {
+        let noun = if more_lines == 1 { "line" } else { "lines" };
+        preview.push_str(&format!(" [+{} {}]", more_lines, noun));
+    }
+    preview
+}
+
+/// How triggers are presented in listings. Purely presentation: stored
+/// triggers keep their prefix and are still what gets typed and matched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TriggerDisplay {
+    /// Show the prefix; when false it is left out
+    pub display_prefix: bool,
+    pub prefix: String,
+}
+
+impl Default for TriggerDisplay {
+    fn default() -> Self {
+        Self {
+            display_prefix: true,
+            prefix: "::".to_string(),
+        }
+    }
+}
+
+impl TriggerDisplay {
+    /// `trigger` as shown to users. A trigger that is only the prefix, or
+    /// doesn't start with it, is shown whole.
+    pub fn render<'a>(&self, trigger: &'a str) -> &'a str {
+        if self.display_prefix || self.prefix.is_empty() {
+            return trigger;
+        }
+        match trigger.strip_prefix(self.prefix.as_str()) {
+            Some(rest) if !rest.is_empty() => rest,
+            _ => trigger,
+        }
+    }
+}
+
+/// Byte range of a search match, always on UTF-8 character boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A search result with the places the search term was found, for highlighting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MatchedSnippetDto {
+    pub snippet: SnippetDto,
+    pub trigger_matches: Vec<MatchRange>,
+    pub replacement_matches: Vec<MatchRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnippetVersionDto {
+    pub snippet_id: Uuid,
+    pub trigger: String,
+    pub replacement: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// How much a snippet has been used, from its usage log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnippetUsageDto {
+    pub snippet_id: Uuid,
+    pub usage_count: u64,
+    /// Expansions since local midnight
+    pub used_today: u64,
+    pub first_used: Option<DateTime<Utc>>,
+}
+
+/// Totals across every snippet, for `typely-cli stats` and the GUI dashboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StatisticsDto {
+    pub total_snippets: u64,
+    pub active_snippets: u64,
+    pub inactive_snippets: u64,
+    /// Expansions of every snippet, active or not
+    pub total_usage: u64,
+    /// The most used active snippets, most used first
+    pub most_used: Vec<SnippetDto>,
+    /// Every tag in use, sorted
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FragmentDto {
+    pub name: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SnippetListRequest {
     pub search_term: Option<String>,
+    /// Where `search_term` is looked for; trigger and replacement when unset
+    #[serde(default)]
+    pub search_fields: Option<Vec<SearchField>>,
     pub tags: Option<Vec<String>>,
+    /// How `tags` are combined: "any" (default) or "all"
+    #[serde(default)]
+    pub tag_match: Option<String>,
     pub is_active: Option<bool>,
+    /// Only snippets imported from this source
+    #[serde(default)]
+    pub source: Option<String>,
     pub limit: Option<u32>,
     pub offset: Option<u32>,
+    /// `PageInfo::next_cursor` of the previous page; used instead of `offset`
+    #[serde(default)]
+    pub after: Option<String>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SnippetListResponse {
     pub snippets: Vec<SnippetDto>,
     pub total_count: u64,
     pub page_info: PageInfo,
 }
 
+/// A page of `list_summaries`, paged like `SnippetListResponse`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnippetSummaryListResponse {
+    pub summaries: Vec<SnippetSummaryDto>,
+    pub total_count: u64,
+    pub page_info: PageInfo,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PageInfo {
     pub has_next_page: bool,
     pub has_previous_page: bool,
     pub total_pages: u32,
+    /// Counted from 1; 0 on pages fetched by cursor, whose number isn't known
     pub current_page: u32,
+    /// Pass as `after` to fetch the next page, when there is one
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExpansionRequest {
     pub trigger: String,
     pub context: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExpansionResponse {
     pub success: bool,
     pub expanded_text: Option<String>,
     pub error_message: Option<String>,
+    #[serde(default)]
+    pub content_type: ContentType,
+    /// The snippet asks for confirmation before it is typed
+    #[serde(default)]
+    pub confirm_before_expand: bool,
+    /// Type the expansion after the trigger instead of replacing it
+    #[serde(default)]
+    pub keep_trigger: bool,
+    /// Per-snippet pause between typed characters, overriding the engine default
+    #[serde(default)]
+    pub type_delay_ms: Option<u64>,
+    /// The snippet the trigger matched, if any, even when it didn't expand
+    #[serde(default)]
+    pub snippet_id: Option<Uuid>,
+    /// That snippet's usage count, including this expansion
+    #[serde(default)]
+    pub usage_count: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ImportSnippetsRequest {
     pub snippets: Vec<ImportSnippetData>,
-    pub overwrite_existing: bool,
+    #[serde(default)]
+    pub conflict_strategy: ConflictStrategy,
+    /// Recorded as the `source` of every imported snippet, e.g. the pack name
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// What to do when an imported snippet's trigger already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Leave the existing snippet alone
+    #[default]
+    Skip,
+    /// Replace the existing snippet's content
+    Overwrite,
+    /// Import under a new trigger with a numeric suffix (e.g. `::hello_2`)
+    Rename,
+    /// Overwrite only if the incoming snippet has a newer `updated_at`
+    KeepNewer,
+    /// Keep the existing snippet, with its usage and history, but deactivate
+    /// it under a suffixed trigger (e.g. `::hello_2`); the incoming snippet
+    /// takes the trigger
+    DeactivateOld,
+}
+
+impl From<bool> for ConflictStrategy {
+    fn from(overwrite_existing: bool) -> Self {
+        if overwrite_existing {
+            ConflictStrategy::Overwrite
+        } else {
+            ConflictStrategy::Skip
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ImportSnippetData {
     pub trigger: String,
     pub replacement: String,
     pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<ContentType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
+/// Export file format written by this version. Bump it whenever the meaning
+/// of an existing field changes (e.g. how `tags` are interpreted), so older
+/// releases refuse files they would misread.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Top level of an export file. Files written before versioning are a bare
+/// array of snippets and are still accepted on import.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExportEnvelope {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub snippets: Vec<ImportSnippetData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ImportResult {
     pub imported_count: u32,
     pub skipped_count: u32,
     pub error_count: u32,
     pub errors: Vec<String>,
+    /// One entry per record, in file order, adding up to the counts above
+    #[serde(default)]
+    pub items: Vec<ImportItemResult>,
+}
+
+/// What happened to one record of an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported,
+    Skipped,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ImportItemResult {
+    /// `None` for a record that couldn't be read
+    pub trigger: Option<String>,
+    pub outcome: ImportOutcome,
+    /// Why the record was skipped or failed, or the trigger it was renamed to
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExportSnippetsRequest {
     pub include_inactive: bool,
     pub tags_filter: Option<Vec<String>>,
 }
 
+/// File format for exported snippets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Typely's own JSON format, readable by import
+    #[default]
+    Json,
+    /// `alias name='text'` lines for bash/zsh; only plain single-line snippets qualify
+    ShellAliases,
+    /// `trigger,replacement,tags` rows for spreadsheets, tags joined with `;`
+    Csv,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "shell" | "shell-aliases" | "bash" | "zsh" => Ok(ExportFormat::ShellAliases),
+            "csv" => Ok(ExportFormat::Csv),
+            other => Err(anyhow::anyhow!(
+                "Unknown export format '{}' (expected json, csv or shell)",
+                other
+            )),
+        }
+    }
+}
+
+/// Separates tags inside a CSV cell, since commas separate the columns
+pub const CSV_TAG_SEPARATOR: char = ';';
+
+/// File format read by import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// A Typely JSON export
+    #[default]
+    Json,
+    /// A CSV file with `trigger` and `replacement` columns and optional `tags`
+    Csv,
+}
+
+impl std::str::FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ImportFormat::Json),
+            "csv" => Ok(ImportFormat::Csv),
+            other => Err(anyhow::anyhow!(
+                "Unknown import format '{}' (expected json or csv)",
+                other
+            )),
+        }
+    }
+}
+
+/// A snippet left out of an export, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SkippedSnippet {
+    pub trigger: String,
+    pub reason: String,
+}
+
+/// Outcome of syncing the snippets from a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SyncResult {
+    pub import: ImportResult,
+    /// Triggers removed because the file no longer has them
+    pub deleted: Vec<String>,
+}
+
+/// A named bundle of snippets distributed together, e.g. "Rust" or "Email".
+/// Installed snippets are tagged and sourced `pack:<name>` (see `pack_tag`),
+/// which is how uninstalling finds them again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnippetPack {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub snippets: Vec<ImportSnippetData>,
+}
+
+/// The tag, and source, given to snippets installed from the pack `name`
+pub fn pack_tag(name: &str) -> String {
+    format!("pack:{}", name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ShellAliasExport {
+    pub exported: usize,
+    pub skipped: Vec<SkippedSnippet>,
+}
+
 impl From<crate::domain::Snippet> for SnippetDto {
     fn from(snippet: crate::domain::Snippet) -> Self {
         Self {
             id: snippet.id,
+            display_trigger: snippet.trigger.clone(),
             trigger: snippet.trigger,
-            replacement: snippet.replacement,
+            replacement: match snippet.locked {
+                Some(_) => crate::domain::LOCKED_REPLACEMENT.to_string(),
+                None => snippet.replacement,
+            },
             created_at: snippet.created_at,
             updated_at: snippet.updated_at,
             is_active: snippet.is_active,
             usage_count: snippet.usage_count,
             tags: snippet.tags,
+            content_type: snippet.content_type,
+            confirm_before_expand: snippet.confirm_before_expand,
+            keep_trigger: snippet.keep_trigger,
+            aliases: snippet.aliases,
+            type_delay_ms: snippet.type_delay_ms,
+            source: snippet.source,
+            pinned: snippet.pinned,
+            hotkey: snippet.hotkey,
+            active_from: snippet.active_from,
+            active_until: snippet.active_until,
+            case_variants: snippet.case_variants,
+            position: snippet.position,
+            encrypted: snippet.encrypted,
+            locked: snippet.locked.is_some(),
+        }
+    }
+}
+
+impl From<SnippetDto> for SnippetSummaryDto {
+    fn from(snippet: SnippetDto) -> Self {
+        Self {
+            id: snippet.id,
+            preview: replacement_preview(&snippet.replacement, DEFAULT_PREVIEW_LENGTH),
+            trigger: snippet.trigger,
+            display_trigger: snippet.display_trigger,
+            created_at: snippet.created_at,
+            updated_at: snippet.updated_at,
+            is_active: snippet.is_active,
+            usage_count: snippet.usage_count,
+            tags: snippet.tags,
+            pinned: snippet.pinned,
+        }
+    }
+}
+
+impl From<crate::domain::Fragment> for FragmentDto {
+    fn from(fragment: crate::domain::Fragment) -> Self {
+        Self {
+            name: fragment.name,
+            content: fragment.content,
+            created_at: fragment.created_at,
+            updated_at: fragment.updated_at,
+        }
+    }
+}
+
+impl From<crate::domain::SnippetVersion> for SnippetVersionDto {
+    fn from(version: crate::domain::SnippetVersion) -> Self {
+        Self {
+            snippet_id: version.snippet_id,
+            trigger: version.trigger,
+            replacement: version.replacement,
+            changed_at: version.changed_at,
         }
     }
 }
@@ -0,0 +1,79 @@
+//! JSON Schema for the DTOs, so clients in other languages can check their
+//! types against them. Needs the `schema` feature.
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use super::*;
+
+/// The schema of every request and response type, by type name
+pub fn dto_schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("CreateSnippetRequest", schema_for!(CreateSnippetRequest)),
+        ("UpdateSnippetRequest", schema_for!(UpdateSnippetRequest)),
+        ("SnippetDto", schema_for!(SnippetDto)),
+        ("SnippetSummaryDto", schema_for!(SnippetSummaryDto)),
+        ("MatchedSnippetDto", schema_for!(MatchedSnippetDto)),
+        ("SnippetVersionDto", schema_for!(SnippetVersionDto)),
+        ("SnippetUsageDto", schema_for!(SnippetUsageDto)),
+        ("StatisticsDto", schema_for!(StatisticsDto)),
+        ("FragmentDto", schema_for!(FragmentDto)),
+        ("SnippetListRequest", schema_for!(SnippetListRequest)),
+        ("SnippetListResponse", schema_for!(SnippetListResponse)),
+        (
+            "SnippetSummaryListResponse",
+            schema_for!(SnippetSummaryListResponse),
+        ),
+        ("ExpansionRequest", schema_for!(ExpansionRequest)),
+        ("ExpansionResponse", schema_for!(ExpansionResponse)),
+        ("ImportSnippetsRequest", schema_for!(ImportSnippetsRequest)),
+        ("ImportResult", schema_for!(ImportResult)),
+        ("ExportEnvelope", schema_for!(ExportEnvelope)),
+        ("ExportSnippetsRequest", schema_for!(ExportSnippetsRequest)),
+        ("SyncResult", schema_for!(SyncResult)),
+        ("SnippetPack", schema_for!(SnippetPack)),
+        ("ShellAliasExport", schema_for!(ShellAliasExport)),
+        ("TriggerDisplay", schema_for!(TriggerDisplay)),
+    ]
+}
+
+/// The schema of the DTO called `name`, e.g. `SnippetDto`
+pub fn dto_schema(name: &str) -> Option<RootSchema> {
+    dto_schemas()
+        .into_iter()
+        .find(|(type_name, _)| *type_name == name)
+        .map(|(_, schema)| schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_dto_schema() {
+        let schema = serde_json::to_value(dto_schema("SnippetDto").unwrap()).unwrap();
+        assert_eq!(schema["title"], "SnippetDto");
+
+        let properties = schema["properties"].as_object().unwrap();
+        for field in [
+            "id",
+            "trigger",
+            "replacement",
+            "tags",
+            "content_type",
+            "usage_count",
+        ] {
+            assert!(properties.contains_key(field), "{}", field);
+        }
+        assert_eq!(properties["id"]["format"], "uuid");
+        assert_eq!(properties["created_at"]["format"], "date-time");
+
+        // Fields with a serde default aren't required
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&"trigger".into()));
+        assert!(!required.contains(&"aliases".into()));
+        assert!(schema["definitions"]["ContentType"].is_object());
+
+        assert!(dto_schema("Unknown").is_none());
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Process-wide expansion counters. Updates are single atomic increments, so
+/// recording is cheap enough to do on every expansion.
+#[derive(Debug)]
+pub struct Metrics {
+    expansions: AtomicU64,
+    failures: AtomicU64,
+    last_expansion: Mutex<Option<DateTime<Utc>>>,
+    started_at: Instant,
+}
+
+/// Point-in-time copy of the counters in `Metrics`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub expansions_total: u64,
+    pub expansion_failures_total: u64,
+    pub uptime_seconds: f64,
+    /// Successful expansions per minute, averaged over the uptime
+    pub expansions_per_minute: f64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            expansions: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            last_expansion: Mutex::new(None),
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn record_expansion(&self) {
+        self.expansions.fetch_add(1, Ordering::Relaxed);
+        *self.last_expansion.lock().unwrap() = Some(Utc::now());
+    }
+
+    /// When the most recent successful expansion happened
+    pub fn last_expansion(&self) -> Option<DateTime<Utc>> {
+        *self.last_expansion.lock().unwrap()
+    }
+
+    pub fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let expansions_total = self.expansions.load(Ordering::Relaxed);
+        let uptime_seconds = self.started_at.elapsed().as_secs_f64();
+        let expansions_per_minute = if uptime_seconds > 0.0 {
+            expansions_total as f64 / (uptime_seconds / 60.0)
+        } else {
+            0.0
+        };
+
+        MetricsSnapshot {
+            expansions_total,
+            expansion_failures_total: self.failures.load(Ordering::Relaxed),
+            uptime_seconds,
+            expansions_per_minute,
+        }
+    }
+}
+
+impl MetricsSnapshot {
+    /// Render the snapshot in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut output = String::new();
+        let metrics = [
+            (
+                "typely_expansions_total",
+                "counter",
+                "Successful snippet expansions",
+                self.expansions_total as f64,
+            ),
+            (
+                "typely_expansion_failures_total",
+                "counter",
+                "Failed snippet expansions",
+                self.expansion_failures_total as f64,
+            ),
+            (
+                "typely_expansions_per_minute",
+                "gauge",
+                "Successful expansions per minute since start",
+                self.expansions_per_minute,
+            ),
+            (
+                "typely_uptime_seconds",
+                "gauge",
+                "Seconds since metrics collection started",
+                self.uptime_seconds,
+            ),
+        ];
+
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(output, "{} {}", name, value);
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let metrics = Metrics::new();
+        assert_eq!(metrics.last_expansion(), None);
+        metrics.record_expansion();
+        metrics.record_expansion();
+        metrics.record_failure();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.expansions_total, 2);
+        assert_eq!(snapshot.expansion_failures_total, 1);
+        assert!(snapshot.expansions_per_minute > 0.0);
+        assert!(metrics.last_expansion().is_some());
+    }
+
+    #[test]
+    fn test_prometheus_format() {
+        let snapshot = MetricsSnapshot {
+            expansions_total: 3,
+            expansion_failures_total: 1,
+            uptime_seconds: 60.0,
+            expansions_per_minute: 3.0,
+        };
+
+        let output = snapshot.to_prometheus();
+        assert!(output.contains("# TYPE typely_expansions_total counter\n"));
+        assert!(output.contains("typely_expansions_total 3\n"));
+        assert!(output.contains("typely_expansion_failures_total 1\n"));
+        assert!(output.contains("typely_expansions_per_minute 3\n"));
+    }
+}
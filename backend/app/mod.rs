@@ -1,8 +1,10 @@
 // Application layer - Services and DTOs
 
 pub mod dto;
+pub mod metrics;
 pub mod services;
 
 // Re-export
 pub use dto::*;
+pub use metrics::*;
 pub use services::*;
@@ -1,5 +1,5 @@
 use crate::app::dto::{CreateSnippetRequest, SnippetDto};
-use crate::domain::{DomainEvent, Snippet, SnippetRepository};
+use crate::domain::{DomainEvent, DuplicateTrigger, Snippet, SnippetRepository};
 use anyhow::Result;
 use std::sync::Arc;
 
@@ -13,16 +13,14 @@ impl CreateSnippetService {
     }
 
     pub async fn execute(&self, request: CreateSnippetRequest) -> Result<SnippetDto> {
-        // Validate that the trigger doesn't already exist
+        // The repository refuses a taken trigger itself, even under concurrent
+        // creates; this catches one taken as another snippet's alias
         if self
             .repository
             .exists_with_trigger(&request.trigger)
             .await?
         {
-            return Err(anyhow::anyhow!(
-                "A snippet with trigger '{}' already exists",
-                request.trigger
-            ));
+            return Err(DuplicateTrigger(request.trigger).into());
         }
 
         // Create the snippet
@@ -35,6 +33,35 @@ impl CreateSnippetService {
             }
         }
 
+        if let Some(content_type) = request.content_type {
+            snippet.set_content_type(content_type);
+        }
+
+        if let Some(confirm_before_expand) = request.confirm_before_expand {
+            snippet.set_confirm_before_expand(confirm_before_expand);
+        }
+
+        if let Some(keep_trigger) = request.keep_trigger {
+            snippet.set_keep_trigger(keep_trigger);
+        }
+
+        snippet.set_type_delay_ms(request.type_delay_ms);
+
+        if let Some(aliases) = request.aliases {
+            snippet.set_aliases(aliases)?;
+        }
+        if request.generate_case_variants {
+            snippet.generate_case_variants()?;
+        }
+        snippet.set_encrypted(request.encrypted);
+
+        // Aliases share the trigger namespace, so each must be unused
+        for alias in &snippet.aliases {
+            if self.repository.exists_with_trigger(alias).await? {
+                return Err(DuplicateTrigger(alias.clone()).into());
+            }
+        }
+
         // Save to repository
         self.repository.save(&snippet).await?;
 
@@ -70,9 +97,8 @@ mod tests {
         let (use_case, _temp_dir) = create_test_use_case().await;
 
         let request = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Hello, World!".to_string(),
             tags: Some(vec!["greeting".to_string()]),
+            ..CreateSnippetRequest::new("::hello", "Hello, World!")
         };
 
         let result = use_case.execute(request).await.unwrap();
@@ -88,17 +114,9 @@ mod tests {
     async fn test_create_snippet_duplicate_trigger() {
         let (use_case, _temp_dir) = create_test_use_case().await;
 
-        let request1 = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "First".to_string(),
-            tags: None,
-        };
+        let request1 = CreateSnippetRequest::new("::test", "First");
 
-        let request2 = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Second".to_string(),
-            tags: None,
-        };
+        let request2 = CreateSnippetRequest::new("::test", "Second");
 
         // First creation should succeed
         let result1 = use_case.execute(request1).await;
@@ -114,13 +132,33 @@ mod tests {
     async fn test_create_snippet_invalid_trigger() {
         let (use_case, _temp_dir) = create_test_use_case().await;
 
-        let request = CreateSnippetRequest {
-            trigger: "".to_string(), // Empty trigger
-            replacement: "Test".to_string(),
-            tags: None,
-        };
+        let request = CreateSnippetRequest::new("", "Test"); // Empty trigger
 
         let result = use_case.execute(request).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_aliases_collide_with_triggers() {
+        let (use_case, _temp_dir) = create_test_use_case().await;
+        let request = |trigger: &str, aliases: &[&str]| CreateSnippetRequest {
+            aliases: Some(aliases.iter().map(|a| a.to_string()).collect()),
+            ..CreateSnippetRequest::new(trigger, "1 Main Street")
+        };
+
+        let created = use_case
+            .execute(request("::address", &["::addr", "::addr", "::address"]))
+            .await
+            .unwrap();
+        assert_eq!(created.aliases, vec!["::addr".to_string()]);
+
+        // A new trigger can't reuse an alias, and a new alias can't reuse a trigger
+        let error = use_case.execute(request("::addr", &[])).await.unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+        let error = use_case
+            .execute(request("::home", &["::address"]))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("'::address' already exists"));
+    }
 }
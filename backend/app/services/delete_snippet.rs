@@ -42,43 +42,29 @@ impl DeleteSnippetService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::app::dto::CreateSnippetRequest;
-    use crate::app::services::CreateSnippetService;
-    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
-    use tempfile::TempDir;
-
-    async fn create_test_use_case() -> (DeleteSnippetService, CreateSnippetService, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
-        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
-        let delete_use_case = DeleteSnippetService::new(repository.clone());
-        let create_use_case = CreateSnippetService::new(repository);
-        (delete_use_case, create_use_case, temp_dir)
-    }
+    use crate::test_support::{seeded_service, snippet};
 
     #[tokio::test]
     async fn test_delete_existing_snippet() {
-        let (delete_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
-
-        // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Test".to_string(),
-            tags: None,
-        };
-        let created = create_use_case.execute(create_request).await.unwrap();
+        let seeded = snippet("::test", "Test");
+        let id = seeded.id;
+        let service = seeded_service(vec![seeded]).await;
 
         // Delete the snippet
-        let deleted = delete_use_case.execute(created.id).await.unwrap();
+        let deleted = service.delete_snippet_service.execute(id).await.unwrap();
         assert!(deleted);
+        assert!(service.get_snippet(id).await.unwrap().is_none());
     }
 
     #[tokio::test]
     async fn test_delete_nonexistent_snippet() {
-        let (delete_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+        let service = seeded_service(Vec::new()).await;
 
-        let deleted = delete_use_case.execute(Uuid::new_v4()).await.unwrap();
+        let deleted = service
+            .delete_snippet_service
+            .execute(Uuid::new_v4())
+            .await
+            .unwrap();
         assert!(!deleted);
     }
 }
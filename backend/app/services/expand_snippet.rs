@@ -1,11 +1,25 @@
 use crate::app::dto::{ExpansionRequest, ExpansionResponse};
-use crate::domain::{DomainEvent, ExpansionContext, ExpansionService, SnippetRepository};
+use crate::app::metrics::Metrics;
+use crate::domain::{
+    fragment_references, normalize_trigger, resolve_fragments, ContentType, DomainEvent,
+    ExpansionContext, ExpansionRules, ExpansionService, FileAccess, FragmentRepository, Snippet,
+    SnippetRepository, TriggerPrefix,
+};
+use crate::infra::ExpansionLogger;
 use anyhow::Result;
+use chrono_tz::Tz;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
 
 pub struct ExpandSnippetService {
     repository: Arc<dyn SnippetRepository>,
     expansion_service: ExpansionService,
+    metrics: Arc<Metrics>,
+    expansion_logger: Option<Arc<ExpansionLogger>>,
+    fragment_repository: Option<Arc<dyn FragmentRepository>>,
+    privacy_mode: bool,
 }
 
 impl ExpandSnippetService {
@@ -13,12 +27,121 @@ impl ExpandSnippetService {
         Self {
             repository,
             expansion_service: ExpansionService::new(),
+            metrics: Arc::new(Metrics::new()),
+            expansion_logger: None,
+            fragment_repository: None,
+            privacy_mode: false,
         }
     }
 
+    /// Expansion counters, shared with anything else that records expansions
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Override the maximum length of expanded text (defaults to 50k characters)
+    pub fn with_max_expansion_length(mut self, max_expansion_length: usize) -> Self {
+        self.expansion_service = self
+            .expansion_service
+            .with_max_expansion_length(max_expansion_length);
+        self
+    }
+
+    /// Override how deeply fragments may include other fragments (defaults to 16)
+    pub fn with_max_expansion_depth(mut self, max_expansion_depth: usize) -> Self {
+        self.expansion_service = self
+            .expansion_service
+            .with_max_expansion_depth(max_expansion_depth);
+        self
+    }
+
+    /// Only expand `{env:NAME}` for the named environment variables
+    pub fn with_env_allowlist(mut self, names: Vec<String>) -> Self {
+        self.expansion_service = self.expansion_service.with_env_allowlist(names);
+        self
+    }
+
+    /// Let `{file:PATH}` read files from `file_access.base_dir`
+    pub fn with_file_access(mut self, file_access: FileAccess) -> Self {
+        self.expansion_service = self.expansion_service.with_file_access(file_access);
+        self
+    }
+
+    /// Show dates and times in `timezone` instead of the system's local time
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.expansion_service = self.expansion_service.with_timezone(timezone);
+        self
+    }
+
+    /// Look for triggers starting with `prefix` instead of `::` in text
+    pub fn with_trigger_prefix(mut self, prefix: &TriggerPrefix) -> Self {
+        self.expansion_service = self.expansion_service.with_trigger_prefix(prefix);
+        self
+    }
+
+    /// Run the built-in transforms `rules` turns on over every expansion
+    pub fn with_expansion_rules(mut self, rules: ExpansionRules) -> Self {
+        self.expansion_service = self.expansion_service.with_rules(rules);
+        self
+    }
+
+    /// Record every expansion attempt in a log file (off unless set)
+    pub fn with_expansion_logger(mut self, expansion_logger: Arc<ExpansionLogger>) -> Self {
+        self.expansion_logger = Some(expansion_logger);
+        self
+    }
+
+    /// Resolve `{fragment:name}` references from `fragment_repository`
+    pub fn with_fragment_repository(
+        mut self,
+        fragment_repository: Arc<dyn FragmentRepository>,
+    ) -> Self {
+        self.fragment_repository = Some(fragment_repository);
+        self
+    }
+
+    /// Keep triggers out of logs and spans and don't write the expansion
+    /// log; only the expansion counts are kept
+    pub fn with_privacy_mode(mut self, privacy_mode: bool) -> Self {
+        self.privacy_mode = privacy_mode;
+        self
+    }
+
     pub async fn execute(&self, request: ExpansionRequest) -> Result<ExpansionResponse> {
-        // Find the snippet by trigger
-        let snippet = match self.repository.find_by_trigger(&request.trigger).await? {
+        let span = tracing::info_span!(
+            "expand_snippet",
+            trigger = (!self.privacy_mode).then(|| tracing::field::display(&request.trigger)),
+            duration_ms = tracing::field::Empty,
+        );
+
+        let trigger = self
+            .expansion_logger
+            .as_ref()
+            .filter(|_| !self.privacy_mode)
+            .map(|_| request.trigger.clone());
+
+        let started = Instant::now();
+        let result = self.expand(request).instrument(span.clone()).await;
+        span.record("duration_ms", started.elapsed().as_millis() as u64);
+
+        let success = matches!(&result, Ok(response) if response.success);
+        if success {
+            self.metrics.record_expansion();
+        } else {
+            self.metrics.record_failure();
+        }
+
+        if let (Some(logger), Some(trigger)) = (&self.expansion_logger, trigger) {
+            logger.record(&trigger, success);
+        }
+
+        result
+    }
+
+    async fn expand(&self, request: ExpansionRequest) -> Result<ExpansionResponse> {
+        // Find the snippet by trigger; stored triggers are NFC-normalized
+        let trigger = normalize_trigger(&request.trigger);
+        let snippet = match self.repository.find_by_trigger(&trigger).await? {
             Some(snippet) => snippet,
             None => {
                 return Ok(ExpansionResponse {
@@ -28,6 +151,12 @@ impl ExpandSnippetService {
                         "No snippet found for trigger: {}",
                         request.trigger
                     )),
+                    content_type: ContentType::Plain,
+                    confirm_before_expand: false,
+                    keep_trigger: false,
+                    type_delay_ms: None,
+                    snippet_id: None,
+                    usage_count: None,
                 });
             }
         };
@@ -38,6 +167,27 @@ impl ExpandSnippetService {
                 success: false,
                 expanded_text: None,
                 error_message: Some("Snippet is inactive".to_string()),
+                content_type: ContentType::Plain,
+                confirm_before_expand: false,
+                keep_trigger: false,
+                type_delay_ms: None,
+                snippet_id: Some(snippet.id),
+                usage_count: Some(snippet.usage_count),
+            });
+        }
+
+        // Scheduled snippets only expand inside their active window
+        if !snippet.in_active_window(chrono::Utc::now()) {
+            return Ok(ExpansionResponse {
+                success: false,
+                expanded_text: None,
+                error_message: Some("Snippet is outside its active window".to_string()),
+                content_type: ContentType::Plain,
+                confirm_before_expand: false,
+                keep_trigger: false,
+                type_delay_ms: None,
+                snippet_id: Some(snippet.id),
+                usage_count: Some(snippet.usage_count),
             });
         }
 
@@ -48,8 +198,26 @@ impl ExpandSnippetService {
             application_context: None,
         };
 
+        // Fragments are resolved into a copy so the stored replacement keeps its references
+        let expandable = match self.with_fragments_resolved(&snippet).await? {
+            Ok(expandable) => expandable,
+            Err(e) => {
+                return Ok(ExpansionResponse {
+                    success: false,
+                    expanded_text: None,
+                    error_message: Some(e.to_string()),
+                    content_type: ContentType::Plain,
+                    confirm_before_expand: false,
+                    keep_trigger: false,
+                    type_delay_ms: None,
+                    snippet_id: Some(snippet.id),
+                    usage_count: Some(snippet.usage_count),
+                });
+            }
+        };
+
         // Expand the snippet
-        let expansion_result = self.expansion_service.expand_snippet(&snippet, &context);
+        let expansion_result = self.expansion_service.expand_snippet(&expandable, &context);
 
         if expansion_result.success {
             // Update usage count
@@ -60,29 +228,144 @@ impl ExpandSnippetService {
             if let Err(e) = self.repository.update(&updated_snippet).await {
                 log::warn!("Failed to update snippet usage count: {}", e);
             }
+            let used_at = chrono::Utc::now();
+            if let Err(e) = self
+                .repository
+                .record_usage(&updated_snippet.id, used_at)
+                .await
+            {
+                log::warn!("Failed to log snippet usage: {}", e);
+            }
 
             // Log domain event
             let event = DomainEvent::SnippetExpanded {
                 snippet_id: updated_snippet.id,
                 trigger: updated_snippet.trigger.clone(),
-                timestamp: chrono::Utc::now(),
+                timestamp: used_at,
+            };
+            if self.privacy_mode {
+                log::info!("Snippet expanded");
+            } else {
+                log::info!("Snippet expanded: {:?}", event);
+            }
+
+            // A case variant such as `::BTW` expands in its case
+            let expanded_text = match updated_snippet.case_of(&trigger) {
+                Some(case) => case.apply(&expansion_result.expanded_text),
+                None => expansion_result.expanded_text,
             };
-            log::info!("Snippet expanded: {:?}", event);
 
             Ok(ExpansionResponse {
                 success: true,
-                expanded_text: Some(expansion_result.expanded_text),
+                expanded_text: Some(expanded_text),
                 error_message: None,
+                content_type: updated_snippet.content_type,
+                confirm_before_expand: updated_snippet.confirm_before_expand,
+                keep_trigger: updated_snippet.keep_trigger,
+                type_delay_ms: updated_snippet.type_delay_ms,
+                snippet_id: Some(updated_snippet.id),
+                usage_count: Some(updated_snippet.usage_count),
             })
         } else {
             Ok(ExpansionResponse {
                 success: false,
                 expanded_text: None,
                 error_message: expansion_result.error,
+                content_type: ContentType::Plain,
+                confirm_before_expand: false,
+                keep_trigger: false,
+                type_delay_ms: None,
+                snippet_id: Some(snippet.id),
+                usage_count: Some(snippet.usage_count),
             })
         }
     }
 
+    /// What `snippet` would expand to now, fragments and placeholders
+    /// resolved, without counting a use. Fails when it can't be expanded,
+    /// e.g. when it would be too long.
+    pub async fn preview(&self, snippet: &Snippet) -> Result<String> {
+        let expandable = self.with_fragments_resolved(snippet).await??;
+        let context = ExpansionContext {
+            cursor_position: None,
+            surrounding_text: None,
+            application_context: None,
+        };
+
+        let expansion_result = self.expansion_service.expand_snippet(&expandable, &context);
+        if !expansion_result.success {
+            return Err(anyhow::anyhow!(expansion_result
+                .error
+                .unwrap_or_else(|| format!("Can't expand '{}'", snippet.trigger))));
+        }
+        Ok(expansion_result.expanded_text)
+    }
+
+    /// A copy of `snippet` with its fragment references replaced. The outer
+    /// result carries storage errors, including a replacement that couldn't be
+    /// decrypted, the inner one fragment cycles.
+    async fn with_fragments_resolved(&self, snippet: &Snippet) -> Result<Result<Snippet>> {
+        snippet.ensure_unlocked()?;
+        let Some(fragment_repository) = &self.fragment_repository else {
+            return Ok(Ok(snippet.clone()));
+        };
+
+        // Load every fragment reachable from the replacement
+        let mut fragments = HashMap::new();
+        let mut looked_up = HashSet::new();
+        let mut pending = fragment_references(&snippet.replacement);
+        while let Some(name) = pending.pop() {
+            if !looked_up.insert(name.clone()) {
+                continue;
+            }
+            if let Some(fragment) = fragment_repository.find_by_name(&name).await? {
+                pending.extend(fragment_references(&fragment.content));
+                fragments.insert(name, fragment.content);
+            }
+        }
+
+        if fragments.is_empty() {
+            return Ok(Ok(snippet.clone()));
+        }
+
+        Ok(resolve_fragments(
+            &snippet.replacement,
+            &fragments,
+            &self.expansion_service.fragment_limits(),
+        )
+        .map(|replacement| Snippet {
+            replacement,
+            ..snippet.clone()
+        }))
+    }
+
+    /// Expand every known, active trigger in `text`, leaving the rest untouched.
+    /// Only the triggers that actually appear in the text are looked up. Fails
+    /// when the result is longer than the maximum expansion length.
+    pub async fn expand_text(&self, text: &str) -> Result<String> {
+        let mut snippets = HashMap::new();
+        for trigger_match in self.expansion_service.find_triggers(text) {
+            let trigger = trigger_match.normalized_trigger();
+            if snippets.contains_key(&trigger) {
+                continue;
+            }
+            if let Some(snippet) = self.repository.find_by_trigger(&trigger).await? {
+                snippets.insert(trigger, self.with_fragments_resolved(&snippet).await??);
+            }
+        }
+
+        let snippet_refs: HashMap<String, &Snippet> = snippets
+            .iter()
+            .map(|(trigger, snippet)| (trigger.clone(), snippet))
+            .collect();
+
+        let result = self.expansion_service.expand_in_text(text, &snippet_refs);
+        match result.error {
+            Some(error) => Err(anyhow::anyhow!(error)),
+            None => Ok(result.expanded_text),
+        }
+    }
+
     pub async fn find_matching_snippets(&self, text: &str) -> Result<Vec<String>> {
         let triggers = self.expansion_service.find_triggers(text);
         let mut matching_triggers = Vec::new();
@@ -90,7 +373,7 @@ impl ExpandSnippetService {
         for trigger_match in triggers {
             if self
                 .repository
-                .exists_with_trigger(&trigger_match.trigger)
+                .exists_with_trigger(&trigger_match.normalized_trigger())
                 .await?
             {
                 matching_triggers.push(trigger_match.trigger);
@@ -106,8 +389,78 @@ mod tests {
     use super::*;
     use crate::app::dto::CreateSnippetRequest;
     use crate::app::services::CreateSnippetService;
-    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use crate::domain::Fragment;
+    use crate::infra::{DatabaseConnection, SqliteFragmentRepository, SqliteSnippetRepository};
+    use std::sync::Mutex;
     use tempfile::TempDir;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    #[derive(Debug, PartialEq)]
+    struct RecordedSpan {
+        name: String,
+        trigger: Option<String>,
+    }
+
+    /// Records the name and `trigger` field of every span that is opened,
+    /// and every field value of any span or event as `name=value`
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        fields: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[derive(Default)]
+    struct FieldVisitor(Vec<(String, String)>);
+
+    impl Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl SpanRecorder {
+        fn keep_fields(&self, visitor: &FieldVisitor) {
+            let fields = visitor
+                .0
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value));
+            self.fields.lock().unwrap().extend(fields);
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            let mut visitor = FieldVisitor::default();
+            attrs.record(&mut visitor);
+            self.keep_fields(&visitor);
+            let trigger = visitor
+                .0
+                .into_iter()
+                .find(|(name, _)| name == "trigger")
+                .map(|(_, value)| value);
+            self.spans.lock().unwrap().push(RecordedSpan {
+                name: attrs.metadata().name().to_string(),
+                trigger,
+            });
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = FieldVisitor::default();
+            values.record(&mut visitor);
+            self.keep_fields(&visitor);
+        }
+
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = FieldVisitor::default();
+            event.record(&mut visitor);
+            self.keep_fields(&visitor);
+        }
+    }
 
     async fn create_test_use_case() -> (ExpandSnippetService, CreateSnippetService, TempDir) {
         let temp_dir = TempDir::new().unwrap();
@@ -124,11 +477,7 @@ mod tests {
         let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Hello, World!".to_string(),
-            tags: None,
-        };
+        let create_request = CreateSnippetRequest::new("::hello", "Hello, World!");
         create_use_case.execute(create_request).await.unwrap();
 
         // Expand the snippet
@@ -143,6 +492,304 @@ mod tests {
         assert!(response.expanded_text.is_some());
         assert_eq!(response.expanded_text.unwrap(), "Hello, World!");
         assert!(response.error_message.is_none());
+        assert!(!response.confirm_before_expand);
+    }
+
+    #[tokio::test]
+    async fn test_expand_reports_the_matched_snippet() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let create_request = CreateSnippetRequest {
+            aliases: Some(vec!["::hi".to_string()]),
+            ..CreateSnippetRequest::new("::hello", "Hello, World!")
+        };
+        let created = create_use_case.execute(create_request).await.unwrap();
+
+        for (expected_usage, trigger) in [(1, "::hello"), (2, "::hi")] {
+            let response = expand_use_case
+                .execute(ExpansionRequest {
+                    trigger: trigger.to_string(),
+                    context: None,
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.snippet_id, Some(created.id));
+            assert_eq!(response.usage_count, Some(expected_usage));
+        }
+
+        let response = expand_use_case
+            .execute(ExpansionRequest {
+                trigger: "::missing".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.snippet_id, None);
+        assert_eq!(response.usage_count, None);
+    }
+
+    #[tokio::test]
+    async fn test_expand_reports_confirmation_flag() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let create_request = CreateSnippetRequest {
+            confirm_before_expand: Some(true),
+            ..CreateSnippetRequest::new("::rmrf", "rm -rf ./build")
+        };
+        create_use_case.execute(create_request).await.unwrap();
+
+        let expansion_request = ExpansionRequest {
+            trigger: "::rmrf".to_string(),
+            context: None,
+        };
+
+        let response = expand_use_case.execute(expansion_request).await.unwrap();
+
+        assert!(response.success);
+        assert!(response.confirm_before_expand);
+    }
+
+    #[tokio::test]
+    async fn test_expand_emits_tracing_span() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let create_request = CreateSnippetRequest::new("::traced", "Traced");
+        create_use_case.execute(create_request).await.unwrap();
+
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let expansion_request = ExpansionRequest {
+            trigger: "::traced".to_string(),
+            context: None,
+        };
+        expand_use_case.execute(expansion_request).await.unwrap();
+
+        let spans = recorder.spans.lock().unwrap();
+        assert!(spans.contains(&RecordedSpan {
+            name: "expand_snippet".to_string(),
+            trigger: Some("::traced".to_string()),
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_expansions_are_logged_when_enabled() {
+        let (expand_use_case, create_use_case, temp_dir) = create_test_use_case().await;
+        let log_path = temp_dir.path().join("expansions.log");
+        let expand_use_case = expand_use_case.with_expansion_logger(Arc::new(
+            ExpansionLogger::new(crate::infra::ExpansionLogConfig::new(log_path.clone())),
+        ));
+
+        let create_request = CreateSnippetRequest::new("::logged", "Logged");
+        create_use_case.execute(create_request).await.unwrap();
+
+        for trigger in ["::logged", "::missing"] {
+            expand_use_case
+                .execute(ExpansionRequest {
+                    trigger: trigger.to_string(),
+                    context: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("\t::logged\tok"));
+        assert!(lines[1].ends_with("\t::missing\tfailed"));
+    }
+
+    #[tokio::test]
+    async fn test_privacy_mode_keeps_triggers_out_of_logs() {
+        let (expand_use_case, create_use_case, temp_dir) = create_test_use_case().await;
+        let log_path = temp_dir.path().join("expansions.log");
+        let expand_use_case = expand_use_case
+            .with_expansion_logger(Arc::new(ExpansionLogger::new(
+                crate::infra::ExpansionLogConfig::new(log_path.clone()),
+            )))
+            .with_privacy_mode(true);
+
+        let create_request = CreateSnippetRequest::new("::secret", "Secret");
+        create_use_case.execute(create_request).await.unwrap();
+
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let logs = crate::test_support::LogCapture::start();
+
+        for trigger in ["::secret", "::missing"] {
+            expand_use_case
+                .execute(ExpansionRequest {
+                    trigger: trigger.to_string(),
+                    context: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let messages = logs.messages();
+        assert_eq!(messages, vec!["Snippet expanded"]);
+        // Nothing recorded anywhere, the repository's spans included
+        let fields = recorder.fields.lock().unwrap();
+        assert!(!fields.is_empty());
+        for field in fields.iter() {
+            assert!(!field.contains("::secret"), "{}", field);
+            assert!(!field.contains("::missing"), "{}", field);
+        }
+        assert!(!log_path.exists());
+        let snapshot = expand_use_case.metrics().snapshot();
+        assert_eq!(snapshot.expansions_total, 1);
+        assert_eq!(snapshot.expansion_failures_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_log_does_not_break_expansion() {
+        let (expand_use_case, create_use_case, temp_dir) = create_test_use_case().await;
+        let expand_use_case =
+            expand_use_case.with_expansion_logger(Arc::new(ExpansionLogger::new(
+                crate::infra::ExpansionLogConfig::new(temp_dir.path().to_path_buf()),
+            )));
+
+        let create_request = CreateSnippetRequest::new("::hello", "Hello");
+        create_use_case.execute(create_request).await.unwrap();
+
+        let response = expand_use_case
+            .execute(ExpansionRequest {
+                trigger: "::hello".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+    }
+
+    async fn create_fragment_use_case() -> (
+        ExpandSnippetService,
+        CreateSnippetService,
+        Arc<SqliteFragmentRepository>,
+        TempDir,
+    ) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_connection = DatabaseConnection::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+        let fragments = Arc::new(SqliteFragmentRepository::new(db_connection.pool().clone()));
+        let expand_use_case = ExpandSnippetService::new(repository.clone())
+            .with_fragment_repository(fragments.clone());
+        let create_use_case = CreateSnippetService::new(repository);
+        (expand_use_case, create_use_case, fragments, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_expand_substitutes_fragments() {
+        let (expand_use_case, create_use_case, fragments, _temp_dir) =
+            create_fragment_use_case().await;
+        for (name, content) in [("sig", "Jane Doe\n{fragment:title}"), ("title", "Engineer")] {
+            fragments
+                .save(&Fragment::new(name.to_string(), content.to_string()).unwrap())
+                .await
+                .unwrap();
+        }
+        create_use_case
+            .execute(CreateSnippetRequest::new(
+                "::thanks",
+                "Thanks,\n{fragment:sig}",
+            ))
+            .await
+            .unwrap();
+
+        let response = expand_use_case
+            .execute(ExpansionRequest {
+                trigger: "::thanks".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(
+            response.expanded_text.as_deref(),
+            Some("Thanks,\nJane Doe\nEngineer")
+        );
+
+        // The stored replacement keeps its reference, so later edits to the fragment apply
+        let stored = expand_use_case
+            .repository
+            .find_by_trigger("::thanks")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.replacement, "Thanks,\n{fragment:sig}");
+        assert_eq!(stored.usage_count, 1);
+
+        let expanded = expand_use_case.expand_text("PS ::thanks").await.unwrap();
+        assert_eq!(expanded, "PS Thanks,\nJane Doe\nEngineer");
+    }
+
+    #[tokio::test]
+    async fn test_fragment_cycle_fails_expansion() {
+        let (expand_use_case, create_use_case, fragments, _temp_dir) =
+            create_fragment_use_case().await;
+        for (name, content) in [("a", "{fragment:b}"), ("b", "{fragment:a}")] {
+            fragments
+                .save(&Fragment::new(name.to_string(), content.to_string()).unwrap())
+                .await
+                .unwrap();
+        }
+        create_use_case
+            .execute(CreateSnippetRequest::new("::loop", "{fragment:a}"))
+            .await
+            .unwrap();
+
+        let response = expand_use_case
+            .execute(ExpansionRequest {
+                trigger: "::loop".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert!(!response.success);
+        assert_eq!(
+            response.error_message.as_deref(),
+            Some("Fragment cycle: a -> b -> a")
+        );
+        assert!(expand_use_case.expand_text("::loop").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fragment_nesting_depth_is_capped() {
+        let (expand_use_case, create_use_case, fragments, _temp_dir) =
+            create_fragment_use_case().await;
+        let expand_use_case = expand_use_case.with_max_expansion_depth(2);
+        for (name, content) in [("a", "A{fragment:b}"), ("b", "B{fragment:c}"), ("c", "C")] {
+            fragments
+                .save(&Fragment::new(name.to_string(), content.to_string()).unwrap())
+                .await
+                .unwrap();
+        }
+        for (trigger, replacement) in [("::deep", "{fragment:a}"), ("::shallow", "{fragment:b}")] {
+            create_use_case
+                .execute(CreateSnippetRequest::new(trigger, replacement))
+                .await
+                .unwrap();
+        }
+
+        let expand = |trigger: &str| {
+            expand_use_case.execute(ExpansionRequest {
+                trigger: trigger.to_string(),
+                context: None,
+            })
+        };
+        let response = expand("::deep").await.unwrap();
+        assert!(!response.success);
+        assert_eq!(
+            response.error_message.as_deref(),
+            Some("Fragments are nested more than 2 deep at 'c'")
+        );
+        let response = expand("::shallow").await.unwrap();
+        assert_eq!(response.expanded_text.as_deref(), Some("BC"));
     }
 
     #[tokio::test]
@@ -167,12 +814,8 @@ mod tests {
         let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Test".to_string(),
-            tags: None,
-        };
-        let created = create_use_case.execute(create_request).await.unwrap();
+        let create_request = CreateSnippetRequest::new("::test", "Test");
+        create_use_case.execute(create_request).await.unwrap();
 
         // Deactivate the snippet (this would normally be done through UpdateSnippetService)
         // For this test, we'll assume the snippet is deactivated
@@ -189,6 +832,70 @@ mod tests {
         assert!(response.success);
     }
 
+    #[tokio::test]
+    async fn test_expand_exceeding_max_length() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        let expand_use_case = expand_use_case.with_max_expansion_length(100);
+
+        let create_request = CreateSnippetRequest::new("::boom", "{datetime}".repeat(10));
+        create_use_case.execute(create_request).await.unwrap();
+
+        let expansion_request = ExpansionRequest {
+            trigger: "::boom".to_string(),
+            context: None,
+        };
+
+        let response = expand_use_case.execute(expansion_request).await.unwrap();
+
+        assert!(!response.success);
+        assert!(response.expanded_text.is_none());
+        assert!(response
+            .error_message
+            .unwrap()
+            .contains("exceeding the limit"));
+    }
+
+    #[tokio::test]
+    async fn test_expand_text_with_multiple_triggers() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        for (trigger, replacement) in [("::hello", "Hello"), ("::name", "Typely")] {
+            let request = CreateSnippetRequest::new(trigger, replacement);
+            create_use_case.execute(request).await.unwrap();
+        }
+
+        let expanded = expand_use_case
+            .expand_text("Say ::hello to ::name, ::hello again! ::missing")
+            .await
+            .unwrap();
+
+        assert_eq!(expanded, "Say Hello to Typely, Hello again! ::missing");
+    }
+
+    #[tokio::test]
+    async fn test_expand_text_exceeding_max_length() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        let expand_use_case = expand_use_case.with_max_expansion_length(20);
+        create_use_case
+            .execute(CreateSnippetRequest::new("::ten", "0123456789"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            expand_use_case.expand_text("::ten!").await.unwrap(),
+            "0123456789!"
+        );
+        // Each expansion fits, but all of the text together doesn't
+        let error = expand_use_case
+            .expand_text("::ten ::ten")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expanded text is 21 characters, exceeding the limit of 20"
+        );
+    }
+
     #[tokio::test]
     async fn test_find_matching_snippets() {
         let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
@@ -201,11 +908,7 @@ mod tests {
         ];
 
         for (trigger, replacement) in snippets {
-            let request = CreateSnippetRequest {
-                trigger: trigger.to_string(),
-                replacement: replacement.to_string(),
-                tags: None,
-            };
+            let request = CreateSnippetRequest::new(trigger, replacement);
             create_use_case.execute(request).await.unwrap();
         }
 
@@ -218,4 +921,154 @@ mod tests {
         assert!(matching.contains(&"::test".to_string()));
         assert!(!matching.contains(&"::nonexistent".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_expansion_metrics() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let create_request = CreateSnippetRequest::new("::metric", "Counted");
+        create_use_case.execute(create_request).await.unwrap();
+
+        for trigger in ["::metric", "::metric", "::metric", "::missing"] {
+            let request = ExpansionRequest {
+                trigger: trigger.to_string(),
+                context: None,
+            };
+            expand_use_case.execute(request).await.unwrap();
+        }
+
+        let snapshot = expand_use_case.metrics().snapshot();
+        assert_eq!(snapshot.expansions_total, 3);
+        assert_eq!(snapshot.expansion_failures_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_expand_nfc_equivalent_trigger() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        // Stored with a precomposed "é"...
+        let create_request = CreateSnippetRequest::new("::caf\u{e9}", "Coffee :coffee:");
+        create_use_case.execute(create_request).await.unwrap();
+
+        // ...and typed as "e" followed by a combining acute accent
+        let expansion_request = ExpansionRequest {
+            trigger: "::cafe\u{301}".to_string(),
+            context: None,
+        };
+
+        let response = expand_use_case.execute(expansion_request).await.unwrap();
+
+        assert!(response.success);
+        assert_eq!(response.expanded_text.unwrap(), "Coffee ☕");
+    }
+
+    #[tokio::test]
+    async fn test_expand_via_alias_uses_primary_snippet() {
+        let mut address = crate::test_support::snippet("::address", "1 Main Street");
+        address.set_aliases(vec!["::addr".to_string()]).unwrap();
+        let service = crate::test_support::seeded_service([address]).await;
+
+        let response = service
+            .expand_snippet(ExpansionRequest {
+                trigger: "::addr".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.expanded_text.unwrap(), "1 Main Street");
+
+        let expanded = service.expand_text("Ship to ::addr").await.unwrap();
+        assert_eq!(expanded, "Ship to 1 Main Street");
+
+        let primary = service
+            .get_snippet_by_trigger("::address")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(primary.usage_count, 1);
+        assert_eq!(primary.aliases, vec!["::addr".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_case_variants_expand_in_their_case() {
+        let (expand_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        let created = create_use_case
+            .execute(CreateSnippetRequest {
+                generate_case_variants: true,
+                encrypted: false,
+                ..CreateSnippetRequest::new("::btw", "by the way")
+            })
+            .await
+            .unwrap();
+        assert!(created.case_variants);
+        assert_eq!(created.aliases, ["::Btw", "::BTW"]);
+
+        for (trigger, expected) in [
+            ("::btw", "by the way"),
+            ("::Btw", "By the way"),
+            ("::BTW", "BY THE WAY"),
+        ] {
+            let response = expand_use_case
+                .execute(ExpansionRequest {
+                    trigger: trigger.to_string(),
+                    context: None,
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.expanded_text.as_deref(), Some(expected));
+        }
+        let expanded = expand_use_case.expand_text("::Btw, ::BTW").await.unwrap();
+        assert_eq!(expanded, "By the way, BY THE WAY");
+
+        // Without the flag, neither variant is a trigger
+        create_use_case
+            .execute(CreateSnippetRequest::new("::omw", "on my way"))
+            .await
+            .unwrap();
+        let response = expand_use_case
+            .execute(ExpansionRequest {
+                trigger: "::OMW".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert!(!response.success);
+
+        // The variants are taken like any alias
+        let error = create_use_case
+            .execute(CreateSnippetRequest::new("::BTW", "Bring the wine"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+    }
+
+    #[tokio::test]
+    async fn test_expand_respects_active_window() {
+        let now = chrono::Utc::now();
+        let day = chrono::Duration::days(1);
+        let mut away = crate::test_support::snippet("::away", "Out of office this week");
+        away.set_active_window(Some(now - day), Some(now + day))
+            .unwrap();
+        let mut expired = crate::test_support::snippet("::ooo", "Out of office last week");
+        expired.set_active_window(None, Some(now - day)).unwrap();
+        let service = crate::test_support::seeded_service([away, expired]).await;
+        let expand = |trigger: &str| ExpansionRequest {
+            trigger: trigger.to_string(),
+            context: None,
+        };
+
+        let response = service.expand_snippet(expand("::away")).await.unwrap();
+        assert!(response.success);
+        assert_eq!(response.expanded_text.unwrap(), "Out of office this week");
+
+        let response = service.expand_snippet(expand("::ooo")).await.unwrap();
+        assert!(!response.success);
+        assert_eq!(
+            response.error_message.unwrap(),
+            "Snippet is outside its active window"
+        );
+        let expanded = service.expand_text("Note: ::ooo").await.unwrap();
+        assert_eq!(expanded, "Note: ::ooo");
+    }
 }
@@ -1,6 +1,14 @@
-use crate::app::dto::{ExportSnippetsRequest, ImportSnippetData};
-use crate::domain::{SnippetQuery, SnippetRepository};
+use crate::app::dto::{
+    ExportEnvelope, ExportSnippetsRequest, ImportSnippetData, ShellAliasExport, SkippedSnippet,
+    CSV_TAG_SEPARATOR, EXPORT_FORMAT_VERSION,
+};
+use crate::domain::{
+    has_dynamic_content, Snippet, SnippetQuery, SnippetRepository, SortBy, SortOrder,
+};
 use anyhow::Result;
+use chrono::Utc;
+use futures::TryStreamExt;
+use std::io::Write;
 use std::sync::Arc;
 
 pub struct ExportSnippetsService {
@@ -13,7 +21,181 @@ impl ExportSnippetsService {
     }
 
     pub async fn execute(&self, request: ExportSnippetsRequest) -> Result<Vec<ImportSnippetData>> {
-        // Build query based on request
+        let query = Self::build_query(request);
+
+        // Get all matching snippets
+        let snippets = self.repository.find_all(&query).await?;
+
+        // Convert to export format
+        let export_data = snippets
+            .into_iter()
+            .map(Self::to_export_data)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(export_data)
+    }
+
+    /// Write matching snippets to `writer` as an `ExportEnvelope`, one
+    /// snippet at a time, so large libraries never have to fit in memory.
+    /// Returns the number of snippets written.
+    pub async fn export_to_writer<W: Write + Send>(
+        &self,
+        request: ExportSnippetsRequest,
+        mut writer: W,
+    ) -> Result<usize> {
+        let query = Self::build_query(request);
+        let mut snippets = self.repository.stream_all(&query);
+
+        // serde can't stream into a struct field, so the envelope is written
+        // by hand with the same layout `to_string_pretty` would produce
+        write!(
+            writer,
+            "{{\n  \"version\": {},\n  \"exported_at\": {},\n  \"snippets\": [",
+            EXPORT_FORMAT_VERSION,
+            Self::to_json(&Utc::now())?
+        )?;
+
+        let mut count = 0;
+        while let Some(snippet) = snippets.try_next().await? {
+            let element = Self::to_json(&Self::to_export_data(snippet)?)?;
+            let separator = if count == 0 { "" } else { "," };
+            write!(
+                writer,
+                "{}\n    {}",
+                separator,
+                element.replace('\n', "\n    ")
+            )?;
+            count += 1;
+        }
+
+        if count > 0 {
+            write!(writer, "\n  ")?;
+        }
+        write!(writer, "]\n}}")?;
+
+        Ok(count)
+    }
+
+    fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+        serde_json::to_string_pretty(value)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize to JSON: {}", e))
+    }
+
+    /// Write matching snippets to `writer` as CSV with a `trigger,replacement,tags`
+    /// header. Fields with commas, quotes or newlines are quoted. Returns the
+    /// number of snippets written.
+    pub async fn export_to_csv<W: Write + Send>(
+        &self,
+        request: ExportSnippetsRequest,
+        writer: W,
+    ) -> Result<usize> {
+        let query = Self::build_query(request);
+        let mut snippets = self.repository.stream_all(&query);
+
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        csv_writer.write_record(["trigger", "replacement", "tags"])?;
+
+        let mut count = 0;
+        while let Some(snippet) = snippets.try_next().await? {
+            snippet.ensure_unlocked()?;
+            let tags = snippet.tags.join(&CSV_TAG_SEPARATOR.to_string());
+            csv_writer.write_record([&snippet.trigger, &snippet.replacement, &tags])?;
+            count += 1;
+        }
+
+        csv_writer.flush()?;
+        Ok(count)
+    }
+
+    /// Write every snippet's trigger and usage count to `writer` as CSV with a
+    /// `trigger,usage_count` header, sorted by trigger. Returns the number of
+    /// snippets written.
+    pub async fn export_usage_csv<W: Write + Send>(&self, writer: W) -> Result<usize> {
+        let query = SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Asc);
+        let mut snippets = self.repository.stream_all(&query);
+
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        csv_writer.write_record(["trigger", "usage_count"])?;
+
+        let mut count = 0;
+        while let Some(snippet) = snippets.try_next().await? {
+            csv_writer.write_record([snippet.trigger, snippet.usage_count.to_string()])?;
+            count += 1;
+        }
+
+        csv_writer.flush()?;
+        Ok(count)
+    }
+
+    /// Write matching snippets to `writer` as shell `alias` definitions. Only
+    /// plain, single-line snippets without placeholders can be expressed as an
+    /// alias; the rest are reported in `skipped`.
+    pub async fn export_shell_aliases<W: Write + Send>(
+        &self,
+        request: ExportSnippetsRequest,
+        mut writer: W,
+    ) -> Result<ShellAliasExport> {
+        let query = Self::build_query(request);
+        let mut snippets = self.repository.stream_all(&query);
+
+        writeln!(writer, "# Shell aliases exported from Typely")?;
+
+        let mut names = std::collections::HashSet::new();
+        let mut report = ShellAliasExport {
+            exported: 0,
+            skipped: Vec::new(),
+        };
+        while let Some(snippet) = snippets.try_next().await? {
+            snippet.ensure_unlocked()?;
+            match Self::to_shell_alias(&snippet) {
+                Ok((name, _)) if !names.insert(name.clone()) => {
+                    report.skipped.push(SkippedSnippet {
+                        trigger: snippet.trigger,
+                        reason: format!("alias '{}' is already defined", name),
+                    });
+                }
+                Ok((_, line)) => {
+                    writeln!(writer, "{}", line)?;
+                    report.exported += 1;
+                }
+                Err(reason) => report.skipped.push(SkippedSnippet {
+                    trigger: snippet.trigger,
+                    reason,
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// The alias name and definition line for `snippet`, or why it can't be one
+    fn to_shell_alias(snippet: &Snippet) -> std::result::Result<(String, String), String> {
+        let name = snippet.trigger.trim_start_matches(':');
+        let valid_name = !name.is_empty()
+            && !name.starts_with('-')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            return Err("trigger is not a valid alias name".to_string());
+        }
+
+        if snippet.content_type.is_rich() {
+            return Err(format!("{} content", snippet.content_type));
+        }
+        if snippet.replacement.contains(['\n', '\r']) {
+            return Err("replacement spans multiple lines".to_string());
+        }
+        if has_dynamic_content(&snippet.replacement) {
+            return Err("replacement uses placeholders".to_string());
+        }
+
+        // Inside single quotes only `'` needs escaping: close, add \', reopen
+        let quoted = snippet.replacement.replace('\'', r"'\''");
+        Ok((name.to_string(), format!("alias {}='{}'", name, quoted)))
+    }
+
+    fn build_query(request: ExportSnippetsRequest) -> SnippetQuery {
         let mut query = SnippetQuery::new();
 
         // Set active filter
@@ -32,33 +214,37 @@ impl ExportSnippetsService {
         query.limit = None;
         query.offset = None;
 
-        // Get all matching snippets
-        let snippets = self.repository.find_all(&query).await?;
-
-        // Convert to export format
-        let export_data: Vec<ImportSnippetData> = snippets
-            .into_iter()
-            .map(|snippet| ImportSnippetData {
-                trigger: snippet.trigger,
-                replacement: snippet.replacement,
-                tags: if snippet.tags.is_empty() {
-                    None
-                } else {
-                    Some(snippet.tags)
-                },
-            })
-            .collect();
+        query
+    }
 
-        Ok(export_data)
+    /// `snippet` as exported, or an error when its replacement is locked
+    fn to_export_data(snippet: Snippet) -> Result<ImportSnippetData> {
+        snippet.ensure_unlocked()?;
+        Ok(ImportSnippetData {
+            trigger: snippet.trigger,
+            replacement: snippet.replacement,
+            tags: if snippet.tags.is_empty() {
+                None
+            } else {
+                Some(snippet.tags)
+            },
+            content_type: if snippet.content_type.is_rich() {
+                Some(snippet.content_type)
+            } else {
+                None
+            },
+            updated_at: Some(snippet.updated_at),
+        })
     }
 
     pub async fn export_to_json(&self, request: ExportSnippetsRequest) -> Result<String> {
-        let export_data = self.execute(request).await?;
-
-        let json = serde_json::to_string_pretty(&export_data)
-            .map_err(|e| anyhow::anyhow!("Failed to serialize to JSON: {}", e))?;
+        let envelope = ExportEnvelope {
+            version: EXPORT_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            snippets: self.execute(request).await?,
+        };
 
-        Ok(json)
+        Self::to_json(&envelope)
     }
 
     pub async fn export_all_to_json(&self) -> Result<String> {
@@ -98,7 +284,9 @@ mod tests {
     use super::*;
     use crate::app::dto::CreateSnippetRequest;
     use crate::app::services::CreateSnippetService;
+    use crate::domain::ContentType;
     use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use crate::test_support::{seeded_service, snippet};
     use tempfile::TempDir;
 
     async fn create_test_use_case() -> (ExportSnippetsService, CreateSnippetService, TempDir) {
@@ -141,9 +329,8 @@ mod tests {
 
         for (trigger, replacement, tags) in snippets_data {
             let request = CreateSnippetRequest {
-                trigger: trigger.to_string(),
-                replacement: replacement.to_string(),
                 tags,
+                ..CreateSnippetRequest::new(trigger, replacement)
             };
             create_use_case.execute(request).await.unwrap();
         }
@@ -170,9 +357,8 @@ mod tests {
 
         // Create a test snippet
         let request = CreateSnippetRequest {
-            trigger: "::json".to_string(),
-            replacement: "JSON test".to_string(),
             tags: Some(vec!["json".to_string(), "test".to_string()]),
+            ..CreateSnippetRequest::new("::json", "JSON test")
         };
         create_use_case.execute(request).await.unwrap();
 
@@ -186,8 +372,10 @@ mod tests {
             .await
             .unwrap();
 
-        // Verify it's valid JSON
-        let parsed: Vec<ImportSnippetData> = serde_json::from_str(&json_result).unwrap();
+        // Verify it's a versioned envelope
+        let envelope: ExportEnvelope = serde_json::from_str(&json_result).unwrap();
+        assert_eq!(envelope.version, EXPORT_FORMAT_VERSION);
+        let parsed = envelope.snippets;
         assert_eq!(parsed.len(), 1);
         assert_eq!(parsed[0].trigger, "::json");
         assert_eq!(parsed[0].replacement, "JSON test");
@@ -203,16 +391,8 @@ mod tests {
         let (export_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create active snippets (all snippets are active by default)
-        let request1 = CreateSnippetRequest {
-            trigger: "::active1".to_string(),
-            replacement: "Active 1".to_string(),
-            tags: None,
-        };
-        let request2 = CreateSnippetRequest {
-            trigger: "::active2".to_string(),
-            replacement: "Active 2".to_string(),
-            tags: None,
-        };
+        let request1 = CreateSnippetRequest::new("::active1", "Active 1");
+        let request2 = CreateSnippetRequest::new("::active2", "Active 2");
 
         create_use_case.execute(request1).await.unwrap();
         create_use_case.execute(request2).await.unwrap();
@@ -220,7 +400,9 @@ mod tests {
         // Export active only
         let json_result = export_use_case.export_active_to_json().await.unwrap();
 
-        let parsed: Vec<ImportSnippetData> = serde_json::from_str(&json_result).unwrap();
+        let parsed = serde_json::from_str::<ExportEnvelope>(&json_result)
+            .unwrap()
+            .snippets;
         assert_eq!(parsed.len(), 2);
     }
 
@@ -229,17 +411,198 @@ mod tests {
         let (export_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create a test snippet
-        let request = CreateSnippetRequest {
-            trigger: "::all".to_string(),
-            replacement: "All test".to_string(),
-            tags: None,
-        };
+        let request = CreateSnippetRequest::new("::all", "All test");
         create_use_case.execute(request).await.unwrap();
 
         let json_result = export_use_case.export_all_to_json().await.unwrap();
 
-        let parsed: Vec<ImportSnippetData> = serde_json::from_str(&json_result).unwrap();
+        let parsed = serde_json::from_str::<ExportEnvelope>(&json_result)
+            .unwrap()
+            .snippets;
         assert_eq!(parsed.len(), 1);
         assert_eq!(parsed[0].trigger, "::all");
     }
+
+    #[tokio::test]
+    async fn test_streaming_export_round_trip() {
+        let (export_use_case, create_use_case, temp_dir) = create_test_use_case().await;
+
+        for i in 0..5_000 {
+            let request = CreateSnippetRequest {
+                tags: if i % 2 == 0 {
+                    Some(vec!["even".to_string()])
+                } else {
+                    None
+                },
+                ..CreateSnippetRequest::new(
+                    format!("::bulk{}", i),
+                    format!("Bulk snippet {} \"quoted\" ✓", i),
+                )
+            };
+            create_use_case.execute(request).await.unwrap();
+        }
+
+        let export_path = temp_dir.path().join("export.json");
+        let file = std::io::BufWriter::new(std::fs::File::create(&export_path).unwrap());
+        let request = ExportSnippetsRequest {
+            include_inactive: true,
+            tags_filter: None,
+        };
+        let written = export_use_case
+            .export_to_writer(request.clone(), file)
+            .await
+            .unwrap();
+        assert_eq!(written, 5_000);
+
+        // Re-import into a fresh database
+        let import_dir = TempDir::new().unwrap();
+        let db_connection = DatabaseConnection::new(&import_dir.path().join("import.db"))
+            .await
+            .unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+        let import_use_case = crate::app::services::ImportSnippetsService::new(repository.clone());
+
+        let json_data = std::fs::read_to_string(&export_path).unwrap();
+        let result = import_use_case
+            .import_from_json(&json_data, false)
+            .await
+            .unwrap();
+        assert_eq!(result.imported_count, 5_000);
+        assert_eq!(result.error_count, 0);
+
+        let by_trigger = |data: Vec<ImportSnippetData>| {
+            data.into_iter()
+                .map(|d| (d.trigger, (d.replacement, d.tags, d.content_type)))
+                .collect::<std::collections::HashMap<_, _>>()
+        };
+        let original = by_trigger(export_use_case.execute(request.clone()).await.unwrap());
+        let reimported = by_trigger(
+            ExportSnippetsService::new(repository)
+                .execute(request)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(original.len(), 5_000);
+        assert_eq!(original, reimported);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_export_matches_json_export() {
+        let (export_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        for (trigger, replacement) in [("::one", "One"), ("::two", "Two\nlines")] {
+            let request = CreateSnippetRequest {
+                tags: Some(vec!["a".to_string()]),
+                ..CreateSnippetRequest::new(trigger, replacement)
+            };
+            create_use_case.execute(request).await.unwrap();
+        }
+
+        let request = ExportSnippetsRequest {
+            include_inactive: true,
+            tags_filter: None,
+        };
+        let mut buffer = Vec::new();
+        export_use_case
+            .export_to_writer(request.clone(), &mut buffer)
+            .await
+            .unwrap();
+
+        let json = export_use_case.export_to_json(request).await.unwrap();
+
+        // Identical apart from the timestamp, which differs between the calls
+        let without_timestamp = |json: &str| {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            let exported_at = value["exported_at"].as_str().unwrap();
+            json.replace(exported_at, "")
+        };
+        let streamed = String::from_utf8(buffer).unwrap();
+        assert_eq!(without_timestamp(&streamed), without_timestamp(&json));
+    }
+
+    #[tokio::test]
+    async fn test_csv_round_trip_keeps_commas_quotes_and_newlines() {
+        let mut tricky = snippet("::addr", "1 Main St, Apt \"B\"\nSpringfield");
+        tricky.tags = vec!["home".to_string(), "mail".to_string()];
+        let service = seeded_service(vec![tricky, snippet("::plain", "Plain")]).await;
+
+        let request = ExportSnippetsRequest {
+            include_inactive: true,
+            tags_filter: None,
+        };
+        let mut csv_data = Vec::new();
+        let written = service
+            .export_to_csv(request.clone(), &mut csv_data)
+            .await
+            .unwrap();
+        assert_eq!(written, 2);
+
+        let csv_data = String::from_utf8(csv_data).unwrap();
+        assert!(csv_data.starts_with("trigger,replacement,tags\n"));
+        assert!(csv_data.contains("\"1 Main St, Apt \"\"B\"\"\nSpringfield\",home;mail"));
+
+        let reimported = seeded_service(Vec::new()).await;
+        let result = reimported
+            .import_from_csv_with_strategy(&csv_data, crate::app::dto::ConflictStrategy::Skip, None)
+            .await
+            .unwrap();
+        assert_eq!(result.imported_count, 2);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let addr = reimported
+            .get_snippet_by_trigger("::addr")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(addr.replacement, "1 Main St, Apt \"B\"\nSpringfield");
+        assert_eq!(addr.tags, vec!["home", "mail"]);
+    }
+
+    #[tokio::test]
+    async fn test_export_shell_aliases() {
+        let mut markdown = snippet("::bold", "**bold**");
+        markdown.set_content_type(ContentType::Markdown);
+        let service = seeded_service(vec![
+            snippet("::hello", "Hello, World!"),
+            snippet("::quote", "it's fine"),
+            snippet("::sig", "Best regards,\nJane"),
+            snippet("::today", "Today is {date}"),
+            snippet("::a:b", "colon"),
+            markdown,
+        ])
+        .await;
+
+        let mut output = Vec::new();
+        let request = ExportSnippetsRequest {
+            include_inactive: true,
+            tags_filter: None,
+        };
+        let report = service
+            .export_snippets_service
+            .export_shell_aliases(request, &mut output)
+            .await
+            .unwrap();
+        let script = String::from_utf8(output).unwrap();
+
+        assert_eq!(report.exported, 2);
+        assert!(script.contains("alias hello='Hello, World!'\n"));
+        assert!(script.contains(r"alias quote='it'\''s fine'"));
+        assert!(!script.contains("sig") && !script.contains("today"));
+
+        let mut skipped: Vec<(&str, &str)> = report
+            .skipped
+            .iter()
+            .map(|s| (s.trigger.as_str(), s.reason.as_str()))
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            vec![
+                ("::a:b", "trigger is not a valid alias name"),
+                ("::bold", "markdown content"),
+                ("::sig", "replacement spans multiple lines"),
+                ("::today", "replacement uses placeholders"),
+            ]
+        );
+    }
 }
@@ -1,6 +1,8 @@
-use crate::app::dto::SnippetDto;
+use crate::app::dto::{SnippetDto, SnippetUsageDto, SnippetVersionDto};
+use crate::app::services::ExpandSnippetService;
 use crate::domain::SnippetRepository;
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -22,83 +24,178 @@ impl GetSnippetService {
         let snippet = self.repository.find_by_trigger(trigger).await?;
         Ok(snippet.map(SnippetDto::from))
     }
+
+    /// The snippet and what it expands to now, as rendered by `expander`,
+    /// without counting a use
+    pub async fn execute_with_preview(
+        &self,
+        id: Uuid,
+        expander: &ExpandSnippetService,
+    ) -> Result<Option<(SnippetDto, String)>> {
+        let Some(snippet) = self.repository.find_by_id(&id).await? else {
+            return Ok(None);
+        };
+        let preview = expander.preview(&snippet).await?;
+        Ok(Some((SnippetDto::from(snippet), preview)))
+    }
+
+    /// Past versions of a snippet, newest first
+    pub async fn history(&self, id: Uuid) -> Result<Vec<SnippetVersionDto>> {
+        let versions = self.repository.history(&id).await?;
+        Ok(versions.into_iter().map(SnippetVersionDto::from).collect())
+    }
+
+    /// Past versions of every snippet recorded at or after `since`, newest
+    /// first, see `SnippetRepository::edits_since`
+    pub async fn edits_since(&self, since: DateTime<Utc>) -> Result<Vec<SnippetVersionDto>> {
+        let versions = self.repository.edits_since(since).await?;
+        Ok(versions.into_iter().map(SnippetVersionDto::from).collect())
+    }
+
+    /// Usage of a snippet, with "today" the 24 hours from `day_start`.
+    /// `None` when there's no such snippet.
+    pub async fn usage(
+        &self,
+        id: Uuid,
+        day_start: DateTime<Utc>,
+    ) -> Result<Option<SnippetUsageDto>> {
+        let Some(snippet) = self.repository.find_by_id(&id).await? else {
+            return Ok(None);
+        };
+        let used_today = self
+            .repository
+            .usage_count_between(&id, day_start, day_start + Duration::days(1))
+            .await?;
+
+        Ok(Some(SnippetUsageDto {
+            snippet_id: id,
+            usage_count: snippet.usage_count,
+            used_today,
+            first_used: self.repository.first_used(&id).await?,
+        }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::app::dto::CreateSnippetRequest;
-    use crate::app::services::CreateSnippetService;
-    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
-    use tempfile::TempDir;
-
-    async fn create_test_use_case() -> (GetSnippetService, CreateSnippetService, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
-        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
-        let get_use_case = GetSnippetService::new(repository.clone());
-        let create_use_case = CreateSnippetService::new(repository);
-        (get_use_case, create_use_case, temp_dir)
-    }
+    use crate::app::dto::ExpansionRequest;
+    use crate::test_support::{seeded_service, snippet};
 
     #[tokio::test]
     async fn test_get_snippet_by_id() {
-        let (get_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
-
-        // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Test replacement".to_string(),
-            tags: Some(vec!["test".to_string()]),
-        };
-        let created = create_use_case.execute(create_request).await.unwrap();
+        let mut seeded = snippet("::test", "Test replacement");
+        seeded.add_tag("test".to_string());
+        let id = seeded.id;
+        let service = seeded_service(vec![seeded]).await;
 
         // Get the snippet by ID
-        let found = get_use_case.execute(created.id).await.unwrap();
+        let found = service.get_snippet_service.execute(id).await.unwrap();
 
         assert!(found.is_some());
         let snippet = found.unwrap();
-        assert_eq!(snippet.id, created.id);
+        assert_eq!(snippet.id, id);
         assert_eq!(snippet.trigger, "::test");
         assert_eq!(snippet.replacement, "Test replacement");
+        assert_eq!(snippet.tags, vec!["test".to_string()]);
     }
 
     #[tokio::test]
     async fn test_get_snippet_by_trigger() {
-        let (get_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
-
-        // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Hello, World!".to_string(),
-            tags: None,
-        };
-        let created = create_use_case.execute(create_request).await.unwrap();
+        let seeded = snippet("::hello", "Hello, World!");
+        let id = seeded.id;
+        let service = seeded_service(vec![seeded]).await;
 
         // Get the snippet by trigger
-        let found = get_use_case.execute_by_trigger("::hello").await.unwrap();
+        let found = service
+            .get_snippet_service
+            .execute_by_trigger("::hello")
+            .await
+            .unwrap();
 
         assert!(found.is_some());
         let snippet = found.unwrap();
-        assert_eq!(snippet.id, created.id);
+        assert_eq!(snippet.id, id);
         assert_eq!(snippet.trigger, "::hello");
         assert_eq!(snippet.replacement, "Hello, World!");
     }
 
     #[tokio::test]
     async fn test_get_nonexistent_snippet() {
-        let (get_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+        let service = seeded_service(Vec::new()).await;
 
         // Try to get a nonexistent snippet by ID
-        let found = get_use_case.execute(Uuid::new_v4()).await.unwrap();
+        let found = service
+            .get_snippet_service
+            .execute(Uuid::new_v4())
+            .await
+            .unwrap();
         assert!(found.is_none());
 
         // Try to get a nonexistent snippet by trigger
-        let found = get_use_case
+        let found = service
+            .get_snippet_service
             .execute_by_trigger("::nonexistent")
             .await
             .unwrap();
         assert!(found.is_none());
     }
+
+    #[tokio::test]
+    async fn test_preview_renders_placeholders_without_counting_a_use() {
+        let seeded = snippet("::today", "Today is {date}");
+        let id = seeded.id;
+        let service = seeded_service(vec![seeded]).await;
+
+        let (found, preview) = service.get_snippet_preview(id).await.unwrap().unwrap();
+        assert_eq!(found.replacement, "Today is {date}");
+        assert_ne!(preview, found.replacement);
+        assert!(preview.starts_with("Today is "));
+        assert!(!preview.contains("{date}"));
+
+        let (found, _) = service.get_snippet_preview(id).await.unwrap().unwrap();
+        assert_eq!(found.usage_count, 0);
+        assert!(service
+            .get_snippet_preview(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_usage_counts_expansions_since_midnight() {
+        let seeded = snippet("::hello", "Hello!");
+        let id = seeded.id;
+        let service = seeded_service(vec![seeded]).await;
+
+        let usage = service.snippet_usage(id).await.unwrap().unwrap();
+        assert_eq!((usage.usage_count, usage.used_today), (0, 0));
+        assert_eq!(usage.first_used, None);
+
+        for _ in 0..2 {
+            let request = ExpansionRequest {
+                trigger: "::hello".to_string(),
+                context: None,
+            };
+            assert!(service.expand_snippet(request).await.unwrap().success);
+        }
+        let usage = service.snippet_usage(id).await.unwrap().unwrap();
+        assert_eq!((usage.usage_count, usage.used_today), (2, 2));
+        assert!(usage.first_used.is_some());
+
+        // A day that ended before them has none
+        let earlier = Utc::now() - Duration::days(2);
+        let usage = service
+            .get_snippet_service
+            .usage(id, earlier)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(usage.used_today, 0);
+        assert!(service
+            .snippet_usage(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
 }
@@ -1,8 +1,16 @@
-use crate::app::dto::{ImportResult, ImportSnippetsRequest};
+use crate::app::dto::{
+    ConflictStrategy, ExportEnvelope, ImportItemResult, ImportOutcome, ImportResult,
+    ImportSnippetData, ImportSnippetsRequest, CSV_TAG_SEPARATOR, EXPORT_FORMAT_VERSION,
+};
 use crate::domain::{Snippet, SnippetRepository};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+const MAX_RENAME_ATTEMPTS: u32 = 100;
+
+const TRIGGER_TAKEN: &str = "A snippet with this trigger already exists";
+
 pub struct ImportSnippetsService {
     repository: Arc<dyn SnippetRepository>,
 }
@@ -17,22 +25,53 @@ impl ImportSnippetsService {
         let mut skipped_count = 0;
         let mut error_count = 0;
         let mut errors = Vec::new();
+        let mut items = Vec::new();
 
         for snippet_data in request.snippets {
-            match self
-                .import_single_snippet(&snippet_data, request.overwrite_existing)
+            let (outcome, message) = match self
+                .import_single_snippet(
+                    &snippet_data,
+                    request.conflict_strategy,
+                    request.source.as_deref(),
+                )
                 .await
             {
-                Ok(ImportStatus::Imported) => imported_count += 1,
-                Ok(ImportStatus::Skipped) => skipped_count += 1,
+                Ok(ImportStatus::Imported) => {
+                    imported_count += 1;
+                    (ImportOutcome::Imported, None)
+                }
+                Ok(ImportStatus::Renamed(trigger)) => {
+                    imported_count += 1;
+                    (
+                        ImportOutcome::Imported,
+                        Some(format!("Renamed to '{}'", trigger)),
+                    )
+                }
+                Ok(ImportStatus::DeactivatedOld(trigger)) => {
+                    imported_count += 1;
+                    (
+                        ImportOutcome::Imported,
+                        Some(format!("Previous snippet deactivated as '{}'", trigger)),
+                    )
+                }
+                Ok(ImportStatus::Skipped(reason)) => {
+                    skipped_count += 1;
+                    (ImportOutcome::Skipped, Some(reason.to_string()))
+                }
                 Err(e) => {
                     error_count += 1;
                     errors.push(format!(
                         "Failed to import '{}': {}",
                         snippet_data.trigger, e
                     ));
+                    (ImportOutcome::Error, Some(e.to_string()))
                 }
-            }
+            };
+            items.push(ImportItemResult {
+                trigger: Some(snippet_data.trigger),
+                outcome,
+                message,
+            });
         }
 
         Ok(ImportResult {
@@ -40,13 +79,27 @@ impl ImportSnippetsService {
             skipped_count,
             error_count,
             errors,
+            items,
         })
     }
 
+    /// Put the records that couldn't be read in front of those that were
+    fn prepend_read_errors(result: &mut ImportResult, read_errors: Vec<String>) {
+        result.error_count += read_errors.len() as u32;
+        let items = read_errors.iter().map(|error| ImportItemResult {
+            trigger: None,
+            outcome: ImportOutcome::Error,
+            message: Some(error.clone()),
+        });
+        result.items.splice(0..0, items);
+        result.errors.splice(0..0, read_errors);
+    }
+
     async fn import_single_snippet(
         &self,
-        snippet_data: &crate::app::dto::ImportSnippetData,
-        overwrite_existing: bool,
+        snippet_data: &ImportSnippetData,
+        strategy: ConflictStrategy,
+        source: Option<&str>,
     ) -> Result<ImportStatus> {
         // Check if snippet with trigger already exists
         let exists = self
@@ -54,15 +107,106 @@ impl ImportSnippetsService {
             .exists_with_trigger(&snippet_data.trigger)
             .await?;
 
-        if exists && !overwrite_existing {
-            return Ok(ImportStatus::Skipped);
+        if !exists {
+            self.save_new(snippet_data, snippet_data.trigger.clone(), source)
+                .await?;
+            return Ok(ImportStatus::Imported);
+        }
+
+        match strategy {
+            ConflictStrategy::Skip => Ok(ImportStatus::Skipped(TRIGGER_TAKEN)),
+            ConflictStrategy::Overwrite => {
+                if let Some(existing) = self
+                    .repository
+                    .find_by_trigger(&snippet_data.trigger)
+                    .await?
+                {
+                    self.overwrite(existing, snippet_data, source).await?;
+                }
+                Ok(ImportStatus::Imported)
+            }
+            ConflictStrategy::Rename => {
+                let trigger = self.find_free_trigger(&snippet_data.trigger).await?;
+                self.save_new(snippet_data, trigger.clone(), source).await?;
+                Ok(ImportStatus::Renamed(trigger))
+            }
+            ConflictStrategy::KeepNewer => {
+                let Some(existing) = self
+                    .repository
+                    .find_by_trigger(&snippet_data.trigger)
+                    .await?
+                else {
+                    return Ok(ImportStatus::Skipped(TRIGGER_TAKEN));
+                };
+
+                // Without a timestamp we can't tell which is newer, so keep ours
+                match snippet_data.updated_at {
+                    Some(incoming) if incoming > existing.updated_at => {
+                        self.overwrite(existing, snippet_data, source).await?;
+                        Ok(ImportStatus::Imported)
+                    }
+                    _ => Ok(ImportStatus::Skipped(
+                        "The existing snippet is as new or newer",
+                    )),
+                }
+            }
+            ConflictStrategy::DeactivateOld => {
+                let Some(mut existing) = self
+                    .repository
+                    .find_by_trigger(&snippet_data.trigger)
+                    .await?
+                else {
+                    return Ok(ImportStatus::Skipped(TRIGGER_TAKEN));
+                };
+
+                // Build the incoming snippet before touching the old one, so
+                // an invalid record leaves it as it was
+                let incoming = Self::build_new(snippet_data, snippet_data.trigger.clone(), source)?;
+                let original = existing.clone();
+
+                // Free the trigger for the incoming snippet, whether the old
+                // one has it as its trigger or as an alias
+                if existing.trigger == snippet_data.trigger {
+                    let retired = self.find_free_trigger(&existing.trigger).await?;
+                    existing.update_trigger(retired)?;
+                } else {
+                    let aliases = existing
+                        .aliases
+                        .iter()
+                        .filter(|alias| **alias != snippet_data.trigger)
+                        .cloned()
+                        .collect();
+                    existing.set_aliases(aliases)?;
+                }
+                existing.deactivate();
+                self.repository.update(&existing).await?;
+
+                if let Err(error) = self.repository.save(&incoming).await {
+                    self.repository.update(&original).await?;
+                    return Err(error);
+                }
+                Ok(ImportStatus::DeactivatedOld(existing.trigger))
+            }
         }
+    }
 
-        // Create new snippet
-        let mut snippet = Snippet::new(
-            snippet_data.trigger.clone(),
-            snippet_data.replacement.clone(),
-        )?;
+    async fn save_new(
+        &self,
+        snippet_data: &ImportSnippetData,
+        trigger: String,
+        source: Option<&str>,
+    ) -> Result<()> {
+        let snippet = Self::build_new(snippet_data, trigger, source)?;
+        self.repository.save(&snippet).await
+    }
+
+    fn build_new(
+        snippet_data: &ImportSnippetData,
+        trigger: String,
+        source: Option<&str>,
+    ) -> Result<Snippet> {
+        let mut snippet = Snippet::new(trigger, snippet_data.replacement.clone())?;
+        snippet.set_source(source.map(str::to_string));
 
         // Add tags if provided
         if let Some(ref tags) = snippet_data.tags {
@@ -71,32 +215,54 @@ impl ImportSnippetsService {
             }
         }
 
-        if exists && overwrite_existing {
-            // Find existing snippet and update it
-            if let Some(existing) = self
-                .repository
-                .find_by_trigger(&snippet_data.trigger)
-                .await?
-            {
-                let mut updated_snippet = existing;
-                updated_snippet.update_replacement(snippet_data.replacement.clone())?;
-
-                // Update tags if provided
-                if let Some(ref tags) = snippet_data.tags {
-                    updated_snippet.tags.clear();
-                    for tag in tags {
-                        updated_snippet.add_tag(tag.clone());
-                    }
-                }
+        if let Some(content_type) = snippet_data.content_type {
+            snippet.set_content_type(content_type);
+        }
+
+        Ok(snippet)
+    }
+
+    async fn overwrite(
+        &self,
+        existing: Snippet,
+        snippet_data: &ImportSnippetData,
+        source: Option<&str>,
+    ) -> Result<()> {
+        let mut updated_snippet = existing;
+        updated_snippet.update_replacement(snippet_data.replacement.clone())?;
+        if source.is_some() {
+            updated_snippet.set_source(source.map(str::to_string));
+        }
+
+        // Update tags if provided
+        if let Some(ref tags) = snippet_data.tags {
+            updated_snippet.tags.clear();
+            for tag in tags {
+                updated_snippet.add_tag(tag.clone());
+            }
+        }
+
+        if let Some(content_type) = snippet_data.content_type {
+            updated_snippet.set_content_type(content_type);
+        }
 
-                self.repository.update(&updated_snippet).await?;
+        self.repository.update(&updated_snippet).await
+    }
+
+    /// Find the first `<trigger>_N` (N >= 2) that isn't taken yet
+    async fn find_free_trigger(&self, trigger: &str) -> Result<String> {
+        for suffix in 2..=MAX_RENAME_ATTEMPTS + 1 {
+            let candidate = format!("{}_{}", trigger, suffix);
+            if !self.repository.exists_with_trigger(&candidate).await? {
+                return Ok(candidate);
             }
-        } else {
-            // Save new snippet
-            self.repository.save(&snippet).await?;
         }
 
-        Ok(ImportStatus::Imported)
+        Err(anyhow::anyhow!(
+            "No free trigger found for '{}' after {} attempts",
+            trigger,
+            MAX_RENAME_ATTEMPTS
+        ))
     }
 
     pub async fn import_from_json(
@@ -104,29 +270,300 @@ impl ImportSnippetsService {
         json_data: &str,
         overwrite_existing: bool,
     ) -> Result<ImportResult> {
-        let import_data: Vec<crate::app::dto::ImportSnippetData> = serde_json::from_str(json_data)
-            .map_err(|e| anyhow::anyhow!("Failed to parse JSON: {}", e))?;
+        self.import_from_json_with_strategy(json_data, overwrite_existing.into(), None)
+            .await
+    }
 
+    /// Import an export file, recording `source` (e.g. the pack name) on
+    /// every snippet it adds or overwrites
+    pub async fn import_from_json_with_strategy(
+        &self,
+        json_data: &str,
+        conflict_strategy: ConflictStrategy,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
         let request = ImportSnippetsRequest {
-            snippets: import_data,
-            overwrite_existing,
+            snippets: Self::parse_export(json_data)?,
+            conflict_strategy,
+            source,
         };
 
         self.execute(request).await
     }
+
+    /// Like `import_from_json_with_strategy`, but a record that can't be read
+    /// is reported in `errors` by its position and the others still import.
+    /// Only a file that isn't JSON at all fails the whole import.
+    pub async fn import_from_json_lenient(
+        &self,
+        json_data: &str,
+        conflict_strategy: ConflictStrategy,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        let (snippets, record_errors) = Self::parse_export_lenient(json_data)?;
+
+        let mut result = self
+            .execute(ImportSnippetsRequest {
+                snippets,
+                conflict_strategy,
+                source,
+            })
+            .await?;
+
+        Self::prepend_read_errors(&mut result, record_errors);
+        Ok(result)
+    }
+
+    /// Import a CSV file with `trigger` and `replacement` columns and an
+    /// optional `tags` column (tags separated by `;`), in any order. Rows that
+    /// can't be read are reported in `errors` by row number and the rest are
+    /// still imported. `source` is recorded as for JSON imports.
+    pub async fn import_from_csv_with_strategy(
+        &self,
+        csv_data: &str,
+        conflict_strategy: ConflictStrategy,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        let (snippets, row_errors) = Self::parse_csv(csv_data)?;
+
+        let mut result = self
+            .execute(ImportSnippetsRequest {
+                snippets,
+                conflict_strategy,
+                source,
+            })
+            .await?;
+
+        Self::prepend_read_errors(&mut result, row_errors);
+        Ok(result)
+    }
+
+    /// Import a directory holding one snippet per `*.txt` file: the file name
+    /// without `.txt` is the trigger, with `prefix` (e.g. `::`) put in front
+    /// unless it starts with it already, and the contents are the replacement,
+    /// less one trailing newline. Files that can't be read are reported in
+    /// `errors` by name and the rest are still imported; other files and
+    /// subdirectories are left alone.
+    pub async fn import_from_directory(
+        &self,
+        dir: &Path,
+        conflict_strategy: ConflictStrategy,
+        prefix: Option<&str>,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        let (snippets, file_errors) = Self::read_directory(dir, prefix)?;
+
+        let mut result = self
+            .execute(ImportSnippetsRequest {
+                snippets,
+                conflict_strategy,
+                source,
+            })
+            .await?;
+
+        Self::prepend_read_errors(&mut result, file_errors);
+        Ok(result)
+    }
+
+    /// The directory's text files as snippets, in file name order, plus a
+    /// message for every file that couldn't be read
+    fn read_directory(
+        dir: &Path,
+        prefix: Option<&str>,
+    ) -> Result<(Vec<ImportSnippetData>, Vec<String>)> {
+        let mut paths = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read directory {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<PathBuf>>>()?;
+        paths.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"));
+        paths.sort();
+
+        let mut snippets = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                errors.push(format!("{}: file name isn't valid UTF-8", name));
+                continue;
+            };
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    errors.push(format!("{}: {}", name, e));
+                    continue;
+                }
+            };
+
+            let trigger = match prefix {
+                Some(prefix) if !stem.starts_with(prefix) => format!("{}{}", prefix, stem),
+                _ => stem.to_string(),
+            };
+            let replacement = contents
+                .strip_suffix('\n')
+                .map(|text| text.strip_suffix('\r').unwrap_or(text))
+                .unwrap_or(&contents);
+            snippets.push(ImportSnippetData {
+                trigger,
+                replacement: replacement.to_string(),
+                tags: None,
+                content_type: None,
+                updated_at: None,
+            });
+        }
+
+        Ok((snippets, errors))
+    }
+
+    /// Rows as snippets, plus a message for every row that couldn't be read
+    fn parse_csv(csv_data: &str) -> Result<(Vec<ImportSnippetData>, Vec<String>)> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(csv_data.as_bytes());
+
+        let headers = reader
+            .headers()
+            .map_err(|e| anyhow::anyhow!("Failed to parse CSV header: {}", e))?
+            .clone();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.trim().eq_ignore_ascii_case(name))
+        };
+        let (Some(trigger_column), Some(replacement_column)) =
+            (column("trigger"), column("replacement"))
+        else {
+            return Err(anyhow::anyhow!(
+                "CSV header must name 'trigger' and 'replacement' columns"
+            ));
+        };
+        let tags_column = column("tags");
+
+        let mut snippets = Vec::new();
+        let mut errors = Vec::new();
+        for (index, record) in reader.records().enumerate() {
+            // Row 1 is the header
+            let row = index + 2;
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    errors.push(format!("Row {}: {}", row, e));
+                    continue;
+                }
+            };
+
+            let (Some(trigger), Some(replacement)) =
+                (record.get(trigger_column), record.get(replacement_column))
+            else {
+                errors.push(format!(
+                    "Row {}: expected {} fields, found {}",
+                    row,
+                    headers.len(),
+                    record.len()
+                ));
+                continue;
+            };
+
+            let tags: Vec<String> = tags_column
+                .and_then(|column| record.get(column))
+                .unwrap_or_default()
+                .split(CSV_TAG_SEPARATOR)
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+
+            snippets.push(ImportSnippetData {
+                trigger: trigger.to_string(),
+                replacement: replacement.to_string(),
+                tags: (!tags.is_empty()).then_some(tags),
+                content_type: None,
+                updated_at: None,
+            });
+        }
+
+        Ok((snippets, errors))
+    }
+
+    /// Snippets from an export file, either an `ExportEnvelope` or the bare
+    /// array older versions wrote
+    pub(crate) fn parse_export(json_data: &str) -> Result<Vec<ImportSnippetData>> {
+        let parse_error = |e: serde_json::Error| anyhow::anyhow!("Failed to parse JSON: {}", e);
+
+        if json_data.trim_start().starts_with('[') {
+            return serde_json::from_str(json_data).map_err(parse_error);
+        }
+
+        let envelope: ExportEnvelope = serde_json::from_str(json_data).map_err(parse_error)?;
+        Self::check_version(envelope.version.into())?;
+        Ok(envelope.snippets)
+    }
+
+    /// Like `parse_export`, but each record is read on its own: records that
+    /// can't be read are left out and described by their 1-based position
+    fn parse_export_lenient(json_data: &str) -> Result<(Vec<ImportSnippetData>, Vec<String>)> {
+        let document: serde_json::Value = serde_json::from_str(json_data)
+            .map_err(|e| anyhow::anyhow!("Failed to parse JSON: {}", e))?;
+
+        let records = match document {
+            serde_json::Value::Array(records) => records,
+            serde_json::Value::Object(mut envelope) => {
+                let version = envelope
+                    .get("version")
+                    .and_then(serde_json::Value::as_u64)
+                    .ok_or_else(|| anyhow::anyhow!("Export file has no version"))?;
+                Self::check_version(version)?;
+
+                match envelope.remove("snippets") {
+                    Some(serde_json::Value::Array(records)) => records,
+                    _ => return Err(anyhow::anyhow!("Export file has no snippets array")),
+                }
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Failed to parse JSON: expected an array of snippets or an export file"
+                ))
+            }
+        };
+
+        let mut snippets = Vec::new();
+        let mut errors = Vec::new();
+        for (index, record) in records.into_iter().enumerate() {
+            match serde_json::from_value::<ImportSnippetData>(record) {
+                Ok(snippet) => snippets.push(snippet),
+                Err(e) => errors.push(format!("Record {}: {}", index + 1, e)),
+            }
+        }
+
+        Ok((snippets, errors))
+    }
+
+    fn check_version(version: u64) -> Result<()> {
+        if version > EXPORT_FORMAT_VERSION.into() {
+            return Err(anyhow::anyhow!(
+                "Export file version {} is newer than this version of Typely supports ({})",
+                version,
+                EXPORT_FORMAT_VERSION
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
 enum ImportStatus {
     Imported,
-    Skipped,
+    /// Imported under this trigger, as the one in the file was taken
+    Renamed(String),
+    /// Imported, and the snippet that had the trigger deactivated under this one
+    DeactivatedOld(String),
+    /// Left out, for this reason
+    Skipped(&'static str),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::app::dto::CreateSnippetRequest;
-    use crate::app::dto::ImportSnippetData;
     use crate::app::services::CreateSnippetService;
     use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
     use tempfile::TempDir;
@@ -150,17 +587,22 @@ mod tests {
                 trigger: "::hello".to_string(),
                 replacement: "Hello, World!".to_string(),
                 tags: Some(vec!["greeting".to_string()]),
+                content_type: None,
+                updated_at: None,
             },
             ImportSnippetData {
                 trigger: "::test".to_string(),
                 replacement: "This is a test".to_string(),
                 tags: None,
+                content_type: None,
+                updated_at: None,
             },
         ];
 
         let request = ImportSnippetsRequest {
             snippets,
-            overwrite_existing: false,
+            conflict_strategy: ConflictStrategy::Skip,
+            source: None,
         };
 
         let result = import_use_case.execute(request).await.unwrap();
@@ -176,11 +618,7 @@ mod tests {
         let (import_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create an existing snippet
-        let existing_request = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Original Hello".to_string(),
-            tags: None,
-        };
+        let existing_request = CreateSnippetRequest::new("::hello", "Original Hello");
         create_use_case.execute(existing_request).await.unwrap();
 
         // Try to import snippets including the existing one
@@ -189,17 +627,22 @@ mod tests {
                 trigger: "::hello".to_string(),
                 replacement: "New Hello".to_string(),
                 tags: None,
+                content_type: None,
+                updated_at: None,
             },
             ImportSnippetData {
                 trigger: "::new".to_string(),
                 replacement: "New snippet".to_string(),
                 tags: None,
+                content_type: None,
+                updated_at: None,
             },
         ];
 
         let request = ImportSnippetsRequest {
             snippets,
-            overwrite_existing: false,
+            conflict_strategy: ConflictStrategy::Skip,
+            source: None,
         };
 
         let result = import_use_case.execute(request).await.unwrap();
@@ -214,11 +657,7 @@ mod tests {
         let (import_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create an existing snippet
-        let existing_request = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Original Hello".to_string(),
-            tags: None,
-        };
+        let existing_request = CreateSnippetRequest::new("::hello", "Original Hello");
         create_use_case.execute(existing_request).await.unwrap();
 
         // Import with overwrite enabled
@@ -226,11 +665,14 @@ mod tests {
             trigger: "::hello".to_string(),
             replacement: "Updated Hello".to_string(),
             tags: Some(vec!["updated".to_string()]),
+            content_type: None,
+            updated_at: None,
         }];
 
         let request = ImportSnippetsRequest {
             snippets,
-            overwrite_existing: true,
+            conflict_strategy: ConflictStrategy::Overwrite,
+            source: None,
         };
 
         let result = import_use_case.execute(request).await.unwrap();
@@ -268,6 +710,141 @@ mod tests {
         assert_eq!(result.error_count, 0);
     }
 
+    #[tokio::test]
+    async fn test_import_versioned_envelope() {
+        let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let json_data = r#"
+        {
+            "version": 1,
+            "exported_at": "2024-01-01T00:00:00Z",
+            "snippets": [
+                { "trigger": "::env1", "replacement": "Envelope 1", "tags": ["a"] },
+                { "trigger": "::env2", "replacement": "Envelope 2", "tags": null }
+            ]
+        }
+        "#;
+
+        let result = import_use_case
+            .import_from_json(json_data, false)
+            .await
+            .unwrap();
+
+        assert_eq!(result.imported_count, 2);
+        assert_eq!(result.error_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_newer_format_version() {
+        let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let json_data =
+            r#"{ "version": 99, "exported_at": "2024-01-01T00:00:00Z", "snippets": [] }"#;
+        let error = import_use_case
+            .import_from_json(json_data, false)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("version 99"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_csv_rows_that_cannot_be_read_are_reported() {
+        let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let csv_data = "replacement,trigger,tags\n\
+            Hello,::hi,greeting; casual\n\
+            only one field\n\
+            Bye,::bye,\n";
+        let result = import_use_case
+            .import_from_csv_with_strategy(csv_data, ConflictStrategy::Skip, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.imported_count, 2);
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.errors, vec!["Row 3: expected 3 fields, found 1"]);
+
+        let hi = import_use_case
+            .repository
+            .find_by_trigger("::hi")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hi.replacement, "Hello");
+        assert_eq!(hi.tags, vec!["greeting", "casual"]);
+    }
+
+    #[tokio::test]
+    async fn test_csv_without_required_columns_is_rejected() {
+        let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let error = import_use_case
+            .import_from_csv_with_strategy("name,text\n::hi,Hello\n", ConflictStrategy::Skip, None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("'trigger'"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_lenient_import_skips_records_that_cannot_be_read() {
+        let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let json_data = r#"[
+            { "trigger": "::good", "replacement": "Good", "tags": null },
+            { "trigger": "::bad", "tags": "not a list" },
+            { "trigger": "::also", "replacement": "Also good" }
+        ]"#;
+
+        // The strict import refuses the whole file
+        assert!(import_use_case
+            .import_from_json(json_data, false)
+            .await
+            .is_err());
+
+        let result = import_use_case
+            .import_from_json_lenient(json_data, ConflictStrategy::Skip, None)
+            .await
+            .unwrap();
+        assert_eq!(result.imported_count, 2);
+        assert_eq!(result.error_count, 1);
+        assert_eq!(result.errors.len(), 1);
+        assert!(
+            result.errors[0].starts_with("Record 2: "),
+            "{}",
+            result.errors[0]
+        );
+
+        let repository = &import_use_case.repository;
+        assert!(repository.exists_with_trigger("::good").await.unwrap());
+        assert!(repository.exists_with_trigger("::also").await.unwrap());
+        assert!(!repository.exists_with_trigger("::bad").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_lenient_import_reads_envelopes_and_checks_their_version() {
+        let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let json_data = r#"{
+            "version": 1,
+            "exported_at": "2024-01-01T00:00:00Z",
+            "snippets": [42, { "trigger": "::env", "replacement": "Envelope" }]
+        }"#;
+        let result = import_use_case
+            .import_from_json_lenient(json_data, ConflictStrategy::Skip, None)
+            .await
+            .unwrap();
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(result.error_count, 1);
+
+        let newer = r#"{ "version": 99, "snippets": [] }"#;
+        let error = import_use_case
+            .import_from_json_lenient(newer, ConflictStrategy::Skip, None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("version 99"), "{}", error);
+    }
+
     #[tokio::test]
     async fn test_import_invalid_json() {
         let (import_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
@@ -290,11 +867,14 @@ mod tests {
             trigger: "".to_string(), // Invalid empty trigger
             replacement: "Test".to_string(),
             tags: None,
+            content_type: None,
+            updated_at: None,
         }];
 
         let request = ImportSnippetsRequest {
             snippets,
-            overwrite_existing: false,
+            conflict_strategy: ConflictStrategy::Skip,
+            source: None,
         };
 
         let result = import_use_case.execute(request).await.unwrap();
@@ -303,4 +883,362 @@ mod tests {
         assert_eq!(result.error_count, 1);
         assert!(!result.errors.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_items_line_up_with_counts() {
+        let (import_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        create_use_case
+            .execute(CreateSnippetRequest::new("::taken", "Ours"))
+            .await
+            .unwrap();
+
+        let json_data = r#"[
+            { "trigger": "::new", "replacement": "New" },
+            { "trigger": "::taken", "replacement": "Theirs" },
+            { "trigger": "::unreadable", "tags": "not a list" },
+            { "trigger": "", "replacement": "No trigger" }
+        ]"#;
+        let result = import_use_case
+            .import_from_json_lenient(json_data, ConflictStrategy::Skip, None)
+            .await
+            .unwrap();
+
+        let outcomes: Vec<(Option<&str>, ImportOutcome)> = result
+            .items
+            .iter()
+            .map(|item| (item.trigger.as_deref(), item.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (None, ImportOutcome::Error),
+                (Some("::new"), ImportOutcome::Imported),
+                (Some("::taken"), ImportOutcome::Skipped),
+                (Some(""), ImportOutcome::Error),
+            ]
+        );
+        let count = |outcome| {
+            result
+                .items
+                .iter()
+                .filter(|item| item.outcome == outcome)
+                .count() as u32
+        };
+        assert_eq!(count(ImportOutcome::Imported), result.imported_count);
+        assert_eq!(count(ImportOutcome::Skipped), result.skipped_count);
+        assert_eq!(count(ImportOutcome::Error), result.error_count);
+        assert_eq!(result.errors.len() as u32, result.error_count);
+
+        assert!(result.items[0]
+            .message
+            .as_ref()
+            .unwrap()
+            .starts_with("Record 3: "));
+        assert_eq!(result.items[1].message, None);
+        assert_eq!(result.items[2].message.as_deref(), Some(TRIGGER_TAKEN));
+
+        // Renamed imports say what they were renamed to
+        let renamed = import_use_case
+            .import_from_json_with_strategy(
+                r#"[{ "trigger": "::taken", "replacement": "Theirs" }]"#,
+                ConflictStrategy::Rename,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(renamed.items[0].outcome, ImportOutcome::Imported);
+        assert_eq!(
+            renamed.items[0].message.as_deref(),
+            Some("Renamed to '::taken_2'")
+        );
+    }
+
+    async fn create_conflict_fixture(
+    ) -> (ImportSnippetsService, Arc<SqliteSnippetRepository>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+
+        let existing = Snippet::new("::hello".to_string(), "Original Hello".to_string()).unwrap();
+        repository.save(&existing).await.unwrap();
+
+        let import_use_case = ImportSnippetsService::new(repository.clone());
+        (import_use_case, repository, temp_dir)
+    }
+
+    fn incoming_hello(updated_at: Option<chrono::DateTime<chrono::Utc>>) -> ImportSnippetsRequest {
+        ImportSnippetsRequest {
+            snippets: vec![ImportSnippetData {
+                trigger: "::hello".to_string(),
+                replacement: "Incoming Hello".to_string(),
+                tags: None,
+                content_type: None,
+                updated_at,
+            }],
+            conflict_strategy: ConflictStrategy::Skip,
+            source: None,
+        }
+    }
+
+    async fn replacement_of(repository: &SqliteSnippetRepository, trigger: &str) -> Option<String> {
+        repository
+            .find_by_trigger(trigger)
+            .await
+            .unwrap()
+            .map(|snippet| snippet.replacement)
+    }
+
+    #[tokio::test]
+    async fn test_imported_snippets_carry_their_source() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+
+        let json_data = r#"[{"trigger": "::pr", "replacement": "Pull request", "tags": null}]"#;
+        import_use_case
+            .import_from_json_with_strategy(
+                json_data,
+                ConflictStrategy::Overwrite,
+                Some("dev-pack".to_string()),
+            )
+            .await
+            .unwrap();
+        import_use_case
+            .execute(ImportSnippetsRequest {
+                conflict_strategy: ConflictStrategy::Overwrite,
+                source: Some("greetings".to_string()),
+                ..incoming_hello(None)
+            })
+            .await
+            .unwrap();
+
+        let pr = repository.find_by_trigger("::pr").await.unwrap().unwrap();
+        assert_eq!(pr.source.as_deref(), Some("dev-pack"));
+        // Overwriting takes the snippet over into the new source
+        let hello = repository
+            .find_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hello.source.as_deref(), Some("greetings"));
+    }
+
+    #[test]
+    fn test_conflict_strategy_from_bool() {
+        assert_eq!(ConflictStrategy::from(false), ConflictStrategy::Skip);
+        assert_eq!(ConflictStrategy::from(true), ConflictStrategy::Overwrite);
+        assert_eq!(ConflictStrategy::default(), ConflictStrategy::Skip);
+    }
+
+    #[tokio::test]
+    async fn test_conflict_skip() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+
+        let request = ImportSnippetsRequest {
+            conflict_strategy: ConflictStrategy::Skip,
+            ..incoming_hello(None)
+        };
+        let result = import_use_case.execute(request).await.unwrap();
+
+        assert_eq!(result.skipped_count, 1);
+        assert_eq!(
+            replacement_of(&repository, "::hello").await.as_deref(),
+            Some("Original Hello")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflict_overwrite() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+
+        let request = ImportSnippetsRequest {
+            conflict_strategy: ConflictStrategy::Overwrite,
+            ..incoming_hello(None)
+        };
+        let result = import_use_case.execute(request).await.unwrap();
+
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(
+            replacement_of(&repository, "::hello").await.as_deref(),
+            Some("Incoming Hello")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflict_rename() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+
+        let taken = Snippet::new("::hello_2".to_string(), "Taken".to_string()).unwrap();
+        repository.save(&taken).await.unwrap();
+
+        let request = ImportSnippetsRequest {
+            conflict_strategy: ConflictStrategy::Rename,
+            ..incoming_hello(None)
+        };
+        let result = import_use_case.execute(request).await.unwrap();
+
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(
+            replacement_of(&repository, "::hello").await.as_deref(),
+            Some("Original Hello")
+        );
+        assert_eq!(
+            replacement_of(&repository, "::hello_2").await.as_deref(),
+            Some("Taken")
+        );
+        assert_eq!(
+            replacement_of(&repository, "::hello_3").await.as_deref(),
+            Some("Incoming Hello")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflict_keep_newer() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+        let existing_updated_at = repository
+            .find_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap()
+            .updated_at;
+
+        // Older and missing timestamps keep the existing snippet
+        for updated_at in [None, Some(existing_updated_at - chrono::Duration::days(1))] {
+            let request = ImportSnippetsRequest {
+                conflict_strategy: ConflictStrategy::KeepNewer,
+                ..incoming_hello(updated_at)
+            };
+            let result = import_use_case.execute(request).await.unwrap();
+            assert_eq!(result.skipped_count, 1);
+        }
+        assert_eq!(
+            replacement_of(&repository, "::hello").await.as_deref(),
+            Some("Original Hello")
+        );
+
+        let request = ImportSnippetsRequest {
+            conflict_strategy: ConflictStrategy::KeepNewer,
+            ..incoming_hello(Some(existing_updated_at + chrono::Duration::days(1)))
+        };
+        let result = import_use_case.execute(request).await.unwrap();
+
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(
+            replacement_of(&repository, "::hello").await.as_deref(),
+            Some("Incoming Hello")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflict_deactivate_old() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+        let mut old = repository
+            .find_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap();
+        old.increment_usage();
+        repository.update(&old).await.unwrap();
+
+        let request = ImportSnippetsRequest {
+            conflict_strategy: ConflictStrategy::DeactivateOld,
+            ..incoming_hello(None)
+        };
+        let result = import_use_case.execute(request).await.unwrap();
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(
+            result.items[0].message.as_deref(),
+            Some("Previous snippet deactivated as '::hello_2'")
+        );
+
+        let new = repository
+            .find_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(new.is_active);
+        assert_ne!(new.id, old.id);
+        assert_eq!(new.replacement, "Incoming Hello");
+
+        // Same snippet, usage and all, just out of the way
+        let retired = repository.find_by_id(&old.id).await.unwrap().unwrap();
+        assert!(!retired.is_active);
+        assert_eq!(retired.trigger, "::hello_2");
+        assert_eq!(retired.replacement, "Original Hello");
+        assert_eq!(retired.usage_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_conflict_deactivate_old_keeps_it_when_incoming_is_invalid() {
+        let (import_use_case, repository, _temp_dir) = create_conflict_fixture().await;
+
+        let mut request = ImportSnippetsRequest {
+            conflict_strategy: ConflictStrategy::DeactivateOld,
+            ..incoming_hello(None)
+        };
+        request.snippets[0].replacement = String::new();
+        let result = import_use_case.execute(request).await.unwrap();
+        assert_eq!(result.error_count, 1);
+
+        let old = repository
+            .find_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(old.is_active);
+        assert_eq!(old.replacement, "Original Hello");
+        assert!(replacement_of(&repository, "::hello_2").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_from_directory() {
+        let (import_use_case, repository, temp_dir) = create_conflict_fixture().await;
+        let dir = temp_dir.path().join("snippets");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("sig.txt"), "Best,\nAda\n").unwrap();
+        std::fs::write(dir.join("::addr.txt"), "1 Main Street").unwrap();
+        std::fs::write(dir.join("hello.txt"), "Incoming Hello").unwrap();
+        std::fs::write(dir.join("binary.txt"), [0xff, 0xfe, 0x00]).unwrap();
+        std::fs::write(dir.join("empty.txt"), "").unwrap();
+        std::fs::write(dir.join("notes.md"), "Not a snippet").unwrap();
+        std::fs::create_dir(dir.join("nested.txt")).unwrap();
+
+        let result = import_use_case
+            .import_from_directory(&dir, ConflictStrategy::Skip, Some("::"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.imported_count, 2);
+        assert_eq!(result.skipped_count, 1);
+        assert_eq!(result.error_count, 2);
+        assert!(
+            result.errors[0].starts_with("binary.txt: "),
+            "{:?}",
+            result.errors
+        );
+        assert!(
+            result.errors[1].contains("'::empty'"),
+            "{:?}",
+            result.errors
+        );
+
+        // One trailing newline is dropped, and the prefix isn't doubled
+        assert_eq!(
+            replacement_of(&repository, "::sig").await.as_deref(),
+            Some("Best,\nAda")
+        );
+        assert_eq!(
+            replacement_of(&repository, "::addr").await.as_deref(),
+            Some("1 Main Street")
+        );
+        assert_eq!(
+            replacement_of(&repository, "::hello").await.as_deref(),
+            Some("Original Hello")
+        );
+        assert!(replacement_of(&repository, "::notes").await.is_none());
+
+        assert!(import_use_case
+            .import_from_directory(&dir.join("missing"), ConflictStrategy::Skip, None, None)
+            .await
+            .is_err());
+    }
 }
@@ -1,31 +1,80 @@
-use crate::app::dto::{PageInfo, SnippetDto, SnippetListRequest, SnippetListResponse};
-use crate::domain::{SnippetQuery, SnippetRepository, SortOrder};
+use crate::app::dto::{
+    MatchRange, MatchedSnippetDto, PageInfo, SnippetDto, SnippetListRequest, SnippetListResponse,
+    SnippetSummaryDto, SnippetSummaryListResponse, StatisticsDto,
+};
+use crate::domain::{
+    PageCursor, Snippet, SnippetQuery, SnippetRepository, SortBy, SortOrder, TagMatch,
+};
 use anyhow::Result;
+use futures::TryStreamExt;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::Regex;
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
+/// Above this many active snippets, fuzzy search only scores snippets that
+/// share a two-letter chunk with the search term
+pub const DEFAULT_FUZZY_PREFILTER_THRESHOLD: u64 = 1_000;
+
+/// Words at least this similar to the search term (0.0-1.0) count as typo matches
+const TYPO_SIMILARITY_THRESHOLD: f64 = 0.6;
+
 pub struct ListSnippetsService {
     repository: Arc<dyn SnippetRepository>,
+    matcher: SkimMatcherV2,
+    fuzzy_prefilter_threshold: u64,
 }
 
 impl ListSnippetsService {
     pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            matcher: SkimMatcherV2::default().ignore_case(),
+            fuzzy_prefilter_threshold: DEFAULT_FUZZY_PREFILTER_THRESHOLD,
+        }
+    }
+
+    /// Override how many snippets fuzzy search scores before pre-filtering in SQL
+    pub fn with_fuzzy_prefilter_threshold(mut self, threshold: u64) -> Self {
+        self.fuzzy_prefilter_threshold = threshold;
+        self
     }
 
     pub async fn execute(&self, request: SnippetListRequest) -> Result<SnippetListResponse> {
         // Convert request to domain query
-        let query = self.convert_request_to_query(&request);
+        let mut query = self.convert_request_to_query(&request)?;
+
+        // A cursor page can't be checked against the total, so fetch one extra
+        // row to learn whether another page follows
+        let cursor_limit = query.after.as_ref().and(query.limit);
+        if let Some(limit) = cursor_limit {
+            query.limit = Some(limit.saturating_add(1));
+        }
 
         // Get snippets and total count
-        let snippets = self.repository.find_all(&query).await?;
+        let mut snippets = self.repository.find_all(&query).await?;
         let total_count = self.repository.count(&query).await?;
 
+        // Calculate pagination info
+        let mut page_info = self.calculate_page_info(&request, total_count);
+        if let Some(limit) = cursor_limit {
+            page_info.has_next_page = snippets.len() > limit as usize;
+            page_info.has_previous_page = true;
+            page_info.current_page = 0;
+            snippets.truncate(limit as usize);
+        }
+        // Without a limit every remaining snippet is already in this page
+        if page_info.has_next_page && request.limit.is_some() {
+            let sort_by = query.sort_by.unwrap_or_default();
+            page_info.next_cursor = snippets
+                .last()
+                .map(|last| PageCursor::after_snippet(last, sort_by).to_string());
+        }
+
         // Convert domain snippets to DTOs
         let snippet_dtos: Vec<SnippetDto> = snippets.into_iter().map(SnippetDto::from).collect();
 
-        // Calculate pagination info
-        let page_info = self.calculate_page_info(&request, total_count);
-
         Ok(SnippetListResponse {
             snippets: snippet_dtos,
             total_count,
@@ -33,7 +82,189 @@ impl ListSnippetsService {
         })
     }
 
-    fn convert_request_to_query(&self, request: &SnippetListRequest) -> SnippetQuery {
+    /// Run a listing like `execute`, also reporting where the search term
+    /// occurs in each snippet's trigger and replacement
+    pub async fn search_with_highlights(
+        &self,
+        request: SnippetListRequest,
+    ) -> Result<Vec<MatchedSnippetDto>> {
+        let term = request.search_term.clone().unwrap_or_default();
+        let response = self.execute(request).await?;
+
+        Ok(response
+            .snippets
+            .into_iter()
+            .map(|snippet| MatchedSnippetDto {
+                trigger_matches: match_ranges(&snippet.trigger, &term),
+                replacement_matches: match_ranges(&snippet.replacement, &term),
+                snippet,
+            })
+            .collect())
+    }
+
+    /// `execute`, with each replacement cut down to a preview for list views
+    pub async fn list_summaries(
+        &self,
+        request: SnippetListRequest,
+    ) -> Result<SnippetSummaryListResponse> {
+        let response = self.execute(request).await?;
+        Ok(SnippetSummaryListResponse {
+            summaries: response
+                .snippets
+                .into_iter()
+                .map(SnippetSummaryDto::from)
+                .collect(),
+            total_count: response.total_count,
+            page_info: response.page_info,
+        })
+    }
+
+    /// The `limit` most used active snippets, most used first
+    pub async fn most_used(&self, limit: u32) -> Result<Vec<SnippetDto>> {
+        let snippets = self.repository.top_by_usage(limit).await?;
+        Ok(snippets.into_iter().map(SnippetDto::from).collect())
+    }
+
+    /// Up to `limit` active snippets picked at random
+    pub async fn random(&self, limit: u32) -> Result<Vec<SnippetDto>> {
+        let snippets = self.repository.random_active(limit).await?;
+        Ok(snippets.into_iter().map(SnippetDto::from).collect())
+    }
+
+    /// Total number of snippets, active or not
+    pub async fn count_all(&self) -> Result<u64> {
+        self.repository.count_all().await
+    }
+
+    /// Counts, total usage and tags over every snippet, with the
+    /// `most_used_limit` most used active ones
+    pub async fn statistics(&self, most_used_limit: u32) -> Result<StatisticsDto> {
+        let total_snippets = self.repository.count_all().await?;
+        let active_snippets = self
+            .repository
+            .count(&SnippetQuery::new().with_active_only())
+            .await?;
+
+        let mut total_usage = 0;
+        let mut tags = BTreeSet::new();
+        let mut snippets = self.repository.stream_all(&SnippetQuery::new());
+        while let Some(snippet) = snippets.try_next().await? {
+            total_usage += snippet.usage_count;
+            tags.extend(snippet.tags);
+        }
+
+        Ok(StatisticsDto {
+            total_snippets,
+            active_snippets,
+            inactive_snippets: total_snippets.saturating_sub(active_snippets),
+            total_usage,
+            most_used: self.most_used(most_used_limit).await?,
+            tags: tags.into_iter().collect(),
+        })
+    }
+
+    /// Rank active snippets by how closely their trigger or replacement matches
+    /// `term`, best first. Tolerates typos such as swapped letters.
+    pub async fn fuzzy_search(&self, term: &str, limit: u32) -> Result<Vec<(SnippetDto, i64)>> {
+        let term = term.trim();
+        if term.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query = SnippetQuery::new().with_active_only();
+        if self.repository.count(&query).await? > self.fuzzy_prefilter_threshold {
+            query = query.with_search_any(Self::coarse_tokens(term));
+        }
+
+        let mut scored: Vec<(Snippet, i64)> = self
+            .repository
+            .find_all(&query)
+            .await?
+            .into_iter()
+            .filter_map(|snippet| {
+                let score = self.fuzzy_score(&snippet, term)?;
+                Some((snippet, score))
+            })
+            .collect();
+
+        scored.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.cmp(a_score).then_with(|| a.trigger.cmp(&b.trigger))
+        });
+        scored.truncate(limit as usize);
+
+        Ok(scored
+            .into_iter()
+            .map(|(snippet, score)| (SnippetDto::from(snippet), score))
+            .collect())
+    }
+
+    /// Active snippets whose replacement matches the regular expression
+    /// `pattern`, most used first. SQLite has no portable regex support, so
+    /// this reads every active snippet and matches in Rust.
+    pub async fn regex_search(&self, pattern: &str) -> Result<Vec<SnippetDto>> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid regular expression '{}': {}", pattern, e))?;
+
+        let query = SnippetQuery::new()
+            .with_active_only()
+            .with_sort(SortBy::UsageCount, SortOrder::Desc);
+        Ok(self
+            .repository
+            .find_all(&query)
+            .await?
+            .into_iter()
+            .filter(|snippet| snippet.locked.is_none() && regex.is_match(&snippet.replacement))
+            .map(SnippetDto::from)
+            .collect())
+    }
+
+    /// Best score across trigger and replacement; trigger matches count double
+    fn fuzzy_score(&self, snippet: &Snippet, term: &str) -> Option<i64> {
+        let trigger = snippet.trigger.trim_start_matches(':');
+        let trigger_score = self.field_score(trigger, term).map(|score| score * 2);
+        let replacement_score = match snippet.locked {
+            Some(_) => None,
+            None => self.field_score(&snippet.replacement, term),
+        };
+        trigger_score.max(replacement_score)
+    }
+
+    fn field_score(&self, field: &str, term: &str) -> Option<i64> {
+        let skim_score = self.matcher.fuzzy_match(field, term);
+
+        // Subsequence matching misses transposed letters, so also compare whole words
+        let term = term.to_lowercase();
+        let similarity = field
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| strsim::normalized_damerau_levenshtein(&word.to_lowercase(), &term))
+            .fold(0.0, f64::max);
+        let typo_score =
+            (similarity >= TYPO_SIMILARITY_THRESHOLD).then_some((similarity * 100.0) as i64);
+
+        skim_score.max(typo_score)
+    }
+
+    /// Two-letter chunks of each word in `term`; a typo rarely breaks all of them
+    fn coarse_tokens(term: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        for word in term.split_whitespace() {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() <= 2 {
+                tokens.push(word.to_string());
+                continue;
+            }
+            for pair in chars.windows(2) {
+                let token: String = pair.iter().collect();
+                if !tokens.contains(&token) {
+                    tokens.push(token);
+                }
+            }
+        }
+        tokens
+    }
+
+    fn convert_request_to_query(&self, request: &SnippetListRequest) -> Result<SnippetQuery> {
         let mut query = SnippetQuery::new();
 
         // Apply filters
@@ -41,10 +272,26 @@ impl ListSnippetsService {
             query = query.with_search(search_term.clone());
         }
 
+        if let Some(ref fields) = request.search_fields {
+            query = query.with_search_fields(fields.clone());
+        }
+
         if let Some(ref tags) = request.tags {
             query = query.with_tags(tags.clone());
         }
 
+        if let Some(ref tag_match) = request.tag_match {
+            match tag_match.as_str() {
+                "any" => query = query.with_tag_match(TagMatch::Any),
+                "all" => query = query.with_tag_match(TagMatch::All),
+                _ => {} // Keep default tag matching
+            }
+        }
+
+        if let Some(ref source) = request.source {
+            query = query.with_source(source.clone());
+        }
+
         if let Some(is_active) = request.is_active {
             if is_active {
                 query = query.with_active_only();
@@ -58,7 +305,9 @@ impl ListSnippetsService {
             query = query.with_limit(limit);
         }
 
-        if let Some(offset) = request.offset {
+        if let Some(ref cursor) = request.after {
+            query = query.after(cursor.parse()?);
+        } else if let Some(offset) = request.offset {
             query = query.with_offset(offset);
         }
 
@@ -68,27 +317,27 @@ impl ListSnippetsService {
                 "usage" => query = query.sort_by_usage(),
                 "created" => query = query.sort_by_created(),
                 "alphabetical" => query = query.sort_alphabetically(),
+                "position" => query = query.sort_by_position(),
                 _ => {} // Use default sorting
             }
         }
 
         // Override sort order if specified
         if let Some(ref sort_order) = request.sort_order {
-            match sort_order.as_str() {
-                "asc" => query.sort_order = Some(SortOrder::Asc),
-                "desc" => query.sort_order = Some(SortOrder::Desc),
-                _ => {} // Keep current sort order
+            // Anything unrecognised keeps the current sort order
+            if let Ok(sort_order) = sort_order.parse::<SortOrder>() {
+                query.sort_order = Some(sort_order);
             }
         }
 
-        query
+        Ok(query)
     }
 
     fn calculate_page_info(&self, request: &SnippetListRequest, total_count: u64) -> PageInfo {
         let limit = request.limit.unwrap_or(100) as u64;
         let offset = request.offset.unwrap_or(0) as u64;
 
-        let current_page = if limit > 0 { (offset / limit) + 1 } else { 1 };
+        let current_page = offset.checked_div(limit).map_or(1, |page| page + 1);
         let total_pages = if limit > 0 {
             total_count.div_ceil(limit) as u32
         } else {
@@ -103,8 +352,37 @@ impl ListSnippetsService {
             has_previous_page,
             total_pages,
             current_page: current_page as u32,
+            next_cursor: None,
+        }
+    }
+}
+
+/// Non-overlapping occurrences of `term` in `text`, ignoring ASCII case the
+/// way SQLite's `LIKE` does
+fn match_ranges(text: &str, term: &str) -> Vec<MatchRange> {
+    let mut ranges = Vec::new();
+    if term.is_empty() {
+        return ranges;
+    }
+
+    let (text_bytes, term_bytes) = (text.as_bytes(), term.as_bytes());
+    let mut start = 0;
+    while start + term_bytes.len() <= text_bytes.len() {
+        // Non-ASCII bytes must match exactly, so a match that starts on a
+        // character boundary also ends on one
+        if text.is_char_boundary(start)
+            && text_bytes[start..start + term_bytes.len()].eq_ignore_ascii_case(term_bytes)
+        {
+            ranges.push(MatchRange {
+                start,
+                end: start + term_bytes.len(),
+            });
+            start += term_bytes.len();
+        } else {
+            start += 1;
         }
     }
+    ranges
 }
 
 #[cfg(test)]
@@ -112,7 +390,9 @@ mod tests {
     use super::*;
     use crate::app::dto::CreateSnippetRequest;
     use crate::app::services::CreateSnippetService;
+    use crate::domain::SearchField;
     use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use crate::test_support::snippet;
     use tempfile::TempDir;
 
     async fn create_test_use_case() -> (ListSnippetsService, CreateSnippetService, TempDir) {
@@ -131,12 +411,16 @@ mod tests {
 
         let request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: None,
             is_active: None,
             limit: None,
             offset: None,
             sort_by: None,
             sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let response = list_use_case.execute(request).await.unwrap();
@@ -154,21 +438,24 @@ mod tests {
         // Create some test snippets
         for i in 1..=3 {
             let request = CreateSnippetRequest {
-                trigger: format!("::test{}", i),
-                replacement: format!("Test {}", i),
                 tags: Some(vec!["test".to_string()]),
+                ..CreateSnippetRequest::new(format!("::test{}", i), format!("Test {}", i))
             };
             create_use_case.execute(request).await.unwrap();
         }
 
         let request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: None,
             is_active: Some(true),
             limit: None,
             offset: None,
             sort_by: None,
             sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let response = list_use_case.execute(request).await.unwrap();
@@ -182,16 +469,8 @@ mod tests {
         let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create test snippets
-        let request1 = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Hello, World!".to_string(),
-            tags: None,
-        };
-        let request2 = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Test replacement".to_string(),
-            tags: None,
-        };
+        let request1 = CreateSnippetRequest::new("::hello", "Hello, World!");
+        let request2 = CreateSnippetRequest::new("::test", "Test replacement");
 
         create_use_case.execute(request1).await.unwrap();
         create_use_case.execute(request2).await.unwrap();
@@ -199,18 +478,38 @@ mod tests {
         // Search for "hello"
         let search_request = SnippetListRequest {
             search_term: Some("hello".to_string()),
+            search_fields: None,
             tags: None,
             is_active: None,
             limit: None,
             offset: None,
             sort_by: None,
             sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
-        let response = list_use_case.execute(search_request).await.unwrap();
+        let response = list_use_case.execute(search_request.clone()).await.unwrap();
 
         assert_eq!(response.snippets.len(), 1);
         assert_eq!(response.snippets[0].trigger, "::hello");
+
+        // "replacement" is only in a replacement, "::" only in triggers
+        for (term, field, expected) in [
+            ("replacement", SearchField::Trigger, 0),
+            ("replacement", SearchField::Replacement, 1),
+            ("::", SearchField::Trigger, 2),
+            ("::", SearchField::Replacement, 0),
+        ] {
+            let request = SnippetListRequest {
+                search_term: Some(term.to_string()),
+                search_fields: Some(vec![field]),
+                ..search_request.clone()
+            };
+            let response = list_use_case.execute(request).await.unwrap();
+            assert_eq!(response.total_count, expected, "{} in {:?}", term, field);
+        }
     }
 
     #[tokio::test]
@@ -219,23 +518,23 @@ mod tests {
 
         // Create 5 test snippets
         for i in 1..=5 {
-            let request = CreateSnippetRequest {
-                trigger: format!("::test{}", i),
-                replacement: format!("Test {}", i),
-                tags: None,
-            };
+            let request = CreateSnippetRequest::new(format!("::test{}", i), format!("Test {}", i));
             create_use_case.execute(request).await.unwrap();
         }
 
         // Get first page (2 items)
         let request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: None,
             is_active: None,
             limit: Some(2),
             offset: Some(0),
             sort_by: None,
             sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let response = list_use_case.execute(request).await.unwrap();
@@ -247,4 +546,328 @@ mod tests {
         assert_eq!(response.page_info.current_page, 1);
         assert_eq!(response.page_info.total_pages, 3);
     }
+
+    /// Every trigger of a listing, fetched `page_size` at a time by cursor
+    async fn triggers_by_cursor(
+        list_use_case: &ListSnippetsService,
+        sort_by: &str,
+        sort_order: &str,
+        page_size: Option<u32>,
+    ) -> Vec<String> {
+        let mut triggers = Vec::new();
+        let mut after = None;
+        loop {
+            let request = SnippetListRequest {
+                search_term: None,
+                search_fields: None,
+                tags: None,
+                is_active: None,
+                limit: page_size,
+                offset: None,
+                sort_by: Some(sort_by.to_string()),
+                sort_order: Some(sort_order.to_string()),
+                tag_match: None,
+                source: None,
+                after: after.take(),
+            };
+            let response = list_use_case.execute(request).await.unwrap();
+            assert!(response.snippets.len() <= page_size.unwrap_or(u32::MAX) as usize);
+            triggers.extend(response.snippets.into_iter().map(|s| s.trigger));
+
+            match response.page_info.next_cursor {
+                Some(cursor) => after = Some(cursor),
+                None => return triggers,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination_has_no_gaps_or_duplicates() {
+        let connection = DatabaseConnection::new_in_memory().await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(connection.pool().clone()));
+        for i in 0..300 {
+            let mut snippet = snippet(&format!("::s{:03}", i), "Text");
+            // Plenty of ties, and counts that sort differently as text
+            snippet.usage_count = i % 13;
+            // Pages also have to cross from pinned to unpinned snippets
+            snippet.set_pinned(i % 7 == 0);
+            repository.save(&snippet).await.unwrap();
+        }
+        let list_use_case = ListSnippetsService::new(repository);
+
+        for (sort_by, sort_order) in [
+            ("updated", "desc"),
+            ("usage", "desc"),
+            ("usage", "asc"),
+            ("alphabetical", "asc"),
+        ] {
+            let all = triggers_by_cursor(&list_use_case, sort_by, sort_order, None).await;
+            assert_eq!(all.len(), 300);
+
+            let paged = triggers_by_cursor(&list_use_case, sort_by, sort_order, Some(17)).await;
+            assert_eq!(paged, all, "sorted by {} {}", sort_by, sort_order);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sorting_by_created_follows_the_requested_order() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        // Created in an order that isn't alphabetical
+        for trigger in ["::second", "::first", "::third"] {
+            let request = CreateSnippetRequest::new(trigger, "Text");
+            create_use_case.execute(request).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let newest_first = ["::third", "::first", "::second"];
+        for sort_order in ["desc", "DESC", "descending"] {
+            let triggers = triggers_by_cursor(&list_use_case, "created", sort_order, None).await;
+            assert_eq!(triggers, newest_first, "sorted {}", sort_order);
+        }
+        let oldest_first = triggers_by_cursor(&list_use_case, "created", "asc", None).await;
+        assert_eq!(oldest_first, ["::second", "::first", "::third"]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_cursor_is_rejected() {
+        let (list_use_case, _create_use_case, _temp_dir) = create_test_use_case().await;
+
+        let request = SnippetListRequest {
+            search_term: None,
+            search_fields: None,
+            tags: None,
+            is_active: None,
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            sort_order: None,
+            tag_match: None,
+            source: None,
+            after: Some("not-a-cursor".to_string()),
+        };
+
+        let error = list_use_case.execute(request).await.unwrap_err();
+        assert_eq!(error.to_string(), "Invalid page cursor 'not-a-cursor'");
+    }
+
+    #[tokio::test]
+    async fn test_list_snippets_tag_match() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+
+        for (trigger, tags) in [("::both", vec!["a", "b"]), ("::only_a", vec!["a"])] {
+            let request = CreateSnippetRequest {
+                tags: Some(tags.into_iter().map(String::from).collect()),
+                ..CreateSnippetRequest::new(trigger, "Tagged")
+            };
+            create_use_case.execute(request).await.unwrap();
+        }
+
+        for (tag_match, expected) in [(None, 2), (Some("any"), 2), (Some("all"), 1)] {
+            let request = SnippetListRequest {
+                search_term: None,
+                search_fields: None,
+                tags: Some(vec!["a".to_string(), "b".to_string()]),
+                is_active: None,
+                limit: None,
+                offset: None,
+                sort_by: None,
+                sort_order: None,
+                tag_match: tag_match.map(String::from),
+                source: None,
+                after: None,
+            };
+
+            let response = list_use_case.execute(request).await.unwrap();
+            assert_eq!(response.total_count, expected, "tag_match {:?}", tag_match);
+            assert_eq!(response.snippets.len(), expected as usize);
+        }
+    }
+
+    async fn create_fuzzy_fixtures(create_use_case: &CreateSnippetService) {
+        for (trigger, replacement) in [
+            ("::hello", "Hello, World!"),
+            ("::help", "See the help pages"),
+            ("::addr", "1 Main Street"),
+        ] {
+            let request = CreateSnippetRequest::new(trigger, replacement);
+            create_use_case.execute(request).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_tolerates_transposed_letters() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        create_fuzzy_fixtures(&create_use_case).await;
+
+        let results = list_use_case.fuzzy_search("hlelo", 10).await.unwrap();
+
+        assert!(!results.is_empty());
+        assert_eq!(results[0].0.trigger, "::hello");
+        assert!(results
+            .iter()
+            .all(|(snippet, _)| snippet.trigger != "::addr"));
+        assert!(results.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_with_sql_prefilter() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        let list_use_case = list_use_case.with_fuzzy_prefilter_threshold(0);
+        create_fuzzy_fixtures(&create_use_case).await;
+
+        let results = list_use_case.fuzzy_search("hlelo", 10).await.unwrap();
+        assert_eq!(results[0].0.trigger, "::hello");
+
+        let results = list_use_case.fuzzy_search("stret", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.trigger, "::addr");
+    }
+
+    #[tokio::test]
+    async fn test_summaries_preview_the_first_line() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        let long_line = "word ".repeat(20);
+        for (trigger, replacement) in [
+            (
+                "::letter",
+                format!("{}\nSecond line\nThird line", long_line),
+            ),
+            ("::short", "Hi\nthere".to_string()),
+            ("::one", "One line".to_string()),
+        ] {
+            create_use_case
+                .execute(CreateSnippetRequest::new(trigger, replacement))
+                .await
+                .unwrap();
+        }
+
+        let request = SnippetListRequest {
+            search_term: None,
+            search_fields: None,
+            tags: None,
+            is_active: None,
+            limit: Some(2),
+            offset: None,
+            sort_by: Some("alphabetical".to_string()),
+            sort_order: Some("asc".to_string()),
+            tag_match: None,
+            source: None,
+            after: None,
+        };
+        let response = list_use_case.list_summaries(request).await.unwrap();
+        assert_eq!(response.total_count, 3);
+        assert!(response.page_info.has_next_page);
+
+        let previews: Vec<(&str, &str)> = response
+            .summaries
+            .iter()
+            .map(|s| (s.trigger.as_str(), s.preview.as_str()))
+            .collect();
+        let cut = format!("{}... [+2 lines]", &long_line[..47]);
+        assert_eq!(
+            previews,
+            vec![("::letter", cut.as_str()), ("::one", "One line")]
+        );
+        assert_eq!(
+            crate::app::dto::replacement_preview("Hi\nthere", 50),
+            "Hi [+1 line]"
+        );
+        assert_eq!(
+            crate::app::dto::replacement_preview("caf\u{e9} au lait", 6),
+            "caf..."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regex_search_matches_replacements() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        for (trigger, replacement) in [
+            ("::work", "Mail me at jane@example.com"),
+            ("::home", "jane.doe@mail.example.org"),
+            ("::addr", "1 Main Street"),
+        ] {
+            create_use_case
+                .execute(CreateSnippetRequest::new(trigger, replacement))
+                .await
+                .unwrap();
+        }
+
+        let results = list_use_case
+            .regex_search(r"[\w.]+@[\w.]+\.(com|org)")
+            .await
+            .unwrap();
+        let mut triggers: Vec<&str> = results.iter().map(|s| s.trigger.as_str()).collect();
+        triggers.sort();
+        assert_eq!(triggers, vec!["::home", "::work"]);
+
+        // Only the replacement is searched
+        assert!(list_use_case.regex_search("^::").await.unwrap().is_empty());
+
+        let error = list_use_case.regex_search("(unclosed").await.unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid regular expression '(unclosed'"));
+    }
+
+    #[test]
+    fn test_match_ranges_respect_char_boundaries() {
+        assert_eq!(
+            match_ranges("Café CAFÉ café", "café"),
+            vec![
+                MatchRange { start: 0, end: 5 },
+                MatchRange { start: 12, end: 17 },
+            ]
+        );
+        assert_eq!(match_ranges("aaaa", "aa").len(), 2);
+        assert!(match_ranges("anything", "").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_with_highlights() {
+        let (list_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
+        create_use_case
+            .execute(CreateSnippetRequest::new(
+                "::thx",
+                "Thanks, and thanks again — thanks!",
+            ))
+            .await
+            .unwrap();
+
+        let results = list_use_case
+            .search_with_highlights(SnippetListRequest {
+                search_term: Some("thanks".to_string()),
+                search_fields: None,
+                tags: None,
+                is_active: None,
+                limit: None,
+                offset: None,
+                sort_by: None,
+                sort_order: None,
+                tag_match: None,
+                source: None,
+                after: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert!(result.trigger_matches.is_empty());
+        let replacement = &result.snippet.replacement;
+        let matched: Vec<&str> = result
+            .replacement_matches
+            .iter()
+            .map(|range| &replacement[range.start..range.end])
+            .collect();
+        assert_eq!(matched, vec!["Thanks", "thanks", "thanks"]);
+        // The em dash is three bytes, so the last match starts after it
+        assert_eq!(
+            result.replacement_matches[1],
+            MatchRange { start: 12, end: 18 }
+        );
+        assert_eq!(
+            result.replacement_matches[2],
+            MatchRange { start: 29, end: 35 }
+        );
+    }
 }
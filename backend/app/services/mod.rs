@@ -5,8 +5,16 @@ pub mod export_snippets;
 pub mod get_snippet;
 pub mod import_snippets;
 pub mod list_snippets;
+pub mod rename_tag;
+pub mod reorder_snippets;
+pub mod replace_text;
+pub mod reset_usage;
+pub mod set_active;
+pub mod snippet_packs;
+pub mod sync_snippets;
 pub mod typely_service;
 pub mod update_snippet;
+pub mod validate_snippets;
 
 pub use create_snippet::*;
 pub use delete_snippet::*;
@@ -15,5 +23,13 @@ pub use export_snippets::*;
 pub use get_snippet::*;
 pub use import_snippets::*;
 pub use list_snippets::*;
+pub use rename_tag::*;
+pub use reorder_snippets::*;
+pub use replace_text::*;
+pub use reset_usage::*;
+pub use set_active::*;
+pub use snippet_packs::*;
+pub use sync_snippets::*;
 pub use typely_service::*;
 pub use update_snippet::*;
+pub use validate_snippets::*;
@@ -0,0 +1,94 @@
+use crate::domain::{SnippetQuery, SnippetRepository};
+use anyhow::Result;
+use std::sync::Arc;
+
+pub struct RenameTagService {
+    repository: Arc<dyn SnippetRepository>,
+}
+
+impl RenameTagService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Replace tag `from` with `to` on every snippet that has it. A snippet
+    /// already tagged `to` just loses `from`. Returns how many snippets changed.
+    pub async fn execute(&self, from: &str, to: &str) -> Result<u32> {
+        let from = from.trim();
+        let to = to.trim();
+        if from.is_empty() || to.is_empty() {
+            return Err(anyhow::anyhow!("Tag names cannot be empty"));
+        }
+        if from == to {
+            return Ok(0);
+        }
+
+        let query = SnippetQuery::new().with_tags(vec![from.to_string()]);
+        let snippets = self.repository.find_all(&query).await?;
+
+        let mut renamed = 0;
+        for mut snippet in snippets {
+            if snippet.rename_tag(from, to) {
+                self.repository.update(&snippet).await?;
+                renamed += 1;
+            }
+        }
+
+        log::info!(
+            "Renamed tag '{}' to '{}' on {} snippet(s)",
+            from,
+            to,
+            renamed
+        );
+        Ok(renamed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Snippet;
+    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+
+    async fn save_tagged(repository: &SqliteSnippetRepository, trigger: &str, tags: &[&str]) {
+        let mut snippet = Snippet::new(trigger.to_string(), "Text".to_string()).unwrap();
+        for tag in tags {
+            snippet.add_tag(tag.to_string());
+        }
+        repository.save(&snippet).await.unwrap();
+    }
+
+    async fn tags_of(repository: &SqliteSnippetRepository, trigger: &str) -> Vec<String> {
+        repository
+            .find_by_trigger(trigger)
+            .await
+            .unwrap()
+            .unwrap()
+            .tags
+    }
+
+    #[tokio::test]
+    async fn test_rename_tag_on_every_snippet() {
+        let connection = DatabaseConnection::new_in_memory().await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(connection.pool().clone()));
+        save_tagged(&repository, "::one", &["personal", "work"]).await;
+        save_tagged(&repository, "::two", &["work", "email"]).await;
+        save_tagged(&repository, "::both", &["work", "business"]).await;
+        save_tagged(&repository, "::home", &["personal"]).await;
+        let service = RenameTagService::new(repository.clone());
+
+        assert_eq!(service.execute("work", "business").await.unwrap(), 3);
+
+        // Renamed in place, without duplicating a tag the snippet already had
+        assert_eq!(
+            tags_of(&repository, "::one").await,
+            ["personal", "business"]
+        );
+        assert_eq!(tags_of(&repository, "::two").await, ["business", "email"]);
+        assert_eq!(tags_of(&repository, "::both").await, ["business"]);
+        assert_eq!(tags_of(&repository, "::home").await, ["personal"]);
+
+        assert_eq!(service.execute("work", "business").await.unwrap(), 0);
+        assert!(service.execute("business", " ").await.is_err());
+    }
+}
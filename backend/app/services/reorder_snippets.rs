@@ -0,0 +1,69 @@
+use crate::domain::SnippetRepository;
+use anyhow::Result;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub struct ReorderSnippetsService {
+    repository: Arc<dyn SnippetRepository>,
+}
+
+impl ReorderSnippetsService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Put the snippets in `ordered_ids` first in the `SortBy::Position`
+    /// order, in the order given. Ids that match no snippet are skipped.
+    /// Returns the number of snippets placed.
+    pub async fn execute(&self, ordered_ids: &[Uuid]) -> Result<u64> {
+        let placed = self.repository.reorder(ordered_ids).await?;
+        if placed < ordered_ids.len() as u64 {
+            log::warn!(
+                "Reordered {} of {} snippet ids; the rest weren't found",
+                placed,
+                ordered_ids.len()
+            );
+        } else {
+            log::info!("Reordered {} snippet(s)", placed);
+        }
+
+        Ok(placed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{SnippetQuery, SortBy, SortOrder};
+    use crate::infra::HashMapSnippetRepository;
+    use crate::test_support::snippet;
+
+    #[tokio::test]
+    async fn test_positions_follow_the_given_order() {
+        let repository = Arc::new(HashMapSnippetRepository::new());
+        let mut ids = Vec::new();
+        for trigger in ["::one", "::two", "::three"] {
+            let snippet = snippet(trigger, "Text");
+            ids.push(snippet.id);
+            repository.save(&snippet).await.unwrap();
+        }
+        let reorder_use_case = ReorderSnippetsService::new(repository.clone());
+
+        let placed = reorder_use_case
+            .execute(&[ids[2], ids[0], Uuid::new_v4(), ids[1]])
+            .await
+            .unwrap();
+        assert_eq!(placed, 3);
+
+        let query = SnippetQuery::new().with_sort(SortBy::Position, SortOrder::Asc);
+        let sorted = repository.find_all(&query).await.unwrap();
+        let positions: Vec<(&str, Option<u32>)> = sorted
+            .iter()
+            .map(|snippet| (snippet.trigger.as_str(), snippet.position))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![("::three", Some(0)), ("::one", Some(1)), ("::two", Some(2))]
+        );
+    }
+}
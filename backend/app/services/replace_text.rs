@@ -0,0 +1,184 @@
+use super::DEFAULT_MAX_SNIPPET_VERSIONS;
+use crate::domain::{SnippetQuery, SnippetRepository, SnippetVersion};
+use anyhow::Result;
+use std::sync::Arc;
+
+pub struct ReplaceTextService {
+    repository: Arc<dyn SnippetRepository>,
+}
+
+impl ReplaceTextService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Replace every occurrence of `find` with `replace` in the replacements
+    /// of all snippets, active or not, matching case exactly. The old text
+    /// is kept in each changed snippet's history. With `dry_run` nothing is
+    /// written. Returns how many snippets changed, or would.
+    ///
+    /// Encrypted replacements are decrypted to be searched, so when any
+    /// snippet is encrypted this fails unless the repository can decrypt it.
+    pub async fn execute(&self, find: &str, replace: &str, dry_run: bool) -> Result<u32> {
+        if find.is_empty() {
+            return Err(anyhow::anyhow!("Text to find cannot be empty"));
+        }
+        if find == replace {
+            return Ok(0);
+        }
+
+        // A replacement search would skip encrypted snippets, and ignores
+        // case anyway, so every snippet is checked here
+        let snippets = self.repository.find_all(&SnippetQuery::new()).await?;
+
+        let mut changed = 0;
+        for mut snippet in snippets {
+            snippet.ensure_unlocked()?;
+            if !snippet.replacement.contains(find) {
+                continue;
+            }
+            changed += 1;
+            if dry_run {
+                continue;
+            }
+
+            let previous = SnippetVersion::of(&snippet);
+            snippet.update_replacement(snippet.replacement.replace(find, replace))?;
+            self.repository.update(&snippet).await?;
+            self.repository
+                .record_version(&previous, DEFAULT_MAX_SNIPPET_VERSIONS)
+                .await?;
+        }
+
+        if !dry_run {
+            log::info!(
+                "Replaced '{}' with '{}' in {} snippet(s)",
+                find,
+                replace,
+                changed
+            );
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{
+        DatabaseConnection, EncryptionService, HashMapSnippetRepository, SqliteSnippetRepository,
+    };
+    use crate::test_support::snippet;
+
+    async fn replacement_of(repository: &HashMapSnippetRepository, trigger: &str) -> String {
+        repository
+            .find_by_trigger(trigger)
+            .await
+            .unwrap()
+            .unwrap()
+            .replacement
+    }
+
+    #[tokio::test]
+    async fn test_replace_across_replacements() {
+        let repository = Arc::new(HashMapSnippetRepository::new());
+        let mut retired = snippet("::old", "Mail old.example.com");
+        retired.deactivate();
+        for snippet in [
+            snippet(
+                "::site",
+                "https://old.example.com/docs and old.example.com/faq",
+            ),
+            snippet("::other", "https://new.example.com"),
+            snippet("::shout", "OLD.EXAMPLE.COM"),
+            retired,
+        ] {
+            repository.save(&snippet).await.unwrap();
+        }
+        let service = ReplaceTextService::new(repository.clone());
+
+        // A preview counts without writing
+        let previewed = service
+            .execute("old.example.com", "new.example.com", true)
+            .await
+            .unwrap();
+        assert_eq!(previewed, 2);
+        assert_eq!(
+            replacement_of(&repository, "::old").await,
+            "Mail old.example.com"
+        );
+
+        let changed = service
+            .execute("old.example.com", "new.example.com", false)
+            .await
+            .unwrap();
+        assert_eq!(changed, 2);
+        assert_eq!(
+            replacement_of(&repository, "::site").await,
+            "https://new.example.com/docs and new.example.com/faq"
+        );
+        assert_eq!(
+            replacement_of(&repository, "::old").await,
+            "Mail new.example.com"
+        );
+
+        // Non-matching snippets, including different case, are untouched
+        assert_eq!(
+            replacement_of(&repository, "::other").await,
+            "https://new.example.com"
+        );
+        assert_eq!(
+            replacement_of(&repository, "::shout").await,
+            "OLD.EXAMPLE.COM"
+        );
+        let other = repository
+            .find_by_trigger("::other")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(repository.history(&other.id).await.unwrap().is_empty());
+
+        let site = repository.find_by_trigger("::site").await.unwrap().unwrap();
+        let history = repository.history(&site.id).await.unwrap();
+        assert_eq!(
+            history[0].replacement,
+            "https://old.example.com/docs and old.example.com/faq"
+        );
+
+        assert!(service.execute("", "x", false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replace_in_encrypted_snippets() {
+        let connection = DatabaseConnection::new_in_memory().await.unwrap();
+        let pool = connection.pool().clone();
+        let repository = Arc::new(
+            SqliteSnippetRepository::new(pool.clone())
+                .with_encryption(EncryptionService::new("correct horse").unwrap()),
+        );
+        let mut secret = snippet("::pin", "PIN 1234");
+        secret.set_encrypted(true);
+        repository.save(&secret).await.unwrap();
+        repository
+            .save(&snippet("::hi", "PIN 1234 too"))
+            .await
+            .unwrap();
+
+        let changed = ReplaceTextService::new(repository.clone())
+            .execute("1234", "5678", false)
+            .await
+            .unwrap();
+        assert_eq!(changed, 2);
+        let found = repository.find_by_trigger("::pin").await.unwrap().unwrap();
+        assert!(found.encrypted);
+        assert_eq!(found.replacement, "PIN 5678");
+
+        // Without the passphrase the encrypted snippet can't be searched, so
+        // nothing is reported as done
+        let plain = Arc::new(SqliteSnippetRepository::new(pool));
+        let service = ReplaceTextService::new(plain.clone());
+        assert!(service.execute("PIN", "Code", false).await.is_err());
+        let hi = plain.find_by_trigger("::hi").await.unwrap().unwrap();
+        assert_eq!(hi.replacement, "PIN 5678 too");
+    }
+}
@@ -0,0 +1,93 @@
+use crate::domain::SnippetRepository;
+use anyhow::Result;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub struct ResetUsageService {
+    repository: Arc<dyn SnippetRepository>,
+}
+
+impl ResetUsageService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Zero the usage count of one snippet, or of every snippet when `id` is `None`.
+    /// Returns the number of snippets that were reset.
+    pub async fn execute(&self, id: Option<Uuid>) -> Result<u64> {
+        let reset = self.repository.reset_usage(id).await?;
+
+        match id {
+            Some(id) => log::info!("Usage count reset for snippet {}", id),
+            None => log::info!("Usage counts reset for {} snippet(s)", reset),
+        }
+
+        Ok(reset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Snippet;
+    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use tempfile::TempDir;
+
+    async fn create_test_use_case() -> (ResetUsageService, Arc<SqliteSnippetRepository>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+        let reset_use_case = ResetUsageService::new(repository.clone());
+        (reset_use_case, repository, temp_dir)
+    }
+
+    async fn save_used(repository: &SqliteSnippetRepository, trigger: &str, uses: u32) -> Snippet {
+        let mut snippet = Snippet::new(trigger.to_string(), "Used".to_string()).unwrap();
+        snippet.add_tag("shared".to_string());
+        for _ in 0..uses {
+            snippet.increment_usage();
+        }
+        repository.save(&snippet).await.unwrap();
+        snippet
+    }
+
+    #[tokio::test]
+    async fn test_reset_single_snippet() {
+        let (reset_use_case, repository, _temp_dir) = create_test_use_case().await;
+        let first = save_used(&repository, "::first", 3).await;
+        let second = save_used(&repository, "::second", 5).await;
+
+        let reset = reset_use_case.execute(Some(first.id)).await.unwrap();
+        assert_eq!(reset, 1);
+
+        let first_after = repository.find_by_id(&first.id).await.unwrap().unwrap();
+        assert_eq!(first_after.usage_count, 0);
+        assert_eq!(first_after.replacement, first.replacement);
+        assert_eq!(first_after.tags, first.tags);
+        assert_eq!(first_after.updated_at, first.updated_at);
+
+        let second_after = repository.find_by_id(&second.id).await.unwrap().unwrap();
+        assert_eq!(second_after.usage_count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_reset_all_snippets() {
+        let (reset_use_case, repository, _temp_dir) = create_test_use_case().await;
+        let snippets = vec![
+            save_used(&repository, "::first", 3).await,
+            save_used(&repository, "::second", 5).await,
+        ];
+
+        let reset = reset_use_case.execute(None).await.unwrap();
+        assert_eq!(reset, 2);
+
+        for snippet in snippets {
+            let after = repository.find_by_id(&snippet.id).await.unwrap().unwrap();
+            assert_eq!(after.usage_count, 0);
+            assert_eq!(after.trigger, snippet.trigger);
+            assert_eq!(after.is_active, snippet.is_active);
+            assert_eq!(after.updated_at, snippet.updated_at);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::domain::{DomainEvent, SnippetQuery, SnippetRepository};
+use anyhow::Result;
+use std::sync::Arc;
+
+pub struct SetActiveService {
+    repository: Arc<dyn SnippetRepository>,
+}
+
+impl SetActiveService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Activate or deactivate every snippet matching `query` with a single
+    /// update. Returns one event per snippet whose state changed.
+    pub async fn execute(&self, query: &SnippetQuery, is_active: bool) -> Result<Vec<DomainEvent>> {
+        let changed = self
+            .repository
+            .set_active_matching(query, is_active)
+            .await?;
+
+        let timestamp = chrono::Utc::now();
+        let events: Vec<DomainEvent> = changed
+            .into_iter()
+            .map(|(snippet_id, trigger)| {
+                if is_active {
+                    DomainEvent::SnippetActivated {
+                        snippet_id,
+                        trigger,
+                        timestamp,
+                    }
+                } else {
+                    DomainEvent::SnippetDeactivated {
+                        snippet_id,
+                        trigger,
+                        timestamp,
+                    }
+                }
+            })
+            .collect();
+
+        log::info!(
+            "{} {} snippet(s): {:?}",
+            if is_active {
+                "Activated"
+            } else {
+                "Deactivated"
+            },
+            events.len(),
+            events
+        );
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Snippet;
+    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use tempfile::TempDir;
+
+    async fn create_test_use_case() -> (SetActiveService, Arc<SqliteSnippetRepository>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+        let set_active_use_case = SetActiveService::new(repository.clone());
+        (set_active_use_case, repository, temp_dir)
+    }
+
+    async fn save_tagged(repository: &SqliteSnippetRepository, trigger: &str, tag: &str) {
+        let mut snippet = Snippet::new(trigger.to_string(), "Text".to_string()).unwrap();
+        snippet.add_tag(tag.to_string());
+        repository.save(&snippet).await.unwrap();
+    }
+
+    async fn is_active(repository: &SqliteSnippetRepository, trigger: &str) -> bool {
+        repository
+            .find_by_trigger(trigger)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_active
+    }
+
+    #[tokio::test]
+    async fn test_deactivate_only_matching_tag() {
+        let (set_active_use_case, repository, _temp_dir) = create_test_use_case().await;
+        save_tagged(&repository, "::old1", "archive").await;
+        save_tagged(&repository, "::old2", "archive").await;
+        save_tagged(&repository, "::current", "work").await;
+
+        let query = SnippetQuery::new().with_tags(vec!["archive".to_string()]);
+        let events = set_active_use_case.execute(&query, false).await.unwrap();
+
+        let mut triggers: Vec<&str> = events.iter().map(|e| e.trigger()).collect();
+        triggers.sort();
+        assert_eq!(triggers, vec!["::old1", "::old2"]);
+        assert!(events
+            .iter()
+            .all(|e| matches!(e, DomainEvent::SnippetDeactivated { .. })));
+
+        assert!(!is_active(&repository, "::old1").await);
+        assert!(!is_active(&repository, "::old2").await);
+        assert!(is_active(&repository, "::current").await);
+
+        // Snippets already in the requested state are not reported again
+        assert!(set_active_use_case
+            .execute(&query, false)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_activate_by_search() {
+        let (set_active_use_case, repository, _temp_dir) = create_test_use_case().await;
+        save_tagged(&repository, "::mail-home", "archive").await;
+        save_tagged(&repository, "::mail-work", "archive").await;
+        save_tagged(&repository, "::phone", "archive").await;
+
+        let archived = SnippetQuery::new().with_tags(vec!["archive".to_string()]);
+        set_active_use_case.execute(&archived, false).await.unwrap();
+
+        let query = SnippetQuery::new().with_search("mail".to_string());
+        let events = set_active_use_case.execute(&query, true).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| matches!(e, DomainEvent::SnippetActivated { .. })));
+
+        assert!(is_active(&repository, "::mail-home").await);
+        assert!(is_active(&repository, "::mail-work").await);
+        assert!(!is_active(&repository, "::phone").await);
+    }
+}
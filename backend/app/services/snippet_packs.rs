@@ -0,0 +1,163 @@
+use crate::app::dto::{
+    pack_tag, ConflictStrategy, ImportResult, ImportSnippetsRequest, SnippetPack,
+};
+use crate::app::services::{DeleteSnippetService, ImportSnippetsService};
+use crate::domain::{SnippetQuery, SnippetRepository, SortBy, SortOrder};
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Installs and uninstalls snippet packs.
+///
+/// A pack's snippets are imported with `pack:<name>` as both a tag and their
+/// `source`. Triggers the user already has are skipped rather than taken
+/// over, so uninstalling, which deletes by source, removes exactly what the
+/// pack added.
+pub struct SnippetPackService {
+    repository: Arc<dyn SnippetRepository>,
+    import_service: ImportSnippetsService,
+    delete_service: DeleteSnippetService,
+}
+
+impl SnippetPackService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self {
+            import_service: ImportSnippetsService::new(repository.clone()),
+            delete_service: DeleteSnippetService::new(repository.clone()),
+            repository,
+        }
+    }
+
+    /// A pack from its JSON file
+    pub fn parse_pack(json_data: &str) -> Result<SnippetPack> {
+        serde_json::from_str(json_data).map_err(|e| anyhow::anyhow!("Failed to parse pack: {}", e))
+    }
+
+    pub async fn install(&self, pack: SnippetPack) -> Result<ImportResult> {
+        let name = pack.name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Pack name cannot be empty"));
+        }
+
+        let tag = pack_tag(name);
+        let snippets = pack
+            .snippets
+            .into_iter()
+            .map(|mut snippet| {
+                let tags = snippet.tags.get_or_insert_with(Vec::new);
+                if !tags.contains(&tag) {
+                    tags.push(tag.clone());
+                }
+                snippet
+            })
+            .collect();
+
+        self.import_service
+            .execute(ImportSnippetsRequest {
+                snippets,
+                conflict_strategy: ConflictStrategy::Skip,
+                source: Some(tag),
+            })
+            .await
+    }
+
+    /// Delete every snippet installed from the pack `name`, returning their
+    /// triggers alphabetically
+    pub async fn uninstall(&self, name: &str) -> Result<Vec<String>> {
+        let query = SnippetQuery::new()
+            .with_source(pack_tag(name.trim()))
+            .with_sort(SortBy::Trigger, SortOrder::Asc);
+
+        let mut deleted = Vec::new();
+        for snippet in self.repository.find_all(&query).await? {
+            if self.delete_service.execute(snippet.id).await? {
+                deleted.push(snippet.trigger);
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Snippet;
+    use crate::test_support::{seeded_service, snippet};
+
+    const RUST_PACK: &str = r##"{
+        "name": "Rust",
+        "description": "Rust boilerplate",
+        "version": "1.0.0",
+        "snippets": [
+            {"trigger": "::derive", "replacement": "#[derive(Debug, Clone)]", "tags": ["rust"]},
+            {"trigger": "::test", "replacement": "#[test]\nfn it_works() {}", "tags": null},
+            {"trigger": "::mine", "replacement": "The pack's version", "tags": null}
+        ]
+    }"##;
+
+    async fn all_snippets(repository: &dyn SnippetRepository) -> Vec<Snippet> {
+        let query = SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Asc);
+        repository.find_all(&query).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_install_then_uninstall_restores_the_prior_state() {
+        let mut inactive = snippet("::old", "Switched off");
+        inactive.deactivate();
+        let service = seeded_service(vec![snippet("::mine", "Added by hand"), inactive]).await;
+        let repository = service.snippet_repository();
+        let before = all_snippets(repository.as_ref()).await;
+
+        let pack = SnippetPackService::parse_pack(RUST_PACK).unwrap();
+        let result = service.install_pack(pack).await.unwrap();
+        assert_eq!((result.imported_count, result.skipped_count), (2, 1));
+
+        let derive = repository
+            .find_by_trigger("::derive")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(derive.tags, vec!["rust", "pack:Rust"]);
+        assert_eq!(derive.source.as_deref(), Some("pack:Rust"));
+        let test = repository.find_by_trigger("::test").await.unwrap().unwrap();
+        assert_eq!(test.tags, vec!["pack:Rust"]);
+        // The user's own snippet isn't taken over by the pack
+        let mine = repository.find_by_trigger("::mine").await.unwrap().unwrap();
+        assert_eq!(
+            (mine.replacement.as_str(), mine.source),
+            ("Added by hand", None)
+        );
+
+        assert_eq!(
+            service.uninstall_pack("Rust").await.unwrap(),
+            vec!["::derive", "::test"]
+        );
+        assert_eq!(all_snippets(repository.as_ref()).await, before);
+        assert!(service.uninstall_pack("Rust").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_packs_are_uninstalled_independently() {
+        let service = seeded_service(Vec::new()).await;
+        let email = r#"{"name": "Email", "snippets": [
+            {"trigger": "::regards", "replacement": "Kind regards", "tags": null}
+        ]}"#;
+        for json in [RUST_PACK, email] {
+            let pack = SnippetPackService::parse_pack(json).unwrap();
+            service.install_pack(pack).await.unwrap();
+        }
+
+        assert_eq!(
+            service.uninstall_pack("Email").await.unwrap(),
+            vec!["::regards"]
+        );
+        assert_eq!(service.count_snippets().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pack_needs_a_name() {
+        let service = seeded_service(Vec::new()).await;
+        let pack = SnippetPackService::parse_pack(r#"{"name": " ", "snippets": []}"#).unwrap();
+        assert!(service.install_pack(pack).await.is_err());
+        assert!(SnippetPackService::parse_pack(r#"{"snippets": []}"#).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::app::dto::{ConflictStrategy, ImportSnippetsRequest, SyncResult};
+use crate::app::services::{DeleteSnippetService, ImportSnippetsService};
+use crate::domain::{MatchOptions, SnippetQuery, SnippetRepository};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Keeps the snippets from one export file in step with that file.
+///
+/// Synced snippets are recorded with the file's name as their `source`, and
+/// only snippets from that source are ever deleted, so snippets added by hand
+/// or from other files survive a sync.
+pub struct SyncSnippetsService {
+    repository: Arc<dyn SnippetRepository>,
+    import_service: ImportSnippetsService,
+    delete_service: DeleteSnippetService,
+}
+
+impl SyncSnippetsService {
+    pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
+        Self {
+            import_service: ImportSnippetsService::new(repository.clone()),
+            delete_service: DeleteSnippetService::new(repository.clone()),
+            repository,
+        }
+    }
+
+    /// Import every snippet in `json_data`, overwriting existing ones, and
+    /// with `delete_missing` remove the snippets from `source` it no longer has
+    pub async fn sync_from_json(
+        &self,
+        json_data: &str,
+        source: &str,
+        delete_missing: bool,
+    ) -> Result<SyncResult> {
+        let snippets = ImportSnippetsService::parse_export(json_data)?;
+        let options = MatchOptions::default();
+        let in_file: HashSet<String> = snippets
+            .iter()
+            .map(|snippet| options.key(&snippet.trigger).into_owned())
+            .collect();
+
+        let import = self
+            .import_service
+            .execute(ImportSnippetsRequest {
+                snippets,
+                conflict_strategy: ConflictStrategy::Overwrite,
+                source: Some(source.to_string()),
+            })
+            .await?;
+
+        let mut deleted = Vec::new();
+        if delete_missing {
+            let query = SnippetQuery::new().with_source(source.to_string());
+            for snippet in self.repository.find_all(&query).await? {
+                if !in_file.contains(&snippet.trigger)
+                    && self.delete_service.execute(snippet.id).await?
+                {
+                    deleted.push(snippet.trigger);
+                }
+            }
+        }
+
+        Ok(SyncResult { import, deleted })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use crate::test_support::snippet;
+
+    async fn sync_fixture() -> (SyncSnippetsService, Arc<SqliteSnippetRepository>) {
+        let connection = DatabaseConnection::new_in_memory().await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(connection.pool().clone()));
+        repository
+            .save(&snippet("::mine", "Added by hand"))
+            .await
+            .unwrap();
+        (SyncSnippetsService::new(repository.clone()), repository)
+    }
+
+    async fn triggers(repository: &SqliteSnippetRepository) -> Vec<String> {
+        let query = SnippetQuery::new().sort_alphabetically();
+        let mut triggers: Vec<String> = repository
+            .find_all(&query)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snippet| snippet.trigger)
+            .collect();
+        triggers.sort();
+        triggers
+    }
+
+    #[tokio::test]
+    async fn test_sync_adds_updates_and_removes() {
+        let (service, repository) = sync_fixture().await;
+
+        let first = r#"[
+            {"trigger": "::a", "replacement": "A", "tags": null},
+            {"trigger": "::b", "replacement": "B", "tags": null}
+        ]"#;
+        let result = service.sync_from_json(first, "team", true).await.unwrap();
+        assert_eq!(result.import.imported_count, 2);
+        assert!(result.deleted.is_empty());
+        assert_eq!(triggers(&repository).await, vec!["::a", "::b", "::mine"]);
+
+        let second = r#"[
+            {"trigger": "::b", "replacement": "B, changed", "tags": null},
+            {"trigger": "::c", "replacement": "C", "tags": null}
+        ]"#;
+        let result = service.sync_from_json(second, "team", true).await.unwrap();
+        assert_eq!(result.deleted, vec!["::a"]);
+        assert_eq!(triggers(&repository).await, vec!["::b", "::c", "::mine"]);
+
+        let b = repository.find_by_trigger("::b").await.unwrap().unwrap();
+        assert_eq!(b.replacement, "B, changed");
+    }
+
+    #[tokio::test]
+    async fn test_sync_can_keep_missing_snippets() {
+        let (service, repository) = sync_fixture().await;
+
+        let first = r#"[{"trigger": "::a", "replacement": "A", "tags": null}]"#;
+        service.sync_from_json(first, "team", true).await.unwrap();
+        let result = service.sync_from_json("[]", "team", false).await.unwrap();
+
+        assert!(result.deleted.is_empty());
+        assert_eq!(triggers(&repository).await, vec!["::a", "::mine"]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_file_changes_nothing() {
+        let (service, repository) = sync_fixture().await;
+
+        let first = r#"[{"trigger": "::a", "replacement": "A", "tags": null}]"#;
+        service.sync_from_json(first, "team", true).await.unwrap();
+        assert!(service.sync_from_json("[{", "team", true).await.is_err());
+
+        assert_eq!(triggers(&repository).await, vec!["::a", "::mine"]);
+    }
+}
@@ -1,175 +1,707 @@
 use crate::app::dto::*;
+use crate::app::metrics::Metrics;
 use crate::app::services::*;
-use crate::domain::repositories::SnippetRepository;
-use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
-use anyhow::Result;
+use crate::domain::repositories::{FragmentRepository, SnippetRepository};
+use crate::domain::{
+    ExpansionRules, FileAccess, Fragment, SearchField, SnippetQuery, ValidationIssue,
+};
+use crate::infra::{
+    get_profile_database_path, AppliedMigration, DatabaseConnection, ExpansionLogger,
+    HashMapFragmentRepository, HashMapSnippetRepository, MigrationRunner, SqliteFragmentRepository,
+    SqliteSnippetRepository,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use chrono_tz::Tz;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use uuid::Uuid;
 
-/// Main application service that coordinates all services
+/// Main application service that coordinates all services.
+///
+/// Every method takes `&self` and the service is `Send + Sync`: its only
+/// mutable state is atomics and the database pool, which runs queries
+/// concurrently. Share it as `Arc<TypelyService>` rather than behind a lock.
 pub struct TypelyService {
-    pub create_snippet: CreateSnippetService,
-    pub update_snippet: UpdateSnippetService,
-    pub delete_snippet: DeleteSnippetService,
-    pub get_snippet: GetSnippetService,
-    pub list_snippets: ListSnippetsService,
-    pub expand_snippet: ExpandSnippetService,
-    pub import_snippets: ImportSnippetsService,
-    pub export_snippets: ExportSnippetsService,
+    pub create_snippet_service: CreateSnippetService,
+    pub update_snippet_service: UpdateSnippetService,
+    pub delete_snippet_service: DeleteSnippetService,
+    pub get_snippet_service: GetSnippetService,
+    pub list_snippets_service: ListSnippetsService,
+    pub expand_snippet_service: ExpandSnippetService,
+    pub import_snippets_service: ImportSnippetsService,
+    pub export_snippets_service: ExportSnippetsService,
+    pub reset_usage_service: ResetUsageService,
+    pub rename_tag_service: RenameTagService,
+    pub reorder_snippets_service: ReorderSnippetsService,
+    pub replace_text_service: ReplaceTextService,
+    pub set_active_service: SetActiveService,
+    pub validate_snippets_service: ValidateSnippetsService,
+    pub sync_snippets_service: SyncSnippetsService,
+    pub snippet_pack_service: SnippetPackService,
+    repository: Arc<dyn SnippetRepository>,
+    fragment_repository: Arc<dyn FragmentRepository>,
+    snippet_changes: Arc<AtomicU64>,
+    /// `None` for a service built from repositories alone
+    database_connection: Option<DatabaseConnection>,
 }
 
 impl TypelyService {
+    /// Encrypted snippets use the passphrase in `TYPELY_PASSPHRASE`, if set
     pub async fn new(database_connection: DatabaseConnection) -> Self {
-        let repository: Arc<dyn SnippetRepository> = Arc::new(SqliteSnippetRepository::new(
-            database_connection.pool().clone(),
-        ));
+        let repository = SqliteSnippetRepository::from_env(database_connection.pool().clone());
+        Self::with_snippet_repository(database_connection, Arc::new(repository))
+    }
+
+    /// A service whose snippet services all go through `repository`, e.g. a
+    /// `DryRunSnippetRepository` wrapping the database's own repository
+    pub fn with_snippet_repository(
+        database_connection: DatabaseConnection,
+        repository: Arc<dyn SnippetRepository>,
+    ) -> Self {
+        let fragment_repository: Arc<dyn FragmentRepository> = Arc::new(
+            SqliteFragmentRepository::new(database_connection.pool().clone()),
+        );
+
+        Self {
+            database_connection: Some(database_connection),
+            ..Self::with_repositories(repository, fragment_repository)
+        }
+    }
 
+    /// A service over any repositories, with no database behind it:
+    /// migrations are then a no-op
+    pub fn with_repositories(
+        repository: Arc<dyn SnippetRepository>,
+        fragment_repository: Arc<dyn FragmentRepository>,
+    ) -> Self {
         Self {
-            create_snippet: CreateSnippetService::new(repository.clone()),
-            update_snippet: UpdateSnippetService::new(repository.clone()),
-            delete_snippet: DeleteSnippetService::new(repository.clone()),
-            get_snippet: GetSnippetService::new(repository.clone()),
-            list_snippets: ListSnippetsService::new(repository.clone()),
-            expand_snippet: ExpandSnippetService::new(repository.clone()),
-            import_snippets: ImportSnippetsService::new(repository.clone()),
-            export_snippets: ExportSnippetsService::new(repository.clone()),
+            create_snippet_service: CreateSnippetService::new(repository.clone()),
+            update_snippet_service: UpdateSnippetService::new(repository.clone()),
+            delete_snippet_service: DeleteSnippetService::new(repository.clone()),
+            get_snippet_service: GetSnippetService::new(repository.clone()),
+            list_snippets_service: ListSnippetsService::new(repository.clone()),
+            expand_snippet_service: ExpandSnippetService::new(repository.clone())
+                .with_fragment_repository(fragment_repository.clone()),
+            import_snippets_service: ImportSnippetsService::new(repository.clone()),
+            export_snippets_service: ExportSnippetsService::new(repository.clone()),
+            reset_usage_service: ResetUsageService::new(repository.clone()),
+            rename_tag_service: RenameTagService::new(repository.clone()),
+            reorder_snippets_service: ReorderSnippetsService::new(repository.clone()),
+            replace_text_service: ReplaceTextService::new(repository.clone()),
+            set_active_service: SetActiveService::new(repository.clone()),
+            validate_snippets_service: ValidateSnippetsService::new(
+                repository.clone(),
+                fragment_repository.clone(),
+            ),
+            sync_snippets_service: SyncSnippetsService::new(repository.clone()),
+            snippet_pack_service: SnippetPackService::new(repository.clone()),
+            repository,
+            fragment_repository,
+            snippet_changes: Arc::new(AtomicU64::new(0)),
+            database_connection: None,
+        }
+    }
+
+    /// A service over the named profile's database, created on first use
+    pub async fn for_profile(name: &str) -> Result<Self> {
+        let database_path = get_profile_database_path(name)?;
+        let database_connection = DatabaseConnection::new(&database_path).await?;
+        Ok(Self::new(database_connection).await)
+    }
+
+    /// Log every expansion attempt through `expansion_logger`
+    pub fn with_expansion_logger(mut self, expansion_logger: Arc<ExpansionLogger>) -> Self {
+        self.expand_snippet_service = self
+            .expand_snippet_service
+            .with_expansion_logger(expansion_logger);
+        self
+    }
+
+    /// Let `{file:PATH}` placeholders read files from `file_access.base_dir`
+    pub fn with_file_access(mut self, file_access: FileAccess) -> Self {
+        self.expand_snippet_service = self.expand_snippet_service.with_file_access(file_access);
+        self
+    }
+
+    /// Show `{date}`, `{time}` and the like in `timezone` (the system's local
+    /// time zone unless set)
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.expand_snippet_service = self.expand_snippet_service.with_timezone(timezone);
+        self
+    }
+
+    /// Post-process every expansion with the built-in transforms `rules` turns on
+    pub fn with_expansion_rules(mut self, rules: ExpansionRules) -> Self {
+        self.expand_snippet_service = self.expand_snippet_service.with_expansion_rules(rules);
+        self
+    }
+
+    /// Only expand `{env:NAME}` placeholders for the named environment variables
+    pub fn with_env_allowlist(mut self, names: Vec<String>) -> Self {
+        self.expand_snippet_service = self.expand_snippet_service.with_env_allowlist(names);
+        self
+    }
+
+    /// Keep triggers out of expansion logs, which then only count expansions
+    pub fn with_privacy_mode(mut self, privacy_mode: bool) -> Self {
+        self.expand_snippet_service = self.expand_snippet_service.with_privacy_mode(privacy_mode);
+        self
+    }
+
+    /// A service backed by a private in-memory database, discarded on drop
+    pub async fn new_in_memory() -> Result<Self> {
+        let database_connection = DatabaseConnection::new_in_memory().await?;
+        Ok(Self::new(database_connection).await)
+    }
+
+    /// A service over `HashMapSnippetRepository` and
+    /// `HashMapFragmentRepository`, without SQLite at all
+    pub fn new_without_database() -> Self {
+        Self::with_repositories(
+            Arc::new(HashMapSnippetRepository::new()),
+            Arc::new(HashMapFragmentRepository::new()),
+        )
+    }
+
+    /// The repository every snippet service shares
+    pub fn snippet_repository(&self) -> Arc<dyn SnippetRepository> {
+        Arc::clone(&self.repository)
+    }
+
+    /// Counter bumped after each change made through this service to which
+    /// snippets exist or are active, so caches can tell when they are stale
+    pub fn snippet_changes(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.snippet_changes)
+    }
+
+    fn record_change<T>(&self, result: Result<T>) -> Result<T> {
+        if result.is_ok() {
+            self.snippet_changes.fetch_add(1, Ordering::Release);
         }
+        result
     }
 
     // Convenience methods that delegate to use cases
     pub async fn create_snippet(&self, request: CreateSnippetRequest) -> Result<SnippetDto> {
-        self.create_snippet.execute(request).await
+        self.record_change(self.create_snippet_service.execute(request).await)
     }
 
     pub async fn update_snippet(&self, request: UpdateSnippetRequest) -> Result<SnippetDto> {
-        self.update_snippet.execute(request).await
+        self.record_change(self.update_snippet_service.execute(request).await)
     }
 
     pub async fn delete_snippet(&self, id: Uuid) -> Result<bool> {
-        self.delete_snippet.execute(id).await
+        self.record_change(self.delete_snippet_service.execute(id).await)
+    }
+
+    /// Create a snippet triggered by `new_trigger` with a copy of another
+    /// snippet's replacement, tags and settings. Aliases are not copied, though
+    /// case variants are made for the new trigger, and the copy starts unused.
+    pub async fn duplicate_snippet(&self, id: Uuid, new_trigger: String) -> Result<SnippetDto> {
+        let original = self
+            .get_snippet(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Snippet not found"))?;
+        if original.locked {
+            return Err(anyhow::anyhow!(
+                "'{}' can't be copied: its replacement couldn't be decrypted",
+                original.trigger
+            ));
+        }
+
+        let request = CreateSnippetRequest {
+            trigger: new_trigger,
+            replacement: original.replacement,
+            tags: Some(original.tags),
+            content_type: Some(original.content_type),
+            confirm_before_expand: Some(original.confirm_before_expand),
+            keep_trigger: Some(original.keep_trigger),
+            aliases: None,
+            type_delay_ms: original.type_delay_ms,
+            generate_case_variants: original.case_variants,
+            encrypted: original.encrypted,
+        };
+        self.create_snippet(request).await
     }
 
     pub async fn get_snippet(&self, id: Uuid) -> Result<Option<SnippetDto>> {
-        self.get_snippet.execute(id).await
+        self.get_snippet_service.execute(id).await
+    }
+
+    /// The snippet with what it would expand to now; usage isn't counted
+    pub async fn get_snippet_preview(&self, id: Uuid) -> Result<Option<(SnippetDto, String)>> {
+        self.get_snippet_service
+            .execute_with_preview(id, &self.expand_snippet_service)
+            .await
     }
 
     pub async fn get_snippet_by_trigger(&self, trigger: &str) -> Result<Option<SnippetDto>> {
-        self.get_snippet.execute_by_trigger(trigger).await
+        self.get_snippet_service.execute_by_trigger(trigger).await
+    }
+
+    /// Zero usage counts for one snippet, or every snippet when `id` is `None`
+    pub async fn reset_usage(&self, id: Option<Uuid>) -> Result<u64> {
+        self.reset_usage_service.execute(id).await
+    }
+
+    /// Arrange snippets by hand: `ordered_ids` come first when listing with
+    /// the `position` sort, in the order given. Unknown ids are skipped.
+    /// Returns the number of snippets placed.
+    pub async fn reorder_snippets(&self, ordered_ids: Vec<Uuid>) -> Result<u64> {
+        self.reorder_snippets_service.execute(&ordered_ids).await
+    }
+
+    pub async fn snippet_history(&self, id: Uuid) -> Result<Vec<SnippetVersionDto>> {
+        self.get_snippet_service.history(id).await
+    }
+
+    /// Trigger and replacement edits at or after `since`, across all
+    /// snippets, newest first. Creating, deleting and (de)activating
+    /// snippets isn't recorded.
+    pub async fn edits_since(&self, since: DateTime<Utc>) -> Result<Vec<SnippetVersionDto>> {
+        self.get_snippet_service.edits_since(since).await
+    }
+
+    /// How often a snippet was used in total and since local midnight
+    pub async fn snippet_usage(&self, id: Uuid) -> Result<Option<SnippetUsageDto>> {
+        let midnight = Local::now()
+            .date_naive()
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .context("Local midnight doesn't exist today")?;
+        self.get_snippet_service
+            .usage(id, midnight.with_timezone(&Utc))
+            .await
     }
 
     pub async fn list_snippets(&self, request: SnippetListRequest) -> Result<SnippetListResponse> {
-        self.list_snippets.execute(request).await
+        self.list_snippets_service.execute(request).await
+    }
+
+    /// `list_snippets` for list views, with previews instead of full replacements
+    pub async fn list_summaries(
+        &self,
+        request: SnippetListRequest,
+    ) -> Result<SnippetSummaryListResponse> {
+        self.list_snippets_service.list_summaries(request).await
     }
 
     pub async fn expand_snippet(&self, request: ExpansionRequest) -> Result<ExpansionResponse> {
-        self.expand_snippet.execute(request).await
+        self.expand_snippet_service.execute(request).await
+    }
+
+    pub async fn expand_text(&self, input: &str) -> Result<String> {
+        self.expand_snippet_service.expand_text(input).await
+    }
+
+    /// Lint every snippet: unknown placeholders, missing or cyclic fragments,
+    /// blank replacements and triggers that shadow each other
+    pub async fn validate_all(&self) -> Result<Vec<ValidationIssue>> {
+        self.validate_snippets_service.execute().await
+    }
+
+    /// Create a fragment, or replace the content of the one named `name`
+    pub async fn save_fragment(&self, name: &str, content: &str) -> Result<FragmentDto> {
+        let fragment = Fragment::new(name.to_string(), content.to_string())?;
+        self.fragment_repository.save(&fragment).await?;
+        self.get_fragment(name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Fragment '{}' was not saved", name))
+    }
+
+    pub async fn get_fragment(&self, name: &str) -> Result<Option<FragmentDto>> {
+        Ok(self
+            .fragment_repository
+            .find_by_name(name)
+            .await?
+            .map(FragmentDto::from))
+    }
+
+    pub async fn list_fragments(&self) -> Result<Vec<FragmentDto>> {
+        let fragments = self.fragment_repository.find_all().await?;
+        Ok(fragments.into_iter().map(FragmentDto::from).collect())
+    }
+
+    pub async fn delete_fragment(&self, name: &str) -> Result<bool> {
+        self.fragment_repository.delete(name).await
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.expand_snippet_service.metrics()
     }
 
     pub async fn find_matching_snippets(&self, text: &str) -> Result<Vec<String>> {
-        self.expand_snippet.find_matching_snippets(text).await
+        self.expand_snippet_service
+            .find_matching_snippets(text)
+            .await
     }
 
     pub async fn import_snippets(&self, request: ImportSnippetsRequest) -> Result<ImportResult> {
-        self.import_snippets.execute(request).await
+        self.record_change(self.import_snippets_service.execute(request).await)
     }
 
     pub async fn import_from_json(&self, json_data: &str, overwrite: bool) -> Result<ImportResult> {
-        self.import_snippets
-            .import_from_json(json_data, overwrite)
-            .await
+        self.record_change(
+            self.import_snippets_service
+                .import_from_json(json_data, overwrite)
+                .await,
+        )
+    }
+
+    pub async fn import_from_json_with_strategy(
+        &self,
+        json_data: &str,
+        strategy: ConflictStrategy,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        self.record_change(
+            self.import_snippets_service
+                .import_from_json_with_strategy(json_data, strategy, source)
+                .await,
+        )
+    }
+
+    /// Import an export file, reporting records that can't be read instead
+    /// of failing on them
+    pub async fn import_from_json_lenient(
+        &self,
+        json_data: &str,
+        strategy: ConflictStrategy,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        self.record_change(
+            self.import_snippets_service
+                .import_from_json_lenient(json_data, strategy, source)
+                .await,
+        )
+    }
+
+    /// Import snippets from CSV; unreadable rows are reported, not fatal
+    pub async fn import_from_csv_with_strategy(
+        &self,
+        csv_data: &str,
+        strategy: ConflictStrategy,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        self.record_change(
+            self.import_snippets_service
+                .import_from_csv_with_strategy(csv_data, strategy, source)
+                .await,
+        )
+    }
+
+    /// Import each `*.txt` file in `dir` as a snippet named after the file;
+    /// unreadable files are reported, not fatal
+    pub async fn import_from_directory(
+        &self,
+        dir: &std::path::Path,
+        strategy: ConflictStrategy,
+        prefix: Option<&str>,
+        source: Option<String>,
+    ) -> Result<ImportResult> {
+        self.record_change(
+            self.import_snippets_service
+                .import_from_directory(dir, strategy, prefix, source)
+                .await,
+        )
+    }
+
+    /// Import the JSON export at `path` with `ConflictStrategy::Overwrite`,
+    /// recording the file stem as the snippets' source. With `delete_missing`,
+    /// snippets from that source the file no longer contains are deleted.
+    pub async fn sync_from_file(&self, path: &Path, delete_missing: bool) -> Result<SyncResult> {
+        let json_data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let source = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow::anyhow!("Invalid sync file path: {}", path.display()))?;
+
+        self.record_change(
+            self.sync_snippets_service
+                .sync_from_json(&json_data, &source, delete_missing)
+                .await,
+        )
+    }
+
+    /// Import a pack's snippets tagged and sourced `pack:<name>`, skipping
+    /// triggers that already exist
+    pub async fn install_pack(&self, pack: SnippetPack) -> Result<ImportResult> {
+        self.record_change(self.snippet_pack_service.install(pack).await)
+    }
+
+    /// Delete the snippets installed from the pack `name`, returning their
+    /// triggers
+    pub async fn uninstall_pack(&self, name: &str) -> Result<Vec<String>> {
+        self.record_change(self.snippet_pack_service.uninstall(name).await)
     }
 
     pub async fn export_snippets(
         &self,
         request: ExportSnippetsRequest,
     ) -> Result<Vec<ImportSnippetData>> {
-        self.export_snippets.execute(request).await
+        self.export_snippets_service.execute(request).await
     }
 
     pub async fn export_to_json(&self, request: ExportSnippetsRequest) -> Result<String> {
-        self.export_snippets.export_to_json(request).await
+        self.export_snippets_service.export_to_json(request).await
+    }
+
+    pub async fn export_to_writer<W: std::io::Write + Send>(
+        &self,
+        request: ExportSnippetsRequest,
+        writer: W,
+    ) -> Result<usize> {
+        self.export_snippets_service
+            .export_to_writer(request, writer)
+            .await
+    }
+
+    pub async fn export_to_csv<W: std::io::Write + Send>(
+        &self,
+        request: ExportSnippetsRequest,
+        writer: W,
+    ) -> Result<usize> {
+        self.export_snippets_service
+            .export_to_csv(request, writer)
+            .await
+    }
+
+    /// `trigger,usage_count` CSV rows for every snippet, sorted by trigger
+    pub async fn export_usage_csv<W: std::io::Write + Send>(&self, writer: W) -> Result<usize> {
+        self.export_snippets_service.export_usage_csv(writer).await
+    }
+
+    pub async fn export_shell_aliases<W: std::io::Write + Send>(
+        &self,
+        request: ExportSnippetsRequest,
+        writer: W,
+    ) -> Result<ShellAliasExport> {
+        self.export_snippets_service
+            .export_shell_aliases(request, writer)
+            .await
     }
 
     pub async fn export_all_to_json(&self) -> Result<String> {
-        self.export_snippets.export_all_to_json().await
+        self.export_snippets_service.export_all_to_json().await
+    }
+
+    fn migration_runner(&self) -> Option<MigrationRunner> {
+        self.database_connection
+            .as_ref()
+            .map(DatabaseConnection::migration_runner)
+    }
+
+    pub async fn run_migrations(&self) -> Result<()> {
+        match self.migration_runner() {
+            Some(runner) => runner.run_migrations().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Apply pending migrations up to and including `version`, see
+    /// `MigrationRunner::run_migrations_to`
+    pub async fn run_migrations_to(&self, version: &str) -> Result<()> {
+        match self.migration_runner() {
+            Some(runner) => runner.run_migrations_to(version).await,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn migration_status(&self) -> Result<Vec<AppliedMigration>> {
+        match self.migration_runner() {
+            Some(runner) => runner.status().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn pending_migrations(&self) -> Result<Vec<&'static str>> {
+        match self.migration_runner() {
+            Some(runner) => runner.pending().await,
+            None => Ok(Vec::new()),
+        }
     }
 
     // Additional convenience methods
     pub async fn get_all_active_snippets(&self) -> Result<Vec<SnippetDto>> {
         let request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: None,
             is_active: Some(true),
             limit: None,
             offset: None,
             sort_by: Some("updated".to_string()),
             sort_order: Some("desc".to_string()),
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let response = self.list_snippets(request).await?;
         Ok(response.snippets)
     }
 
+    /// Total number of snippets, active or not
+    pub async fn count_snippets(&self) -> Result<u64> {
+        self.list_snippets_service.count_all().await
+    }
+
+    /// Typo-tolerant search over active snippets, returning each match with its score
+    pub async fn fuzzy_search(&self, term: &str, limit: u32) -> Result<Vec<(SnippetDto, i64)>> {
+        self.list_snippets_service.fuzzy_search(term, limit).await
+    }
+
+    /// Active snippets whose replacement matches the regular expression
+    /// `pattern`. Scans every active snippet rather than querying the database.
+    pub async fn search_by_regex(&self, pattern: &str) -> Result<Vec<SnippetDto>> {
+        self.list_snippets_service.regex_search(pattern).await
+    }
+
     pub async fn search_snippets(&self, search_term: &str) -> Result<Vec<SnippetDto>> {
+        let response = self
+            .list_snippets(Self::search_request(search_term))
+            .await?;
+        Ok(response.snippets)
+    }
+
+    /// Like `search_snippets`, looking for `search_term` only in `fields`
+    pub async fn search_snippets_in(
+        &self,
+        search_term: &str,
+        fields: &[SearchField],
+    ) -> Result<Vec<SnippetDto>> {
         let request = SnippetListRequest {
+            search_fields: Some(fields.to_vec()),
+            ..Self::search_request(search_term)
+        };
+        Ok(self.list_snippets(request).await?.snippets)
+    }
+
+    /// Same results as `search_snippets`, with the byte ranges to highlight
+    pub async fn search_highlighted(&self, search_term: &str) -> Result<Vec<MatchedSnippetDto>> {
+        self.list_snippets_service
+            .search_with_highlights(Self::search_request(search_term))
+            .await
+    }
+
+    fn search_request(search_term: &str) -> SnippetListRequest {
+        SnippetListRequest {
             search_term: Some(search_term.to_string()),
+            search_fields: None,
             tags: None,
             is_active: Some(true),
             limit: Some(50),
             offset: None,
             sort_by: Some("usage".to_string()),
             sort_order: Some("desc".to_string()),
-        };
-
-        let response = self.list_snippets(request).await?;
-        Ok(response.snippets)
+            tag_match: None,
+            source: None,
+            after: None,
+        }
     }
 
     pub async fn get_snippets_by_tag(&self, tag: &str) -> Result<Vec<SnippetDto>> {
         let request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: Some(vec![tag.to_string()]),
             is_active: Some(true),
             limit: None,
             offset: None,
             sort_by: Some("updated".to_string()),
             sort_order: Some("desc".to_string()),
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let response = self.list_snippets(request).await?;
         Ok(response.snippets)
     }
 
+    /// The `limit` most used active snippets, without counting or paging
+    /// as `list_snippets` does
     pub async fn get_most_used_snippets(&self, limit: u32) -> Result<Vec<SnippetDto>> {
-        let request = SnippetListRequest {
-            search_term: None,
-            tags: None,
-            is_active: Some(true),
-            limit: Some(limit),
-            offset: None,
-            sort_by: Some("usage".to_string()),
-            sort_order: Some("desc".to_string()),
-        };
+        self.list_snippets_service.most_used(limit).await
+    }
 
-        let response = self.list_snippets(request).await?;
-        Ok(response.snippets)
+    /// Snippet counts, total usage and tags, listing the `most_used` most
+    /// used active snippets
+    pub async fn statistics(&self, most_used: u32) -> Result<StatisticsDto> {
+        self.list_snippets_service.statistics(most_used).await
+    }
+
+    /// One active snippet picked at random, e.g. for a "try this snippet"
+    /// tip. `None` when there are no active snippets.
+    pub async fn random_snippet(&self) -> Result<Option<SnippetDto>> {
+        Ok(self.list_snippets_service.random(1).await?.pop())
+    }
+
+    /// Up to `count` different active snippets picked at random
+    pub async fn random_snippets(&self, count: u32) -> Result<Vec<SnippetDto>> {
+        self.list_snippets_service.random(count).await
     }
 
     pub async fn get_recent_snippets(&self, limit: u32) -> Result<Vec<SnippetDto>> {
         let request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: None,
             is_active: Some(true),
             limit: Some(limit),
             offset: None,
             sort_by: Some("updated".to_string()),
             sort_order: Some("desc".to_string()),
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let response = self.list_snippets(request).await?;
         Ok(response.snippets)
     }
 
+    /// Rename tag `from` to `to` on every snippet, merging it into `to` where
+    /// a snippet has both. Returns the number of snippets that changed.
+    pub async fn rename_tag(&self, from: &str, to: &str) -> Result<u32> {
+        self.record_change(self.rename_tag_service.execute(from, to).await)
+    }
+
+    /// Replace `find` with `replace` in every snippet's replacement, e.g. an
+    /// old domain after a rebrand. With `dry_run` nothing changes. Returns
+    /// the number of snippets changed, or that would be.
+    pub async fn replace_in_replacements(
+        &self,
+        find: &str,
+        replace: &str,
+        dry_run: bool,
+    ) -> Result<u32> {
+        let result = self
+            .replace_text_service
+            .execute(find, replace, dry_run)
+            .await;
+        if dry_run {
+            result
+        } else {
+            self.record_change(result)
+        }
+    }
+
+    /// Activate or deactivate every snippet matching `query`'s filters at
+    /// once. Returns the number of snippets that changed.
+    pub async fn set_active_for_matching(
+        &self,
+        query: &SnippetQuery,
+        is_active: bool,
+    ) -> Result<u64> {
+        let events = self.record_change(self.set_active_service.execute(query, is_active).await)?;
+        Ok(events.len() as u64)
+    }
+
     pub async fn activate_snippet(&self, id: Uuid) -> Result<SnippetDto> {
         let request = UpdateSnippetRequest {
             id,
@@ -177,6 +709,16 @@ impl TypelyService {
             replacement: None,
             tags: None,
             is_active: Some(true),
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
         };
 
         self.update_snippet(request).await
@@ -189,6 +731,16 @@ impl TypelyService {
             replacement: None,
             tags: None,
             is_active: Some(false),
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
         };
 
         self.update_snippet(request).await
@@ -199,6 +751,7 @@ impl TypelyService {
 mod tests {
     use super::*;
     use crate::infra::DatabaseConnection;
+    use crate::test_support::{seeded_service, snippet};
     use tempfile::TempDir;
 
     async fn create_test_service() -> (TypelyService, TempDir) {
@@ -215,9 +768,8 @@ mod tests {
 
         // Create a snippet
         let create_request = CreateSnippetRequest {
-            trigger: "::hello".to_string(),
-            replacement: "Hello, World!".to_string(),
             tags: Some(vec!["greeting".to_string()]),
+            ..CreateSnippetRequest::new("::hello", "Hello, World!")
         };
 
         let created = service.create_snippet(create_request).await.unwrap();
@@ -230,12 +782,16 @@ mod tests {
         // List snippets
         let list_request = SnippetListRequest {
             search_term: None,
+            search_fields: None,
             tags: None,
             is_active: None,
             limit: None,
             offset: None,
             sort_by: None,
             sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
         };
 
         let list_response = service.list_snippets(list_request).await.unwrap();
@@ -263,9 +819,8 @@ mod tests {
         // Create some test snippets
         for i in 1..=3 {
             let request = CreateSnippetRequest {
-                trigger: format!("::test{}", i),
-                replacement: format!("Test {}", i),
                 tags: Some(vec!["test".to_string()]),
+                ..CreateSnippetRequest::new(format!("::test{}", i), format!("Test {}", i))
             };
             service.create_snippet(request).await.unwrap();
         }
@@ -282,4 +837,414 @@ mod tests {
         let tagged_snippets = service.get_snippets_by_tag("test").await.unwrap();
         assert_eq!(tagged_snippets.len(), 3);
     }
+
+    #[tokio::test]
+    async fn test_random_snippets() {
+        let (service, _temp_dir) = create_test_service().await;
+        assert!(service.random_snippet().await.unwrap().is_none());
+        assert!(service.random_snippets(3).await.unwrap().is_empty());
+
+        let mut active = Vec::new();
+        for i in 1..=3 {
+            let request = CreateSnippetRequest::new(format!("::tip{}", i), format!("Tip {}", i));
+            active.push(service.create_snippet(request).await.unwrap().id);
+        }
+        let retired = CreateSnippetRequest::new("::retired", "Retired");
+        let retired = service.create_snippet(retired).await.unwrap();
+        service.deactivate_snippet(retired.id).await.unwrap();
+
+        for _ in 0..10 {
+            let tip = service.random_snippet().await.unwrap().unwrap();
+            assert!(tip.is_active);
+            assert!(active.contains(&tip.id));
+        }
+
+        // More than there are gives every active snippet
+        let mut all: Vec<Uuid> = service
+            .random_snippets(10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snippet| snippet.id)
+            .collect();
+        all.sort();
+        active.sort();
+        assert_eq!(all, active);
+    }
+
+    #[tokio::test]
+    async fn test_statistics_and_usage_csv() {
+        let mut busy = snippet("::busy", "Busy");
+        busy.add_tag("work".to_string());
+        busy.usage_count = 1_234;
+        let mut old = snippet("::old", "Old");
+        old.add_tag("archive".to_string());
+        old.add_tag("work".to_string());
+        old.usage_count = 2;
+        old.deactivate();
+        let service = seeded_service(vec![busy, old, snippet("::new", "New")]).await;
+
+        let stats = service.statistics(5).await.unwrap();
+        assert_eq!(stats.total_snippets, 3);
+        assert_eq!(stats.active_snippets, 2);
+        assert_eq!(stats.inactive_snippets, 1);
+        assert_eq!(stats.total_usage, 1_236);
+        assert_eq!(stats.tags, vec!["archive", "work"]);
+        let most_used: Vec<&str> = stats.most_used.iter().map(|s| s.trigger.as_str()).collect();
+        assert_eq!(most_used, vec!["::busy", "::new"]);
+
+        // Plain digits, with no thousands separators to split a CSV field
+        let mut csv = Vec::new();
+        assert_eq!(service.export_usage_csv(&mut csv).await.unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "trigger,usage_count\n::busy,1234\n::new,0\n::old,2\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reorder_snippets() {
+        let (service, _temp_dir) = create_test_service().await;
+        let mut ids = Vec::new();
+        for trigger in ["::first", "::second", "::third"] {
+            let request = CreateSnippetRequest::new(trigger, "Text");
+            ids.push(service.create_snippet(request).await.unwrap().id);
+        }
+
+        let placed = service
+            .reorder_snippets(vec![ids[1], Uuid::new_v4(), ids[2], ids[0]])
+            .await
+            .unwrap();
+        assert_eq!(placed, 3);
+
+        let request = SnippetListRequest {
+            search_term: None,
+            search_fields: None,
+            tags: None,
+            is_active: None,
+            limit: None,
+            offset: None,
+            sort_by: Some("position".to_string()),
+            sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
+        };
+        let listed: Vec<(String, Option<u32>)> = service
+            .list_snippets(request)
+            .await
+            .unwrap()
+            .snippets
+            .into_iter()
+            .map(|snippet| (snippet.trigger, snippet.position))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("::second".to_string(), Some(0)),
+                ("::third".to_string(), Some(1)),
+                ("::first".to_string(), Some(2)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snippet_changes_count_successful_writes() {
+        let service = TypelyService::new_in_memory().await.unwrap();
+        let changes = service.snippet_changes();
+        let generation = || changes.load(Ordering::Acquire);
+
+        let create = || CreateSnippetRequest::new("::hi", "Hello");
+        let snippet = service.create_snippet(create()).await.unwrap();
+        assert_eq!(generation(), 1);
+
+        // A rejected write changes nothing
+        assert!(service.create_snippet(create()).await.is_err());
+        assert_eq!(generation(), 1);
+
+        service.deactivate_snippet(snippet.id).await.unwrap();
+        service.delete_snippet(snippet.id).await.unwrap();
+        assert_eq!(generation(), 3);
+
+        service.get_all_active_snippets().await.unwrap();
+        assert_eq!(generation(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_service_without_database() {
+        let service = TypelyService::new_without_database();
+        service.save_fragment("sig", "Jane").await.unwrap();
+        let created = service
+            .create_snippet(CreateSnippetRequest::new("::bye", "Bye, {fragment:sig}"))
+            .await
+            .unwrap();
+
+        let expansion = service
+            .expand_snippet(ExpansionRequest {
+                trigger: "::bye".to_string(),
+                context: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(expansion.expanded_text.unwrap(), "Bye, Jane");
+        assert_eq!(service.count_snippets().await.unwrap(), 1);
+        assert!(service.delete_snippet(created.id).await.unwrap());
+
+        service.run_migrations().await.unwrap();
+        assert!(service.migration_status().await.unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_shared_service_handles_concurrent_calls() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TypelyService>();
+
+        let (service, _temp_dir) = create_test_service().await;
+        for i in 0..20 {
+            service
+                .create_snippet(CreateSnippetRequest::new(
+                    format!("::s{}", i),
+                    format!("Snippet {}", i),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let service = Arc::new(service);
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let service = Arc::clone(&service);
+                tokio::spawn(async move {
+                    let request = SnippetListRequest {
+                        search_term: None,
+                        search_fields: None,
+                        tags: None,
+                        tag_match: None,
+                        is_active: None,
+                        source: None,
+                        limit: None,
+                        offset: None,
+                        after: None,
+                        sort_by: None,
+                        sort_order: None,
+                    };
+                    service.list_snippets(request).await.unwrap().total_count
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 20);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_snippet() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let original = service
+            .create_snippet(CreateSnippetRequest {
+                tags: Some(vec!["email".to_string()]),
+                confirm_before_expand: Some(true),
+                aliases: Some(vec!["::signature".to_string()]),
+                type_delay_ms: Some(20),
+                ..CreateSnippetRequest::new("::sig", "Best,\nJane")
+            })
+            .await
+            .unwrap();
+        let expand = |trigger: &str| ExpansionRequest {
+            trigger: trigger.to_string(),
+            context: None,
+        };
+        service.expand_snippet(expand("::sig")).await.unwrap();
+
+        let copy = service
+            .duplicate_snippet(original.id, "::sig2".to_string())
+            .await
+            .unwrap();
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.trigger, "::sig2");
+        assert_eq!(copy.replacement, original.replacement);
+        assert_eq!(copy.tags, original.tags);
+        assert!(copy.confirm_before_expand);
+        assert_eq!(copy.type_delay_ms, Some(20));
+        assert!(copy.aliases.is_empty());
+        assert_eq!(copy.usage_count, 0);
+
+        // Usage is counted separately from here on
+        service.expand_snippet(expand("::sig2")).await.unwrap();
+        service.expand_snippet(expand("::sig2")).await.unwrap();
+        let usage = |trigger: &'static str| {
+            let service = &service;
+            async move {
+                service
+                    .get_snippet_by_trigger(trigger)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .usage_count
+            }
+        };
+        assert_eq!(usage("::sig").await, 1);
+        assert_eq!(usage("::sig2").await, 2);
+
+        // The new trigger has to be free
+        assert!(service
+            .duplicate_snippet(original.id, "::signature".to_string())
+            .await
+            .is_err());
+        assert!(service
+            .duplicate_snippet(Uuid::new_v4(), "::other".to_string())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sync_from_file_follows_the_file() {
+        let (service, temp_dir) = create_test_service().await;
+        let path = temp_dir.path().join("team.json");
+
+        std::fs::write(
+            &path,
+            r#"[{"trigger": "::a", "replacement": "A", "tags": null},
+                {"trigger": "::b", "replacement": "B", "tags": null}]"#,
+        )
+        .unwrap();
+        let result = service.sync_from_file(&path, true).await.unwrap();
+        assert_eq!(result.import.imported_count, 2);
+
+        std::fs::write(
+            &path,
+            r#"[{"trigger": "::b", "replacement": "B", "tags": null}]"#,
+        )
+        .unwrap();
+        let result = service.sync_from_file(&path, true).await.unwrap();
+        assert_eq!(result.deleted, vec!["::a"]);
+
+        let b = service
+            .get_snippet_by_trigger("::b")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(b.source.as_deref(), Some("team"));
+        assert!(service
+            .get_snippet_by_trigger("::a")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_migration_status() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let applied = service.migration_status().await.unwrap();
+        assert_eq!(applied.len(), crate::infra::MIGRATIONS.len());
+        assert!(service.pending_migrations().await.unwrap().is_empty());
+    }
+
+    /// Pins the signature of every public method; callers across the clients
+    /// rely on these, so a change here should be deliberate.
+    #[allow(clippy::too_many_arguments)]
+    async fn public_surface(
+        service: &TypelyService,
+        create: CreateSnippetRequest,
+        update: UpdateSnippetRequest,
+        list: SnippetListRequest,
+        expansion: ExpansionRequest,
+        import: ImportSnippetsRequest,
+        export: ExportSnippetsRequest,
+        id: Uuid,
+    ) {
+        let _: Result<TypelyService> = TypelyService::new_in_memory().await;
+        let _: TypelyService = TypelyService::new_without_database();
+        let _: Result<TypelyService> = TypelyService::for_profile("work").await;
+        let _: Result<SnippetDto> = service.create_snippet(create).await;
+        let _: Result<SnippetDto> = service.update_snippet(update).await;
+        let _: Result<bool> = service.delete_snippet(id).await;
+        let _: Result<Option<SnippetDto>> = service.get_snippet(id).await;
+        let _: Result<Option<(SnippetDto, String)>> = service.get_snippet_preview(id).await;
+        let _: Result<Option<SnippetDto>> = service.get_snippet_by_trigger("").await;
+        let _: Result<u64> = service.reset_usage(Some(id)).await;
+        let _: Result<u64> = service.reorder_snippets(vec![id]).await;
+        let _: Result<u32> = service.rename_tag("", "").await;
+        let _: Result<u32> = service.replace_in_replacements("", "", true).await;
+        let _: Result<Vec<SnippetVersionDto>> = service.snippet_history(id).await;
+        let _: Result<Vec<SnippetVersionDto>> = service.edits_since(Utc::now()).await;
+        let _: Result<Option<SnippetUsageDto>> = service.snippet_usage(id).await;
+        let _: Result<SnippetListResponse> = service.list_snippets(list.clone()).await;
+        let _: Result<SnippetSummaryListResponse> = service.list_summaries(list).await;
+        let _: Result<ExpansionResponse> = service.expand_snippet(expansion).await;
+        let _: Result<String> = service.expand_text("").await;
+        let _: Result<Vec<SnippetDto>> = service.search_by_regex("").await;
+        let _: Arc<Metrics> = service.metrics();
+        let _: Result<u64> = service.count_snippets().await;
+        let _: Result<Vec<ValidationIssue>> = service.validate_all().await;
+        let _: Result<FragmentDto> = service.save_fragment("", "").await;
+        let _: Result<Option<FragmentDto>> = service.get_fragment("").await;
+        let _: Result<Vec<FragmentDto>> = service.list_fragments().await;
+        let _: Result<bool> = service.delete_fragment("").await;
+        let _: Result<u64> = service
+            .set_active_for_matching(&SnippetQuery::new(), true)
+            .await;
+        let _: Result<Vec<String>> = service.find_matching_snippets("").await;
+        let _: Result<ImportResult> = service.import_snippets(import).await;
+        let _: Result<ImportResult> = service.import_from_json("", false).await;
+        let _: Result<ImportResult> = service
+            .import_from_json_with_strategy("", ConflictStrategy::Skip, None)
+            .await;
+        let _: Result<Vec<ImportSnippetData>> = service.export_snippets(export.clone()).await;
+        let _: Result<String> = service.export_to_json(export.clone()).await;
+        let _: Result<usize> = service.export_to_writer(export.clone(), Vec::new()).await;
+        let _: Result<usize> = service.export_to_csv(export.clone(), Vec::new()).await;
+        let _: Result<ImportResult> = service
+            .import_from_csv_with_strategy("", ConflictStrategy::Skip, None)
+            .await;
+        let _: Result<ImportResult> = service
+            .import_from_json_lenient("", ConflictStrategy::Skip, None)
+            .await;
+        let _: Result<ImportResult> = service
+            .import_from_directory(std::path::Path::new(""), ConflictStrategy::Skip, None, None)
+            .await;
+        let _: Result<SnippetDto> = service.duplicate_snippet(Uuid::nil(), String::new()).await;
+        let _: Result<SyncResult> = service.sync_from_file(std::path::Path::new(""), true).await;
+        let pack = SnippetPack {
+            name: String::new(),
+            description: None,
+            version: None,
+            author: None,
+            snippets: Vec::new(),
+        };
+        let _: Result<ImportResult> = service.install_pack(pack).await;
+        let _: Result<Vec<String>> = service.uninstall_pack("").await;
+        let _: Result<ShellAliasExport> = service.export_shell_aliases(export, Vec::new()).await;
+        let _: Result<String> = service.export_all_to_json().await;
+        let _: Result<()> = service.run_migrations().await;
+        let _: Result<Vec<AppliedMigration>> = service.migration_status().await;
+        let _: Result<Vec<&'static str>> = service.pending_migrations().await;
+        let _: Result<Vec<SnippetDto>> = service.get_all_active_snippets().await;
+        let _: Result<Vec<(SnippetDto, i64)>> = service.fuzzy_search("", 10).await;
+        let _: Result<Vec<SnippetDto>> = service.search_snippets("").await;
+        let _: Result<Vec<SnippetDto>> = service
+            .search_snippets_in("", &[SearchField::Trigger])
+            .await;
+        let _: Result<Vec<MatchedSnippetDto>> = service.search_highlighted("").await;
+        let _: Arc<dyn SnippetRepository> = service.snippet_repository();
+        let _: Arc<AtomicU64> = service.snippet_changes();
+        let _: Result<Vec<SnippetDto>> = service.get_snippets_by_tag("").await;
+        let _: Result<Vec<SnippetDto>> = service.get_most_used_snippets(10).await;
+        let _: Result<StatisticsDto> = service.statistics(5).await;
+        let _: Result<usize> = service.export_usage_csv(Vec::new()).await;
+        let _: Result<Option<SnippetDto>> = service.random_snippet().await;
+        let _: Result<Vec<SnippetDto>> = service.random_snippets(3).await;
+        let _: Result<Vec<SnippetDto>> = service.get_recent_snippets(10).await;
+        let _: Result<SnippetDto> = service.activate_snippet(id).await;
+        let _: Result<SnippetDto> = service.deactivate_snippet(id).await;
+    }
+
+    #[test]
+    fn test_public_surface_compiles() {
+        // Only needs to type-check; the body is exercised by the other tests
+        let _ = public_surface;
+    }
 }
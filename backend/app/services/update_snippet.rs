@@ -1,15 +1,31 @@
 use crate::app::dto::{SnippetDto, UpdateSnippetRequest};
-use crate::domain::{DomainEvent, SnippetRepository};
+use crate::domain::{
+    normalize_trigger, DomainEvent, SnippetQuery, SnippetRepository, SnippetVersion,
+};
 use anyhow::Result;
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// Number of past versions kept per snippet unless overridden
+pub const DEFAULT_MAX_SNIPPET_VERSIONS: usize = 50;
 
 pub struct UpdateSnippetService {
     repository: Arc<dyn SnippetRepository>,
+    max_versions: usize,
 }
 
 impl UpdateSnippetService {
     pub fn new(repository: Arc<dyn SnippetRepository>) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            max_versions: DEFAULT_MAX_SNIPPET_VERSIONS,
+        }
+    }
+
+    /// Override how many past versions are retained per snippet
+    pub fn with_max_versions(mut self, max_versions: usize) -> Self {
+        self.max_versions = max_versions;
+        self
     }
 
     pub async fn execute(&self, request: UpdateSnippetRequest) -> Result<SnippetDto> {
@@ -19,21 +35,34 @@ impl UpdateSnippetService {
             .find_by_id(&request.id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Snippet not found"))?;
+        let previous = SnippetVersion::of(&snippet);
+
+        // The old replacement goes in the history when the trigger or
+        // replacement changes, and is re-stored when encryption changes, so
+        // those need it decrypted
+        if request.trigger.is_some() || request.replacement.is_some() || request.encrypted.is_some()
+        {
+            snippet.ensure_unlocked()?;
+        }
 
         // Update trigger if provided
         if let Some(new_trigger) = request.trigger {
             if new_trigger != snippet.trigger {
-                // Check if the new trigger already exists
-                if self.repository.exists_with_trigger(&new_trigger).await? {
-                    return Err(anyhow::anyhow!(
-                        "A snippet with trigger '{}' already exists",
-                        new_trigger
-                    ));
-                }
+                // Check if the new trigger already exists (it may be one of our own aliases)
+                self.ensure_trigger_available(&new_trigger, snippet.id)
+                    .await?;
                 snippet.update_trigger(new_trigger)?;
             }
         }
 
+        // Replace aliases if provided
+        if let Some(aliases) = request.aliases {
+            snippet.set_aliases(aliases)?;
+            for alias in &snippet.aliases {
+                self.ensure_trigger_available(alias, snippet.id).await?;
+            }
+        }
+
         // Update replacement if provided
         if let Some(new_replacement) = request.replacement {
             snippet.update_replacement(new_replacement)?;
@@ -48,6 +77,52 @@ impl UpdateSnippetService {
             }
         }
 
+        // Update content type if provided
+        if let Some(content_type) = request.content_type {
+            snippet.set_content_type(content_type);
+        }
+
+        // Update confirmation requirement if provided
+        if let Some(confirm_before_expand) = request.confirm_before_expand {
+            snippet.set_confirm_before_expand(confirm_before_expand);
+        }
+
+        // Update whether the trigger is kept if provided
+        if let Some(keep_trigger) = request.keep_trigger {
+            snippet.set_keep_trigger(keep_trigger);
+        }
+
+        // Update or clear the typing delay if provided
+        if let Some(type_delay_ms) = request.type_delay_ms {
+            snippet.set_type_delay_ms(type_delay_ms);
+        }
+
+        // Pin or unpin if provided
+        if let Some(pinned) = request.pinned {
+            snippet.set_pinned(pinned);
+        }
+
+        // Start or stop encrypting the replacement if asked
+        if let Some(encrypted) = request.encrypted {
+            snippet.set_encrypted(encrypted);
+        }
+
+        // Bind or remove the hotkey if provided
+        if let Some(hotkey) = request.hotkey {
+            snippet.set_hotkey(hotkey.as_deref())?;
+            if let Some(hotkey) = &snippet.hotkey {
+                self.ensure_hotkey_available(hotkey, snippet.id).await?;
+            }
+        }
+
+        // Change either end of the active window if provided
+        if request.active_from.is_some() || request.active_until.is_some() {
+            snippet.set_active_window(
+                request.active_from.unwrap_or(snippet.active_from),
+                request.active_until.unwrap_or(snippet.active_until),
+            )?;
+        }
+
         // Update active status if provided
         if let Some(is_active) = request.is_active {
             if is_active && !snippet.is_active {
@@ -60,6 +135,13 @@ impl UpdateSnippetService {
         // Save to repository
         self.repository.update(&snippet).await?;
 
+        // Keep the old trigger/replacement around if either changed
+        if previous.trigger != snippet.trigger || previous.replacement != snippet.replacement {
+            self.repository
+                .record_version(&previous, self.max_versions)
+                .await?;
+        }
+
         // Log domain event
         let event = DomainEvent::SnippetUpdated {
             snippet_id: snippet.id,
@@ -70,6 +152,37 @@ impl UpdateSnippetService {
 
         Ok(SnippetDto::from(snippet))
     }
+
+    /// Fail if `trigger` is the trigger or an alias of a snippet other than `id`
+    async fn ensure_trigger_available(&self, trigger: &str, id: Uuid) -> Result<()> {
+        match self
+            .repository
+            .find_by_trigger(&normalize_trigger(trigger))
+            .await?
+        {
+            Some(other) if other.id != id => Err(anyhow::anyhow!(
+                "A snippet with trigger '{}' already exists",
+                trigger
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if `hotkey` (in canonical form) is bound to a snippet other than `id`
+    async fn ensure_hotkey_available(&self, hotkey: &str, id: Uuid) -> Result<()> {
+        let snippets = self.repository.find_all(&SnippetQuery::new()).await?;
+        match snippets
+            .iter()
+            .find(|other| other.id != id && other.hotkey.as_deref() == Some(hotkey))
+        {
+            Some(other) => Err(anyhow::anyhow!(
+                "Hotkey {} is already bound to '{}'",
+                hotkey,
+                other.trigger
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -95,11 +208,7 @@ mod tests {
         let (update_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Original".to_string(),
-            tags: None,
-        };
+        let create_request = CreateSnippetRequest::new("::test", "Original");
         let created = create_use_case.execute(create_request).await.unwrap();
 
         // Update the replacement
@@ -109,6 +218,16 @@ mod tests {
             replacement: Some("Updated replacement".to_string()),
             tags: None,
             is_active: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
         };
 
         let updated = update_use_case.execute(update_request).await.unwrap();
@@ -122,11 +241,7 @@ mod tests {
         let (update_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::old".to_string(),
-            replacement: "Test".to_string(),
-            tags: None,
-        };
+        let create_request = CreateSnippetRequest::new("::old", "Test");
         let created = create_use_case.execute(create_request).await.unwrap();
 
         // Update the trigger
@@ -136,6 +251,16 @@ mod tests {
             replacement: None,
             tags: None,
             is_active: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
         };
 
         let updated = update_use_case.execute(update_request).await.unwrap();
@@ -149,11 +274,7 @@ mod tests {
         let (update_use_case, create_use_case, _temp_dir) = create_test_use_case().await;
 
         // Create a snippet first
-        let create_request = CreateSnippetRequest {
-            trigger: "::test".to_string(),
-            replacement: "Test".to_string(),
-            tags: None,
-        };
+        let create_request = CreateSnippetRequest::new("::test", "Test");
         let created = create_use_case.execute(create_request).await.unwrap();
         assert!(created.is_active);
 
@@ -164,6 +285,16 @@ mod tests {
             replacement: None,
             tags: None,
             is_active: Some(false),
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
         };
 
         let updated = update_use_case.execute(update_request).await.unwrap();
@@ -180,10 +311,172 @@ mod tests {
             replacement: Some("Test".to_string()),
             tags: None,
             is_active: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
         };
 
         let result = update_use_case.execute(update_request).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("not found"));
     }
+
+    #[tokio::test]
+    async fn test_update_records_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+        let update_use_case = UpdateSnippetService::new(repository.clone());
+        let create_use_case = CreateSnippetService::new(repository.clone());
+
+        let create_request = CreateSnippetRequest::new("::sig", "First");
+        let created = create_use_case.execute(create_request).await.unwrap();
+
+        for replacement in ["Second", "Third"] {
+            let update_request = UpdateSnippetRequest {
+                id: created.id,
+                trigger: None,
+                replacement: Some(replacement.to_string()),
+                tags: None,
+                is_active: None,
+                content_type: None,
+                confirm_before_expand: None,
+                keep_trigger: None,
+                aliases: None,
+                type_delay_ms: None,
+                pinned: None,
+                hotkey: None,
+                active_from: None,
+                active_until: None,
+                encrypted: None,
+            };
+            update_use_case.execute(update_request).await.unwrap();
+        }
+
+        // Status-only changes don't add versions
+        let deactivate_request = UpdateSnippetRequest {
+            id: created.id,
+            trigger: None,
+            replacement: None,
+            tags: None,
+            is_active: Some(false),
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
+        };
+        update_use_case.execute(deactivate_request).await.unwrap();
+
+        let history = repository.history(&created.id).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].replacement, "Second");
+        assert_eq!(history[1].replacement, "First");
+        assert!(history[0].changed_at >= history[1].changed_at);
+        assert!(history.iter().all(|version| version.trigger == "::sig"));
+    }
+
+    #[tokio::test]
+    async fn test_update_aliases() {
+        use crate::test_support::{seeded_service, snippet};
+
+        let mut address = snippet("::address", "1 Main Street");
+        address.set_aliases(vec!["::addr".to_string()]).unwrap();
+        let service = seeded_service([address.clone(), snippet("::home", "Home")]).await;
+        let request = |trigger: Option<&str>, aliases: Option<Vec<&str>>| UpdateSnippetRequest {
+            id: address.id,
+            trigger: trigger.map(str::to_string),
+            replacement: None,
+            tags: None,
+            is_active: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: aliases.map(|a| a.into_iter().map(str::to_string).collect()),
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
+        };
+
+        // Promoting an alias to the trigger is not a collision with ourselves
+        let updated = service
+            .update_snippet(request(Some("::addr"), None))
+            .await
+            .unwrap();
+        assert_eq!(updated.trigger, "::addr");
+        assert!(updated.aliases.is_empty());
+
+        let error = service
+            .update_snippet(request(None, Some(vec!["::home"])))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("'::home' already exists"));
+
+        let updated = service
+            .update_snippet(request(None, Some(vec!["::address", "::adr"])))
+            .await
+            .unwrap();
+        assert_eq!(updated.aliases, vec!["::address", "::adr"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_hotkey() {
+        use crate::test_support::{seeded_service, snippet};
+
+        let sig = snippet("::sig", "Regards");
+        let home = snippet("::home", "Home");
+        let service = seeded_service([sig.clone(), home.clone()]).await;
+        let request = |id: Uuid, hotkey: Option<&str>| UpdateSnippetRequest {
+            id,
+            trigger: None,
+            replacement: None,
+            tags: None,
+            is_active: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: None,
+            hotkey: Some(hotkey.map(str::to_string)),
+            active_from: None,
+            active_until: None,
+            encrypted: None,
+        };
+
+        let updated = service
+            .update_snippet(request(sig.id, Some("alt + ctrl + s")))
+            .await
+            .unwrap();
+        assert_eq!(updated.hotkey.as_deref(), Some("Ctrl+Alt+S"));
+
+        let error = service
+            .update_snippet(request(home.id, Some("Ctrl+Alt+S")))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("already bound to '::sig'"));
+        assert!(service
+            .update_snippet(request(home.id, Some("Shift+H")))
+            .await
+            .is_err());
+
+        let updated = service.update_snippet(request(sig.id, None)).await.unwrap();
+        assert_eq!(updated.hotkey, None);
+    }
 }
@@ -0,0 +1,83 @@
+use crate::domain::{
+    validate_snippets, FragmentRepository, SnippetQuery, SnippetRepository, ValidationIssue,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct ValidateSnippetsService {
+    repository: Arc<dyn SnippetRepository>,
+    fragment_repository: Arc<dyn FragmentRepository>,
+}
+
+impl ValidateSnippetsService {
+    pub fn new(
+        repository: Arc<dyn SnippetRepository>,
+        fragment_repository: Arc<dyn FragmentRepository>,
+    ) -> Self {
+        Self {
+            repository,
+            fragment_repository,
+        }
+    }
+
+    /// Check every stored snippet, active or not
+    pub async fn execute(&self) -> Result<Vec<ValidationIssue>> {
+        let snippets = self.repository.find_all(&SnippetQuery::new()).await?;
+        let fragments: HashMap<String, String> = self
+            .fragment_repository
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|fragment| (fragment.name, fragment.content))
+            .collect();
+
+        Ok(validate_snippets(&snippets, &fragments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{IssueSeverity, Snippet};
+    use crate::infra::{DatabaseConnection, SqliteFragmentRepository, SqliteSnippetRepository};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_validate_reports_seeded_issues() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_connection = DatabaseConnection::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let repository = Arc::new(SqliteSnippetRepository::new(db_connection.pool().clone()));
+        let fragments = Arc::new(SqliteFragmentRepository::new(db_connection.pool().clone()));
+        let validate_use_case = ValidateSnippetsService::new(repository.clone(), fragments);
+
+        for (trigger, replacement) in [
+            ("::today", "Today is {dat}"),
+            ("::addr", "1 Main St"),
+            ("::addr-work", "2 Office Rd"),
+            ("::ok", "Fine on {date}"),
+        ] {
+            let snippet = Snippet::new(trigger.to_string(), replacement.to_string()).unwrap();
+            repository.save(&snippet).await.unwrap();
+        }
+
+        let issues = validate_use_case.execute().await.unwrap();
+        let reported: Vec<(&str, IssueSeverity)> = issues
+            .iter()
+            .map(|issue| (issue.trigger.as_str(), issue.severity))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                ("::addr-work", IssueSeverity::Warning),
+                ("::today", IssueSeverity::Warning),
+            ]
+        );
+        assert!(issues[0]
+            .message
+            .contains("'::addr' (from '::addr') expands first"));
+        assert!(issues[1].message.contains("{dat}"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Run with `cargo bench -p typely --bench expansion`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::{HashMap, VecDeque};
+use typely::domain::{ExpansionService, Snippet, TriggerDetectionService, TriggerTrie};
+
+fn snippets(count: usize) -> Vec<Snippet> {
+    (0..count)
+        .map(|i| {
+            Snippet::new(
+                format!("::snippet{}", i),
+                format!("Replacement text number {}", i),
+            )
+            .unwrap()
+        })
+        .collect()
+}
+
+/// Roughly `words` words of prose with a trigger every tenth word, mixing
+/// known triggers with ones that have no snippet
+fn document(words: usize, snippet_count: usize) -> String {
+    (0..words)
+        .map(|i| match i % 10 {
+            0 if i % 20 == 0 => format!("::snippet{}", (i / 20) % snippet_count),
+            0 => format!("::missing{}", i),
+            _ => "lorem".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bench_find_triggers(c: &mut Criterion) {
+    let service = ExpansionService::new();
+    let mut group = c.benchmark_group("find_triggers");
+
+    for words in [1_000, 10_000, 100_000] {
+        let text = document(words, 100);
+        group.bench_with_input(BenchmarkId::from_parameter(words), &text, |b, text| {
+            b.iter(|| service.find_triggers(black_box(text)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_replace_in_text(c: &mut Criterion) {
+    let service = ExpansionService::new().with_emoji_shortcodes(false);
+    let mut group = c.benchmark_group("replace_in_text");
+
+    for (words, snippet_count) in [(1_000, 10), (10_000, 100), (100_000, 1_000)] {
+        let owned = snippets(snippet_count);
+        let by_trigger: HashMap<String, &Snippet> =
+            owned.iter().map(|s| (s.trigger.clone(), s)).collect();
+        let text = document(words, snippet_count);
+
+        group.bench_with_input(
+            BenchmarkId::new(format!("{}_snippets", snippet_count), words),
+            &text,
+            |b, text| b.iter(|| service.replace_in_text(black_box(text), &by_trigger)),
+        );
+    }
+
+    group.finish();
+}
+
+/// Typing `text` one character at a time, looking for a trigger at the end
+/// after each like the engine does: by searching the last 100 characters,
+/// or by following a trie of the known triggers
+fn bench_detect_while_typing(c: &mut Criterion) {
+    let detection = TriggerDetectionService::new();
+    let mut group = c.benchmark_group("detect_while_typing");
+
+    for snippet_count in [10, 100, 1_000] {
+        let triggers: Vec<String> = snippets(snippet_count)
+            .into_iter()
+            .map(|snippet| snippet.trigger)
+            .collect();
+        let trie = TriggerTrie::new(&triggers).unwrap();
+        let text = document(1_000, snippet_count);
+
+        group.bench_with_input(
+            BenchmarkId::new("search", snippet_count),
+            &text,
+            |b, text| {
+                b.iter(|| {
+                    let mut buffer = VecDeque::with_capacity(100);
+                    let mut found = 0;
+                    for c in text.chars() {
+                        if buffer.len() == 100 {
+                            buffer.pop_front();
+                        }
+                        buffer.push_back(c);
+                        let typed: String = buffer.iter().collect();
+                        let at_end = detection
+                            .find_triggers_in_text(&typed)
+                            .pop()
+                            .filter(|found| found.end_position == typed.len());
+                        found += at_end.is_some() as usize;
+                    }
+                    black_box(found)
+                })
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("trie", snippet_count), &text, |b, text| {
+            b.iter(|| {
+                let mut cursor = trie.cursor();
+                let mut found = 0;
+                for c in text.chars() {
+                    trie.advance(&mut cursor, c.encode_utf8(&mut [0; 4]));
+                    found += trie.trigger_at(&cursor).is_some() as usize;
+                }
+                black_box(found)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_find_triggers,
+    bench_replace_in_text,
+    bench_detect_while_typing
+);
+criterion_main!(benches);
@@ -1,24 +1,89 @@
 use anyhow::Result;
-use log::info;
+use clap::Parser;
+use log::{info, warn};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use typely::app::services::TypelyService;
 use typely::infra::database::DatabaseConnection;
-use typely::infra::engine::TextExpansionEngine;
+use typely::infra::engine::{
+    get_default_status_path, ExpansionConfig, TextExpansionEngine, STATUS_WRITE_INTERVAL_SECS,
+};
 use typely::infra::get_default_database_path;
 
+/// Typely text expansion engine
+#[derive(Parser)]
+#[command(name = "typely", version)]
+struct EngineArgs {
+    /// Characters of recent typing searched for triggers. Longer triggers
+    /// can't be typed.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    buffer_size: Option<u64>,
+
+    /// Keep typed keys, triggers and expanded text out of the logs; only
+    /// counts are recorded
+    #[arg(long)]
+    privacy_mode: bool,
+}
+
+impl EngineArgs {
+    fn expansion_config(&self) -> ExpansionConfig {
+        let mut config = ExpansionConfig::default();
+        if let Some(buffer_size) = self.buffer_size {
+            config.buffer_size = buffer_size as usize;
+        }
+        config.privacy_mode = self.privacy_mode;
+        config
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = EngineArgs::parse();
     env_logger::init();
 
     let db_path = get_default_database_path()?;
     info!("Using database: {}", db_path.display());
 
     let db_connection = DatabaseConnection::new(&db_path).await?;
-    let service = TypelyService::new(db_connection).await;
+    let service = TypelyService::new(db_connection)
+        .await
+        .with_privacy_mode(args.privacy_mode);
 
     info!("Starting Typely text expansion engine...");
-    let engine = TextExpansionEngine::new(Arc::new(service), None)?;
+    let engine = TextExpansionEngine::new(Arc::new(service), Some(args.expansion_config()))?;
     engine.start().await?;
 
+    // Publish status until interrupted so `typely-cli status` can report on us
+    let status_path = get_default_status_path()?;
+    let mut interval = tokio::time::interval(Duration::from_secs(STATUS_WRITE_INTERVAL_SECS));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                // The CLI edits snippets from another process, so re-read them now and then
+                engine.invalidate_trigger_cache().await;
+                write_status(&engine, &status_path).await;
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    info!("Stopping Typely text expansion engine...");
+    if let Err(e) = engine.shutdown().await {
+        warn!("{}", e);
+    }
+    write_status(&engine, &status_path).await;
+
     Ok(())
 }
+
+async fn write_status(engine: &TextExpansionEngine, path: &Path) {
+    let result = match engine.status().await {
+        Ok(status) => status.write_to(path),
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        warn!("Failed to write engine status: {}", e);
+    }
+}
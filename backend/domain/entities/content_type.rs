@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a snippet's replacement should be inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ContentType {
+    /// Typed as plain text
+    #[default]
+    Plain,
+    /// Pasted as HTML via the clipboard
+    Html,
+    /// Rendered to HTML, then pasted like `Html`
+    Markdown,
+}
+
+impl ContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Plain => "plain",
+            ContentType::Html => "html",
+            ContentType::Markdown => "markdown",
+        }
+    }
+
+    /// Whether the replacement is inserted through the clipboard as rich text
+    pub fn is_rich(&self) -> bool {
+        !matches!(self, ContentType::Plain)
+    }
+
+    /// Produce the HTML to paste for rich content, or `None` for plain text
+    pub fn render_html(&self, text: &str) -> Option<String> {
+        match self {
+            ContentType::Plain => None,
+            ContentType::Html => Some(text.to_string()),
+            ContentType::Markdown => Some(markdown_to_html(text)),
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" | "text" => Ok(ContentType::Plain),
+            "html" => Ok(ContentType::Html),
+            "markdown" | "md" => Ok(ContentType::Markdown),
+            other => Err(anyhow::anyhow!(
+                "Unknown content type '{}' (expected plain, html or markdown)",
+                other
+            )),
+        }
+    }
+}
+
+pub fn markdown_to_html(markdown: &str) -> String {
+    let parser = pulldown_cmark::Parser::new(markdown);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_to_html() {
+        let html = markdown_to_html("**Jane Doe**\n\n*Engineer*");
+        assert_eq!(
+            html,
+            "<p><strong>Jane Doe</strong></p>\n<p><em>Engineer</em></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_html_by_content_type() {
+        assert_eq!(ContentType::Plain.render_html("**hi**"), None);
+        assert_eq!(
+            ContentType::Html.render_html("<b>hi</b>"),
+            Some("<b>hi</b>".to_string())
+        );
+        assert_eq!(
+            ContentType::Markdown.render_html("**hi**"),
+            Some("<p><strong>hi</strong></p>\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_content_type() {
+        assert_eq!("plain".parse::<ContentType>().unwrap(), ContentType::Plain);
+        assert_eq!("HTML".parse::<ContentType>().unwrap(), ContentType::Html);
+        assert_eq!("md".parse::<ContentType>().unwrap(), ContentType::Markdown);
+        assert!("rtf".parse::<ContentType>().is_err());
+    }
+}
@@ -1,4 +1,18 @@
+use super::{ExpansionRules, FragmentLimits, MatchOptions, TextTransform, TriggerPrefix};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    // Triggers under the default `::` prefix, compiled once and shared
+    static ref TRIGGER_REGEX: Regex = TriggerPrefix::default().trigger_regex();
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExpansionContext {
@@ -66,18 +80,433 @@ impl ExpansionResult {
     }
 }
 
+/// Placeholders replaced at expansion time
+pub const PLACEHOLDERS: &[&str] = &[
+    "{date}",
+    "{time}",
+    "{datetime}",
+    "{utcdate}",
+    "{timestamp}",
+    "{user}",
+];
+
+/// Starts of placeholders that take an argument, e.g. `{date:%d/%m/%Y}`
+const PARAMETERIZED_PLACEHOLDERS: &[&str] = &["{date:", "{time:", "{datetime:", "{env:", "{file:"];
+
+/// Whether `text` changes when expanded, through placeholders or emoji shortcodes
+pub fn has_dynamic_content(text: &str) -> bool {
+    PLACEHOLDERS
+        .iter()
+        .chain(PARAMETERIZED_PLACEHOLDERS)
+        .any(|placeholder| text.contains(placeholder))
+        || super::shortcodes::expand_shortcodes(text) != text
+}
+
+/// Names of the `{name}`-style tokens in `text`, skipping escaped braces.
+/// Only word-like names count, so braces in code or JSON aren't reported.
+pub fn placeholder_names(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        let tail = &rest[pos..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            rest = &tail[2..];
+            continue;
+        }
+
+        rest = &tail[1..];
+        let Some(inner) = tail.strip_prefix('{') else {
+            continue;
+        };
+        let end = inner
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':')))
+            .unwrap_or(inner.len());
+        if end > 0 && inner[end..].starts_with('}') {
+            names.push(&inner[..end]);
+            rest = &inner[end + 1..];
+        }
+    }
+
+    names
+}
+
+/// Prefix of `{env:NAME}` placeholders
+const ENV_PREFIX: &str = "env:";
+const FILE_PREFIX: &str = "file:";
+
+/// Whether `{name}` is one of the built-in `PLACEHOLDERS`, an `{env:NAME}`
+/// or a usable `{date:…}`-style placeholder
+pub fn is_known_placeholder(name: &str) -> bool {
+    name.strip_prefix(ENV_PREFIX)
+        .or_else(|| name.strip_prefix(FILE_PREFIX))
+        .is_some_and(|arg| !arg.is_empty())
+        || parse_date_time(name).is_some_and(|spec| spec.is_ok())
+        || PLACEHOLDERS
+            .iter()
+            .any(|placeholder| placeholder[1..placeholder.len() - 1] == *name)
+}
+
+/// A `{date:…}`, `{time:…}` or `{datetime:…}` placeholder
+struct DateTimeSpec<'a> {
+    format: &'a str,
+    /// Days added to the current date
+    days: i64,
+}
+
+/// Parse the name of a placeholder with a custom format: `date`, `time` and
+/// `datetime` take a strftime format after a colon (`{time:%I:%M %p}`), and
+/// `date` also takes a day offset, optionally followed by a format
+/// (`{date:+3d}`, `{date:-1d:%A}`). `None` if `name` isn't one of these,
+/// `Err` with the reason if it is but can't be rendered.
+fn parse_date_time(name: &str) -> Option<Result<DateTimeSpec<'_>, String>> {
+    let (kind, argument) = name.split_once(':')?;
+    let default_format = match kind {
+        "date" => "%Y-%m-%d",
+        "time" => "%H:%M:%S",
+        "datetime" => "%Y-%m-%d %H:%M:%S",
+        _ => return None,
+    };
+
+    let (days, format) = if kind == "date" && argument.starts_with(['+', '-']) {
+        match parse_day_offset(argument) {
+            Some((days, format)) => (days, format.unwrap_or(default_format)),
+            None => return Some(Err(format!("invalid day offset '{}'", argument))),
+        }
+    } else {
+        (0, argument)
+    };
+
+    let invalid =
+        format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error));
+    if invalid {
+        return Some(Err(format!("invalid date/time format '{}'", format)));
+    }
+    Some(Ok(DateTimeSpec { format, days }))
+}
+
+/// `+3d` or `-1d`, optionally followed by `:FORMAT`
+fn parse_day_offset(argument: &str) -> Option<(i64, Option<&str>)> {
+    let end = argument.find('d')?;
+    let days = argument[..end].parse().ok()?;
+    match &argument[end + 1..] {
+        "" => Some((days, None)),
+        rest => Some((days, Some(rest.strip_prefix(':')?))),
+    }
+}
+
+fn render_date_time(name: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    let spec = match parse_date_time(name)? {
+        Ok(spec) => spec,
+        Err(reason) => {
+            log::warn!("Not expanding {{{}}}: {}", name, reason);
+            return None;
+        }
+    };
+
+    let Some(date) =
+        chrono::Duration::try_days(spec.days).and_then(|days| now.checked_add_signed(days))
+    else {
+        log::warn!("Not expanding {{{}}}: date out of range", name);
+        return None;
+    };
+    Some(date.format(spec.format).to_string())
+}
+
+/// The time zone named `name`, e.g. `Europe/Berlin`
+pub fn parse_timezone(name: &str) -> anyhow::Result<Tz> {
+    name.parse().map_err(|_| {
+        anyhow::anyhow!(
+            "Unknown time zone '{}' (expected an IANA name such as Europe/Berlin)",
+            name
+        )
+    })
+}
+
+/// `now` as seen in `timezone`, or in the system's local time zone without one
+fn local_time(now: DateTime<Utc>, timezone: Option<Tz>) -> DateTime<FixedOffset> {
+    match timezone {
+        Some(timezone) => now.with_timezone(&timezone).fixed_offset(),
+        None => now.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+/// Replace placeholders in a single pass. Dates and times are in the
+/// system's local time zone, except `{utcdate}`. Doubled braces (`{{`, `}}`) render as
+/// literal braces, and unknown or unavailable placeholders are left untouched.
+///
+/// Only the environment variables in `env_allowlist` are read, or any of
+/// them without one. `{env:NAME}` for any other variable is left as typed;
+/// an allowed variable that isn't set renders as nothing.
+pub fn render_placeholders_with_env(text: &str, env_allowlist: Option<&HashSet<String>>) -> String {
+    render_placeholders_at(text, env_allowlist, None, local_time(Utc::now(), None))
+}
+
+fn render_placeholders_at(
+    text: &str,
+    env_allowlist: Option<&HashSet<String>>,
+    file_access: Option<&FileAccess>,
+    now: DateTime<FixedOffset>,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        result.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            result.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+
+        let value = tail
+            .strip_prefix('{')
+            .and_then(|inner| inner.find(['{', '}']).map(|end| &inner[..end]))
+            .filter(|name| tail[1 + name.len()..].starts_with('}'))
+            .and_then(|name| {
+                let value = if let Some(var) = name.strip_prefix(ENV_PREFIX) {
+                    env_value(var, env_allowlist)?
+                } else if let Some(path) = name.strip_prefix(FILE_PREFIX) {
+                    file_value(path, file_access)?
+                } else if name.contains(':') {
+                    render_date_time(name, now)?
+                } else {
+                    match name {
+                        "date" => now.format("%Y-%m-%d").to_string(),
+                        "time" => now.format("%H:%M:%S").to_string(),
+                        "datetime" => now.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        "utcdate" => now.with_timezone(&Utc).format("%Y-%m-%d").to_string(),
+                        "timestamp" => now.timestamp().to_string(),
+                        "user" => std::env::var("USER").ok()?,
+                        _ => return None,
+                    }
+                };
+                Some((value, name.len() + 2))
+            });
+
+        match value {
+            Some((value, consumed)) => {
+                result.push_str(&value);
+                rest = &tail[consumed..];
+            }
+            None => {
+                result.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// The value of `{env:var}`, or `None` to leave the placeholder as typed
+fn env_value(var: &str, env_allowlist: Option<&HashSet<String>>) -> Option<String> {
+    if var.is_empty() {
+        return None;
+    }
+    if env_allowlist.is_some_and(|allowed| !allowed.contains(var)) {
+        log::warn!(
+            "Environment variable '{}' is not in the allowlist; not expanding it",
+            var
+        );
+        return None;
+    }
+
+    match std::env::var(var) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::warn!(
+                "Environment variable '{}' is unavailable ({}); expanding to nothing",
+                var,
+                e
+            );
+            Some(String::new())
+        }
+    }
+}
+
+/// Default cap on the size of a file read through `{file:PATH}`
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 64 * 1024;
+
+/// Where `{file:PATH}` placeholders may read from. Paths are relative to
+/// `base_dir`, and a path leading outside it (through `..`, an absolute path
+/// or a symlink) is refused, so shared snippets can't read arbitrary files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileAccess {
+    pub base_dir: PathBuf,
+    pub max_bytes: u64,
+}
+
+impl FileAccess {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            max_bytes: DEFAULT_MAX_FILE_BYTES,
+        }
+    }
+
+    /// The contents of `path`, which must be inside `base_dir`
+    fn read(&self, path: &str) -> anyhow::Result<String> {
+        let base_dir = self.base_dir.canonicalize()?;
+        let full_path = base_dir.join(Path::new(path)).canonicalize()?;
+        if !full_path.starts_with(&base_dir) {
+            return Err(anyhow::anyhow!("it is outside {}", self.base_dir.display()));
+        }
+
+        let size = fs::metadata(&full_path)?.len();
+        if size > self.max_bytes {
+            return Err(anyhow::anyhow!(
+                "it is {} bytes, over the limit of {}",
+                size,
+                self.max_bytes
+            ));
+        }
+        Ok(fs::read_to_string(&full_path)?)
+    }
+}
+
+/// The value of `{file:path}`, or `None` to leave the placeholder as typed
+/// when no directory is allowed
+fn file_value(path: &str, file_access: Option<&FileAccess>) -> Option<String> {
+    if path.is_empty() {
+        return None;
+    }
+    let Some(file_access) = file_access else {
+        log::warn!(
+            "No directory is allowed for {{file:...}}; not expanding '{}'",
+            path
+        );
+        return None;
+    };
+
+    match file_access.read(path) {
+        Ok(contents) => Some(contents),
+        Err(e) => {
+            log::error!(
+                "Can't read '{}' for {{file:...}} ({}); expanding to nothing",
+                path,
+                e
+            );
+            Some(String::new())
+        }
+    }
+}
+
+/// Default cap on the length (in characters) of fully expanded text
+pub const DEFAULT_MAX_EXPANSION_LENGTH: usize = 50_000;
+
+/// Default cap on how deeply fragments may include other fragments
+pub const DEFAULT_MAX_EXPANSION_DEPTH: usize = 16;
+
 /// Simple expansion service for CLI-only builds
-pub struct ExpansionService;
+pub struct ExpansionService {
+    max_expansion_length: usize,
+    max_expansion_depth: usize,
+    emoji_shortcodes: bool,
+    env_allowlist: Option<HashSet<String>>,
+    file_access: Option<FileAccess>,
+    /// Zone dates and times are shown in; the system's when `None`
+    timezone: Option<Tz>,
+    /// Run in order on the text once placeholders and shortcodes are resolved
+    transforms: Vec<TextTransform>,
+    trigger_regex: Regex,
+}
 
 impl ExpansionService {
     pub fn new() -> Self {
-        Self
+        Self {
+            max_expansion_length: DEFAULT_MAX_EXPANSION_LENGTH,
+            max_expansion_depth: DEFAULT_MAX_EXPANSION_DEPTH,
+            emoji_shortcodes: true,
+            env_allowlist: None,
+            file_access: None,
+            timezone: None,
+            transforms: Vec::new(),
+            trigger_regex: TRIGGER_REGEX.clone(),
+        }
+    }
+
+    /// Limit the length of expanded text, guarding against placeholders that balloon
+    pub fn with_max_expansion_length(mut self, max_expansion_length: usize) -> Self {
+        self.max_expansion_length = max_expansion_length;
+        self
+    }
+
+    /// Limit how deeply fragments may include other fragments
+    pub fn with_max_expansion_depth(mut self, max_expansion_depth: usize) -> Self {
+        self.max_expansion_depth = max_expansion_depth;
+        self
+    }
+
+    /// Turn `:smile:`-style emoji shortcodes in replacements on or off (on by default)
+    pub fn with_emoji_shortcodes(mut self, enabled: bool) -> Self {
+        self.emoji_shortcodes = enabled;
+        self
+    }
+
+    /// Only expand `{env:NAME}` for these variables, so shared snippets can't
+    /// read arbitrary secrets from the environment (all are allowed unless set)
+    pub fn with_env_allowlist<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.env_allowlist = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Let `{file:PATH}` read files from one directory (off unless set)
+    pub fn with_file_access(mut self, file_access: FileAccess) -> Self {
+        self.file_access = Some(file_access);
+        self
+    }
+
+    /// Show dates and times in `timezone` rather than the system's local time
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Find triggers starting with `prefix` instead of `::`
+    pub fn with_trigger_prefix(mut self, prefix: &TriggerPrefix) -> Self {
+        self.trigger_regex = prefix.trigger_regex();
+        self
+    }
+
+    /// Add a step run on every expansion, after the ones added before it
+    pub fn with_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Add the built-in transforms `rules` turns on
+    pub fn with_rules(mut self, rules: ExpansionRules) -> Self {
+        self.transforms.extend(rules.transforms());
+        self
+    }
+
+    pub fn max_expansion_length(&self) -> usize {
+        self.max_expansion_length
+    }
+
+    /// How far fragments may be resolved before expanding
+    pub fn fragment_limits(&self) -> FragmentLimits {
+        FragmentLimits {
+            max_depth: self.max_expansion_depth,
+            max_length: self.max_expansion_length,
+        }
     }
 
     /// Expand text with placeholders
     pub fn expand_text(&self, text: &str, _context: &ExpansionContext) -> ExpansionResult {
         let expanded = self.process_placeholders(text);
-        ExpansionResult::success(text.to_string(), expanded)
+        self.check_length(text.to_string(), expanded)
     }
 
     /// Expand a snippet (used by expand_snippet service)
@@ -87,47 +516,91 @@ impl ExpansionService {
         _context: &ExpansionContext,
     ) -> ExpansionResult {
         let expanded = self.process_placeholders(&snippet.replacement);
-        ExpansionResult::success(snippet.replacement.clone(), expanded)
+        self.check_length(snippet.replacement.clone(), expanded)
+    }
+
+    fn check_length(&self, original: String, expanded: String) -> ExpansionResult {
+        let length = expanded.chars().count();
+        if length > self.max_expansion_length {
+            return ExpansionResult::failure(
+                original,
+                format!(
+                    "Expanded text is {} characters, exceeding the limit of {}",
+                    length, self.max_expansion_length
+                ),
+            );
+        }
+
+        ExpansionResult::success(original, expanded)
     }
 
-    /// Find triggers in text (stub implementation)
+    /// Find triggers in text, under the configured prefix
     pub fn find_triggers(&self, text: &str) -> Vec<super::triggers::TriggerMatch> {
-        let mut matches = Vec::new();
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let mut pos = 0;
-
-        for word in words {
-            if let Some(word_pos) = text[pos..].find(word) {
-                let absolute_pos = pos + word_pos;
-                if word.starts_with("::") && word.len() > 2 {
-                    matches.push(super::triggers::TriggerMatch::new(
-                        word.to_string(),
-                        absolute_pos,
-                        absolute_pos + word.len(),
-                    ));
+        self.trigger_regex
+            .find_iter(text)
+            .map(|m| super::triggers::TriggerMatch::new(m.as_str().to_string(), m.start(), m.end()))
+            .collect()
+    }
+
+    /// Replace every trigger in `text` whose snippet in `snippets` is active now,
+    /// which is keyed by NFC-normalized trigger. The output is built in one
+    /// pass, so the cost grows with the length of the text rather than with
+    /// the number of triggers in it.
+    pub fn replace_in_text(
+        &self,
+        text: &str,
+        snippets: &HashMap<String, &crate::domain::Snippet>,
+    ) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut copied_up_to = 0;
+        let now = Utc::now();
+
+        for found in self.trigger_regex.find_iter(text) {
+            let key = MatchOptions::default().key(found.as_str());
+            if let Some(snippet) = snippets
+                .get(key.as_ref())
+                .filter(|snippet| snippet.is_active_at(now))
+            {
+                let expanded = self.process_placeholders(&snippet.replacement);
+                result.push_str(&text[copied_up_to..found.start()]);
+                match snippet.case_of(found.as_str()) {
+                    Some(case) => result.push_str(&case.apply(&expanded)),
+                    None => result.push_str(&expanded),
                 }
-                pos = absolute_pos + word.len();
+                copied_up_to = found.end();
             }
         }
-        matches
+
+        result.push_str(&text[copied_up_to..]);
+        result
     }
 
-    fn process_placeholders(&self, text: &str) -> String {
-        let mut result = text.to_string();
-        let now = chrono::Utc::now();
+    /// `replace_in_text`, failing when the whole result is longer than
+    /// `max_expansion_length`
+    pub fn expand_in_text(
+        &self,
+        text: &str,
+        snippets: &HashMap<String, &crate::domain::Snippet>,
+    ) -> ExpansionResult {
+        let expanded = self.replace_in_text(text, snippets);
+        self.check_length(text.to_string(), expanded)
+    }
 
-        // Date/time placeholders
-        result = result.replace("{date}", &now.format("%Y-%m-%d").to_string());
-        result = result.replace("{time}", &now.format("%H:%M:%S").to_string());
-        result = result.replace("{datetime}", &now.format("%Y-%m-%d %H:%M:%S").to_string());
-        result = result.replace("{timestamp}", &now.timestamp().to_string());
+    fn process_placeholders(&self, text: &str) -> String {
+        let mut result = render_placeholders_at(
+            text,
+            self.env_allowlist.as_ref(),
+            self.file_access.as_ref(),
+            local_time(Utc::now(), self.timezone),
+        );
 
-        // User info placeholders (simplified for CLI)
-        if let Ok(username) = std::env::var("USER") {
-            result = result.replace("{user}", &username);
+        if self.emoji_shortcodes {
+            result = super::shortcodes::expand_shortcodes(&result);
         }
 
-        result
+        self.transforms
+            .iter()
+            .fold(result, |text, transform| transform(text))
     }
 }
 
@@ -136,3 +609,338 @@ impl Default for ExpansionService {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Snippet, TriggerPolicy};
+
+    #[test]
+    fn test_find_triggers_ignores_punctuation() {
+        let service = ExpansionService::new();
+        let triggers = service.find_triggers("Hi ::name, see ::a:b.");
+
+        let found: Vec<&str> = triggers.iter().map(|t| t.trigger.as_str()).collect();
+        assert_eq!(found, vec!["::name", "::a:b"]);
+        assert_eq!(triggers[0].start_position, 3);
+        assert_eq!(triggers[0].end_position, 9);
+    }
+
+    #[test]
+    fn test_find_triggers_under_another_prefix() {
+        let prefix = TriggerPrefix::new(";;").unwrap();
+        let service = ExpansionService::new().with_trigger_prefix(&prefix);
+        let triggers = service.find_triggers("Hi ;;name, not ::this; see ;;a:b.");
+
+        let found: Vec<&str> = triggers.iter().map(|t| t.trigger.as_str()).collect();
+        assert_eq!(found, vec![";;name", ";;a:b"]);
+
+        let policy = TriggerPolicy::default().allowing(prefix.as_str());
+        let sig =
+            Snippet::new_with_policy(";;sig".to_string(), "Best".to_string(), &policy).unwrap();
+        let snippets = HashMap::from([(sig.trigger.clone(), &sig)]);
+        assert_eq!(service.replace_in_text("ok ;;sig", &snippets), "ok Best");
+    }
+
+    #[test]
+    fn test_replace_in_text() {
+        let service = ExpansionService::new();
+        let hello = Snippet::new("::hello".to_string(), "Hello, World!".to_string()).unwrap();
+        let mut inactive = Snippet::new("::off".to_string(), "unused".to_string()).unwrap();
+        inactive.deactivate();
+
+        let mut snippets = HashMap::new();
+        snippets.insert(hello.trigger.clone(), &hello);
+        snippets.insert(inactive.trigger.clone(), &inactive);
+
+        let result = service.replace_in_text("Say ::hello, ::off and ::unknown", &snippets);
+        assert_eq!(result, "Say Hello, World!, ::off and ::unknown");
+    }
+
+    /// The original implementation: find every trigger, then splice
+    /// replacements in from the end
+    fn replace_in_text_by_ranges(
+        service: &ExpansionService,
+        text: &str,
+        snippets: &HashMap<String, &Snippet>,
+    ) -> String {
+        let mut result = text.to_string();
+        let now = Utc::now();
+        for trigger_match in service.find_triggers(text).into_iter().rev() {
+            if let Some(snippet) = snippets.get(&trigger_match.normalized_trigger()) {
+                if snippet.is_active_at(now) {
+                    let expanded = service.process_placeholders(&snippet.replacement);
+                    result.replace_range(
+                        trigger_match.start_position..trigger_match.end_position,
+                        &expanded,
+                    );
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_single_pass_replacement_matches_original() {
+        let service = ExpansionService::new();
+        let mut owned = [
+            Snippet::new("::sig".to_string(), "Best,\nJane :smile:".to_string()).unwrap(),
+            Snippet::new("::a:b".to_string(), "nested".to_string()).unwrap(),
+            Snippet::new("::ünï".to_string(), "unicode".to_string()).unwrap(),
+            Snippet::new("::x".to_string(), "{{literal}} x".to_string()).unwrap(),
+            Snippet::new("::off".to_string(), "unused".to_string()).unwrap(),
+        ];
+        owned[4].deactivate();
+        let snippets: HashMap<String, &Snippet> =
+            owned.iter().map(|s| (s.trigger.clone(), s)).collect();
+
+        let fixture = "::sig at the start, ::a:b: then ::a and ::x::x,\n\
+                       ::off stays, ::unknown stays, émoji ::sig 🎉 ::x\n\
+                       ::ünï is NFC, :::sig and ::sig-";
+        assert_eq!(
+            service.replace_in_text(fixture, &snippets),
+            replace_in_text_by_ranges(&service, fixture, &snippets)
+        );
+
+        let large = fixture.repeat(200);
+        assert_eq!(
+            service.replace_in_text(&large, &snippets),
+            replace_in_text_by_ranges(&service, &large, &snippets)
+        );
+    }
+
+    #[test]
+    fn test_expansion_within_limit() {
+        let service = ExpansionService::new();
+        let snippet = Snippet::new("::hi".to_string(), "Hello {date}".to_string()).unwrap();
+
+        let result = service.expand_snippet(&snippet, &ExpansionContext::new());
+        assert!(result.success);
+        assert!(result.expanded_text.starts_with("Hello "));
+    }
+
+    #[test]
+    fn test_expansion_exceeding_limit_is_rejected() {
+        // Each {datetime} placeholder (10 chars) grows to 19 chars once expanded
+        let replacement = "{datetime}".repeat(10);
+        let snippet = Snippet::new("::big".to_string(), replacement.clone()).unwrap();
+        let service = ExpansionService::new().with_max_expansion_length(replacement.len());
+
+        let result = service.expand_snippet(&snippet, &ExpansionContext::new());
+        assert!(!result.success);
+        assert!(result.expanded_text.is_empty());
+        assert!(result.error.unwrap().contains("exceeding the limit"));
+    }
+
+    #[test]
+    fn test_expansion_resolves_emoji_shortcodes() {
+        let snippet = Snippet::new("::ty".to_string(), "Thank you :tada:".to_string()).unwrap();
+
+        let result = ExpansionService::new().expand_snippet(&snippet, &ExpansionContext::new());
+        assert_eq!(result.expanded_text, "Thank you 🎉");
+
+        let result = ExpansionService::new()
+            .with_emoji_shortcodes(false)
+            .expand_snippet(&snippet, &ExpansionContext::new());
+        assert_eq!(result.expanded_text, "Thank you :tada:");
+    }
+
+    #[test]
+    fn test_escaped_placeholder_is_literal() {
+        let service = ExpansionService::new();
+        assert_eq!(
+            service
+                .expand_text("{{date}}", &ExpansionContext::new())
+                .expanded_text,
+            "{date}"
+        );
+        assert_eq!(
+            service
+                .expand_text("{{ and }}", &ExpansionContext::new())
+                .expanded_text,
+            "{ and }"
+        );
+    }
+
+    #[test]
+    fn test_placeholder_names() {
+        assert_eq!(
+            placeholder_names("{date} {{time}} {dat} {fragment:sig} {\"a\": 1} {}"),
+            vec!["date", "dat", "fragment:sig"]
+        );
+        assert!(is_known_placeholder("date"));
+        assert!(is_known_placeholder("env:HOME"));
+        assert!(!is_known_placeholder("dat"));
+        assert!(!is_known_placeholder("env:"));
+    }
+
+    #[test]
+    fn test_placeholder_is_replaced() {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let result = ExpansionService::new().expand_text("{date}", &ExpansionContext::new());
+        assert_eq!(result.expanded_text, today);
+    }
+
+    #[test]
+    fn test_env_placeholder_is_replaced() {
+        std::env::set_var("TYPELY_TEST_ENV_BRANCH", "main");
+        std::env::remove_var("TYPELY_TEST_ENV_UNSET");
+        let service = ExpansionService::new();
+
+        let result = service.expand_text(
+            "Deploying {env:TYPELY_TEST_ENV_BRANCH}{env:TYPELY_TEST_ENV_UNSET} ({{env:TYPELY_TEST_ENV_BRANCH}})",
+            &ExpansionContext::new(),
+        );
+        assert_eq!(
+            result.expanded_text,
+            "Deploying main ({env:TYPELY_TEST_ENV_BRANCH})"
+        );
+    }
+
+    #[test]
+    fn test_env_placeholder_outside_allowlist_is_not_expanded() {
+        std::env::set_var("TYPELY_TEST_ENV_ALLOWED", "ok");
+        std::env::set_var("TYPELY_TEST_ENV_SECRET", "hunter2");
+        let service = ExpansionService::new().with_env_allowlist(["TYPELY_TEST_ENV_ALLOWED"]);
+
+        let result = service.expand_text(
+            "{env:TYPELY_TEST_ENV_ALLOWED} {env:TYPELY_TEST_ENV_SECRET}",
+            &ExpansionContext::new(),
+        );
+        assert_eq!(result.expanded_text, "ok {env:TYPELY_TEST_ENV_SECRET}");
+    }
+
+    #[test]
+    fn test_file_placeholder_reads_from_allowed_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("headers")).unwrap();
+        fs::write(temp_dir.path().join("headers/mit.txt"), "MIT License").unwrap();
+        let service = ExpansionService::new().with_file_access(FileAccess::new(temp_dir.path()));
+
+        let result = service.expand_text("// {file:headers/mit.txt}\n", &ExpansionContext::new());
+        assert_eq!(result.expanded_text, "// MIT License\n");
+
+        // Missing files expand to nothing
+        let result = service.expand_text("[{file:missing.txt}]", &ExpansionContext::new());
+        assert_eq!(result.expanded_text, "[]");
+    }
+
+    #[test]
+    fn test_file_placeholder_stays_inside_its_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("allowed");
+        fs::create_dir(&allowed).unwrap();
+        fs::write(temp_dir.path().join("secret.txt"), "hunter2").unwrap();
+        fs::write(allowed.join("big.txt"), "x".repeat(100)).unwrap();
+        let file_access = FileAccess {
+            max_bytes: 10,
+            ..FileAccess::new(&allowed)
+        };
+        let service = ExpansionService::new().with_file_access(file_access);
+
+        let secret = temp_dir.path().join("secret.txt");
+        for text in [
+            "{file:../secret.txt}".to_string(),
+            format!("{{file:{}}}", secret.display()),
+            "{file:big.txt}".to_string(),
+        ] {
+            let result = service.expand_text(&text, &ExpansionContext::new());
+            assert_eq!(result.expanded_text, "", "{}", text);
+        }
+
+        // Without an allowed directory the placeholder is left as typed
+        let result =
+            ExpansionService::new().expand_text("{file:big.txt}", &ExpansionContext::new());
+        assert_eq!(result.expanded_text, "{file:big.txt}");
+    }
+
+    #[test]
+    fn test_date_and_time_with_custom_format() {
+        let now = DateTime::parse_from_rfc3339("2024-03-09T15:04:05Z").unwrap();
+        let render = |text: &str| render_placeholders_at(text, None, None, now);
+
+        assert_eq!(render("{date:%d/%m/%Y}"), "09/03/2024");
+        assert_eq!(render("{time:%I:%M %p}"), "03:04 PM");
+        assert_eq!(render("{datetime:%Y%m%dT%H%M}"), "20240309T1504");
+        assert_eq!(render("{date} {time}"), "2024-03-09 15:04:05");
+    }
+
+    #[test]
+    fn test_date_with_day_offset() {
+        let now = DateTime::parse_from_rfc3339("2024-02-27T10:00:00Z").unwrap();
+        let render = |text: &str| render_placeholders_at(text, None, None, now);
+
+        assert_eq!(render("Due {date:+3d}"), "Due 2024-03-01");
+        assert_eq!(render("{date:-1d:%A}"), "Monday");
+        assert_eq!(render("{date:+0d}"), "2024-02-27");
+    }
+
+    #[test]
+    fn test_dates_in_configured_timezone() {
+        let instant = DateTime::parse_from_rfc3339("2024-03-10T03:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let render = |timezone: &str| {
+            let now = local_time(instant, Some(parse_timezone(timezone).unwrap()));
+            render_placeholders_at("{date} {time} {utcdate} {time:%z}", None, None, now)
+        };
+
+        assert_eq!(
+            render("America/New_York"),
+            "2024-03-09 22:30:00 2024-03-10 -0500"
+        );
+        assert_eq!(
+            render("Asia/Kolkata"),
+            "2024-03-10 09:00:00 2024-03-10 +0530"
+        );
+        assert_eq!(render("UTC"), "2024-03-10 03:30:00 2024-03-10 +0000");
+        assert!(parse_timezone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_invalid_date_format_is_left_as_typed() {
+        let now = Utc::now().fixed_offset();
+        for text in ["{date:%Q}", "{time:}", "{date:+3x}", "{date:+99999999999d}"] {
+            assert_eq!(render_placeholders_at(text, None, None, now), text);
+        }
+        assert!(is_known_placeholder("date:+3d"));
+        assert!(!is_known_placeholder("date:%Q"));
+        assert!(has_dynamic_content("Due {date:+3d}"));
+    }
+
+    #[test]
+    fn test_transforms_run_in_order_after_placeholders() {
+        let service = ExpansionService::new()
+            .with_transform(|text| format!("[{}]", text))
+            .with_transform(|text| text.replace('[', "<"));
+        let result = service.expand_text("Hi {date:%Y} :wave:", &ExpansionContext::new());
+        let year = Local::now().format("%Y").to_string();
+        assert_eq!(result.expanded_text, format!("<Hi {} 👋]", year));
+
+        let service = ExpansionService::new().with_rules(ExpansionRules {
+            smart_quotes: true,
+            trim_trailing: true,
+        });
+        let result = service.expand_text("\"{date:%Y}\"  \nBye ", &ExpansionContext::new());
+        assert_eq!(
+            result.expanded_text,
+            format!("\u{201c}{}\u{201d}\nBye", year)
+        );
+    }
+
+    #[test]
+    fn test_mixed_escaped_and_real_placeholders() {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let result =
+            render_placeholders_with_env("Type {{date}} to get {date}; {unknown} and } stay", None);
+        assert_eq!(
+            result,
+            format!("Type {{date}} to get {}; {{unknown}} and }} stay", today)
+        );
+        // A tripled brace escapes one and opens a placeholder with the next
+        assert_eq!(
+            render_placeholders_with_env("{{{date}}}", None),
+            format!("{{{}}}", today)
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use super::{DEFAULT_MAX_EXPANSION_DEPTH, DEFAULT_MAX_EXPANSION_LENGTH};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A named piece of text shared between snippets, referenced as
+/// `{fragment:name}`. Unlike a snippet it has no trigger and can't be typed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fragment {
+    pub id: Uuid,
+    pub name: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Fragment {
+    pub fn new(name: String, content: String) -> anyhow::Result<Self> {
+        Self::validate_name(&name)?;
+        if content.is_empty() {
+            return Err(anyhow::anyhow!("Fragment content cannot be empty"));
+        }
+
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name,
+            content,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn validate_name(name: &str) -> anyhow::Result<()> {
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Fragment name cannot be empty"));
+        }
+        if !name.chars().all(is_fragment_name_char) {
+            return Err(anyhow::anyhow!(
+                "Invalid fragment name '{}': use letters, digits, '-' and '_' only",
+                name
+            ));
+        }
+        Ok(())
+    }
+}
+
+const FRAGMENT_PREFIX: &str = "{fragment:";
+
+fn is_fragment_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+enum Piece<'a> {
+    Literal(&'a str),
+    /// A `{fragment:name}` reference; `raw` is the reference as written
+    Fragment {
+        name: &'a str,
+        raw: &'a str,
+    },
+}
+
+/// Split `text` into literal runs and fragment references, passing escaped
+/// braces (`{{`, `}}`) through untouched for placeholder rendering
+fn split_fragments(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        pieces.push(Piece::Literal(&rest[..pos]));
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            pieces.push(Piece::Literal(&tail[..2]));
+            rest = &tail[2..];
+            continue;
+        }
+
+        let reference = tail.strip_prefix(FRAGMENT_PREFIX).and_then(|inner| {
+            let end = inner.find(|c: char| !is_fragment_name_char(c))?;
+            let name = &inner[..end];
+            (!name.is_empty() && inner[end..].starts_with('}')).then_some(name)
+        });
+
+        match reference {
+            Some(name) => {
+                let consumed = FRAGMENT_PREFIX.len() + name.len() + 1;
+                pieces.push(Piece::Fragment {
+                    name,
+                    raw: &tail[..consumed],
+                });
+                rest = &tail[consumed..];
+            }
+            None => {
+                pieces.push(Piece::Literal(&tail[..1]));
+                rest = &tail[1..];
+            }
+        }
+    }
+
+    pieces.push(Piece::Literal(rest));
+    pieces
+}
+
+/// Names of the fragments `text` refers to, in order of appearance
+pub fn fragment_references(text: &str) -> Vec<String> {
+    split_fragments(text)
+        .into_iter()
+        .filter_map(|piece| match piece {
+            Piece::Fragment { name, .. } => Some(name.to_string()),
+            Piece::Literal(_) => None,
+        })
+        .collect()
+}
+
+/// How far fragment resolution may go before it gives up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentLimits {
+    /// Fragments nested inside fragments, counting the outermost as 1
+    pub max_depth: usize,
+    /// Characters the resolved text may have, checked as it grows
+    pub max_length: usize,
+}
+
+impl Default for FragmentLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_EXPANSION_DEPTH,
+            max_length: DEFAULT_MAX_EXPANSION_LENGTH,
+        }
+    }
+}
+
+/// Replace every `{fragment:name}` in `text` with that fragment's content,
+/// resolving fragments that refer to other fragments. References to unknown
+/// fragments are left untouched; a fragment that ends up including itself,
+/// or nesting deeper or growing longer than `limits` allow, is an error.
+/// Both limits are checked along the way, so a fragment that repeats another
+/// many times over fails before it has been built.
+pub fn resolve_fragments(
+    text: &str,
+    fragments: &HashMap<String, String>,
+    limits: &FragmentLimits,
+) -> anyhow::Result<String> {
+    let mut result = Resolved {
+        text: String::with_capacity(text.len()),
+        length: 0,
+        max_length: limits.max_length,
+    };
+    resolve_into(text, fragments, limits, &mut Vec::new(), &mut result)?;
+    Ok(result.text)
+}
+
+/// Text resolved so far, with its length in characters
+struct Resolved {
+    text: String,
+    length: usize,
+    max_length: usize,
+}
+
+impl Resolved {
+    fn push(&mut self, piece: &str) -> anyhow::Result<()> {
+        self.length += piece.chars().count();
+        if self.length > self.max_length {
+            return Err(anyhow::anyhow!(
+                "Fragments expand to more than {} characters",
+                self.max_length
+            ));
+        }
+        self.text.push_str(piece);
+        Ok(())
+    }
+}
+
+fn resolve_into<'a>(
+    text: &'a str,
+    fragments: &'a HashMap<String, String>,
+    limits: &FragmentLimits,
+    stack: &mut Vec<&'a str>,
+    result: &mut Resolved,
+) -> anyhow::Result<()> {
+    for piece in split_fragments(text) {
+        let (name, content) = match piece {
+            Piece::Fragment { name, raw } => match fragments.get(name) {
+                Some(content) => (name, content),
+                None => {
+                    result.push(raw)?;
+                    continue;
+                }
+            },
+            Piece::Literal(literal) => {
+                result.push(literal)?;
+                continue;
+            }
+        };
+
+        if stack.contains(&name) {
+            let mut cycle: Vec<&str> = stack.clone();
+            cycle.push(name);
+            return Err(anyhow::anyhow!("Fragment cycle: {}", cycle.join(" -> ")));
+        }
+        if stack.len() >= limits.max_depth {
+            return Err(anyhow::anyhow!(
+                "Fragments are nested more than {} deep at '{}'",
+                limits.max_depth,
+                name
+            ));
+        }
+
+        stack.push(name);
+        resolve_into(content, fragments, limits, stack, result)?;
+        stack.pop();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragments(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_fragment_name_validation() {
+        assert!(Fragment::new("signature".to_string(), "Jane".to_string()).is_ok());
+        assert!(Fragment::new("work-sig_2".to_string(), "Jane".to_string()).is_ok());
+        assert!(Fragment::new("".to_string(), "Jane".to_string()).is_err());
+        assert!(Fragment::new("my sig".to_string(), "Jane".to_string()).is_err());
+        assert!(Fragment::new("sig".to_string(), "".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_fragment_references() {
+        assert_eq!(
+            fragment_references("Hi,\n{fragment:sig} {date} {fragment:footer}"),
+            vec!["sig", "footer"]
+        );
+        assert!(fragment_references("{{fragment:sig}} {fragment:} {fragment:a b}").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_fragments() {
+        let fragments = fragments(&[("sig", "Jane Doe\n{fragment:title}"), ("title", "Engineer")]);
+
+        assert_eq!(
+            resolve_fragments(
+                "Thanks,\n{fragment:sig}",
+                &fragments,
+                &FragmentLimits::default()
+            )
+            .unwrap(),
+            "Thanks,\nJane Doe\nEngineer"
+        );
+        // Placeholders and escaped braces are left for placeholder rendering
+        assert_eq!(
+            resolve_fragments(
+                "{date} {{fragment:sig}}",
+                &fragments,
+                &FragmentLimits::default()
+            )
+            .unwrap(),
+            "{date} {{fragment:sig}}"
+        );
+        assert_eq!(
+            resolve_fragments("{fragment:missing}", &fragments, &FragmentLimits::default())
+                .unwrap(),
+            "{fragment:missing}"
+        );
+    }
+
+    #[test]
+    fn test_same_fragment_twice_is_not_a_cycle() {
+        let fragments = fragments(&[("a", "{fragment:b}{fragment:b}"), ("b", "x")]);
+        assert_eq!(
+            resolve_fragments("{fragment:a}", &fragments, &FragmentLimits::default()).unwrap(),
+            "xx"
+        );
+    }
+
+    #[test]
+    fn test_fragment_cycle_is_detected() {
+        let fragments = fragments(&[
+            ("a", "A {fragment:b}"),
+            ("b", "B {fragment:a}"),
+            ("self", "{fragment:self}"),
+        ]);
+
+        let error =
+            resolve_fragments("{fragment:a}", &fragments, &FragmentLimits::default()).unwrap_err();
+        assert_eq!(error.to_string(), "Fragment cycle: a -> b -> a");
+
+        let error = resolve_fragments("{fragment:self}", &fragments, &FragmentLimits::default())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Fragment cycle: self -> self");
+    }
+
+    #[test]
+    fn test_fragment_nesting_depth_is_capped() {
+        // chain-0 -> chain-1 -> ... -> chain-9, no cycle
+        let chain: Vec<(String, String)> = (0..10)
+            .map(|i| {
+                (
+                    format!("chain-{}", i),
+                    format!("{{fragment:chain-{}}}", i + 1),
+                )
+            })
+            .collect();
+        let fragments: HashMap<String, String> = chain.into_iter().collect();
+        let limits = FragmentLimits {
+            max_depth: 5,
+            ..FragmentLimits::default()
+        };
+
+        let error = resolve_fragments("{fragment:chain-0}", &fragments, &limits).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Fragments are nested more than 5 deep at 'chain-5'"
+        );
+        assert_eq!(
+            resolve_fragments("{fragment:chain-5}", &fragments, &limits).unwrap(),
+            "{fragment:chain-10}"
+        );
+    }
+
+    #[test]
+    fn test_fragment_growth_is_capped_while_resolving() {
+        // Each level repeats the next ten times: 10^12 characters in full
+        let mut fragments: HashMap<String, String> = (0..12)
+            .map(|i| {
+                (
+                    format!("level-{}", i),
+                    format!("{{fragment:level-{}}}", i + 1).repeat(10),
+                )
+            })
+            .collect();
+        fragments.insert("level-12".to_string(), "x".to_string());
+        let limits = FragmentLimits {
+            max_length: 1000,
+            ..FragmentLimits::default()
+        };
+
+        let error = resolve_fragments("{fragment:level-0}", &fragments, &limits).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Fragments expand to more than 1000 characters"
+        );
+        assert_eq!(
+            resolve_fragments("{fragment:level-10}", &fragments, &limits)
+                .unwrap()
+                .len(),
+            100
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+
+/// Non-character keys a hotkey can use, by the names keyboard events report
+const NAMED_KEYS: &[&str] = &[
+    "Space",
+    "Tab",
+    "Return",
+    "Escape",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "UpArrow",
+    "DownArrow",
+    "LeftArrow",
+    "RightArrow",
+];
+
+/// Punctuation keys, as `KeyboardMonitor::physical_key_to_string` reports them
+const SYMBOL_KEYS: &str = "-=[];'`\\,./";
+
+/// The modifier keys held down, either live while typing or as part of a `Hotkey`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ModifierState {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl ModifierState {
+    pub fn has_any(&self) -> bool {
+        self.ctrl || self.shift || self.alt || self.meta
+    }
+
+    pub fn is_ctrl_only(&self) -> bool {
+        self.ctrl && !self.shift && !self.alt && !self.meta
+    }
+
+    pub fn is_shift_only(&self) -> bool {
+        !self.ctrl && self.shift && !self.alt && !self.meta
+    }
+
+    pub fn is_alt_only(&self) -> bool {
+        !self.ctrl && !self.shift && self.alt && !self.meta
+    }
+
+    pub fn matches(&self, other: &ModifierState) -> bool {
+        self.ctrl == other.ctrl
+            && self.shift == other.shift
+            && self.alt == other.alt
+            && self.meta == other.meta
+    }
+
+    /// Record `key` (a key event name such as `ControlLeft`) going down or
+    /// up. Returns whether it is a modifier at all.
+    pub fn update(&mut self, key: &str, pressed: bool) -> bool {
+        let held = match key {
+            "ControlLeft" | "ControlRight" => &mut self.ctrl,
+            "Alt" | "AltGr" => &mut self.alt,
+            "ShiftLeft" | "ShiftRight" => &mut self.shift,
+            "MetaLeft" | "MetaRight" => &mut self.meta,
+            _ => return false,
+        };
+        *held = pressed;
+        true
+    }
+
+    /// Whether pressing `key` with these modifiers held fires `hotkey`. The
+    /// modifiers must be exactly the hotkey's, so Ctrl+Alt+1 doesn't fire on
+    /// Ctrl+Alt+Shift+1.
+    pub fn matches_combo(&self, hotkey: &Hotkey, key: &str) -> bool {
+        self.matches(&hotkey.modifiers) && hotkey.key.eq_ignore_ascii_case(key)
+    }
+}
+
+/// A modifier+key combination bound to a snippet, e.g. `Ctrl+Alt+1`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    pub modifiers: ModifierState,
+    /// A lowercase character (`1`, `k`, `/`), `F1`-`F12` or one of the named
+    /// keys such as `Space`
+    pub key: String,
+}
+
+impl Hotkey {
+    /// Parse `Ctrl+Alt+1`-style text, ignoring case and spaces around `+`.
+    /// `Control`, `Option`, `Cmd`, `Super` and `Win` are accepted for the
+    /// modifiers. Ctrl, Alt or Meta is required so the hotkey can't fire
+    /// while typing.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut modifiers = ModifierState::default();
+        let mut key = None;
+
+        for part in text.split('+').map(str::trim) {
+            let held = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "alt" | "option" => &mut modifiers.alt,
+                "shift" => &mut modifiers.shift,
+                "meta" | "cmd" | "command" | "super" | "win" => &mut modifiers.meta,
+                _ => {
+                    if key.is_some() {
+                        return Err(anyhow::anyhow!(
+                            "Hotkey '{}' has more than one key besides modifiers",
+                            text
+                        ));
+                    }
+                    key = Some(Self::parse_key(text, part)?);
+                    continue;
+                }
+            };
+            *held = true;
+        }
+
+        let Some(key) = key else {
+            return Err(anyhow::anyhow!(
+                "Hotkey '{}' has no key besides modifiers",
+                text
+            ));
+        };
+        if !(modifiers.ctrl || modifiers.alt || modifiers.meta) {
+            return Err(anyhow::anyhow!(
+                "Hotkey '{}' needs Ctrl, Alt or Meta, or it would fire while typing",
+                text
+            ));
+        }
+        Ok(Self { modifiers, key })
+    }
+
+    fn parse_key(text: &str, part: &str) -> anyhow::Result<String> {
+        let mut chars = part.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if c.is_ascii_alphanumeric() || SYMBOL_KEYS.contains(c) {
+                return Ok(c.to_ascii_lowercase().to_string());
+            }
+        }
+
+        let lower = part.to_ascii_lowercase();
+        if let Some(number) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            if (1..=12).contains(&number) {
+                return Ok(format!("F{}", number));
+            }
+        }
+        let alias = match lower.as_str() {
+            "enter" => "Return",
+            "esc" => "Escape",
+            "up" => "UpArrow",
+            "down" => "DownArrow",
+            "left" => "LeftArrow",
+            "right" => "RightArrow",
+            _ => "",
+        };
+        NAMED_KEYS
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(part) || **name == alias)
+            .map(|name| name.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Unknown key '{}' in hotkey '{}'", part, text))
+    }
+}
+
+impl fmt::Display for Hotkey {
+    /// The canonical form, e.g. `Ctrl+Alt+K`, which `parse` reads back
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.meta, "Meta"),
+        ];
+        for (_, name) in modifiers.iter().filter(|(held, _)| *held) {
+            write!(f, "{}+", name)?;
+        }
+        // Letters are shown in capitals, as on the keycaps
+        if self.key.chars().count() == 1 {
+            write!(f, "{}", self.key.to_ascii_uppercase())
+        } else {
+            write!(f, "{}", self.key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hotkeys() {
+        let hotkey = Hotkey::parse("Ctrl+Alt+1").unwrap();
+        assert!(hotkey.modifiers.ctrl && hotkey.modifiers.alt);
+        assert!(!hotkey.modifiers.shift && !hotkey.modifiers.meta);
+        assert_eq!(hotkey.key, "1");
+
+        for (text, canonical) in [
+            ("ctrl + shift + k", "Ctrl+Shift+K"),
+            ("Cmd+F5", "Meta+F5"),
+            ("option+control+enter", "Ctrl+Alt+Return"),
+            ("Alt+/", "Alt+/"),
+            ("Super+Space", "Meta+Space"),
+        ] {
+            let hotkey = Hotkey::parse(text).unwrap();
+            assert_eq!(hotkey.to_string(), canonical, "{}", text);
+            assert_eq!(Hotkey::parse(canonical).unwrap(), hotkey);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_hotkeys() {
+        for text in [
+            "",
+            "Ctrl+Alt",
+            "Shift+A",
+            "K",
+            "Ctrl+A+B",
+            "Ctrl+F13",
+            "Ctrl+é",
+            "Ctrl+Nope",
+        ] {
+            assert!(Hotkey::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_modifier_state_matches_exact_combo() {
+        let hotkey = Hotkey::parse("Ctrl+Alt+1").unwrap();
+        let mut state = ModifierState::default();
+        assert!(!state.matches_combo(&hotkey, "1"));
+
+        assert!(state.update("ControlLeft", true));
+        assert!(state.update("Alt", true));
+        assert!(!state.update("Num1", true));
+        assert!(state.matches_combo(&hotkey, "1"));
+        assert!(!state.matches_combo(&hotkey, "2"));
+
+        // An extra modifier makes it a different combo
+        state.update("ShiftRight", true);
+        assert!(!state.matches_combo(&hotkey, "1"));
+        state.update("ShiftRight", false);
+        assert!(state.matches_combo(&hotkey, "1"));
+
+        state.update("ControlLeft", false);
+        assert!(!state.matches_combo(&hotkey, "1"));
+
+        let held = ModifierState {
+            ctrl: true,
+            shift: true,
+            ..Default::default()
+        };
+        assert!(held.matches_combo(&Hotkey::parse("Ctrl+Shift+K").unwrap(), "k"));
+    }
+}
@@ -1,11 +1,27 @@
+pub mod content_type;
 pub mod events;
 pub mod expansion;
+pub mod fragment;
+pub mod hotkey;
 pub mod query;
+pub mod shortcodes;
 pub mod snippet;
+pub mod transforms;
+pub mod trigger_policy;
+pub mod trigger_trie;
 pub mod triggers;
+pub mod validation;
 
+pub use content_type::*;
 pub use events::*;
 pub use expansion::*;
+pub use fragment::*;
+pub use hotkey::*;
 pub use query::*;
+pub use shortcodes::*;
 pub use snippet::*;
+pub use transforms::*;
+pub use trigger_policy::*;
+pub use trigger_trie::*;
 pub use triggers::*;
+pub use validation::*;
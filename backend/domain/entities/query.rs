@@ -1,27 +1,49 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 use uuid::Uuid;
 
+use super::Snippet;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SnippetQuery {
     pub search: Option<String>,
+    /// Match snippets where one of `search_fields` contains any of these
+    pub search_any: Vec<String>,
+    /// Where `search` and `search_any` look; trigger and replacement by default
+    pub search_fields: Vec<SearchField>,
     pub tags: Vec<String>,
+    pub tag_match: TagMatch,
     pub is_active: Option<bool>,
+    /// Only snippets whose active window contains this time
+    pub active_at: Option<DateTime<Utc>>,
+    /// Only snippets with this `source`
+    pub source: Option<String>,
     pub sort_by: Option<SortBy>,
     pub sort_order: Option<SortOrder>,
     pub limit: Option<u32>,
     pub offset: Option<u32>,
+    /// Start after this position instead of skipping `offset` rows
+    pub after: Option<PageCursor>,
 }
 
 impl Default for SnippetQuery {
     fn default() -> Self {
         Self {
             search: None,
+            search_any: Vec::new(),
+            search_fields: SearchField::DEFAULT.to_vec(),
             tags: Vec::new(),
+            tag_match: TagMatch::Any,
             is_active: None,
+            active_at: None,
+            source: None,
             sort_by: Some(SortBy::UpdatedAt),
             sort_order: Some(SortOrder::Desc),
             limit: None,
             offset: None,
+            after: None,
         }
     }
 }
@@ -36,16 +58,39 @@ impl SnippetQuery {
         self
     }
 
+    pub fn with_search_any(mut self, terms: Vec<String>) -> Self {
+        self.search_any = terms;
+        self
+    }
+
+    /// Search only these fields. An empty list keeps the default.
+    pub fn with_search_fields(mut self, fields: Vec<SearchField>) -> Self {
+        if !fields.is_empty() {
+            self.search_fields = fields;
+        }
+        self
+    }
+
     pub fn with_tags(mut self, tags: Vec<String>) -> Self {
         self.tags = tags;
         self
     }
 
+    pub fn with_tag_match(mut self, tag_match: TagMatch) -> Self {
+        self.tag_match = tag_match;
+        self
+    }
+
     pub fn with_active(mut self, is_active: bool) -> Self {
         self.is_active = Some(is_active);
         self
     }
 
+    pub fn with_source(mut self, source: String) -> Self {
+        self.source = Some(source);
+        self
+    }
+
     pub fn with_sort(mut self, sort_by: SortBy, sort_order: SortOrder) -> Self {
         self.sort_by = Some(sort_by);
         self.sort_order = Some(sort_order);
@@ -63,6 +108,18 @@ impl SnippetQuery {
         self
     }
 
+    /// Only snippets that expand right now: active and inside their window
+    pub fn with_active_now(mut self) -> Self {
+        self.is_active = Some(true);
+        self.active_at = Some(Utc::now());
+        self
+    }
+
+    pub fn with_active_at(mut self, time: DateTime<Utc>) -> Self {
+        self.active_at = Some(time);
+        self
+    }
+
     pub fn with_all(self) -> Self {
         // This keeps the query as is, showing all snippets regardless of active status
         self
@@ -78,6 +135,13 @@ impl SnippetQuery {
         self
     }
 
+    /// Continue a listing after `cursor` (keyset pagination), which stays
+    /// fast at any depth where a large `offset` has to skip every row before it
+    pub fn after(mut self, cursor: PageCursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
     pub fn sort_by_usage(mut self) -> Self {
         self.sort_by = Some(SortBy::UsageCount);
         self
@@ -92,6 +156,129 @@ impl SnippetQuery {
         self.sort_by = Some(SortBy::Trigger);
         self
     }
+
+    /// In the order arranged by hand, which reads lowest position first
+    pub fn sort_by_position(mut self) -> Self {
+        self.sort_by = Some(SortBy::Position);
+        self.sort_order = Some(SortOrder::Asc);
+        self
+    }
+}
+
+/// The position after a snippet in a sorted listing: whether it is pinned and
+/// its value of the sort column, with its id to break ties. Written as
+/// `<id>:<0|1>:<sort key>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub pinned: bool,
+    pub sort_key: String,
+    pub id: Uuid,
+}
+
+impl PageCursor {
+    /// The cursor just past `snippet` in a listing sorted by `sort_by`
+    pub fn after_snippet(snippet: &Snippet, sort_by: SortBy) -> Self {
+        let sort_key = match sort_by {
+            SortBy::Trigger => snippet.trigger.clone(),
+            SortBy::CreatedAt => snippet.created_at.to_rfc3339(),
+            SortBy::UpdatedAt => snippet.updated_at.to_rfc3339(),
+            SortBy::UsageCount => snippet.usage_count.to_string(),
+            SortBy::Position => snippet.position.unwrap_or(UNPLACED_POSITION).to_string(),
+        };
+
+        Self {
+            pinned: snippet.pinned,
+            sort_key,
+            id: snippet.id,
+        }
+    }
+}
+
+impl fmt::Display for PageCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.id, self.pinned as u8, self.sort_key)
+    }
+}
+
+impl FromStr for PageCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            // Ids never contain ':', sort keys may
+            let (id, rest) = s.split_once(':')?;
+            let (pinned, sort_key) = rest.split_once(':')?;
+            let pinned = match pinned {
+                "0" => false,
+                "1" => true,
+                _ => return None,
+            };
+            Some(Self {
+                pinned,
+                sort_key: sort_key.to_string(),
+                id: Uuid::parse_str(id).ok()?,
+            })
+        };
+
+        parse().ok_or_else(|| anyhow::anyhow!("Invalid page cursor '{}'", s))
+    }
+}
+
+/// A part of a snippet that a search term is looked for in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Trigger,
+    Replacement,
+    /// Any one of the tags
+    Tags,
+}
+
+impl SearchField {
+    /// Where searches look unless told otherwise
+    pub const DEFAULT: [SearchField; 2] = [SearchField::Trigger, SearchField::Replacement];
+}
+
+impl FromStr for SearchField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trigger" => Ok(Self::Trigger),
+            "replacement" => Ok(Self::Replacement),
+            "tags" | "tag" => Ok(Self::Tags),
+            _ => Err(anyhow::anyhow!(
+                "Unknown search field '{}' (use trigger, replacement or tags)",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "asc" | "ascending" => Ok(Self::Asc),
+            "desc" | "descending" => Ok(Self::Desc),
+            _ => Err(anyhow::anyhow!(
+                "Unknown sort order '{}' (use asc or desc)",
+                s
+            )),
+        }
+    }
+}
+
+/// How multiple tags in a query are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TagMatch {
+    /// Snippets having at least one of the tags
+    #[default]
+    Any,
+    /// Snippets having every one of the tags
+    All,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -101,8 +288,13 @@ pub enum SortBy {
     #[default]
     UpdatedAt,
     UsageCount,
+    /// The order set by hand with `SnippetRepository::reorder`
+    Position,
 }
 
+/// Where snippets without a position sort, after every placed one
+pub const UNPLACED_POSITION: u32 = u32::MAX;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum SortOrder {
     Asc,
@@ -0,0 +1,108 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref SHORTCODE_REGEX: Regex = Regex::new(r":([a-z0-9_+-]+):").unwrap();
+}
+
+/// Built-in `:name:` shortcodes, using the names GitHub and Slack use
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("blush", "😊"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("coffee", "☕"),
+    ("cry", "😢"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("grin", "😁"),
+    ("heart", "❤️"),
+    ("joy", "😂"),
+    ("laughing", "😆"),
+    ("ok_hand", "👌"),
+    ("pray", "🙏"),
+    ("rocket", "🚀"),
+    ("see_no_evil", "🙈"),
+    ("shrug", "🤷"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("x", "❌"),
+];
+
+/// Look up the emoji for a shortcode name (without the surrounding colons)
+pub fn emoji_for_shortcode(name: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by(|(code, _)| code.cmp(&name))
+        .ok()
+        .map(|index| SHORTCODES[index].1)
+}
+
+/// Replace known `:name:` shortcodes in `text` with their emoji.
+///
+/// Unknown names are left alone, and so is anything directly after another
+/// colon, so `::smile:`-style triggers never turn into emoji.
+pub fn expand_shortcodes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last_end = 0;
+
+    for captures in SHORTCODE_REGEX.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        let follows_colon = whole.start() > last_end && text[..whole.start()].ends_with(':');
+        if follows_colon {
+            continue;
+        }
+
+        if let Some(emoji) = emoji_for_shortcode(&captures[1]) {
+            result.push_str(&text[last_end..whole.start()]);
+            result.push_str(emoji);
+            last_end = whole.end();
+        }
+    }
+
+    result.push_str(&text[last_end..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_is_sorted() {
+        assert!(SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_expand_known_shortcodes() {
+        assert_eq!(expand_shortcodes("Thanks :smile: :tada:"), "Thanks 😄 🎉");
+        assert_eq!(expand_shortcodes(":+1::heart:"), "👍❤️");
+    }
+
+    #[test]
+    fn test_unknown_shortcodes_are_kept() {
+        assert_eq!(expand_shortcodes("at 10:30: :nope:"), "at 10:30: :nope:");
+    }
+
+    #[test]
+    fn test_triggers_are_not_shortcodes() {
+        assert_eq!(
+            expand_shortcodes("type ::smile: here"),
+            "type ::smile: here"
+        );
+        assert_eq!(expand_shortcodes("::a:smile:"), "::a:smile:");
+    }
+}
@@ -1,7 +1,15 @@
+use super::{
+    normalize_trigger, render_placeholders_with_env, ContentType, Hotkey, MatchOptions,
+    TriggerCase, TriggerPolicy,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use uuid::Uuid;
 
+/// Shown in place of a replacement that couldn't be decrypted
+pub const LOCKED_REPLACEMENT: &str = "[encrypted]";
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Snippet {
     pub id: Uuid,
@@ -12,11 +20,67 @@ pub struct Snippet {
     pub is_active: bool,
     pub usage_count: u64,
     pub tags: Vec<String>,
+    pub content_type: ContentType,
+    /// Ask the user before typing this snippet (e.g. sensitive or very large content)
+    pub confirm_before_expand: bool,
+    /// Leave the typed trigger in place and insert the expansion after it
+    pub keep_trigger: bool,
+    /// Other triggers that expand to this snippet (e.g. `::addr` for `::address`)
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Pause between typed characters, for apps that drop fast input.
+    /// `None` uses the engine's default.
+    #[serde(default)]
+    pub type_delay_ms: Option<u64>,
+    /// Where the snippet came from, e.g. the pack or file it was imported from
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Listed before unpinned snippets whatever the sort
+    #[serde(default)]
+    pub pinned: bool,
+    /// Global hotkey that expands the snippet without typing its trigger,
+    /// stored in `Hotkey`'s canonical form (e.g. `Ctrl+Alt+1`)
+    #[serde(default)]
+    pub hotkey: Option<String>,
+    /// Start of the time the snippet expands, e.g. for an out-of-office reply.
+    /// `None` means it always has.
+    #[serde(default)]
+    pub active_from: Option<DateTime<Utc>>,
+    /// End of the time the snippet expands (exclusive). `None` means it never ends.
+    #[serde(default)]
+    pub active_until: Option<DateTime<Utc>>,
+    /// The trigger's Title and UPPER case forms are aliases whose expansions
+    /// follow their case, see `generate_case_variants`
+    #[serde(default)]
+    pub case_variants: bool,
+    /// Place in an order arranged by hand, lowest first (`SortBy::Position`).
+    /// Snippets without one come after those with one.
+    #[serde(default)]
+    pub position: Option<u32>,
+    /// The replacement is stored encrypted, see `EncryptionService`. It
+    /// can't be matched by database searches while it is.
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Why an `encrypted` replacement couldn't be decrypted, e.g. a missing
+    /// passphrase. `replacement` then still holds the stored ciphertext, so
+    /// saving the snippet keeps it as it was.
+    #[serde(skip)]
+    pub locked: Option<String>,
 }
 
 impl Snippet {
     pub fn new(trigger: String, replacement: String) -> anyhow::Result<Self> {
-        Self::validate_trigger(&trigger)?;
+        Self::new_with_policy(trigger, replacement, &TriggerPolicy::default())
+    }
+
+    /// Like `new`, but validates the trigger against `policy` instead of the default rules
+    pub fn new_with_policy(
+        trigger: String,
+        replacement: String,
+        policy: &TriggerPolicy,
+    ) -> anyhow::Result<Self> {
+        let trigger = normalize_trigger(&trigger);
+        policy.validate(&trigger)?;
         Self::validate_replacement(&replacement)?;
 
         let now = Utc::now();
@@ -29,6 +93,20 @@ impl Snippet {
             is_active: true,
             usage_count: 0,
             tags: Vec::new(),
+            content_type: ContentType::Plain,
+            confirm_before_expand: false,
+            keep_trigger: false,
+            aliases: Vec::new(),
+            type_delay_ms: None,
+            source: None,
+            pinned: false,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            case_variants: false,
+            position: None,
+            encrypted: false,
+            locked: None,
         })
     }
 
@@ -51,23 +129,138 @@ impl Snippet {
             is_active: true,
             usage_count: 0,
             tags: Vec::new(),
+            content_type: ContentType::Plain,
+            confirm_before_expand: false,
+            keep_trigger: false,
+            aliases: Vec::new(),
+            type_delay_ms: None,
+            source: None,
+            pinned: false,
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            case_variants: false,
+            position: None,
+            encrypted: false,
+            locked: None,
         })
     }
 
     pub fn update_replacement(&mut self, replacement: String) -> anyhow::Result<()> {
         Self::validate_replacement(&replacement)?;
         self.replacement = replacement;
+        self.locked = None;
         self.updated_at = Utc::now();
         Ok(())
     }
 
+    /// Fail with the reason when the replacement couldn't be decrypted, for
+    /// anything that needs to read it
+    pub fn ensure_unlocked(&self) -> anyhow::Result<()> {
+        match &self.locked {
+            Some(reason) => Err(anyhow::anyhow!("'{}': {}", self.trigger, reason)),
+            None => Ok(()),
+        }
+    }
+
     pub fn update_trigger(&mut self, trigger: String) -> anyhow::Result<()> {
-        Self::validate_trigger(&trigger)?;
+        self.update_trigger_with_policy(trigger, &TriggerPolicy::default())
+    }
+
+    pub fn update_trigger_with_policy(
+        &mut self,
+        trigger: String,
+        policy: &TriggerPolicy,
+    ) -> anyhow::Result<()> {
+        let trigger = normalize_trigger(&trigger);
+        policy.validate(&trigger)?;
+        self.aliases.retain(|alias| alias != &trigger);
         self.trigger = trigger;
         self.updated_at = Utc::now();
         Ok(())
     }
 
+    pub fn set_aliases(&mut self, aliases: Vec<String>) -> anyhow::Result<()> {
+        self.set_aliases_with_policy(aliases, &TriggerPolicy::default())
+    }
+
+    /// Replace the aliases, validating each like a trigger. Duplicates and
+    /// aliases equal to the trigger itself are dropped.
+    pub fn set_aliases_with_policy(
+        &mut self,
+        aliases: Vec<String>,
+        policy: &TriggerPolicy,
+    ) -> anyhow::Result<()> {
+        let mut normalized: Vec<String> = Vec::with_capacity(aliases.len());
+        for alias in aliases {
+            let alias = normalize_trigger(&alias);
+            policy.validate(&alias)?;
+            if alias != self.trigger && !normalized.contains(&alias) {
+                normalized.push(alias);
+            }
+        }
+
+        if self.aliases != normalized {
+            self.aliases = normalized;
+            self.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    /// Add the trigger's Title and UPPER case forms as aliases, expanding to
+    /// the replacement in that case: `::Btw` gives "By the way" and `::BTW`
+    /// "BY THE WAY" for `::btw`
+    pub fn generate_case_variants(&mut self) -> anyhow::Result<()> {
+        let variants = TriggerCase::ALL.map(|case| case.apply(&self.trigger));
+        let aliases = self.aliases.iter().cloned().chain(variants).collect();
+        self.set_aliases(aliases)?;
+        self.case_variants = true;
+        Ok(())
+    }
+
+    /// The case to put the expansion in when `typed` is a case variant of
+    /// the trigger. `None` for the trigger itself, other aliases, and
+    /// snippets without case variants.
+    pub fn case_of(&self, typed: &str) -> Option<TriggerCase> {
+        if !self.case_variants {
+            return None;
+        }
+        let typed = MatchOptions::default().key(typed);
+        if typed == self.trigger {
+            return None;
+        }
+        TriggerCase::ALL
+            .into_iter()
+            .find(|case| normalize_trigger(&case.apply(&self.trigger)) == typed)
+    }
+
+    /// The trigger followed by its aliases
+    pub fn triggers(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.trigger.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Whether typed `candidate` is this snippet's trigger, or one of its
+    /// aliases if `options` allow them
+    pub fn matches_trigger(&self, candidate: &str, options: &MatchOptions) -> bool {
+        let candidate = options.key(candidate);
+        let count = if options.include_aliases {
+            1 + self.aliases.len()
+        } else {
+            1
+        };
+
+        self.triggers()
+            .take(count)
+            .any(|trigger| options.key(trigger) == candidate)
+    }
+
+    pub fn set_content_type(&mut self, content_type: ContentType) {
+        if self.content_type != content_type {
+            self.content_type = content_type;
+            self.updated_at = Utc::now();
+        }
+    }
+
     pub fn increment_usage(&mut self) {
         self.usage_count = self.usage_count.saturating_add(1);
         self.updated_at = Utc::now();
@@ -87,41 +280,124 @@ impl Snippet {
         }
     }
 
-    pub fn deactivate(&mut self) {
-        self.is_active = false;
+    /// Replace tag `from` with `to` where `from` was, dropping `from` instead
+    /// if the snippet already has `to`. Returns whether the tags changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> bool {
+        let Some(pos) = self.tags.iter().position(|t| t == from) else {
+            return false;
+        };
+        if self.tags.iter().any(|t| t == to) {
+            self.tags.remove(pos);
+        } else {
+            self.tags[pos] = to.to_string();
+        }
         self.updated_at = Utc::now();
+        true
     }
 
-    pub fn activate(&mut self) {
-        self.is_active = true;
-        self.updated_at = Utc::now();
+    pub fn set_confirm_before_expand(&mut self, confirm_before_expand: bool) {
+        if self.confirm_before_expand != confirm_before_expand {
+            self.confirm_before_expand = confirm_before_expand;
+            self.updated_at = Utc::now();
+        }
     }
 
-    fn validate_trigger(trigger: &str) -> anyhow::Result<()> {
-        if trigger.is_empty() {
-            return Err(anyhow::anyhow!("Trigger cannot be empty"));
+    pub fn set_keep_trigger(&mut self, keep_trigger: bool) {
+        if self.keep_trigger != keep_trigger {
+            self.keep_trigger = keep_trigger;
+            self.updated_at = Utc::now();
         }
+    }
 
-        if trigger.len() > 50 {
-            return Err(anyhow::anyhow!(
-                "Trigger cannot be longer than 50 characters"
-            ));
+    pub fn set_type_delay_ms(&mut self, type_delay_ms: Option<u64>) {
+        if self.type_delay_ms != type_delay_ms {
+            self.type_delay_ms = type_delay_ms;
+            self.updated_at = Utc::now();
         }
+    }
 
-        if trigger.contains(' ') {
-            return Err(anyhow::anyhow!("Trigger cannot contain spaces"));
+    pub fn set_source(&mut self, source: Option<String>) {
+        if self.source != source {
+            self.source = source;
+            self.updated_at = Utc::now();
         }
+    }
 
-        if !trigger
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || ":_-".contains(c))
-        {
-            return Err(anyhow::anyhow!("Trigger can only contain alphanumeric characters, colons, underscores, and hyphens"));
+    /// Bind the snippet to a hotkey such as `Ctrl+Alt+1`, or unbind it with `None`
+    pub fn set_hotkey(&mut self, hotkey: Option<&str>) -> anyhow::Result<()> {
+        let hotkey = hotkey
+            .map(|text| Hotkey::parse(text).map(|hotkey| hotkey.to_string()))
+            .transpose()?;
+        if self.hotkey != hotkey {
+            self.hotkey = hotkey;
+            self.updated_at = Utc::now();
         }
+        Ok(())
+    }
+
+    pub fn set_pinned(&mut self, pinned: bool) {
+        if self.pinned != pinned {
+            self.pinned = pinned;
+            self.updated_at = Utc::now();
+        }
+    }
 
+    pub fn set_encrypted(&mut self, encrypted: bool) {
+        if self.encrypted != encrypted {
+            self.encrypted = encrypted;
+            self.updated_at = Utc::now();
+        }
+    }
+
+    /// Limit when the snippet expands to `from..until`, either end open when
+    /// `None`
+    pub fn set_active_window(
+        &mut self,
+        from: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        if let (Some(from), Some(until)) = (from, until) {
+            if from >= until {
+                return Err(anyhow::anyhow!(
+                    "Active window ends ({}) before it starts ({})",
+                    until.to_rfc3339(),
+                    from.to_rfc3339()
+                ));
+            }
+        }
+        if self.active_from != from || self.active_until != until {
+            self.active_from = from;
+            self.active_until = until;
+            self.updated_at = Utc::now();
+        }
         Ok(())
     }
 
+    /// Whether `now` falls inside the active window, whatever `is_active` is
+    pub fn in_active_window(&self, now: DateTime<Utc>) -> bool {
+        self.active_from.is_none_or(|from| from <= now)
+            && self.active_until.is_none_or(|until| now < until)
+    }
+
+    /// Whether the snippet expands at `now`: it is active and inside its window
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.is_active && self.in_active_window(now)
+    }
+
+    pub fn deactivate(&mut self) {
+        self.is_active = false;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn activate(&mut self) {
+        self.is_active = true;
+        self.updated_at = Utc::now();
+    }
+
+    fn validate_trigger(trigger: &str) -> anyhow::Result<()> {
+        TriggerPolicy::default().validate(trigger)
+    }
+
     fn validate_replacement(replacement: &str) -> anyhow::Result<()> {
         if replacement.is_empty() {
             return Err(anyhow::anyhow!("Replacement cannot be empty"));
@@ -136,24 +412,31 @@ impl Snippet {
         Ok(())
     }
 
-    pub fn expand(&self) -> String {
-        let mut result = self.replacement.clone();
-
-        // Handle dynamic placeholders
-        let now = Utc::now();
+    /// The replacement with its placeholders rendered, reading only the
+    /// environment variables in `env_allowlist` (see `--allow-env`)
+    pub fn expand(&self, env_allowlist: Option<&HashSet<String>>) -> String {
+        render_placeholders_with_env(&self.replacement, env_allowlist)
+    }
+}
 
-        // Date/time placeholders
-        result = result.replace("{date}", &now.format("%Y-%m-%d").to_string());
-        result = result.replace("{time}", &now.format("%H:%M:%S").to_string());
-        result = result.replace("{datetime}", &now.format("%Y-%m-%d %H:%M:%S").to_string());
-        result = result.replace("{timestamp}", &now.timestamp().to_string());
+/// A past state of a snippet, recorded before it was changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnippetVersion {
+    pub snippet_id: Uuid,
+    pub trigger: String,
+    pub replacement: String,
+    pub changed_at: DateTime<Utc>,
+}
 
-        // User info placeholders (simplified for now)
-        if let Ok(username) = std::env::var("USER") {
-            result = result.replace("{user}", &username);
+impl SnippetVersion {
+    /// Capture the snippet's current trigger and replacement
+    pub fn of(snippet: &Snippet) -> Self {
+        Self {
+            snippet_id: snippet.id,
+            trigger: snippet.trigger.clone(),
+            replacement: snippet.replacement.clone(),
+            changed_at: Utc::now(),
         }
-
-        result
     }
 }
 
@@ -182,6 +465,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_whitespace_control_and_bare_prefix_triggers_are_rejected() {
+        for trigger in ["::\t", "::\n", "::", "::hi\tthere", "::\u{0}"] {
+            let result = Snippet::new(trigger.to_string(), "Test".to_string());
+            assert!(result.is_err(), "{:?}", trigger);
+        }
+
+        let mut snippet = Snippet::new("::hi".to_string(), "Hi".to_string()).unwrap();
+        assert!(snippet.update_trigger("::\n".to_string()).is_err());
+        assert_eq!(snippet.trigger, "::hi");
+    }
+
+    #[test]
+    fn test_policy_allowing_slash() {
+        let policy = TriggerPolicy::default().allowing("/");
+
+        let snippet =
+            Snippet::new_with_policy("::code/loop".to_string(), "for".to_string(), &policy)
+                .unwrap();
+        assert_eq!(snippet.trigger, "::code/loop");
+
+        // The default rules still reject it
+        assert!(Snippet::new("::code/loop".to_string(), "for".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_policy_forbidding_digits() {
+        let policy = TriggerPolicy::default().without_digits();
+
+        assert!(Snippet::new_with_policy("::sig".to_string(), "Sig".to_string(), &policy).is_ok());
+        let err =
+            Snippet::new_with_policy("::sig2".to_string(), "Sig".to_string(), &policy).unwrap_err();
+        assert!(err.to_string().contains("only contain letters"));
+
+        let mut snippet = Snippet::new("::sig".to_string(), "Sig".to_string()).unwrap();
+        assert!(snippet
+            .update_trigger_with_policy("::sig3".to_string(), &policy)
+            .is_err());
+        assert_eq!(snippet.trigger, "::sig");
+    }
+
     #[test]
     fn test_invalid_replacement() {
         let result = Snippet::new("::test".to_string(), "".to_string());
@@ -225,8 +549,135 @@ mod tests {
     #[test]
     fn test_expansion_with_placeholders() {
         let snippet = Snippet::new("::date".to_string(), "Today is {date}".to_string()).unwrap();
-        let expanded = snippet.expand();
+        let expanded = snippet.expand(None);
         assert!(expanded.contains("Today is"));
         assert!(expanded.len() > "Today is ".len());
     }
+
+    #[test]
+    fn test_expansion_keeps_escaped_braces() {
+        let snippet =
+            Snippet::new("::doc".to_string(), "Use {{date}} for {date}".to_string()).unwrap();
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(snippet.expand(None), format!("Use {{date}} for {}", today));
+    }
+
+    #[test]
+    fn test_expansion_reads_only_allowed_env_vars() {
+        std::env::set_var("TYPELY_TEST_SNIPPET_ALLOWED", "yes");
+        std::env::set_var("TYPELY_TEST_SNIPPET_SECRET", "hunter2");
+        let snippet = Snippet::new(
+            "::env".to_string(),
+            "{env:TYPELY_TEST_SNIPPET_ALLOWED} {env:TYPELY_TEST_SNIPPET_SECRET}".to_string(),
+        )
+        .unwrap();
+
+        let allowlist: HashSet<String> = ["TYPELY_TEST_SNIPPET_ALLOWED".to_string()].into();
+        assert_eq!(
+            snippet.expand(Some(&allowlist)),
+            "yes {env:TYPELY_TEST_SNIPPET_SECRET}"
+        );
+    }
+
+    #[test]
+    fn test_trigger_is_stored_in_nfc() {
+        let mut snippet = Snippet::new("::cafe\u{301}".to_string(), "Coffee".to_string()).unwrap();
+        assert_eq!(snippet.trigger, "::caf\u{e9}");
+
+        snippet.update_trigger("::the\u{301}".to_string()).unwrap();
+        assert_eq!(snippet.trigger, "::th\u{e9}");
+    }
+
+    fn cafe_snippet() -> Snippet {
+        let mut snippet = Snippet::new("::Caf\u{e9}".to_string(), "Coffee".to_string()).unwrap();
+        snippet.set_aliases(vec!["::coffee".to_string()]).unwrap();
+        snippet
+    }
+
+    #[test]
+    fn test_matches_trigger_by_default() {
+        let snippet = cafe_snippet();
+        let options = MatchOptions::default();
+
+        assert!(snippet.matches_trigger("::Caf\u{e9}", &options));
+        assert!(snippet.matches_trigger("::Cafe\u{301}", &options));
+        assert!(snippet.matches_trigger("::coffee", &options));
+        assert!(!snippet.matches_trigger("::caf\u{e9}", &options));
+        assert!(!snippet.matches_trigger("::Caf", &options));
+    }
+
+    #[test]
+    fn test_matches_trigger_ignoring_case() {
+        let snippet = cafe_snippet();
+        let options = MatchOptions::default().ignoring_case();
+
+        assert!(snippet.matches_trigger("::CAFE\u{301}", &options));
+        assert!(snippet.matches_trigger("::COFFEE", &options));
+        assert!(!snippet.matches_trigger("::tea", &options));
+    }
+
+    #[test]
+    fn test_matches_trigger_without_aliases() {
+        let snippet = cafe_snippet();
+        let options = MatchOptions::default().without_aliases();
+
+        assert!(snippet.matches_trigger("::Caf\u{e9}", &options));
+        assert!(!snippet.matches_trigger("::coffee", &options));
+        assert!(!snippet.matches_trigger("::COFFEE", &options.ignoring_case()));
+    }
+
+    #[test]
+    fn test_matches_trigger_without_normalization() {
+        let snippet = cafe_snippet();
+        let options = MatchOptions::default().without_normalization();
+
+        assert!(snippet.matches_trigger("::Caf\u{e9}", &options));
+        assert!(!snippet.matches_trigger("::Cafe\u{301}", &options));
+        assert!(!snippet.matches_trigger("::cafe\u{301}", &options.ignoring_case()));
+        assert!(snippet.matches_trigger("::caf\u{e9}", &options.ignoring_case()));
+    }
+
+    #[test]
+    fn test_active_window() {
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+        let mut snippet = Snippet::new("::ooo".to_string(), "Out of office".to_string()).unwrap();
+        assert!(snippet.is_active_at(now));
+
+        snippet
+            .set_active_window(Some(now - hour), Some(now + hour))
+            .unwrap();
+        assert!(snippet.is_active_at(now));
+        assert!(!snippet.is_active_at(now - hour * 2));
+        // The end is exclusive
+        assert!(!snippet.is_active_at(now + hour));
+
+        snippet.set_active_window(None, Some(now - hour)).unwrap();
+        assert!(!snippet.in_active_window(now));
+        snippet.set_active_window(Some(now - hour), None).unwrap();
+        snippet.deactivate();
+        assert!(snippet.in_active_window(now) && !snippet.is_active_at(now));
+
+        assert!(snippet.set_active_window(Some(now), Some(now)).is_err());
+    }
+
+    #[test]
+    fn test_case_variants() {
+        let mut snippet = Snippet::new("::btw".to_string(), "by the way".to_string()).unwrap();
+        snippet.set_aliases(vec!["::bytheway".to_string()]).unwrap();
+        assert_eq!(snippet.case_of("::BTW"), None);
+
+        snippet.generate_case_variants().unwrap();
+        assert_eq!(snippet.aliases, ["::bytheway", "::Btw", "::BTW"]);
+        assert_eq!(snippet.case_of("::Btw"), Some(TriggerCase::Title));
+        assert_eq!(snippet.case_of("::BTW"), Some(TriggerCase::Upper));
+        assert_eq!(snippet.case_of("::btw"), None);
+        assert_eq!(snippet.case_of("::bytheway"), None);
+
+        // One letter has a single variant, which is taken as Title case
+        let mut short = Snippet::new("::b".to_string(), "best".to_string()).unwrap();
+        short.generate_case_variants().unwrap();
+        assert_eq!(short.aliases, ["::B"]);
+        assert_eq!(short.case_of("::B"), Some(TriggerCase::Title));
+    }
 }
@@ -0,0 +1,107 @@
+/// A step applied to every expansion once its placeholders are resolved
+pub type TextTransform = Box<dyn Fn(String) -> String + Send + Sync>;
+
+/// Which built-in transforms run on expanded text (all off by default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExpansionRules {
+    /// Turn straight quotes into curly ones, see `smart_quotes`
+    pub smart_quotes: bool,
+    /// Drop spaces and tabs at the end of each line
+    pub trim_trailing: bool,
+}
+
+impl ExpansionRules {
+    /// The enabled built-ins, in the order they run: quotes, then trimming
+    pub fn transforms(&self) -> Vec<TextTransform> {
+        let mut transforms: Vec<TextTransform> = Vec::new();
+        if self.smart_quotes {
+            transforms.push(Box::new(|text| smart_quotes(&text)));
+        }
+        if self.trim_trailing {
+            transforms.push(Box::new(|text| trim_trailing_whitespace(&text)));
+        }
+        transforms
+    }
+}
+
+/// Replace straight quotes with curly ones. A quote at the start of the text
+/// or after whitespace or an opening bracket opens; any other closes, which
+/// also makes apostrophes (`don't`) come out right.
+pub fn smart_quotes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+
+    for c in text.chars() {
+        let opens = previous.is_none_or(|p| p.is_whitespace() || "([{\u{2014}".contains(p));
+        let replaced = match (c, opens) {
+            ('"', true) => '\u{201c}',
+            ('"', false) => '\u{201d}',
+            ('\'', true) => '\u{2018}',
+            ('\'', false) => '\u{2019}',
+            _ => c,
+        };
+        result.push(replaced);
+        previous = Some(c);
+    }
+
+    result
+}
+
+/// Drop spaces and tabs before each line break and at the end of the text,
+/// keeping the line breaks themselves
+pub fn trim_trailing_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            // Keeps the `\r` of CRLF line endings
+            let (line, cr) = match line.strip_suffix('\r') {
+                Some(line) => (line, "\r"),
+                None => (line, ""),
+            };
+            format!("{}{}", line.trim_end_matches([' ', '\t']), cr)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smart_quotes() {
+        assert_eq!(
+            smart_quotes(r#"She said "it's fine" ('really')"#),
+            "She said \u{201c}it\u{2019}s fine\u{201d} (\u{2018}really\u{2019})"
+        );
+        assert_eq!(
+            smart_quotes("\"Hi\"\n'you'"),
+            "\u{201c}Hi\u{201d}\n\u{2018}you\u{2019}"
+        );
+        assert_eq!(smart_quotes("no quotes"), "no quotes");
+    }
+
+    #[test]
+    fn test_trim_trailing_whitespace() {
+        assert_eq!(
+            trim_trailing_whitespace("Dear Sam,  \n\t\nThanks \t"),
+            "Dear Sam,\n\nThanks"
+        );
+        assert_eq!(trim_trailing_whitespace("a \r\nb"), "a\r\nb");
+        assert_eq!(trim_trailing_whitespace("  indented"), "  indented");
+    }
+
+    #[test]
+    fn test_rules_choose_transforms() {
+        assert!(ExpansionRules::default().transforms().is_empty());
+
+        let rules = ExpansionRules {
+            smart_quotes: true,
+            trim_trailing: true,
+        };
+        let text = rules
+            .transforms()
+            .iter()
+            .fold("'ok' ".to_string(), |text, transform| transform(text));
+        assert_eq!(text, "\u{2018}ok\u{2019}");
+    }
+}
@@ -0,0 +1,148 @@
+use super::is_trigger_body_char;
+
+/// Rules a snippet trigger must satisfy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerPolicy {
+    /// Maximum trigger length in bytes
+    pub max_length: usize,
+    /// Characters allowed besides letters (and ASCII digits, if enabled)
+    pub extra_chars: String,
+    pub allow_digits: bool,
+}
+
+impl Default for TriggerPolicy {
+    fn default() -> Self {
+        Self {
+            max_length: 50,
+            extra_chars: ":_-".to_string(),
+            allow_digits: true,
+        }
+    }
+}
+
+impl TriggerPolicy {
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Also allow the given characters, e.g. `"/"` for `::code/loop`
+    pub fn allowing(mut self, chars: &str) -> Self {
+        for c in chars.chars() {
+            if !self.extra_chars.contains(c) {
+                self.extra_chars.push(c);
+            }
+        }
+        self
+    }
+
+    pub fn without_digits(mut self) -> Self {
+        self.allow_digits = false;
+        self
+    }
+
+    pub fn validate(&self, trigger: &str) -> anyhow::Result<()> {
+        if trigger.is_empty() {
+            return Err(anyhow::anyhow!("Trigger cannot be empty"));
+        }
+
+        if trigger.len() > self.max_length {
+            return Err(anyhow::anyhow!(
+                "Trigger cannot be longer than {} characters",
+                self.max_length
+            ));
+        }
+
+        // Whatever the extra characters, a trigger has to be typeable as one word
+        if trigger.contains(|c: char| c.is_whitespace() || c.is_control()) {
+            return Err(anyhow::anyhow!(
+                "Trigger cannot contain spaces, tabs, line breaks or control characters"
+            ));
+        }
+
+        if !trigger.chars().any(is_trigger_body_char) {
+            return Err(anyhow::anyhow!(
+                "Trigger needs a letter, digit, '_' or '-' after its prefix"
+            ));
+        }
+
+        if !trigger.chars().all(|c| self.is_allowed(c)) {
+            return Err(anyhow::anyhow!(
+                "Trigger can only contain {} and the characters '{}'",
+                if self.allow_digits {
+                    "alphanumeric characters"
+                } else {
+                    "letters"
+                },
+                self.extra_chars
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn is_allowed(&self, c: char) -> bool {
+        // Any script's letters are fine; triggers are NFC-normalized before validation
+        c.is_alphabetic()
+            || (self.allow_digits && c.is_ascii_digit())
+            || (!c.is_whitespace() && !c.is_control() && self.extra_chars.contains(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = TriggerPolicy::default();
+
+        assert!(policy.validate("::hello_world-2").is_ok());
+        assert!(policy.validate("::code/loop").is_err());
+        assert!(policy.validate(&format!("::{}", "a".repeat(48))).is_ok());
+        assert!(policy.validate(&format!("::{}", "a".repeat(49))).is_err());
+    }
+
+    #[test]
+    fn test_custom_max_length() {
+        let policy = TriggerPolicy::default().with_max_length(5);
+
+        assert!(policy.validate("::abc").is_ok());
+        let err = policy.validate("::abcd").unwrap_err();
+        assert!(err.to_string().contains("longer than 5"));
+    }
+
+    #[test]
+    fn test_spaces_are_never_allowed() {
+        let policy = TriggerPolicy::default().allowing(" ");
+        assert!(policy.validate(":: hi").is_err());
+    }
+
+    #[test]
+    fn test_control_characters_are_never_allowed() {
+        let policy = TriggerPolicy::default().allowing("\t\n\u{7}");
+        for trigger in ["::\thi", "::hi\n", "::h\u{7}i", "::hi\r"] {
+            let err = policy.validate(trigger).unwrap_err();
+            assert!(
+                err.to_string().contains("control characters"),
+                "{:?}",
+                trigger
+            );
+        }
+    }
+
+    #[test]
+    fn test_trigger_needs_a_body() {
+        let policy = TriggerPolicy::default().allowing(";");
+        for trigger in ["::", "::::", ";;"] {
+            assert!(policy.validate(trigger).is_err(), "{:?}", trigger);
+        }
+        assert!(policy
+            .validate("::")
+            .unwrap_err()
+            .to_string()
+            .contains("after its prefix"));
+        assert!(policy.validate(";;a").is_ok());
+        assert!(policy.validate("::é").is_ok());
+    }
+}
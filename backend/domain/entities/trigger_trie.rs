@@ -0,0 +1,222 @@
+use super::MatchOptions;
+use aho_corasick::automaton::{Automaton, StateID};
+use aho_corasick::dfa::DFA;
+use aho_corasick::{Anchored, MatchKind};
+use unicode_normalization::UnicodeNormalization;
+
+/// Known triggers compiled into an Aho-Corasick automaton, for noticing a
+/// trigger as it is typed instead of searching the whole buffer on every
+/// keystroke. Text is fed in with `advance` as it's typed, and `trigger_at`
+/// tells whether it now ends with a trigger.
+///
+/// Triggers are compared as expansion compares them (`MatchOptions::default()`),
+/// so one typed with decomposed accents is found under its NFC form.
+/// `TriggerPrefix::trigger_regex` remains the way to find triggers in a
+/// whole piece of text.
+pub struct TriggerTrie {
+    dfa: DFA,
+    /// The NFC form of the trigger each pattern spells
+    triggers: Vec<String>,
+}
+
+/// How far typed text has taken a `TriggerTrie`. Only means anything to the
+/// trie that made it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrieCursor(StateID);
+
+/// A trigger the typed text ends with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieMatch {
+    /// NFC form, as stored triggers are compared
+    pub trigger: String,
+    /// Bytes of typed text it covers, more than `trigger.len()` when it was
+    /// typed decomposed
+    pub typed_length: usize,
+}
+
+impl TriggerTrie {
+    pub fn new<I, S>(triggers: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut patterns = Vec::new();
+        let mut keys = Vec::new();
+        for trigger in triggers {
+            let key = MatchOptions::default().key(trigger.as_ref()).into_owned();
+            // An empty pattern would match after every keystroke
+            if key.is_empty() {
+                continue;
+            }
+            let decomposed: String = key.nfd().collect();
+            if decomposed != key {
+                patterns.push(decomposed);
+                keys.push(key.clone());
+            }
+            patterns.push(key.clone());
+            keys.push(key);
+        }
+
+        // Standard semantics keep every pattern ending at a state, which
+        // is what lets `trigger_at` pick the longest
+        let dfa = DFA::builder()
+            .match_kind(MatchKind::Standard)
+            .build(&patterns)?;
+        Ok(Self {
+            dfa,
+            triggers: keys,
+        })
+    }
+
+    /// A cursor with nothing typed
+    pub fn cursor(&self) -> TrieCursor {
+        TrieCursor(
+            self.dfa
+                .start_state(Anchored::No)
+                .expect("the trie is built for unanchored searches"),
+        )
+    }
+
+    /// Move `cursor` past `text`, typed after what it has seen
+    pub fn advance(&self, cursor: &mut TrieCursor, text: &str) {
+        for &byte in text.as_bytes() {
+            cursor.0 = self.dfa.next_state(Anchored::No, cursor.0, byte);
+        }
+    }
+
+    /// The longest trigger the text seen by `cursor` ends with
+    pub fn trigger_at(&self, cursor: &TrieCursor) -> Option<TrieMatch> {
+        let state = cursor.0;
+        if !self.dfa.is_match(state) {
+            return None;
+        }
+
+        (0..self.dfa.match_len(state))
+            .map(|index| self.dfa.match_pattern(state, index))
+            .max_by_key(|&pattern| self.dfa.pattern_len(pattern))
+            .map(|pattern| TrieMatch {
+                trigger: self.triggers[pattern.as_usize()].clone(),
+                typed_length: self.dfa.pattern_len(pattern),
+            })
+    }
+
+    /// The longest trigger `text` ends with
+    pub fn find_at_end(&self, text: &str) -> Option<TrieMatch> {
+        let mut cursor = self.cursor();
+        self.advance(&mut cursor, text);
+        self.trigger_at(&cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::TriggerPrefix;
+    use std::collections::HashSet;
+
+    const TRIGGERS: [&str; 8] = [
+        "::a",
+        "::ab",
+        "::sig",
+        "::sig2",
+        "::addr",
+        "::caf\u{e9}",
+        "::x_y-z",
+        "::日本",
+    ];
+
+    /// Typed text covering overlapping triggers, near misses and triggers
+    /// inside words
+    const FIXTURE: &str = "say ::a then ::ab, ::abc and ::sig2 or ::si ::sig.\n\
+        x::addr ::addr:: :::addr ::café ::cafe ::x_y-z! ::x_y ::日本 ::日本語 ::::ab";
+
+    /// What the regex finds: the last trigger-shaped word ending where the
+    /// text ends, if it's a known trigger
+    fn regex_at_end(text: &str, triggers: &HashSet<&str>) -> Option<String> {
+        TriggerPrefix::default()
+            .trigger_regex()
+            .find_iter(text)
+            .filter(|found| found.end() == text.len())
+            .map(|found| found.as_str())
+            .find(|trigger| triggers.contains(trigger))
+            .map(str::to_string)
+    }
+
+    #[test]
+    fn test_matches_the_regex_keystroke_by_keystroke() {
+        let trie = TriggerTrie::new(TRIGGERS).unwrap();
+        let triggers: HashSet<&str> = TRIGGERS.into_iter().collect();
+
+        let mut cursor = trie.cursor();
+        let mut typed = String::new();
+        let mut found = Vec::new();
+        for c in FIXTURE.chars() {
+            typed.push(c);
+            trie.advance(&mut cursor, c.encode_utf8(&mut [0; 4]));
+
+            let from_trie = trie.trigger_at(&cursor).map(|found| found.trigger);
+            assert_eq!(
+                from_trie,
+                regex_at_end(&typed, &triggers),
+                "after {:?}",
+                typed
+            );
+            found.extend(from_trie);
+        }
+
+        assert_eq!(
+            found,
+            vec![
+                "::a",
+                "::a",
+                "::ab",
+                "::a",
+                "::ab",
+                "::sig",
+                "::sig2",
+                "::sig",
+                "::a",
+                "::addr",
+                "::a",
+                "::addr",
+                "::a",
+                "::addr",
+                "::caf\u{e9}",
+                "::x_y-z",
+                "::日本",
+                "::日本",
+                "::a",
+                "::ab",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_longest_trigger_ending_here_wins() {
+        let trie = TriggerTrie::new(["::b", ":::b", "::ab"]).unwrap();
+
+        assert_eq!(trie.find_at_end("x:::b").unwrap().trigger, ":::b");
+        assert_eq!(trie.find_at_end("x::b").unwrap().trigger, "::b");
+        assert_eq!(trie.find_at_end("::ab").unwrap().typed_length, 4);
+        assert!(trie.find_at_end("::b ").is_none());
+    }
+
+    #[test]
+    fn test_decomposed_typing_finds_the_nfc_trigger() {
+        let trie = TriggerTrie::new(["::cafe\u{301}"]).unwrap();
+
+        let composed = trie.find_at_end("::caf\u{e9}").unwrap();
+        let decomposed = trie.find_at_end("::cafe\u{301}").unwrap();
+        assert_eq!(composed.trigger, "::caf\u{e9}");
+        assert_eq!(decomposed.trigger, "::caf\u{e9}");
+        assert_eq!(composed.typed_length, "::caf\u{e9}".len());
+        assert_eq!(decomposed.typed_length, "::cafe\u{301}".len());
+    }
+
+    #[test]
+    fn test_empty_trie_never_matches() {
+        let trie = TriggerTrie::new(Vec::<String>::new()).unwrap();
+        assert!(trie.find_at_end("::anything").is_none());
+        assert!(TriggerTrie::new([""]).unwrap().find_at_end("").is_none());
+    }
+}
@@ -1,25 +1,184 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 
-/// Simple trigger detection service for CLI builds (stub implementation)
-#[derive(Clone)]
-pub struct TriggerDetectionService;
+/// Bring a trigger into Unicode NFC form, so `é` typed as one code point or as
+/// `e` plus a combining accent compares equal
+pub fn normalize_trigger(trigger: &str) -> String {
+    trigger.nfc().collect()
+}
+
+/// Whether `c` can make up a trigger's body, the part after its prefix: a
+/// letter of any script, an ASCII digit, `_` or `-`. A trigger needs at least
+/// one, which is also what `TriggerPrefix::trigger_regex` looks for.
+pub fn is_trigger_body_char(c: char) -> bool {
+    c.is_alphabetic() || c.is_ascii_digit() || TriggerPrefix::BODY_CHARS.contains(c)
+}
+
+/// How typed text is compared with a snippet's triggers. The default is what
+/// expansion uses: case-sensitive, aliases included, NFC-normalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchOptions {
+    pub case_sensitive: bool,
+    /// Accept a snippet's aliases as well as its trigger
+    pub include_aliases: bool,
+    /// Compare in NFC form, so composed and decomposed accents match
+    pub normalize: bool,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self {
+            case_sensitive: true,
+            include_aliases: true,
+            normalize: true,
+        }
+    }
+}
+
+impl MatchOptions {
+    pub fn ignoring_case(mut self) -> Self {
+        self.case_sensitive = false;
+        self
+    }
+
+    pub fn without_aliases(mut self) -> Self {
+        self.include_aliases = false;
+        self
+    }
+
+    pub fn without_normalization(mut self) -> Self {
+        self.normalize = false;
+        self
+    }
+
+    /// `trigger` in the form it is compared in. Two triggers match under
+    /// these options exactly when their keys are equal, so keys can be used
+    /// for map and set lookups. Borrows when nothing needs to change.
+    pub fn key<'a>(&self, trigger: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(trigger);
+        if self.normalize && !is_nfc(trigger) {
+            key = Cow::Owned(normalize_trigger(trigger));
+        }
+        if !self.case_sensitive && key.chars().any(char::is_uppercase) {
+            key = Cow::Owned(key.to_lowercase());
+        }
+        key
+    }
+}
+
+/// The case a trigger's case variant is typed in (`::Btw` or `::BTW` for
+/// `::btw`), which its expansion is put into as well
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerCase {
+    /// The first letter capitalized
+    Title,
+    Upper,
+}
+
+impl TriggerCase {
+    pub const ALL: [TriggerCase; 2] = [TriggerCase::Title, TriggerCase::Upper];
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            TriggerCase::Title => capitalize_first_letter(text),
+            TriggerCase::Upper => text.to_uppercase(),
+        }
+    }
+}
+
+/// `text` with its first letter upper-cased, skipping leading punctuation
+/// such as a trigger's `::`
+fn capitalize_first_letter(text: &str) -> String {
+    match text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((start, first)) => {
+            let rest = &text[start + first.len_utf8()..];
+            format!("{}{}{}", &text[..start], first.to_uppercase(), rest)
+        }
+        None => text.to_string(),
+    }
+}
+
+/// What triggers start with and how typed text is searched for them: `::`
+/// unless a team configures something else, such as `;;`. Snippets under
+/// another prefix need a `TriggerPolicy` allowing its characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerPrefix(String);
+
+impl TriggerPrefix {
+    /// Characters a trigger's body is made of besides letters and digits,
+    /// which a prefix can't use or it would be unclear where the body starts
+    const BODY_CHARS: &'static str = "_-";
+
+    pub fn new(prefix: &str) -> anyhow::Result<Self> {
+        if prefix.is_empty() {
+            return Err(anyhow::anyhow!("Trigger prefix cannot be empty"));
+        }
+        if let Some(c) = prefix
+            .chars()
+            .find(|c| c.is_alphanumeric() || c.is_whitespace() || Self::BODY_CHARS.contains(*c))
+        {
+            return Err(anyhow::anyhow!(
+                "Trigger prefix '{}' can't contain '{}', which triggers use after the prefix",
+                prefix,
+                c
+            ));
+        }
+        Ok(Self(prefix.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The prefix followed by trigger body characters (see
+    /// `is_trigger_body_char`), matched literally however many regex
+    /// metacharacters it holds. Inner colons are allowed but not trailing ones.
+    pub fn trigger_regex(&self) -> Regex {
+        Regex::new(&format!(
+            r"{}[\p{{Alphabetic}}0-9_-]+(?::[\p{{Alphabetic}}0-9_-]+)*",
+            regex::escape(&self.0)
+        ))
+        .expect("an escaped prefix makes a valid regex")
+    }
+}
 
-impl Default for TriggerDetectionService {
+impl Default for TriggerPrefix {
     fn default() -> Self {
-        Self::new()
+        Self("::".to_string())
     }
 }
 
+impl fmt::Display for TriggerPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Simple trigger detection service for CLI builds (stub implementation)
+#[derive(Clone, Default)]
+pub struct TriggerDetectionService {
+    prefix: TriggerPrefix,
+}
+
 impl TriggerDetectionService {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Look for triggers starting with `prefix` instead of `::`
+    pub fn with_prefix(mut self, prefix: TriggerPrefix) -> Self {
+        self.prefix = prefix;
+        self
     }
 
     pub fn detect_trigger(&self, text: &str) -> Option<TriggerMatch> {
-        // Simple detection - look for :: prefix
-        if let Some(start) = text.rfind("::") {
+        let prefix = self.prefix.as_str();
+        if let Some(start) = text.rfind(prefix) {
             let trigger = &text[start..];
-            if trigger.len() > 2 {
+            if Self::has_body(&trigger[prefix.len()..]) {
                 return Some(TriggerMatch {
                     trigger: trigger.to_string(),
                     start_position: start,
@@ -31,21 +190,22 @@ impl TriggerDetectionService {
     }
 
     pub fn find_triggers_in_text(&self, text: &str) -> Vec<TriggerMatch> {
+        let prefix = self.prefix.as_str();
         let mut matches = Vec::new();
         let mut current_pos = 0;
 
-        while let Some(pos) = text[current_pos..].find("::") {
+        while let Some(pos) = text[current_pos..].find(prefix) {
             let absolute_pos = current_pos + pos;
 
-            // Find end of trigger (whitespace or end of string)
+            // Find end of trigger (whitespace, a control character or end of string)
             let remaining = &text[absolute_pos..];
             let end_pos = remaining
-                .find(char::is_whitespace)
+                .find(|c: char| c.is_whitespace() || c.is_control())
                 .map(|p| absolute_pos + p)
                 .unwrap_or(text.len());
 
             let trigger = text[absolute_pos..end_pos].to_string();
-            if trigger.len() > 2 {
+            if Self::has_body(&trigger[prefix.len()..]) {
                 matches.push(TriggerMatch {
                     trigger,
                     start_position: absolute_pos,
@@ -58,8 +218,19 @@ impl TriggerDetectionService {
 
         matches
     }
+
+    /// Whether what follows a prefix could be a trigger's body: something,
+    /// with no whitespace or control characters
+    fn has_body(body: &str) -> bool {
+        !body.is_empty() && !body.chars().any(|c| c.is_whitespace() || c.is_control())
+    }
 }
 
+/// A trigger found in a piece of text.
+///
+/// `start_position`, `end_position` and `length()` are all byte offsets into the
+/// searched text, so they can be used directly for slicing. Use `char_length()`
+/// when the number of typed characters matters (e.g. how many backspaces to send).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TriggerMatch {
     pub trigger: String,
@@ -76,7 +247,161 @@ impl TriggerMatch {
         }
     }
 
+    /// Length of the match in bytes
     pub fn length(&self) -> usize {
         self.end_position - self.start_position
     }
+
+    /// Length of the trigger in Unicode scalar values (one per typed character)
+    pub fn char_length(&self) -> usize {
+        self.trigger.chars().count()
+    }
+
+    /// The trigger in NFC form, for comparing against stored triggers
+    pub fn normalized_trigger(&self) -> String {
+        MatchOptions::default().key(&self.trigger).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_consistent(text: &str, trigger_match: &TriggerMatch) {
+        assert_eq!(
+            &text[trigger_match.start_position..trigger_match.end_position],
+            trigger_match.trigger
+        );
+        assert_eq!(trigger_match.length(), trigger_match.trigger.len());
+    }
+
+    #[test]
+    fn test_ascii_trigger_units() {
+        let service = TriggerDetectionService::new();
+        let text = "say ::hi";
+
+        let trigger_match = service.detect_trigger(text).unwrap();
+        assert_consistent(text, &trigger_match);
+        assert_eq!(trigger_match.length(), 4);
+        assert_eq!(trigger_match.char_length(), 4);
+    }
+
+    #[test]
+    fn test_accented_trigger_units() {
+        let service = TriggerDetectionService::new();
+        let text = "déjà ::café";
+
+        let matches = service.find_triggers_in_text(text);
+        assert_eq!(matches.len(), 1);
+        assert_consistent(text, &matches[0]);
+        assert_eq!(matches[0].length(), 7);
+        assert_eq!(matches[0].char_length(), 6);
+    }
+
+    #[test]
+    fn test_emoji_trigger_units() {
+        let service = TriggerDetectionService::new();
+        let text = "ok ::🎉";
+
+        let trigger_match = service.detect_trigger(text).unwrap();
+        assert_consistent(text, &trigger_match);
+        assert_eq!(trigger_match.end_position, text.len());
+        assert_eq!(trigger_match.length(), 6);
+        assert_eq!(trigger_match.char_length(), 3);
+    }
+
+    #[test]
+    fn test_triggers_under_another_prefix() {
+        let prefix = TriggerPrefix::new(";;").unwrap();
+        let service = TriggerDetectionService::new().with_prefix(prefix.clone());
+
+        let text = "say ;;hi and ::not ;;bye";
+        let found: Vec<String> = service
+            .find_triggers_in_text(text)
+            .into_iter()
+            .map(|trigger_match| trigger_match.trigger)
+            .collect();
+        assert_eq!(found, vec![";;hi", ";;bye"]);
+        assert_eq!(service.detect_trigger(text).unwrap().start_position, 19);
+        assert!(service.detect_trigger("just ;;").is_none());
+        assert!(service.detect_trigger("just ;;\t").is_none());
+        assert!(service.find_triggers_in_text(";;\n;;\u{7}").is_empty());
+
+        // Regex metacharacters in the prefix are matched literally
+        let regex = TriggerPrefix::new("\\").unwrap().trigger_regex();
+        let found: Vec<&str> = regex
+            .find_iter(r"a \sig, \ and .sig")
+            .map(|m| m.as_str())
+            .collect();
+        assert_eq!(found, vec![r"\sig"]);
+        assert_eq!(
+            prefix.trigger_regex().find("x;;a:b:").unwrap().as_str(),
+            ";;a:b"
+        );
+
+        // The regex takes the same body characters as trigger validation
+        let found: Vec<&str> = prefix
+            .trigger_regex()
+            .find_iter(";;café ;;\t ;; ;;x_1-y")
+            .map(|m| m.as_str())
+            .collect();
+        assert_eq!(found, vec![";;café", ";;x_1-y"]);
+    }
+
+    #[test]
+    fn test_invalid_trigger_prefixes() {
+        assert!(TriggerPrefix::new("")
+            .unwrap_err()
+            .to_string()
+            .contains("empty"));
+        for prefix in ["x", ";a", "_", "-", "; ", "1"] {
+            assert!(TriggerPrefix::new(prefix).is_err(), "{}", prefix);
+        }
+        for prefix in ["::", ";;", "\\", ".", "$("] {
+            assert_eq!(TriggerPrefix::new(prefix).unwrap().as_str(), prefix);
+        }
+        assert_eq!(TriggerPrefix::default().as_str(), "::");
+    }
+
+    #[test]
+    fn test_match_keys() {
+        let decomposed = "::Cafe\u{301}";
+        let default = MatchOptions::default();
+
+        assert!(matches!(default.key("::sig"), Cow::Borrowed("::sig")));
+        assert_eq!(default.key(decomposed), "::Caf\u{e9}");
+        assert_eq!(default.ignoring_case().key(decomposed), "::caf\u{e9}");
+        assert_eq!(default.without_normalization().key(decomposed), decomposed);
+        assert_eq!(
+            default
+                .without_normalization()
+                .ignoring_case()
+                .key(decomposed),
+            "::cafe\u{301}"
+        );
+    }
+
+    #[test]
+    fn test_trigger_case_apply() {
+        assert_eq!(TriggerCase::Title.apply("::btw"), "::Btw");
+        assert_eq!(TriggerCase::Upper.apply("::btw"), "::BTW");
+        assert_eq!(TriggerCase::Title.apply("by the way"), "By the way");
+        assert_eq!(TriggerCase::Title.apply("\u{e9}t\u{e9}"), "\u{c9}t\u{e9}");
+        assert_eq!(TriggerCase::Title.apply("::123"), "::123");
+    }
+
+    #[test]
+    fn test_nfc_equivalent_triggers_normalize_equal() {
+        let composed = "::caf\u{e9}";
+        let decomposed = "::cafe\u{301}";
+        assert_ne!(composed, decomposed);
+
+        assert_eq!(normalize_trigger(composed), normalize_trigger(decomposed));
+
+        let service = TriggerDetectionService::new();
+        let trigger_match = service.detect_trigger(decomposed).unwrap();
+        // Backspaces still follow what was typed; the lookup uses the NFC form
+        assert_eq!(trigger_match.char_length(), 7);
+        assert_eq!(trigger_match.normalized_trigger(), composed);
+    }
 }
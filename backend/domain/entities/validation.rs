@@ -0,0 +1,300 @@
+use super::{
+    fragment_references, is_known_placeholder, placeholder_names, resolve_fragments,
+    FragmentLimits, MatchOptions, Snippet,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// Likely a mistake, but the snippet still expands
+    Warning,
+    /// The snippet can't expand as intended
+    Error,
+}
+
+impl fmt::Display for IssueSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IssueSeverity::Warning => "warning",
+            IssueSeverity::Error => "error",
+        })
+    }
+}
+
+/// A problem found in a stored snippet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    /// Trigger of the snippet the issue is about
+    pub trigger: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(severity: IssueSeverity, trigger: &str, message: String) -> Self {
+        Self {
+            severity,
+            trigger: trigger.to_string(),
+            message,
+        }
+    }
+}
+
+/// Lint a whole snippet collection. `fragments` maps fragment names to their
+/// content. Issues are ordered by trigger, errors first.
+pub fn validate_snippets(
+    snippets: &[Snippet],
+    fragments: &HashMap<String, String>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    for snippet in snippets {
+        validate_replacement(snippet, fragments, &mut issues);
+    }
+
+    // Only active snippets can get in each other's way
+    let active: Vec<&Snippet> = snippets.iter().filter(|s| s.is_active).collect();
+    find_prefix_collisions(&active, &mut issues);
+    find_case_collisions(&active, &mut issues);
+
+    issues.sort_by(|a, b| {
+        a.trigger
+            .cmp(&b.trigger)
+            .then(b.severity.cmp(&a.severity))
+            .then(a.message.cmp(&b.message))
+    });
+    issues
+}
+
+fn validate_replacement(
+    snippet: &Snippet,
+    fragments: &HashMap<String, String>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let trigger = snippet.trigger.as_str();
+
+    // There's nothing to check in a replacement that couldn't be decrypted
+    if snippet.locked.is_some() {
+        return;
+    }
+
+    if snippet.replacement.trim().is_empty() {
+        issues.push(ValidationIssue::new(
+            IssueSeverity::Error,
+            trigger,
+            "Replacement is empty after trimming whitespace".to_string(),
+        ));
+    }
+
+    for name in placeholder_names(&snippet.replacement) {
+        if !is_known_placeholder(name) && !name.starts_with("fragment:") {
+            issues.push(ValidationIssue::new(
+                IssueSeverity::Warning,
+                trigger,
+                format!("Unknown placeholder {{{}}} is left as typed", name),
+            ));
+        }
+    }
+
+    for name in fragment_references(&snippet.replacement) {
+        if !fragments.contains_key(&name) {
+            issues.push(ValidationIssue::new(
+                IssueSeverity::Warning,
+                trigger,
+                format!("Fragment '{}' does not exist", name),
+            ));
+        }
+    }
+
+    if let Err(e) = resolve_fragments(&snippet.replacement, fragments, &FragmentLimits::default()) {
+        issues.push(ValidationIssue::new(
+            IssueSeverity::Error,
+            trigger,
+            e.to_string(),
+        ));
+    }
+}
+
+/// Prefix collisions between the triggers and aliases of the active snippets,
+/// which the expansion engine reports when it starts
+pub fn trigger_collisions(snippets: &[Snippet]) -> Vec<ValidationIssue> {
+    let active: Vec<&Snippet> = snippets.iter().filter(|s| s.is_active).collect();
+    let mut issues = Vec::new();
+    find_prefix_collisions(&active, &mut issues);
+    issues
+}
+
+/// A trigger that another trigger starts with expands as soon as it's typed,
+/// so the longer one can never be reached
+fn find_prefix_collisions(snippets: &[&Snippet], issues: &mut Vec<ValidationIssue>) {
+    let mut triggers: Vec<(&str, &Snippet)> = snippets
+        .iter()
+        .flat_map(|snippet| snippet.triggers().map(move |trigger| (trigger, *snippet)))
+        .collect();
+    triggers.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (i, (prefix, prefix_owner)) in triggers.iter().enumerate() {
+        for (trigger, owner) in &triggers[i + 1..] {
+            if !trigger.starts_with(prefix) {
+                break;
+            }
+            if trigger == prefix {
+                continue;
+            }
+            issues.push(ValidationIssue::new(
+                IssueSeverity::Warning,
+                &owner.trigger,
+                format!(
+                    "'{}' can't be typed: '{}' (from '{}') expands first",
+                    trigger, prefix, prefix_owner.trigger
+                ),
+            ));
+        }
+    }
+}
+
+/// Triggers that differ only by case collide when matching ignores case
+fn find_case_collisions(snippets: &[&Snippet], issues: &mut Vec<ValidationIssue>) {
+    let options = MatchOptions::default().ignoring_case();
+    let mut by_key: HashMap<String, Vec<&str>> = HashMap::new();
+    for snippet in snippets {
+        by_key
+            .entry(options.key(&snippet.trigger).into_owned())
+            .or_default()
+            .push(&snippet.trigger);
+    }
+
+    for triggers in by_key.values_mut().filter(|t| t.len() > 1) {
+        triggers.sort();
+        for trigger in triggers.iter() {
+            let others: Vec<&str> = triggers.iter().copied().filter(|t| t != trigger).collect();
+            issues.push(ValidationIssue::new(
+                IssueSeverity::Warning,
+                trigger,
+                format!(
+                    "Differs only by case from {}, which clashes when matching ignores case",
+                    others.join(", ")
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(trigger: &str, replacement: &str) -> Snippet {
+        Snippet::new(trigger.to_string(), replacement.to_string()).unwrap()
+    }
+
+    fn messages_for<'a>(issues: &'a [ValidationIssue], trigger: &str) -> Vec<&'a str> {
+        issues
+            .iter()
+            .filter(|issue| issue.trigger == trigger)
+            .map(|issue| issue.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_snippets_have_no_issues() {
+        let snippets = vec![
+            snippet("::date", "Today is {date}"),
+            snippet("::sig", "{fragment:sig} {{braces}}"),
+        ];
+        let fragments = HashMap::from([("sig".to_string(), "Jane".to_string())]);
+        assert!(validate_snippets(&snippets, &fragments).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_placeholder_and_prefix_collision() {
+        let snippets = vec![
+            snippet("::today", "Today is {dat}"),
+            snippet("::sig", "Jane"),
+            snippet("::sig2", "Jane Doe"),
+        ];
+        let issues = validate_snippets(&snippets, &HashMap::new());
+
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::new(
+                    IssueSeverity::Warning,
+                    "::sig2",
+                    "'::sig2' can't be typed: '::sig' (from '::sig') expands first".to_string(),
+                ),
+                ValidationIssue::new(
+                    IssueSeverity::Warning,
+                    "::today",
+                    "Unknown placeholder {dat} is left as typed".to_string(),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alias_prefix_collision() {
+        let mut address = snippet("::address", "1 Main St");
+        address.set_aliases(vec!["::ad".to_string()]).unwrap();
+        let snippets = vec![address, snippet("::admin", "root")];
+
+        let issues = validate_snippets(&snippets, &HashMap::new());
+        assert_eq!(
+            messages_for(&issues, "::admin"),
+            vec!["'::admin' can't be typed: '::ad' (from '::address') expands first"]
+        );
+    }
+
+    #[test]
+    fn test_inactive_snippets_do_not_collide() {
+        let mut short = snippet("::sig", "Jane");
+        short.deactivate();
+        let snippets = vec![short, snippet("::sig2", "Jane Doe")];
+        assert!(validate_snippets(&snippets, &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_blank_replacement_and_fragment_problems() {
+        let snippets = vec![
+            snippet("::blank", "   \n"),
+            snippet("::loop", "{fragment:a}"),
+            snippet("::missing", "{fragment:nope}"),
+        ];
+        let fragments = HashMap::from([
+            ("a".to_string(), "{fragment:b}".to_string()),
+            ("b".to_string(), "{fragment:a}".to_string()),
+        ]);
+        let issues = validate_snippets(&snippets, &fragments);
+
+        assert_eq!(
+            messages_for(&issues, "::blank"),
+            vec!["Replacement is empty after trimming whitespace"]
+        );
+        assert_eq!(
+            messages_for(&issues, "::loop"),
+            vec!["Fragment cycle: a -> b -> a"]
+        );
+        assert_eq!(
+            messages_for(&issues, "::missing"),
+            vec!["Fragment 'nope' does not exist"]
+        );
+        assert!(issues
+            .iter()
+            .filter(|issue| issue.trigger != "::missing")
+            .all(|issue| issue.severity == IssueSeverity::Error));
+    }
+
+    #[test]
+    fn test_case_collision() {
+        let snippets = vec![snippet("::Hi", "Hello"), snippet("::hi", "hello")];
+        let issues = validate_snippets(&snippets, &HashMap::new());
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            messages_for(&issues, "::Hi"),
+            vec!["Differs only by case from ::hi, which clashes when matching ignores case"]
+        );
+    }
+}
@@ -1,15 +1,87 @@
-use crate::domain::{Snippet, SnippetQuery};
+use crate::domain::{Fragment, Snippet, SnippetQuery, SnippetVersion};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
 use uuid::Uuid;
 
+/// Another snippet already has this trigger. `SnippetRepository::save` and
+/// `update` fail with it, checked by the storage itself so that concurrent
+/// writers can't both get the trigger; test for it with
+/// `error.is::<DuplicateTrigger>()`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("A snippet with trigger '{0}' already exists")]
+pub struct DuplicateTrigger(pub String);
+
 #[async_trait]
 pub trait SnippetRepository: Send + Sync {
+    /// Insert a new snippet, failing with `DuplicateTrigger` if its trigger
+    /// is taken
     async fn save(&self, snippet: &Snippet) -> anyhow::Result<()>;
     async fn find_by_id(&self, id: &Uuid) -> anyhow::Result<Option<Snippet>>;
+    /// Find the snippet whose trigger or one of whose aliases is `trigger`
     async fn find_by_trigger(&self, trigger: &str) -> anyhow::Result<Option<Snippet>>;
     async fn find_all(&self, query: &SnippetQuery) -> anyhow::Result<Vec<Snippet>>;
+    /// Like `find_all`, but yields snippets one at a time instead of loading them all
+    fn stream_all(&self, query: &SnippetQuery) -> BoxStream<'_, anyhow::Result<Snippet>>;
     async fn update(&self, snippet: &Snippet) -> anyhow::Result<()>;
     async fn delete(&self, id: &Uuid) -> anyhow::Result<bool>;
     async fn count(&self, query: &SnippetQuery) -> anyhow::Result<u64>;
+    /// Total number of snippets, active or not, without building a query
+    async fn count_all(&self) -> anyhow::Result<u64>;
+    /// The `limit` most used active snippets, most used first
+    async fn top_by_usage(&self, limit: u32) -> anyhow::Result<Vec<Snippet>>;
+    /// Up to `limit` active snippets picked at random, in no particular order
+    async fn random_active(&self, limit: u32) -> anyhow::Result<Vec<Snippet>>;
+    /// Whether any snippet uses `trigger` as its trigger or an alias
     async fn exists_with_trigger(&self, trigger: &str) -> anyhow::Result<bool>;
+    /// Set `is_active` on every snippet matching the query's filters in one
+    /// statement, ignoring sorting and paging. Returns the id and trigger of
+    /// each snippet whose state changed.
+    async fn set_active_matching(
+        &self,
+        query: &SnippetQuery,
+        is_active: bool,
+    ) -> anyhow::Result<Vec<(Uuid, String)>>;
+    /// Set `usage_count` to zero and clear the usage log for one snippet, or
+    /// all when `id` is `None`. Returns the number of snippets affected.
+    async fn reset_usage(&self, id: Option<Uuid>) -> anyhow::Result<u64>;
+    /// Give the snippets in `ordered_ids` positions 0, 1, ... in that order,
+    /// in one transaction. Ids without a snippet, and repeats, are skipped.
+    /// Snippets left out that had a position follow in their old order; the
+    /// rest stay unplaced. Returns the number of snippets placed from the list.
+    async fn reorder(&self, ordered_ids: &[Uuid]) -> anyhow::Result<u64>;
+    /// Append a version, keeping at most `max_versions` per snippet
+    async fn record_version(
+        &self,
+        version: &SnippetVersion,
+        max_versions: usize,
+    ) -> anyhow::Result<()>;
+    /// Past versions of a snippet, newest first
+    async fn history(&self, id: &Uuid) -> anyhow::Result<Vec<SnippetVersion>>;
+    /// Past versions of every snippet recorded at or after `since`, newest
+    /// first. Only edits to a trigger or replacement record a version, so
+    /// snippets created, deleted or (de)activated since aren't listed.
+    async fn edits_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<SnippetVersion>>;
+    /// Log one expansion of a snippet. `usage_count` is kept separately.
+    async fn record_usage(&self, id: &Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()>;
+    /// How many logged expansions of a snippet fall in `[from, to)`
+    async fn usage_count_between(
+        &self,
+        id: &Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<u64>;
+    /// When a snippet's earliest logged expansion happened
+    async fn first_used(&self, id: &Uuid) -> anyhow::Result<Option<DateTime<Utc>>>;
+}
+
+/// Storage for named fragments, looked up by name rather than trigger
+#[async_trait]
+pub trait FragmentRepository: Send + Sync {
+    /// Insert the fragment, or replace the content of the one with its name
+    async fn save(&self, fragment: &Fragment) -> anyhow::Result<()>;
+    async fn find_by_name(&self, name: &str) -> anyhow::Result<Option<Fragment>>;
+    /// All fragments, ordered by name
+    async fn find_all(&self) -> anyhow::Result<Vec<Fragment>>;
+    async fn delete(&self, name: &str) -> anyhow::Result<bool>;
 }
@@ -1,4 +1,6 @@
+use super::MigrationRunner;
 use anyhow::Result;
+use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::{Pool, Sqlite, SqlitePool};
 use std::path::{Path, PathBuf};
 
@@ -9,6 +11,17 @@ pub struct DatabaseConnection {
 
 impl DatabaseConnection {
     pub async fn new(database_path: &Path) -> Result<Self> {
+        let connection = Self::new_unmigrated(database_path).await?;
+
+        // Run migrations
+        connection.migrate().await?;
+
+        Ok(connection)
+    }
+
+    /// Open the database at `database_path` without applying pending
+    /// migrations, for running them by hand
+    pub async fn new_unmigrated(database_path: &Path) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = database_path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -18,19 +31,21 @@ impl DatabaseConnection {
 
         let pool = SqlitePool::connect(&database_url).await?;
 
-        let connection = Self {
+        Ok(Self {
             pool,
             database_path: database_path.to_path_buf(),
-        };
-
-        // Run migrations
-        connection.migrate().await?;
-
-        Ok(connection)
+        })
     }
 
     pub async fn new_in_memory() -> Result<Self> {
-        let pool = SqlitePool::connect("sqlite::memory:").await?;
+        // Every connection to `:memory:` opens its own empty database, so the
+        // pool must hold exactly one connection and never recycle it
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
 
         let connection = Self {
             pool,
@@ -52,73 +67,11 @@ impl DatabaseConnection {
     }
 
     async fn migrate(&self) -> Result<()> {
-        // Create snippets table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS snippets (
-                id TEXT PRIMARY KEY NOT NULL,
-                trigger TEXT NOT NULL UNIQUE,
-                replacement TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                is_active INTEGER NOT NULL DEFAULT 1,
-                usage_count INTEGER NOT NULL DEFAULT 0,
-                tags TEXT DEFAULT '[]'
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create indexes for better performance
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_snippets_trigger ON snippets(trigger)
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_snippets_is_active ON snippets(is_active)
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_snippets_usage_count ON snippets(usage_count)
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create events table for domain events (optional, for audit trail)
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS events (
-                id TEXT PRIMARY KEY NOT NULL,
-                event_type TEXT NOT NULL,
-                snippet_id TEXT NOT NULL,
-                event_data TEXT NOT NULL,
-                timestamp TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_events_snippet_id ON events(snippet_id)
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+        self.migration_runner().run_migrations().await
+    }
 
-        Ok(())
+    pub fn migration_runner(&self) -> MigrationRunner {
+        MigrationRunner::new(self.pool.clone())
     }
 
     pub async fn close(self) {
@@ -132,9 +85,86 @@ impl DatabaseConnection {
 }
 
 pub fn get_default_database_path() -> Result<PathBuf> {
-    let home_dir = dirs::home_dir()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+    Ok(app_dir()?.join("snippets.db"))
+}
+
+/// Database for a named profile, e.g. `~/.typely/profiles/work.db`
+pub fn get_profile_database_path(name: &str) -> Result<PathBuf> {
+    profile_database_path_in(&app_dir()?, name)
+}
+
+fn app_dir() -> Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+    Ok(home_dir.join(".typely"))
+}
+
+fn profile_database_path_in(app_dir: &Path, name: &str) -> Result<PathBuf> {
+    // Profile names become file names, so keep them to a safe character set
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid profile name '{}' (use letters, digits, '-' and '_')",
+            name
+        ));
+    }
 
-    let app_dir = home_dir.join(".typely");
-    Ok(app_dir.join("snippets.db"))
+    Ok(app_dir.join("profiles").join(format!("{}.db", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::dto::{CreateSnippetRequest, SnippetListRequest};
+    use crate::app::TypelyService;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profile_paths_are_distinct() {
+        let app_dir = Path::new("/home/jane/.typely");
+        let work = profile_database_path_in(app_dir, "work").unwrap();
+        let personal = profile_database_path_in(app_dir, "personal").unwrap();
+
+        assert_eq!(work, app_dir.join("profiles/work.db"));
+        assert_ne!(work, personal);
+        assert!(profile_database_path_in(app_dir, "../escape").is_err());
+        assert!(profile_database_path_in(app_dir, "").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_profiles_have_independent_snippets() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut triggers = Vec::new();
+
+        for (name, trigger) in [("work", "::standup"), ("personal", "::groceries")] {
+            let path = profile_database_path_in(temp_dir.path(), name).unwrap();
+            let service = TypelyService::new(DatabaseConnection::new(&path).await.unwrap()).await;
+            let request = CreateSnippetRequest::new(trigger, "Profile snippet");
+            service.create_snippet(request).await.unwrap();
+            triggers.push((path, trigger));
+        }
+
+        for (path, trigger) in triggers {
+            let service = TypelyService::new(DatabaseConnection::new(&path).await.unwrap()).await;
+            let request = SnippetListRequest {
+                search_term: None,
+                search_fields: None,
+                tags: None,
+                tag_match: None,
+                is_active: None,
+                limit: None,
+                offset: None,
+                sort_by: None,
+                sort_order: None,
+                source: None,
+                after: None,
+            };
+            let snippets = service.list_snippets(request).await.unwrap().snippets;
+            let found: Vec<&str> = snippets.iter().map(|s| s.trigger.as_str()).collect();
+            assert_eq!(found, vec![trigger]);
+        }
+    }
 }
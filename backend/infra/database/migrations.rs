@@ -1,5 +1,34 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::{Pool, Row, Sqlite};
+
+/// All known migrations, in the order they are applied
+pub const MIGRATIONS: &[&str] = &[
+    "001_create_snippets",
+    "002_add_indexes",
+    "003_create_events",
+    "004_add_content_type",
+    "005_create_snippet_versions",
+    "006_add_confirm_before_expand",
+    "007_add_keep_trigger",
+    "008_add_aliases",
+    "009_create_fragments",
+    "010_add_type_delay",
+    "011_add_source",
+    "012_add_pinned",
+    "013_add_hotkey",
+    "014_add_active_window",
+    "015_create_snippet_usage",
+    "016_add_case_variants",
+    "017_add_position",
+    "018_add_encrypted",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub name: String,
+    pub applied_at: DateTime<Utc>,
+}
 
 pub struct MigrationRunner {
     pool: Pool<Sqlite>,
@@ -15,13 +44,99 @@ impl MigrationRunner {
         self.create_migrations_table().await?;
 
         // Apply migrations in order
-        self.apply_migration_001().await?;
-        self.apply_migration_002().await?;
-        self.apply_migration_003().await?;
+        for index in 0..MIGRATIONS.len() {
+            self.apply_migration(index).await?;
+        }
 
         Ok(())
     }
 
+    /// Apply pending migrations up to and including `version`, given as its
+    /// number (`002`) or full name (`002_add_indexes`). Fails for unknown
+    /// versions and for one that is already applied.
+    pub async fn run_migrations_to(&self, version: &str) -> Result<()> {
+        let last = MIGRATIONS
+            .iter()
+            .position(|name| *name == version || name.split('_').next() == Some(version))
+            .ok_or_else(|| anyhow::anyhow!("Unknown migration '{}'", version))?;
+        if self
+            .status()
+            .await?
+            .iter()
+            .any(|m| m.name == MIGRATIONS[last])
+        {
+            return Err(anyhow::anyhow!(
+                "Migration {} is already applied",
+                MIGRATIONS[last]
+            ));
+        }
+
+        for index in 0..=last {
+            self.apply_migration(index).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the migration at `index` in `MIGRATIONS` unless it already is
+    async fn apply_migration(&self, index: usize) -> Result<()> {
+        match index {
+            0 => self.apply_migration_001().await,
+            1 => self.apply_migration_002().await,
+            2 => self.apply_migration_003().await,
+            3 => self.apply_migration_004().await,
+            4 => self.apply_migration_005().await,
+            5 => self.apply_migration_006().await,
+            6 => self.apply_migration_007().await,
+            7 => self.apply_migration_008().await,
+            8 => self.apply_migration_009().await,
+            9 => self.apply_migration_010().await,
+            10 => self.apply_migration_011().await,
+            11 => self.apply_migration_012().await,
+            12 => self.apply_migration_013().await,
+            13 => self.apply_migration_014().await,
+            14 => self.apply_migration_015().await,
+            15 => self.apply_migration_016().await,
+            16 => self.apply_migration_017().await,
+            17 => self.apply_migration_018().await,
+            _ => Err(anyhow::anyhow!("Unknown migration index {}", index)),
+        }
+    }
+
+    /// List the migrations recorded in the `migrations` table, oldest first
+    pub async fn status(&self) -> Result<Vec<AppliedMigration>> {
+        self.create_migrations_table().await?;
+
+        let rows = sqlx::query("SELECT name, applied_at FROM migrations ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut applied = Vec::new();
+        for row in rows {
+            let applied_at_str: String = row.get("applied_at");
+            let applied_at =
+                NaiveDateTime::parse_from_str(&applied_at_str, "%Y-%m-%d %H:%M:%S")?.and_utc();
+
+            applied.push(AppliedMigration {
+                name: row.get("name"),
+                applied_at,
+            });
+        }
+
+        Ok(applied)
+    }
+
+    /// List known migrations that have not been applied yet
+    pub async fn pending(&self) -> Result<Vec<&'static str>> {
+        let applied = self.status().await?;
+
+        Ok(MIGRATIONS
+            .iter()
+            .copied()
+            .filter(|name| !applied.iter().any(|m| m.name == *name))
+            .collect())
+    }
+
     async fn create_migrations_table(&self) -> Result<()> {
         sqlx::query(
             r#"
@@ -96,6 +211,184 @@ impl MigrationRunner {
         Ok(())
     }
 
+    async fn apply_migration_004(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("004_add_content_type")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_004_add_content_type(&self.pool).await?;
+        self.record_migration("004_add_content_type").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_005(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("005_create_snippet_versions")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_005_create_snippet_versions(&self.pool).await?;
+        self.record_migration("005_create_snippet_versions").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_006(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("006_add_confirm_before_expand")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_006_add_confirm_before_expand(&self.pool).await?;
+        self.record_migration("006_add_confirm_before_expand")
+            .await?;
+        Ok(())
+    }
+
+    async fn apply_migration_007(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("007_add_keep_trigger")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_007_add_keep_trigger(&self.pool).await?;
+        self.record_migration("007_add_keep_trigger").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_008(&self) -> Result<()> {
+        if !self.check_and_record_migration("008_add_aliases").await? {
+            return Ok(());
+        }
+
+        Self::migration_008_add_aliases(&self.pool).await?;
+        self.record_migration("008_add_aliases").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_009(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("009_create_fragments")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_009_create_fragments(&self.pool).await?;
+        self.record_migration("009_create_fragments").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_010(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("010_add_type_delay")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_010_add_type_delay(&self.pool).await?;
+        self.record_migration("010_add_type_delay").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_011(&self) -> Result<()> {
+        if !self.check_and_record_migration("011_add_source").await? {
+            return Ok(());
+        }
+
+        Self::migration_011_add_source(&self.pool).await?;
+        self.record_migration("011_add_source").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_012(&self) -> Result<()> {
+        if !self.check_and_record_migration("012_add_pinned").await? {
+            return Ok(());
+        }
+
+        Self::migration_012_add_pinned(&self.pool).await?;
+        self.record_migration("012_add_pinned").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_013(&self) -> Result<()> {
+        if !self.check_and_record_migration("013_add_hotkey").await? {
+            return Ok(());
+        }
+
+        Self::migration_013_add_hotkey(&self.pool).await?;
+        self.record_migration("013_add_hotkey").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_014(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("014_add_active_window")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_014_add_active_window(&self.pool).await?;
+        self.record_migration("014_add_active_window").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_015(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("015_create_snippet_usage")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_015_create_snippet_usage(&self.pool).await?;
+        self.record_migration("015_create_snippet_usage").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_016(&self) -> Result<()> {
+        if !self
+            .check_and_record_migration("016_add_case_variants")
+            .await?
+        {
+            return Ok(());
+        }
+
+        Self::migration_016_add_case_variants(&self.pool).await?;
+        self.record_migration("016_add_case_variants").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_017(&self) -> Result<()> {
+        if !self.check_and_record_migration("017_add_position").await? {
+            return Ok(());
+        }
+
+        Self::migration_017_add_position(&self.pool).await?;
+        self.record_migration("017_add_position").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_018(&self) -> Result<()> {
+        if !self.check_and_record_migration("018_add_encrypted").await? {
+            return Ok(());
+        }
+
+        Self::migration_018_add_encrypted(&self.pool).await?;
+        self.record_migration("018_add_encrypted").await?;
+        Ok(())
+    }
+
     async fn migration_001_create_snippets(pool: &Pool<Sqlite>) -> Result<()> {
         sqlx::query(
             r#"
@@ -170,4 +463,325 @@ impl MigrationRunner {
 
         Ok(())
     }
+
+    async fn migration_004_add_content_type(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN content_type TEXT NOT NULL DEFAULT 'plain'
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_005_create_snippet_versions(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS snippet_versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                snippet_id TEXT NOT NULL,
+                trigger TEXT NOT NULL,
+                replacement TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_snippet_versions_snippet_id ON snippet_versions(snippet_id)
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_006_add_confirm_before_expand(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN confirm_before_expand INTEGER NOT NULL DEFAULT 0
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_007_add_keep_trigger(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN keep_trigger INTEGER NOT NULL DEFAULT 0
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_008_add_aliases(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN aliases TEXT NOT NULL DEFAULT '[]'
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Alias lookups only need to visit the few snippets that have any
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_snippets_with_aliases ON snippets(id)
+            WHERE aliases != '[]'
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_009_create_fragments(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS fragments (
+                id TEXT PRIMARY KEY NOT NULL,
+                name TEXT NOT NULL UNIQUE,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_010_add_type_delay(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN type_delay_ms INTEGER
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_011_add_source(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN source TEXT
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_snippets_source ON snippets(source)")
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn migration_012_add_pinned(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_013_add_hotkey(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN hotkey TEXT
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_014_add_active_window(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN active_from TEXT
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN active_until TEXT
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_015_create_snippet_usage(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS snippet_usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                snippet_id TEXT NOT NULL,
+                used_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_snippet_usage_snippet_id ON snippet_usage(snippet_id, used_at)
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn migration_016_add_case_variants(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN case_variants INTEGER NOT NULL DEFAULT 0
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Set by `reorder`; NULL until a snippet is placed by hand
+    async fn migration_017_add_position(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN position INTEGER
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks rows whose replacement is encrypted, in snippets and in their
+    /// versions
+    async fn migration_018_add_encrypted(pool: &Pool<Sqlite>) -> Result<()> {
+        sqlx::query(
+            r#"
+            ALTER TABLE snippets ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE snippet_versions ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+
+    async fn create_test_runner() -> MigrationRunner {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MigrationRunner::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_status_before_migrations() {
+        let runner = create_test_runner().await;
+
+        assert!(runner.status().await.unwrap().is_empty());
+        assert_eq!(runner.pending().await.unwrap(), MIGRATIONS.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_status_lists_applied_migrations() {
+        let runner = create_test_runner().await;
+        runner.run_migrations().await.unwrap();
+
+        let names: Vec<String> = runner
+            .status()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+
+        assert_eq!(names, MIGRATIONS.to_vec());
+        assert!(runner.pending().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_to_stops_at_version() {
+        let runner = create_test_runner().await;
+        runner.run_migrations_to("002").await.unwrap();
+
+        let names: Vec<String> = runner
+            .status()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names, vec!["001_create_snippets", "002_add_indexes"]);
+        assert_eq!(runner.pending().await.unwrap(), MIGRATIONS[2..].to_vec());
+
+        // Already applied, or never heard of
+        let error = runner
+            .run_migrations_to("002_add_indexes")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Migration 002_add_indexes is already applied"
+        );
+        let error = runner.run_migrations_to("999").await.unwrap_err();
+        assert_eq!(error.to_string(), "Unknown migration '999'");
+
+        runner.run_migrations_to("003").await.unwrap();
+        assert_eq!(runner.status().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_is_idempotent() {
+        let runner = create_test_runner().await;
+        runner.run_migrations().await.unwrap();
+        runner.run_migrations().await.unwrap();
+
+        assert_eq!(runner.status().await.unwrap().len(), MIGRATIONS.len());
+    }
 }
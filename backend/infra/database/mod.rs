@@ -1,5 +1,11 @@
 pub mod connection;
 pub mod migrations;
+#[cfg(feature = "postgres")]
+pub mod postgres_migrations;
+pub mod storage_backend;
 
 pub use connection::*;
 pub use migrations::*;
+#[cfg(feature = "postgres")]
+pub use postgres_migrations::*;
+pub use storage_backend::*;
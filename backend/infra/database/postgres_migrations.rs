@@ -0,0 +1,150 @@
+use anyhow::Result;
+use sqlx::{Pool, Postgres};
+
+/// All known Postgres migrations, in the order they are applied. The schema
+/// starts out matching SQLite's after `MIGRATIONS`, so there is no history
+/// to replay.
+pub const POSTGRES_MIGRATIONS: &[&str] = &["001_create_schema", "002_add_position"];
+
+pub struct PostgresMigrationRunner {
+    pool: Pool<Postgres>,
+}
+
+impl PostgresMigrationRunner {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn run_migrations(&self) -> Result<()> {
+        // Create migrations table to track applied migrations
+        self.create_migrations_table().await?;
+
+        self.apply_migration_001().await?;
+        self.apply_migration_002().await?;
+
+        Ok(())
+    }
+
+    /// Names of the migrations applied so far, oldest first
+    pub async fn applied(&self) -> Result<Vec<String>> {
+        self.create_migrations_table().await?;
+
+        let names = sqlx::query_scalar("SELECT name FROM migrations ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(names)
+    }
+
+    async fn create_migrations_table(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS migrations (
+                id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_applied(&self, name: &str) -> Result<bool> {
+        let existing =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM migrations WHERE name = $1")
+                .bind(name)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(existing > 0)
+    }
+
+    async fn record_migration(&self, name: &str) -> Result<()> {
+        sqlx::query("INSERT INTO migrations (name) VALUES ($1)")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        log::info!("Applied Postgres migration: {}", name);
+        Ok(())
+    }
+
+    async fn apply_migration_001(&self) -> Result<()> {
+        if self.is_applied("001_create_schema").await? {
+            return Ok(());
+        }
+
+        Self::migration_001_create_schema(&self.pool).await?;
+        self.record_migration("001_create_schema").await?;
+        Ok(())
+    }
+
+    async fn apply_migration_002(&self) -> Result<()> {
+        if self.is_applied("002_add_position").await? {
+            return Ok(());
+        }
+
+        sqlx::query("ALTER TABLE snippets ADD COLUMN IF NOT EXISTS position BIGINT")
+            .execute(&self.pool)
+            .await?;
+        self.record_migration("002_add_position").await?;
+        Ok(())
+    }
+
+    /// Columns mirror SQLite's. Times are kept as RFC 3339 text and compared
+    /// byte-wise (`COLLATE "C"`), so sorting and paging behave the same on
+    /// both backends.
+    async fn migration_001_create_schema(pool: &Pool<Postgres>) -> Result<()> {
+        for statement in [
+            r#"
+            CREATE TABLE IF NOT EXISTS snippets (
+                id TEXT COLLATE "C" PRIMARY KEY NOT NULL,
+                trigger TEXT COLLATE "C" NOT NULL UNIQUE,
+                replacement TEXT NOT NULL,
+                created_at TEXT COLLATE "C" NOT NULL,
+                updated_at TEXT COLLATE "C" NOT NULL,
+                is_active BOOLEAN NOT NULL DEFAULT TRUE,
+                usage_count BIGINT NOT NULL DEFAULT 0,
+                tags TEXT NOT NULL DEFAULT '[]',
+                content_type TEXT NOT NULL DEFAULT 'plain',
+                confirm_before_expand BOOLEAN NOT NULL DEFAULT FALSE,
+                keep_trigger BOOLEAN NOT NULL DEFAULT FALSE,
+                aliases TEXT NOT NULL DEFAULT '[]',
+                type_delay_ms BIGINT,
+                source TEXT,
+                pinned BOOLEAN NOT NULL DEFAULT FALSE,
+                hotkey TEXT,
+                active_from TEXT COLLATE "C",
+                active_until TEXT COLLATE "C",
+                case_variants BOOLEAN NOT NULL DEFAULT FALSE
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_snippets_is_active ON snippets(is_active)",
+            "CREATE INDEX IF NOT EXISTS idx_snippets_usage_count ON snippets(usage_count)",
+            r#"
+            CREATE TABLE IF NOT EXISTS snippet_versions (
+                id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                snippet_id TEXT NOT NULL,
+                trigger TEXT NOT NULL,
+                replacement TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_snippet_versions_snippet_id ON snippet_versions(snippet_id)",
+            r#"
+            CREATE TABLE IF NOT EXISTS snippet_usage (
+                id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                snippet_id TEXT NOT NULL,
+                used_at TEXT COLLATE "C" NOT NULL
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_snippet_usage_snippet_id ON snippet_usage(snippet_id, used_at)",
+        ] {
+            sqlx::query(statement).execute(pool).await?;
+        }
+
+        Ok(())
+    }
+}
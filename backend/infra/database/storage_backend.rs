@@ -0,0 +1,152 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::DatabaseConnection;
+use crate::domain::SnippetRepository;
+use crate::infra::{EncryptionService, SqliteSnippetRepository};
+
+/// Where snippets are stored, chosen by connection string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// A SQLite file, or an in-memory database for `sqlite::memory:`
+    Sqlite(PathBuf),
+    /// A Postgres server, by its full connection string. Needs the
+    /// `postgres` feature.
+    Postgres(String),
+}
+
+impl StorageBackend {
+    /// Read `sqlite://<path>` (or `sqlite:<path>`, `sqlite::memory:`) and
+    /// `postgres://...` / `postgresql://...` connection strings
+    pub fn from_url(url: &str) -> Result<Self> {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            return Ok(Self::Postgres(url.to_string()));
+        }
+        if url == "sqlite::memory:" {
+            return Ok(Self::Sqlite(PathBuf::from(":memory:")));
+        }
+
+        match url
+            .strip_prefix("sqlite://")
+            .or_else(|| url.strip_prefix("sqlite:"))
+        {
+            Some(path) if !path.is_empty() => Ok(Self::Sqlite(PathBuf::from(path))),
+            Some(_) => Err(anyhow::anyhow!("No database path in '{}'", url)),
+            None => Err(anyhow::anyhow!(
+                "Unsupported database URL '{}' (use sqlite://<path> or postgres://...)",
+                url
+            )),
+        }
+    }
+
+    /// Connect, running any pending migrations, and return the repository
+    pub async fn connect(&self) -> Result<Arc<dyn SnippetRepository>> {
+        match self {
+            Self::Sqlite(path) => {
+                let connection = if path.as_os_str() == ":memory:" {
+                    DatabaseConnection::new_in_memory().await?
+                } else {
+                    DatabaseConnection::new(path).await?
+                };
+                let mut repository = SqliteSnippetRepository::new(connection.pool().clone());
+                if let Some(encryption) = EncryptionService::from_env() {
+                    repository = repository.with_encryption(encryption);
+                }
+                Ok(Arc::new(repository))
+            }
+            #[cfg(feature = "postgres")]
+            Self::Postgres(url) => {
+                let pool = sqlx::PgPool::connect(url).await?;
+                super::PostgresMigrationRunner::new(pool.clone())
+                    .run_migrations()
+                    .await?;
+                Ok(Arc::new(crate::infra::PostgresSnippetRepository::new(pool)))
+            }
+            #[cfg(not(feature = "postgres"))]
+            Self::Postgres(_) => Err(anyhow::anyhow!(
+                "Postgres isn't supported in this build; rebuild with `--features postgres`"
+            )),
+        }
+    }
+}
+
+/// The repository for connection string `url`, see `StorageBackend::from_url`
+pub async fn connect_snippet_repository(url: &str) -> Result<Arc<dyn SnippetRepository>> {
+    StorageBackend::from_url(url)?.connect().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippet;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(
+            StorageBackend::from_url("sqlite:///tmp/typely.db").unwrap(),
+            StorageBackend::Sqlite(PathBuf::from("/tmp/typely.db"))
+        );
+        assert_eq!(
+            StorageBackend::from_url("sqlite:snippets.db").unwrap(),
+            StorageBackend::Sqlite(PathBuf::from("snippets.db"))
+        );
+        assert_eq!(
+            StorageBackend::from_url("sqlite::memory:").unwrap(),
+            StorageBackend::Sqlite(PathBuf::from(":memory:"))
+        );
+        for url in [
+            "postgres://team@db.example.com/typely",
+            "postgresql://localhost/typely",
+        ] {
+            assert_eq!(
+                StorageBackend::from_url(url).unwrap(),
+                StorageBackend::Postgres(url.to_string())
+            );
+        }
+
+        for url in ["mysql://localhost/typely", "snippets.db", "sqlite://"] {
+            assert!(StorageBackend::from_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_url_opens_a_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("team/snippets.db");
+        let url = format!("sqlite://{}", path.display());
+
+        let repository = connect_snippet_repository(&url).await.unwrap();
+        repository.save(&snippet("::hi", "Hello")).await.unwrap();
+        assert!(path.exists());
+
+        // A second connection sees what the first stored
+        let reopened = connect_snippet_repository(&url).await.unwrap();
+        assert!(reopened.exists_with_trigger("::hi").await.unwrap());
+
+        let in_memory = connect_snippet_repository("sqlite::memory:").await.unwrap();
+        assert_eq!(in_memory.count_all().await.unwrap(), 0);
+    }
+
+    /// Against `TYPELY_TEST_POSTGRES_URL`, when set
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_url_opens_a_repository() {
+        let Ok(url) = std::env::var("TYPELY_TEST_POSTGRES_URL") else {
+            return;
+        };
+        let repository = connect_snippet_repository(&url).await.unwrap();
+        assert!(!repository.exists_with_trigger("::missing").await.unwrap());
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    #[tokio::test]
+    async fn test_postgres_needs_the_feature() {
+        let error = connect_snippet_repository("postgres://localhost/typely")
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("--features postgres"));
+    }
+}
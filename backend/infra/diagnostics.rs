@@ -0,0 +1,242 @@
+//! Checks behind `typely-cli doctor`: whether this machine can run the engine,
+//! and what to do about it when it can't
+
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+use super::{ClipboardManager, InputBackend, KeyboardMonitor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Typely works, but with something missing
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to fix a warning or failure
+    pub hint: Option<String>,
+}
+
+impl DiagnosticCheck {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run every check, using the database at `db_path`. Input monitoring and
+/// clipboard access are tried for real, briefly.
+pub fn run_diagnostics(db_path: &Path) -> Vec<DiagnosticCheck> {
+    vec![
+        check_database_path(db_path),
+        check_system_integration(cfg!(feature = "system-integration")),
+        check_input_monitoring(&KeyboardMonitor::new()),
+        check_input_simulation(&InputBackend::detect()),
+        check_clipboard(),
+    ]
+}
+
+/// Whether the database at `path` can be written, or created if it doesn't
+/// exist yet. Nothing is left behind either way.
+pub fn check_database_path(path: &Path) -> DiagnosticCheck {
+    const NAME: &str = "Database";
+    let hint = "Fix the permissions, or pass --database to use another file";
+
+    if path.is_dir() {
+        return DiagnosticCheck::fail(
+            NAME,
+            format!("{} is a directory", path.display()),
+            "Pass --database with a file path",
+        );
+    }
+    if path.exists() {
+        return match OpenOptions::new().append(true).open(path) {
+            Ok(_) => DiagnosticCheck::pass(NAME, format!("{} is writable", path.display())),
+            Err(e) => {
+                DiagnosticCheck::fail(NAME, format!("Can't write {}: {}", path.display(), e), hint)
+            }
+        };
+    }
+
+    // Missing directories are created along with the database, so it's the
+    // nearest one that exists that has to be writable
+    let Some(dir) = path.ancestors().skip(1).find(|dir| dir.is_dir()) else {
+        return DiagnosticCheck::fail(
+            NAME,
+            format!("No existing directory above {}", path.display()),
+            hint,
+        );
+    };
+    let probe = dir.join(".typely-doctor-probe");
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            DiagnosticCheck::pass(NAME, format!("{} can be created", path.display()))
+        }
+        Err(e) => DiagnosticCheck::fail(
+            NAME,
+            format!("Can't create files in {}: {}", dir.display(), e),
+            hint,
+        ),
+    }
+}
+
+/// Whether this build can watch and type keys at all
+pub fn check_system_integration(compiled: bool) -> DiagnosticCheck {
+    const NAME: &str = "System integration";
+    if compiled {
+        DiagnosticCheck::pass(NAME, "compiled in")
+    } else {
+        DiagnosticCheck::warn(
+            NAME,
+            "not compiled in: snippets can be managed but aren't expanded while typing",
+            "Rebuild with `--features system-integration`",
+        )
+    }
+}
+
+/// Start `monitor` and stop it again straight away
+pub fn check_input_monitoring(monitor: &KeyboardMonitor) -> DiagnosticCheck {
+    const NAME: &str = "Input monitoring";
+    match monitor.start_monitoring() {
+        Ok(_receiver) => {
+            monitor.stop_monitoring();
+            DiagnosticCheck::pass(NAME, "keyboard events can be read")
+        }
+        Err(e) => DiagnosticCheck::fail(NAME, e.to_string(), input_permission_hint()),
+    }
+}
+
+pub fn check_input_simulation(input: &InputBackend) -> DiagnosticCheck {
+    const NAME: &str = "Input simulation";
+    match input.unavailable_reason() {
+        None => DiagnosticCheck::pass(NAME, "keystrokes can be typed"),
+        Some(reason) => DiagnosticCheck::warn(
+            NAME,
+            format!(
+                "{}: the engine detects triggers but can't type expansions",
+                reason
+            ),
+            input_permission_hint(),
+        ),
+    }
+}
+
+/// Put text on the system clipboard and read it back, then restore what was
+/// there. Builds without one only get a warning, as only `expand --copy`
+/// needs it there.
+pub fn check_clipboard() -> DiagnosticCheck {
+    const NAME: &str = "Clipboard";
+    if let Err(e) = ClipboardManager::system() {
+        if !cfg!(feature = "system-integration") {
+            return DiagnosticCheck::warn(NAME, "no system clipboard in this build", e.to_string());
+        }
+    }
+    let round_trip = || -> anyhow::Result<bool> {
+        let clipboard = ClipboardManager::system()?;
+        clipboard.save()?;
+        let probe = "typely doctor";
+        clipboard.set_text(probe)?;
+        let read = clipboard.get_text()?;
+        clipboard.restore()?;
+        Ok(read == probe)
+    };
+
+    let hint = if cfg!(target_os = "linux") {
+        "Run inside a desktop session (DISPLAY or WAYLAND_DISPLAY must be set)"
+    } else {
+        "Check that another app isn't holding the clipboard"
+    };
+    match round_trip() {
+        Ok(true) => DiagnosticCheck::pass(NAME, "available"),
+        Ok(false) => {
+            DiagnosticCheck::fail(NAME, "text put on the clipboard didn't come back", hint)
+        }
+        Err(e) => DiagnosticCheck::fail(NAME, e.to_string(), hint),
+    }
+}
+
+fn input_permission_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Allow your terminal under System Settings > Privacy & Security > Accessibility and Input Monitoring"
+    } else if cfg!(target_os = "linux") {
+        "Add yourself to the input group (`sudo usermod -aG input $USER`), then log in again; on Wayland use an X11 session"
+    } else {
+        "Run Typely as a user allowed to read and send keyboard input"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_database_path_checks() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let existing = temp_dir.path().join("typely.db");
+        fs::write(&existing, b"").unwrap();
+        assert_eq!(check_database_path(&existing).status, CheckStatus::Pass);
+
+        // Created along with its directories, and the probe is cleaned up
+        let missing = temp_dir.path().join("new/dir/typely.db");
+        let check = check_database_path(&missing);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert!(check.detail.ends_with("can be created"));
+        assert!(!temp_dir.path().join(".typely-doctor-probe").exists());
+
+        let check = check_database_path(temp_dir.path());
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.hint.is_some());
+    }
+
+    #[test]
+    fn test_system_integration_check() {
+        assert_eq!(check_system_integration(true).status, CheckStatus::Pass);
+        let check = check_system_integration(false);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.hint.unwrap().contains("system-integration"));
+
+        // These tests run without the feature, so with the stub clipboard
+        assert_eq!(check_clipboard().status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_input_simulation_check() {
+        let check = check_input_simulation(&InputBackend::unavailable("no display"));
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.starts_with("no display:"));
+
+        let simulator = InputBackend::Simulator(crate::infra::InputSimulator::new().unwrap());
+        assert_eq!(check_input_simulation(&simulator).status, CheckStatus::Pass);
+    }
+}
@@ -0,0 +1,149 @@
+//! Encryption at rest for the replacements of snippets marked `encrypted`
+
+use anyhow::Result;
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Where the passphrase is read from; it is never stored
+pub const PASSPHRASE_ENV_VAR: &str = "TYPELY_PASSPHRASE";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Encrypts text with a key derived from a passphrase by Argon2, using
+/// XChaCha20-Poly1305. Each value is stored as base64 of its salt, nonce and
+/// ciphertext, so it can be decrypted with the passphrase alone.
+///
+/// Deriving a key is deliberately slow, so it happens once per salt: values
+/// written by this service share one salt, and the keys for salts read back
+/// are kept.
+pub struct EncryptionService {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    keys: Mutex<HashMap<[u8; SALT_LEN], Key>>,
+}
+
+impl EncryptionService {
+    pub fn new(passphrase: impl Into<String>) -> Result<Self> {
+        let passphrase = passphrase.into();
+        if passphrase.is_empty() {
+            return Err(anyhow::anyhow!("The encryption passphrase can't be empty"));
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Ok(Self {
+            passphrase,
+            salt,
+            keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// From `TYPELY_PASSPHRASE`, when it is set and not empty
+    pub fn from_env() -> Option<Self> {
+        std::env::var(PASSPHRASE_ENV_VAR)
+            .ok()
+            .and_then(|passphrase| Self::new(passphrase).ok())
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let cipher = XChaCha20Poly1305::new(&self.key(&self.salt)?);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt replacement"))?;
+
+        let mut stored = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+        stored.extend_from_slice(&self.salt);
+        stored.extend_from_slice(&nonce);
+        stored.extend_from_slice(&ciphertext);
+        Ok(STANDARD.encode(stored))
+    }
+
+    /// Fails when `stored` was encrypted with another passphrase, or isn't
+    /// something `encrypt` wrote
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let bytes = STANDARD
+            .decode(stored)
+            .map_err(|_| anyhow::anyhow!("Encrypted replacement isn't valid base64"))?;
+        if bytes.len() < SALT_LEN + NONCE_LEN {
+            return Err(anyhow::anyhow!("Encrypted replacement is truncated"));
+        }
+
+        let (salt, rest) = bytes.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let salt: [u8; SALT_LEN] = salt.try_into()?;
+        let cipher = XChaCha20Poly1305::new(&self.key(&salt)?);
+        let plaintext = cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Can't decrypt replacement: wrong passphrase, or the data is corrupted"
+                )
+            })?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    fn key(&self, salt: &[u8; SALT_LEN]) -> Result<Key> {
+        if let Some(key) = self.keys.lock().get(salt) {
+            return Ok(*key);
+        }
+
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+        self.keys.lock().insert(*salt, key);
+        Ok(key)
+    }
+}
+
+impl fmt::Debug for EncryptionService {
+    /// Leaves out the passphrase and keys
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionService").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_then_decrypt_round_trips() {
+        let service = EncryptionService::new("correct horse").unwrap();
+        let stored = service.encrypt("PIN 1234 ✓").unwrap();
+        assert!(!stored.contains("1234"));
+        assert_eq!(service.decrypt(&stored).unwrap(), "PIN 1234 ✓");
+
+        // A fresh nonce each time, and another service with the same
+        // passphrase (so another salt) reads it too
+        assert_ne!(service.encrypt("PIN 1234 ✓").unwrap(), stored);
+        let reopened = EncryptionService::new("correct horse").unwrap();
+        assert_eq!(reopened.decrypt(&stored).unwrap(), "PIN 1234 ✓");
+    }
+
+    #[test]
+    fn test_wrong_passphrase_fails_cleanly() {
+        let stored = EncryptionService::new("correct horse")
+            .unwrap()
+            .encrypt("secret")
+            .unwrap();
+
+        let wrong = EncryptionService::new("battery staple").unwrap();
+        let error = wrong.decrypt(&stored).unwrap_err();
+        assert!(error.to_string().contains("wrong passphrase"));
+
+        for garbage in ["not base64!", "dG9vIHNob3J0", "plain text"] {
+            assert!(wrong.decrypt(garbage).is_err(), "{}", garbage);
+        }
+        assert!(EncryptionService::new("").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often the focused application is checked
+pub const FOCUS_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Keyboard focus moved from one application to another. Either may be
+/// `None` when the application couldn't be named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Reports focus changes so the engine can drop typed text that belongs to
+/// the application left behind, and a trigger never straddles two apps.
+///
+/// With `system-integration` the focused application (`active_application`)
+/// is polled every `FOCUS_POLL_INTERVAL`. Other builds can't see focus, so
+/// their channel stays quiet.
+pub struct FocusMonitor {
+    is_running: Arc<Mutex<bool>>,
+}
+
+impl Default for FocusMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FocusMonitor {
+    pub fn new() -> Self {
+        Self {
+            is_running: Arc::new(Mutex::new(false)),
+        }
+    }
+
+    #[cfg(feature = "system-integration")]
+    pub fn start_monitoring(&self) -> anyhow::Result<Receiver<FocusChange>> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        *self.is_running.lock().unwrap() = true;
+
+        let is_running = Arc::clone(&self.is_running);
+        std::thread::spawn(move || {
+            watch_focus(
+                crate::infra::active_application,
+                &sender,
+                &is_running,
+                FOCUS_POLL_INTERVAL,
+            )
+        });
+        Ok(receiver)
+    }
+
+    /// Stub: focus can't be observed in CLI builds, so nothing is ever sent
+    #[cfg(not(feature = "system-integration"))]
+    pub fn start_monitoring(&self) -> anyhow::Result<Receiver<FocusChange>> {
+        let (_sender, receiver) = std::sync::mpsc::channel();
+        Ok(receiver)
+    }
+
+    pub fn stop_monitoring(&self) {
+        *self.is_running.lock().unwrap() = false;
+    }
+}
+
+/// Poll `focused` every `interval` while `is_running`, sending a
+/// `FocusChange` whenever its answer differs from the last one. Stops early
+/// once nobody is listening.
+#[cfg(any(feature = "system-integration", test))]
+fn watch_focus(
+    focused: impl Fn() -> Option<String>,
+    sender: &std::sync::mpsc::Sender<FocusChange>,
+    is_running: &Mutex<bool>,
+    interval: Duration,
+) {
+    let mut current = focused();
+    while *is_running.lock().unwrap() {
+        std::thread::sleep(interval);
+
+        let now = focused();
+        if now != current {
+            let change = FocusChange {
+                from: std::mem::replace(&mut current, now.clone()),
+                to: now,
+            };
+            if sender.send(change).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_reported_once_each() {
+        let answers = Mutex::new(vec!["Editor", "Editor", "Browser", "Browser", "Editor"]);
+        let is_running = Mutex::new(true);
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        watch_focus(
+            || {
+                let mut answers = answers.lock().unwrap();
+                if answers.len() == 1 {
+                    *is_running.lock().unwrap() = false;
+                }
+                Some(answers.remove(0).to_string())
+            },
+            &sender,
+            &is_running,
+            Duration::ZERO,
+        );
+
+        let changes: Vec<FocusChange> = receiver.try_iter().collect();
+        let change = |from: &str, to: &str| FocusChange {
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+        };
+        assert_eq!(
+            changes,
+            vec![change("Editor", "Browser"), change("Browser", "Editor")]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::domain::ModifierState;
 use crate::infra::{KeyboardEvent, KeyboardEventType};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -15,14 +16,6 @@ struct KeyState {
     release_time: Option<Instant>,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct ModifierState {
-    pub ctrl: bool,
-    pub shift: bool,
-    pub alt: bool,
-    pub meta: bool,
-}
-
 #[derive(Debug, Clone)]
 pub struct ProcessedKeyEvent {
     pub key: String,
@@ -55,7 +48,7 @@ impl KeyboardEventHandler {
         let processed = ProcessedKeyEvent {
             key: event.key.clone(),
             event_type: event.event_type.clone(),
-            modifiers: self.modifiers.clone(),
+            modifiers: self.modifiers,
             is_modifier: self.is_modifier_key(&event.key),
             is_printable: self.is_printable_key(&event.key),
             should_buffer: self.should_buffer_key(&event),
@@ -117,21 +110,8 @@ impl KeyboardEventHandler {
     }
 
     fn update_modifier_state(&mut self, event: &KeyboardEvent) {
-        match event.key.as_str() {
-            "ControlLeft" | "ControlRight" => {
-                self.modifiers.ctrl = event.event_type == KeyboardEventType::KeyDown;
-            }
-            "ShiftLeft" | "ShiftRight" => {
-                self.modifiers.shift = event.event_type == KeyboardEventType::KeyDown;
-            }
-            "Alt" | "AltGr" => {
-                self.modifiers.alt = event.event_type == KeyboardEventType::KeyDown;
-            }
-            "MetaLeft" | "MetaRight" => {
-                self.modifiers.meta = event.event_type == KeyboardEventType::KeyDown;
-            }
-            _ => {}
-        }
+        self.modifiers
+            .update(&event.key, event.event_type == KeyboardEventType::KeyDown);
     }
 
     fn is_modifier_key(&self, key: &str) -> bool {
@@ -192,31 +172,6 @@ impl Default for KeyboardEventHandler {
     }
 }
 
-impl ModifierState {
-    pub fn has_any(&self) -> bool {
-        self.ctrl || self.shift || self.alt || self.meta
-    }
-
-    pub fn is_ctrl_only(&self) -> bool {
-        self.ctrl && !self.shift && !self.alt && !self.meta
-    }
-
-    pub fn is_shift_only(&self) -> bool {
-        !self.ctrl && self.shift && !self.alt && !self.meta
-    }
-
-    pub fn is_alt_only(&self) -> bool {
-        !self.ctrl && !self.shift && self.alt && !self.meta
-    }
-
-    pub fn matches(&self, other: &ModifierState) -> bool {
-        self.ctrl == other.ctrl
-            && self.shift == other.shift
-            && self.alt == other.alt
-            && self.meta == other.meta
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
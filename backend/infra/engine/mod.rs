@@ -1,5 +1,11 @@
+pub mod focus_monitor;
 pub mod keyboard_event_handler;
+pub mod status;
 pub mod text_expansion_engine;
+pub mod trigger_cache;
 
+pub use focus_monitor::*;
 pub use keyboard_event_handler::*;
+pub use status::*;
 pub use text_expansion_engine::*;
+pub use trigger_cache::*;
@@ -0,0 +1,134 @@
+use crate::infra::get_default_database_path;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How often the daemon rewrites its status file
+pub const STATUS_WRITE_INTERVAL_SECS: u64 = 5;
+
+/// A snapshot of the expansion engine. The daemon writes it to disk so other
+/// processes, such as `typely-cli status`, can report on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineStatus {
+    pub is_running: bool,
+    pub enabled: bool,
+    /// Active snippets available for expansion
+    pub snippet_count: u64,
+    pub uptime_seconds: u64,
+    pub last_expansion: Option<DateTime<Utc>>,
+    pub pid: u32,
+    /// When this snapshot was taken
+    pub updated_at: DateTime<Utc>,
+    /// Problems found when the engine started, such as triggers that
+    /// collide with each other
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+impl EngineStatus {
+    /// Whether the snapshot is recent enough that its writer is still alive.
+    /// A daemon that crashed leaves a file behind that goes stale.
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        let max_age = (STATUS_WRITE_INTERVAL_SECS * 3) as i64;
+        (now - self.updated_at).num_seconds() <= max_age
+    }
+
+    /// Whether the engine that wrote this status is running right now
+    pub fn is_live(&self, now: DateTime<Utc>) -> bool {
+        self.is_running && self.is_fresh(now)
+    }
+
+    /// Write the status as JSON, replacing the file atomically so readers
+    /// never see a partial write
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Read a status file, or `None` if no daemon has written one
+    pub fn read_from(path: &Path) -> Result<Option<Self>> {
+        match fs::read(path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data).with_context(|| {
+                format!("Invalid engine status file {}", path.display())
+            })?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Status file location, next to the default database
+pub fn get_default_status_path() -> Result<PathBuf> {
+    Ok(get_default_database_path()?.with_file_name("engine-status.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn status(updated_at: DateTime<Utc>) -> EngineStatus {
+        EngineStatus {
+            is_running: true,
+            enabled: true,
+            snippet_count: 4,
+            uptime_seconds: 120,
+            last_expansion: None,
+            pid: 4242,
+            updated_at,
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let status = status(Utc::now());
+        let json = serde_json::to_value(&status).unwrap();
+
+        assert_eq!(json["is_running"], true);
+        assert_eq!(json["enabled"], true);
+        assert_eq!(json["snippet_count"], 4);
+        assert_eq!(json["uptime_seconds"], 120);
+        assert!(json["last_expansion"].is_null());
+
+        let parsed: EngineStatus = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, status);
+    }
+
+    #[test]
+    fn test_status_file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("engine-status.json");
+        assert_eq!(EngineStatus::read_from(&path).unwrap(), None);
+
+        let status = status(Utc::now());
+        status.write_to(&path).unwrap();
+        assert_eq!(EngineStatus::read_from(&path).unwrap(), Some(status));
+    }
+
+    #[test]
+    fn test_stale_status_is_not_live() {
+        let now = Utc::now();
+        assert!(status(now).is_live(now));
+
+        let stale = status(now - chrono::Duration::seconds(60));
+        assert!(!stale.is_fresh(now));
+        assert!(!stale.is_live(now));
+
+        let stopped = EngineStatus {
+            is_running: false,
+            ..status(now)
+        };
+        assert!(!stopped.is_live(now));
+    }
+}
@@ -1,31 +1,147 @@
-use crate::app::dto::ExpansionResponse;
+use crate::app::dto::{ExpansionRequest, ExpansionResponse, SnippetListRequest};
+use crate::app::metrics::Metrics;
 use crate::app::TypelyService;
-use crate::domain::TriggerDetectionService;
-use crate::infra::{InputSimulator, KeyboardEvent, KeyboardEventType, KeyboardMonitor};
+use crate::domain::{
+    trigger_collisions, Hotkey, ModifierState, SnippetQuery, TrieCursor, TriggerDetectionService,
+    TriggerMatch, TriggerPrefix, TriggerTrie, DEFAULT_MAX_EXPANSION_LENGTH,
+};
+use crate::infra::{
+    active_application, ClipboardManager, EngineStatus, FocusChange, FocusMonitor, InputBackend,
+    KeyboardEvent, KeyboardEventType, KeyboardMonitor, TriggerCache,
+};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
 use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc as tokio_mpsc;
+use tracing::Instrument;
+use unicode_normalization::char::is_combining_mark;
 
 pub struct TextExpansionEngine {
+    service: Arc<TypelyService>,
     keyboard_monitor: KeyboardMonitor,
+    focus_monitor: FocusMonitor,
     trigger_detection: TriggerDetectionService,
-    input_simulator: Arc<Mutex<InputSimulator>>,
+    input: Arc<Mutex<InputBackend>>,
     is_running: Arc<Mutex<bool>>,
     buffer: Arc<Mutex<TextBuffer>>,
     config: ExpansionConfig,
+    on_expansion: Arc<Mutex<Option<ExpansionCallback>>>,
+    on_confirm: Arc<Mutex<Option<ConfirmationCallback>>>,
+    metrics: Arc<Metrics>,
+    trigger_cache: Arc<TriggerCache>,
+    /// Hotkeys of active snippets and the trigger each one expands
+    hotkeys: Arc<Mutex<Vec<(Hotkey, String)>>>,
+    started_at: Mutex<Option<Instant>>,
+    workers: Mutex<Option<EngineWorkers>>,
+    /// Found by `start`, reported in `status`
+    warnings: Mutex<Vec<String>>,
+}
+
+/// How long `shutdown` waits for queued expansions to finish
+pub const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Handles to the tasks started by `start`
+struct EngineWorkers {
+    /// Keeps the expansion channel open until shutdown
+    expansion_sender: tokio_mpsc::Sender<ExpansionEvent>,
+    keyboard_thread: thread::JoinHandle<()>,
+    expansion_task: tokio::task::JoinHandle<()>,
+}
+
+/// Details about a completed expansion, passed to the expansion callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionInfo {
+    pub trigger: String,
+    pub expanded_length: usize,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Callback invoked after a successful expansion (e.g. to show a GUI notification)
+pub type ExpansionCallback = Box<dyn Fn(&ExpansionInfo) + Send + Sync>;
+
+/// An expansion waiting for the user's approval
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationRequest {
+    pub trigger: String,
+    pub expanded_text: String,
+}
+
+/// Callback asked whether a `confirm_before_expand` snippet may be typed.
+/// Returning `false` leaves the trigger as typed.
+pub type ConfirmationCallback = Box<dyn Fn(&ConfirmationRequest) -> bool + Send + Sync>;
+
+/// What happened to a detected trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExpansionOutcome {
+    Expanded,
+    Declined,
+    NotExpanded,
+    /// Expanded, but not typed because input can't be simulated
+    MonitorOnly,
+    /// Expanded, but not typed because of `simulate_only`
+    Simulated,
 }
 
 #[derive(Debug, Clone)]
 pub struct ExpansionConfig {
+    /// Graphemes of recent typing kept for finding triggers. A trigger
+    /// longer than this can't be typed; `start` warns about any.
     pub buffer_size: usize,
     pub trigger_timeout_ms: u64,
+    /// Pause before the expansion is typed
     pub expansion_delay_ms: u64,
+    /// Pause between typed characters, for snippets without their own delay
+    pub type_delay_ms: u64,
     pub enabled: bool,
     pub case_sensitive: bool,
+    pub max_expansion_length: usize,
+    /// Only expand triggers typed at the start of a word (e.g. not `abc::hi`)
+    pub require_word_start: bool,
+    /// Applications, by name or bundle id, where nothing is ever expanded
+    /// (e.g. a password manager). See `allows_app`.
+    pub app_denylist: Vec<String>,
+    /// When not empty, the only applications where anything is expanded.
+    /// See `allows_app`.
+    pub app_allowlist: Vec<String>,
+    /// What typed triggers start with
+    pub trigger_prefix: TriggerPrefix,
+    /// Detect and resolve expansions, logging them and telling the expansion
+    /// callback, but type nothing. For trying out trigger rules.
+    pub simulate_only: bool,
+    /// Keep typed keys, triggers and expanded text out of the logs, which
+    /// only get counts
+    pub privacy_mode: bool,
+}
+
+impl ExpansionConfig {
+    /// Whether expansion may happen while `app` has focus, ignoring case.
+    /// An allowlisted app always may, even if it's also denylisted. A
+    /// non-empty allowlist shuts out every other app, including an unknown
+    /// one (`None`); otherwise only denylisted apps are shut out.
+    pub fn allows_app(&self, app: Option<&str>) -> bool {
+        let listed =
+            |list: &[String], app: &str| list.iter().any(|id| id.eq_ignore_ascii_case(app));
+
+        match app {
+            Some(app) if listed(&self.app_allowlist, app) => true,
+            _ if !self.app_allowlist.is_empty() => false,
+            Some(app) => !listed(&self.app_denylist, app),
+            None => true,
+        }
+    }
+
+    /// `trigger` as it may appear in logs: hidden in privacy mode
+    fn loggable<'a>(&self, trigger: &'a str) -> &'a str {
+        if self.privacy_mode {
+            "<hidden>"
+        } else {
+            trigger
+        }
+    }
 }
 
 impl Default for ExpansionConfig {
@@ -34,15 +150,25 @@ impl Default for ExpansionConfig {
             buffer_size: 100,
             trigger_timeout_ms: 1000,
             expansion_delay_ms: 50,
+            type_delay_ms: 0,
             enabled: true,
             case_sensitive: true,
+            max_expansion_length: DEFAULT_MAX_EXPANSION_LENGTH,
+            require_word_start: false,
+            app_denylist: Vec::new(),
+            app_allowlist: Vec::new(),
+            trigger_prefix: TriggerPrefix::default(),
+            simulate_only: false,
+            privacy_mode: false,
         }
     }
 }
 
+/// Recently typed text, kept as graphemes: a character with any combining
+/// marks typed after it, which Backspace removes together
 #[derive(Debug, Clone)]
 struct TextBuffer {
-    content: VecDeque<char>,
+    content: VecDeque<String>,
     last_update: Instant,
     max_size: usize,
 }
@@ -56,16 +182,25 @@ impl TextBuffer {
         }
     }
 
-    fn add_char(&mut self, c: char) {
-        if self.content.len() >= self.max_size {
-            self.content.pop_front();
+    /// Add what one key typed: usually a single character, but an input
+    /// method can commit several at once. `max_size` counts graphemes.
+    fn add_text(&mut self, text: &str) {
+        for c in text.chars() {
+            match self.content.back_mut() {
+                Some(grapheme) if is_combining_mark(c) => grapheme.push(c),
+                _ => {
+                    if self.content.len() >= self.max_size {
+                        self.content.pop_front();
+                    }
+                    self.content.push_back(c.to_string());
+                }
+            }
         }
-        self.content.push_back(c);
         self.last_update = Instant::now();
     }
 
     fn get_text(&self) -> String {
-        self.content.iter().collect()
+        self.content.iter().map(String::as_str).collect()
     }
 
     fn clear(&mut self) {
@@ -90,19 +225,64 @@ impl TextBuffer {
 }
 
 impl TextExpansionEngine {
-    pub fn new(_service: Arc<TypelyService>, config: Option<ExpansionConfig>) -> Result<Self> {
+    pub fn new(service: Arc<TypelyService>, config: Option<ExpansionConfig>) -> Result<Self> {
         let config = config.unwrap_or_default();
 
         Ok(Self {
+            metrics: service.metrics(),
+            trigger_cache: Arc::new(TriggerCache::new(
+                service.snippet_repository(),
+                service.snippet_changes(),
+            )),
+            hotkeys: Arc::new(Mutex::new(Vec::new())),
+            service,
             keyboard_monitor: KeyboardMonitor::new(),
-            trigger_detection: TriggerDetectionService::new(),
-            input_simulator: Arc::new(Mutex::new(InputSimulator::new()?)),
+            focus_monitor: FocusMonitor::new(),
+            trigger_detection: TriggerDetectionService::new()
+                .with_prefix(config.trigger_prefix.clone()),
+            input: Arc::new(Mutex::new(InputBackend::detect())),
             is_running: Arc::new(Mutex::new(false)),
             buffer: Arc::new(Mutex::new(TextBuffer::new(config.buffer_size))),
             config,
+            on_expansion: Arc::new(Mutex::new(None)),
+            on_confirm: Arc::new(Mutex::new(None)),
+            started_at: Mutex::new(None),
+            workers: Mutex::new(None),
+            warnings: Mutex::new(Vec::new()),
         })
     }
 
+    /// Type expansions through `input` instead of the detected backend
+    pub fn with_input_backend(self, input: InputBackend) -> Self {
+        *self.input.lock().unwrap() = input;
+        self
+    }
+
+    /// Whether triggers are detected without anything being typed, because
+    /// input can't be simulated in this session
+    pub fn is_monitor_only(&self) -> bool {
+        !self.input.lock().unwrap().is_available()
+    }
+
+    /// Expansion counters, shared with the service the engine was created from
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Register a callback that is notified after every successful expansion.
+    /// The callback runs on a blocking worker, so it never delays typing.
+    pub fn set_on_expansion(&self, callback: ExpansionCallback) {
+        let mut on_expansion = self.on_expansion.lock().unwrap();
+        *on_expansion = Some(callback);
+    }
+
+    /// Register the prompt used for snippets marked `confirm_before_expand`.
+    /// Without one, those snippets are never typed.
+    pub fn set_confirmation_callback(&self, callback: ConfirmationCallback) {
+        let mut on_confirm = self.on_confirm.lock().unwrap();
+        *on_confirm = Some(callback);
+    }
+
     pub async fn start(&self) -> Result<()> {
         {
             let mut is_running = self.is_running.lock().unwrap();
@@ -112,10 +292,40 @@ impl TextExpansionEngine {
             *is_running = true;
         }
 
+        // A failed start leaves the engine stopped, so it can be started again
+        let started = self.start_workers().await;
+        match started {
+            Ok(()) => *self.started_at.lock().unwrap() = Some(Instant::now()),
+            Err(_) => self.signal_stop(),
+        }
+        started
+    }
+
+    /// Load the triggers and hotkeys, start the monitors and spawn the
+    /// threads that expand typed triggers, for `start`
+    async fn start_workers(&self) -> Result<()> {
         log::info!("Starting text expansion engine");
+        let trigger_count = self.trigger_cache.reload().await?;
+        log::debug!("Loaded {} triggers", trigger_count);
+        let hotkey_count = self.reload_hotkeys().await?;
+        log::debug!("Loaded {} hotkeys", hotkey_count);
+        self.check_triggers().await?;
+        if let Some(reason) = self.input.lock().unwrap().unavailable_reason() {
+            self.warnings.lock().unwrap().push(format!(
+                "Input simulation is unavailable ({}): expansions are detected but not typed",
+                reason
+            ));
+        }
+        if self.config.simulate_only {
+            self.warnings
+                .lock()
+                .unwrap()
+                .push("Simulate-only: expansions are detected but not typed".to_string());
+        }
 
         // Start keyboard monitoring
         let receiver = self.keyboard_monitor.start_monitoring()?;
+        let focus_changes = self.focus_monitor.start_monitoring()?;
 
         // Create expansion event channel
         let (expansion_sender, mut expansion_receiver) = tokio_mpsc::channel(100);
@@ -125,14 +335,19 @@ impl TextExpansionEngine {
         let trigger_detection = self.trigger_detection.clone();
         let config = self.config.clone();
         let is_running = Arc::clone(&self.is_running);
+        let hotkeys = Arc::clone(&self.hotkeys);
+        let keyboard_trigger_cache = Arc::clone(&self.trigger_cache);
 
         // Spawn thread to handle keyboard events
         let keyboard_thread_sender = expansion_sender.clone();
-        thread::spawn(move || {
+        let keyboard_thread = thread::spawn(move || {
             Self::handle_keyboard_events(
                 receiver,
+                focus_changes,
                 buffer,
                 trigger_detection,
+                keyboard_trigger_cache,
+                hotkeys,
                 config,
                 is_running,
                 keyboard_thread_sender,
@@ -140,15 +355,23 @@ impl TextExpansionEngine {
         });
 
         // Handle expansion events in async context
-        let input_simulator = Arc::clone(&self.input_simulator);
+        let input = Arc::clone(&self.input);
         let expansion_config = self.config.clone();
+        let on_expansion = Arc::clone(&self.on_expansion);
+        let on_confirm = Arc::clone(&self.on_confirm);
+        let trigger_cache = Arc::clone(&self.trigger_cache);
+        let service = Arc::clone(&self.service);
 
-        tokio::spawn(async move {
+        let expansion_task = tokio::spawn(async move {
             while let Some(expansion_event) = expansion_receiver.recv().await {
                 if let Err(e) = Self::handle_expansion_event(
                     expansion_event,
-                    &input_simulator,
+                    &service,
+                    &trigger_cache,
+                    &input,
                     &expansion_config,
+                    &on_expansion,
+                    &on_confirm,
                 )
                 .await
                 {
@@ -157,17 +380,157 @@ impl TextExpansionEngine {
             }
         });
 
+        *self.workers.lock().unwrap() = Some(EngineWorkers {
+            expansion_sender,
+            keyboard_thread,
+            expansion_task,
+        });
+
+        Ok(())
+    }
+
+    /// Warn about triggers that start with another trigger: the engine
+    /// expands the shorter one as soon as it's typed, so the longer one
+    /// never expands. Triggers too long for the buffer never expand either.
+    async fn check_triggers(&self) -> Result<()> {
+        let snippets = self
+            .service
+            .snippet_repository()
+            .find_all(&SnippetQuery::new().with_active_only())
+            .await?;
+
+        let collisions: Vec<String> = trigger_collisions(&snippets)
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+
+        let buffer_size = self.config.buffer_size;
+        let too_long: Vec<String> = snippets
+            .iter()
+            .flat_map(|snippet| snippet.triggers())
+            .filter(|trigger| {
+                trigger.chars().filter(|c| !is_combining_mark(*c)).count() > buffer_size
+            })
+            .map(|trigger| {
+                format!(
+                    "'{}' can't be typed: it is longer than the {}-character buffer",
+                    trigger, buffer_size
+                )
+            })
+            .collect();
+
+        // The warnings name triggers, so privacy mode only logs how many
+        if self.config.privacy_mode {
+            let count = collisions.len() + too_long.len();
+            if count > 0 {
+                log::warn!("{} triggers can't be typed; see the engine status", count);
+            }
+        } else {
+            for warning in &collisions {
+                log::warn!("Trigger collision: {}", warning);
+            }
+            for warning in &too_long {
+                log::warn!("{}", warning);
+            }
+        }
+
+        let warnings = collisions.into_iter().chain(too_long).collect();
+        *self.warnings.lock().unwrap() = warnings;
         Ok(())
     }
 
+    /// Stop listening for triggers immediately. Expansions already queued may
+    /// still be typed afterwards; use `shutdown` to wait for them.
     pub fn stop(&self) {
+        self.signal_stop();
+        log::info!("Text expansion engine stopped");
+    }
+
+    /// Stop listening for triggers, then wait up to `SHUTDOWN_DRAIN_TIMEOUT`
+    /// for queued expansions to be typed so none is cut off halfway
+    pub async fn shutdown(&self) -> Result<()> {
+        self.signal_stop();
+
+        let Some(workers) = self.workers.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        let keyboard_thread = workers.keyboard_thread;
+        let joined = tokio::time::timeout(
+            SHUTDOWN_DRAIN_TIMEOUT,
+            tokio::task::spawn_blocking(move || keyboard_thread.join()),
+        )
+        .await;
+        if joined.is_err() {
+            log::warn!(
+                "Keyboard thread did not exit within {:?}",
+                SHUTDOWN_DRAIN_TIMEOUT
+            );
+        }
+
+        // With its last sender gone the worker finishes the queue and exits
+        drop(workers.expansion_sender);
+        let mut expansion_task = workers.expansion_task;
+        if tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, &mut expansion_task)
+            .await
+            .is_err()
+        {
+            expansion_task.abort();
+            return Err(anyhow::anyhow!(
+                "Pending expansions were not finished within {:?}",
+                SHUTDOWN_DRAIN_TIMEOUT
+            ));
+        }
+
+        log::info!("Text expansion engine shut down");
+        Ok(())
+    }
+
+    fn signal_stop(&self) {
         {
             let mut is_running = self.is_running.lock().unwrap();
             *is_running = false;
         }
+        *self.started_at.lock().unwrap() = None;
 
         self.keyboard_monitor.stop_monitoring();
-        log::info!("Text expansion engine stopped");
+        self.focus_monitor.stop_monitoring();
+    }
+
+    /// Re-read the known triggers on the next lookup. Changes made through
+    /// this engine's service are noticed automatically; call this when
+    /// another process may have changed the database.
+    pub async fn invalidate_trigger_cache(&self) {
+        self.trigger_cache.invalidate().await;
+    }
+
+    /// Read the hotkeys of active snippets again, returning how many there
+    /// are. Called by `start`; hotkeys changed later take effect after this.
+    pub async fn reload_hotkeys(&self) -> Result<usize> {
+        let snippets = self
+            .service
+            .snippet_repository()
+            .find_all(&SnippetQuery::new().with_active_only())
+            .await?;
+
+        let mut hotkeys = Vec::new();
+        for snippet in snippets {
+            let Some(text) = &snippet.hotkey else {
+                continue;
+            };
+            match Hotkey::parse(text) {
+                Ok(hotkey) => hotkeys.push((hotkey, snippet.trigger)),
+                Err(e) => log::warn!(
+                    "Ignoring hotkey of '{}': {}",
+                    self.config.loggable(&snippet.trigger),
+                    e
+                ),
+            }
+        }
+
+        let count = hotkeys.len();
+        *self.hotkeys.lock().unwrap() = hotkeys;
+        Ok(count)
     }
 
     pub fn is_running(&self) -> bool {
@@ -175,6 +538,40 @@ impl TextExpansionEngine {
         *is_running
     }
 
+    /// A snapshot of the engine's state, for the daemon's status file
+    pub async fn status(&self) -> Result<EngineStatus> {
+        let request = SnippetListRequest {
+            search_term: None,
+            search_fields: None,
+            tags: None,
+            is_active: Some(true),
+            limit: Some(1),
+            offset: None,
+            sort_by: None,
+            sort_order: None,
+            tag_match: None,
+            source: None,
+            after: None,
+        };
+        let snippet_count = self.service.list_snippets(request).await?.total_count;
+        let uptime_seconds = self
+            .started_at
+            .lock()
+            .unwrap()
+            .map_or(0, |started| started.elapsed().as_secs());
+
+        Ok(EngineStatus {
+            is_running: self.is_running(),
+            enabled: self.config.enabled,
+            snippet_count,
+            uptime_seconds,
+            last_expansion: self.metrics.last_expansion(),
+            pid: std::process::id(),
+            updated_at: Utc::now(),
+            warnings: self.warnings.lock().unwrap().clone(),
+        })
+    }
+
     pub fn update_config(&mut self, config: ExpansionConfig) {
         self.config = config;
 
@@ -188,15 +585,31 @@ impl TextExpansionEngine {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn handle_keyboard_events(
         receiver: Receiver<KeyboardEvent>,
+        focus_changes: Receiver<FocusChange>,
         buffer: Arc<Mutex<TextBuffer>>,
         trigger_detection: TriggerDetectionService,
+        trigger_cache: Arc<TriggerCache>,
+        hotkeys: Arc<Mutex<Vec<(Hotkey, String)>>>,
         config: ExpansionConfig,
         is_running: Arc<Mutex<bool>>,
         expansion_sender: tokio_mpsc::Sender<ExpansionEvent>,
     ) {
+        let mut modifiers = ModifierState::default();
+        // Where the buffer has taken the trie of known triggers. Reset
+        // whenever the buffer loses text, and rebuilt from what's left.
+        let mut typed: Option<(Arc<TriggerTrie>, TrieCursor)> = None;
+
         while let Ok(event) = receiver.recv() {
+            // Keys spell out everything typed, passwords included
+            let _span = tracing::trace_span!(
+                "keyboard_event",
+                key = (!config.privacy_mode).then(|| tracing::field::display(&event.key)),
+            )
+            .entered();
+
             // Check if we should continue running
             {
                 let running = is_running.lock().unwrap();
@@ -205,48 +618,94 @@ impl TextExpansionEngine {
                 }
             }
 
+            // Text typed before focus moved belongs to another app
+            if let Some(change) = focus_changes.try_iter().last() {
+                log::debug!("Focus moved to {:?}, clearing the buffer", change.to);
+                buffer.lock().unwrap().clear();
+                typed = None;
+            }
+
             if !config.enabled {
                 continue;
             }
 
+            // Modifiers are tracked on key up as well, for hotkeys
+            let is_modifier =
+                modifiers.update(&event.key, event.event_type == KeyboardEventType::KeyDown);
+
             // Only process key down events for typing
             if event.event_type != KeyboardEventType::KeyDown {
                 continue;
             }
 
-            // Handle different types of keys
-            match event.key.as_str() {
-                // Regular characters
-                key if key.len() == 1 => {
-                    let c = key.chars().next().unwrap();
+            if !is_modifier && modifiers.has_any() {
+                let key = KeyboardMonitor::physical_key_to_string(&event.key);
+                let trigger = hotkeys
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|(hotkey, _)| modifiers.matches_combo(hotkey, &key))
+                    .map(|(_, trigger)| trigger.clone());
+
+                if let Some(trigger) = trigger {
+                    buffer.lock().unwrap().clear();
+                    typed = None;
+                    // Nothing was typed, so nothing is erased
+                    let expansion_event = ExpansionEvent {
+                        trigger,
+                        trigger_length: 0,
+                    };
+                    if expansion_sender.try_send(expansion_event).is_err() {
+                        log::warn!("Expansion event channel is full, skipping expansion");
+                    }
+                    continue;
+                }
+            }
 
-                    // Add character to buffer
+            // Handle different types of keys
+            match KeyboardMonitor::typed_text(&event) {
+                // Typed text: one character, which may be more than one
+                // byte, or several committed at once by an input method
+                Some(text) => {
+                    // Add the text to buffer
                     {
                         let mut buffer = buffer.lock().unwrap();
-                        buffer.add_char(c);
+                        buffer.add_text(&text);
 
                         // Clear buffer if expired
                         if buffer.is_expired(config.trigger_timeout_ms) {
                             buffer.clear();
+                            typed = None;
                             continue;
                         }
                     }
 
-                    // Check for triggers
-                    let buffer_text = {
-                        let buffer = buffer.lock().unwrap();
-                        buffer.get_text()
+                    // Known triggers are followed keystroke by keystroke.
+                    // Until they're loaded, or once they've changed, the
+                    // buffer is searched for anything trigger-shaped instead.
+                    let found = match trigger_cache.trie() {
+                        Some(trie) => Self::follow_typed_text(&mut typed, trie, &text, &buffer),
+                        None => {
+                            typed = None;
+                            let buffer_text = buffer.lock().unwrap().get_text();
+                            trigger_detection
+                                .find_triggers_in_text(&buffer_text)
+                                .pop()
+                                .map(|trigger_match| (buffer_text, trigger_match))
+                        }
                     };
 
-                    let triggers = trigger_detection.find_triggers_in_text(&buffer_text);
-
                     // Process the most recent complete trigger
-                    if let Some(trigger_match) = triggers.last() {
+                    if let Some((buffer_text, trigger_match)) = &found {
                         // Check if this trigger ends at the current position
-                        if trigger_match.end_position == buffer_text.len() {
+                        let at_end = trigger_match.end_position == buffer_text.len();
+                        let word_start_ok = !config.require_word_start
+                            || Self::is_word_start(buffer_text, trigger_match.start_position);
+
+                        if at_end && word_start_ok {
                             let expansion_event = ExpansionEvent {
-                                trigger: trigger_match.trigger.clone(),
-                                trigger_length: trigger_match.length(),
+                                trigger: trigger_match.normalized_trigger(),
+                                trigger_length: trigger_match.char_length(),
                             };
 
                             // Send expansion event (non-blocking)
@@ -257,85 +716,350 @@ impl TextExpansionEngine {
                     }
                 }
                 // Special keys that might interrupt typing
-                "Backspace" => {
-                    let mut buffer = buffer.lock().unwrap();
-                    if !buffer.content.is_empty() {
-                        buffer.content.pop_back();
+                None => match KeyboardMonitor::physical_key_to_string(&event.key).as_str() {
+                    "Backspace" => {
+                        let mut buffer = buffer.lock().unwrap();
+                        if !buffer.content.is_empty() {
+                            buffer.content.pop_back();
+                        }
+                        buffer.last_update = Instant::now();
+                        typed = None;
                     }
-                    buffer.last_update = Instant::now();
-                }
-                "Return" | "Tab" | "Space" => {
-                    // These keys typically end a word/trigger sequence
-                    let mut buffer = buffer.lock().unwrap();
-                    buffer.clear();
-                }
-                "Escape" | "ControlLeft" | "ControlRight" | "Alt" | "MetaLeft" | "MetaRight" => {
-                    // Control keys clear the buffer
-                    let mut buffer = buffer.lock().unwrap();
-                    buffer.clear();
-                }
-                _ => {
-                    // Other keys don't affect the buffer significantly
-                }
+                    "Return" | "Tab" | "Space" => {
+                        // These keys typically end a word/trigger sequence
+                        let mut buffer = buffer.lock().unwrap();
+                        buffer.clear();
+                        typed = None;
+                    }
+                    "Escape" | "ControlLeft" | "ControlRight" | "Alt" | "MetaLeft"
+                    | "MetaRight" => {
+                        // Control keys clear the buffer
+                        let mut buffer = buffer.lock().unwrap();
+                        buffer.clear();
+                        typed = None;
+                    }
+                    _ => {
+                        // Other keys don't affect the buffer significantly
+                    }
+                },
+            }
+        }
+    }
+
+    /// Feed `text`, just added to the buffer, to `trie`, returning the
+    /// buffer and the known trigger it now ends with. Starts over from the
+    /// whole buffer when `typed` was reset or belongs to an older trie.
+    fn follow_typed_text(
+        typed: &mut Option<(Arc<TriggerTrie>, TrieCursor)>,
+        trie: Arc<TriggerTrie>,
+        text: &str,
+        buffer: &Mutex<TextBuffer>,
+    ) -> Option<(String, TriggerMatch)> {
+        let cursor = match typed {
+            Some((followed, cursor)) if Arc::ptr_eq(followed, &trie) => {
+                trie.advance(cursor, text);
+                *cursor
+            }
+            _ => {
+                let mut cursor = trie.cursor();
+                trie.advance(&mut cursor, &buffer.lock().unwrap().get_text());
+                *typed = Some((Arc::clone(&trie), cursor));
+                cursor
             }
+        };
+
+        let found = trie.trigger_at(&cursor)?;
+        let buffer_text = buffer.lock().unwrap().get_text();
+        // The cursor may remember text the buffer has since dropped
+        let start = buffer_text.len().checked_sub(found.typed_length)?;
+        let trigger_match =
+            TriggerMatch::new(buffer_text[start..].to_string(), start, buffer_text.len());
+        Some((buffer_text, trigger_match))
+    }
+
+    /// Whether the text before byte offset `start` ends at a word boundary
+    fn is_word_start(text: &str, start: usize) -> bool {
+        match text[..start].chars().next_back() {
+            Some(c) => !(c.is_alphanumeric() || c == '_'),
+            None => true,
         }
     }
 
     async fn handle_expansion_event(
         event: ExpansionEvent,
-        input_simulator: &Arc<Mutex<InputSimulator>>,
+        service: &TypelyService,
+        trigger_cache: &TriggerCache,
+        input: &Arc<Mutex<InputBackend>>,
         config: &ExpansionConfig,
+        on_expansion: &Arc<Mutex<Option<ExpansionCallback>>>,
+        on_confirm: &Arc<Mutex<Option<ConfirmationCallback>>>,
     ) -> Result<()> {
+        // Most detected triggers are words still being typed, so anything
+        // that isn't a known trigger is dropped before touching the database
+        if !trigger_cache.contains(&event.trigger).await? {
+            return Ok(());
+        }
+
+        let app = active_application();
+        if !config.allows_app(app.as_deref()) {
+            log::debug!(
+                "Not expanding '{}' in {}",
+                config.loggable(&event.trigger),
+                app.as_deref().unwrap_or("an unknown application")
+            );
+            return Ok(());
+        }
+
+        let span = tracing::info_span!(
+            "handle_expansion_event",
+            trigger = (!config.privacy_mode).then(|| tracing::field::display(&event.trigger)),
+            duration_ms = tracing::field::Empty,
+        );
+
+        let started = Instant::now();
+        let result = Self::expand_event(event, service, input, config, on_expansion, on_confirm)
+            .instrument(span.clone())
+            .await;
+        span.record("duration_ms", started.elapsed().as_millis() as u64);
+
+        result.map(|_| ())
+    }
+
+    async fn expand_event(
+        event: ExpansionEvent,
+        service: &TypelyService,
+        input: &Arc<Mutex<InputBackend>>,
+        config: &ExpansionConfig,
+        on_expansion: &Arc<Mutex<Option<ExpansionCallback>>>,
+        on_confirm: &Arc<Mutex<Option<ConfirmationCallback>>>,
+    ) -> Result<ExpansionOutcome> {
         // Small delay to ensure the key event is processed
         tokio::time::sleep(Duration::from_millis(config.expansion_delay_ms)).await;
 
-        // Try to expand the snippet
-        // TODO: Implement snippet expansion logic here
-
-        // Note: This is a blocking call in an async context, which isn't ideal
-        // In a real implementation, you'd want to make the service async
-        let expansion_response = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                // This is a simplified approach - in production you'd want proper async service
-                Ok::<ExpansionResponse, anyhow::Error>(ExpansionResponse {
-                    success: true,
-                    expanded_text: Some(format!("Expanded: {}", event.trigger)),
-                    error_message: None,
-                })
+        let expansion_response = service
+            .expand_snippet(ExpansionRequest {
+                trigger: event.trigger.clone(),
+                context: None,
             })
-        })?;
+            .await?;
+
+        // The service counted the expansion; typing it can still fail
+        let outcome = Self::apply_expansion(
+            event,
+            expansion_response,
+            input,
+            config,
+            on_expansion,
+            on_confirm,
+        )
+        .await;
+        if outcome.is_err() {
+            service.metrics().record_failure();
+        }
+        outcome
+    }
 
+    /// Replace the typed trigger according to the service's response
+    async fn apply_expansion(
+        event: ExpansionEvent,
+        expansion_response: ExpansionResponse,
+        input: &Arc<Mutex<InputBackend>>,
+        config: &ExpansionConfig,
+        on_expansion: &Arc<Mutex<Option<ExpansionCallback>>>,
+        on_confirm: &Arc<Mutex<Option<ConfirmationCallback>>>,
+    ) -> Result<ExpansionOutcome> {
         if expansion_response.success {
             if let Some(expanded_text) = expansion_response.expanded_text {
-                // Perform the text replacement
-                Self::replace_text(&expanded_text, event.trigger_length, input_simulator)?;
+                let expanded_length = expanded_text.chars().count();
+                if expanded_length > config.max_expansion_length {
+                    return Err(anyhow::anyhow!(
+                        "Refusing to type {} characters for '{}' (limit is {})",
+                        expanded_length,
+                        config.loggable(&event.trigger),
+                        config.max_expansion_length
+                    ));
+                }
+
+                let info = ExpansionInfo {
+                    trigger: event.trigger.clone(),
+                    expanded_length,
+                    timestamp: Utc::now(),
+                };
+
+                // Checked before input, so it works without any
+                if config.simulate_only {
+                    if config.privacy_mode {
+                        log::info!("Would expand a trigger to {} characters", expanded_length);
+                    } else {
+                        log::info!("Would expand '{}' to '{}'", event.trigger, expanded_text);
+                    }
+                    Self::notify_expansion(on_expansion, info);
+                    return Ok(ExpansionOutcome::Simulated);
+                }
+
+                if !input.lock().unwrap().is_available() {
+                    log::info!(
+                        "Expanded '{}' but not typing it: the engine is monitor-only",
+                        config.loggable(&event.trigger)
+                    );
+                    return Ok(ExpansionOutcome::MonitorOnly);
+                }
+
+                if expansion_response.confirm_before_expand {
+                    let request = ConfirmationRequest {
+                        trigger: event.trigger.clone(),
+                        expanded_text: expanded_text.clone(),
+                    };
+                    if !Self::confirm(on_confirm, request, config).await {
+                        log::info!(
+                            "Expansion of '{}' was not confirmed",
+                            config.loggable(&event.trigger)
+                        );
+                        return Ok(ExpansionOutcome::Declined);
+                    }
+                }
 
-                log::info!("Expanded '{}' to '{}'", event.trigger, expanded_text);
+                // Keep-trigger snippets are inserted after the trigger, so nothing is erased
+                let erase_length = if expansion_response.keep_trigger {
+                    0
+                } else {
+                    event.trigger_length
+                };
+
+                // Perform the text replacement; rich text is typed as plain
+                // text when it can't be pasted
+                let html = expansion_response.content_type.render_html(&expanded_text);
+                let pasted = match html {
+                    Some(html) => Self::paste_html(&html, erase_length, input)?,
+                    None => false,
+                };
+                if !pasted {
+                    // A snippet's own delay wins over the engine-wide one
+                    let type_delay_ms = expansion_response
+                        .type_delay_ms
+                        .unwrap_or(config.type_delay_ms);
+                    Self::replace_text(&expanded_text, erase_length, type_delay_ms, input)?
+                }
+
+                if config.privacy_mode {
+                    log::info!("Expanded a trigger to {} characters", expanded_length);
+                } else {
+                    log::info!("Expanded '{}' to '{}'", event.trigger, expanded_text);
+                }
+
+                Self::notify_expansion(on_expansion, info);
+
+                return Ok(ExpansionOutcome::Expanded);
             }
         } else if let Some(error) = expansion_response.error_message {
-            log::debug!("Expansion failed for '{}': {}", event.trigger, error);
+            if config.privacy_mode {
+                log::debug!("Expansion failed");
+            } else {
+                log::debug!("Expansion failed for '{}': {}", event.trigger, error);
+            }
         }
 
-        Ok(())
+        Ok(ExpansionOutcome::NotExpanded)
     }
 
+    /// Ask the confirmation callback; declines if none is registered
+    async fn confirm(
+        on_confirm: &Arc<Mutex<Option<ConfirmationCallback>>>,
+        request: ConfirmationRequest,
+        config: &ExpansionConfig,
+    ) -> bool {
+        if on_confirm.lock().unwrap().is_none() {
+            log::warn!(
+                "'{}' needs confirmation but no confirmation handler is registered",
+                config.loggable(&request.trigger)
+            );
+            return false;
+        }
+
+        // A prompt can block for as long as the user takes, so keep it off the async workers
+        let on_confirm = Arc::clone(on_confirm);
+        tokio::task::spawn_blocking(move || {
+            on_confirm
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|callback| callback(&request))
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    fn notify_expansion(on_expansion: &Arc<Mutex<Option<ExpansionCallback>>>, info: ExpansionInfo) {
+        if on_expansion.lock().unwrap().is_none() {
+            return;
+        }
+
+        // Run the callback off the input path so a slow observer can't block typing
+        let on_expansion = Arc::clone(on_expansion);
+        tokio::task::spawn_blocking(move || {
+            if let Some(callback) = on_expansion.lock().unwrap().as_ref() {
+                callback(&info);
+            }
+        });
+    }
+
+    /// Erase `trigger_length` typed characters (none for keep-trigger snippets) and type the expansion
     fn replace_text(
         expanded_text: &str,
         trigger_length: usize,
-        input_simulator: &Arc<Mutex<InputSimulator>>,
+        type_delay_ms: u64,
+        input: &Arc<Mutex<InputBackend>>,
     ) -> Result<()> {
-        let simulator = input_simulator.lock().unwrap();
+        let input = input.lock().unwrap();
+        let simulator = input.simulator()?;
 
         // Method 1: Simple backspace and type (most compatible)
-        simulator.replace_trigger_with_expansion(trigger_length, expanded_text)?;
+        simulator.simulate_backspace(trigger_length)?;
+        simulator.type_text_with_delay(expanded_text, type_delay_ms)?;
 
         Ok(())
     }
+
+    /// Erase `trigger_length` typed characters and paste `html` through the
+    /// system clipboard, putting the user's clipboard back afterwards.
+    /// Returns false, having changed nothing, when the clipboard can't hold
+    /// HTML.
+    fn paste_html(
+        html: &str,
+        trigger_length: usize,
+        input: &Arc<Mutex<InputBackend>>,
+    ) -> Result<bool> {
+        let clipboard = match ClipboardManager::system() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                log::debug!("Typing rich text as plain text: {}", e);
+                return Ok(false);
+            }
+        };
+
+        clipboard.save()?;
+        if let Err(e) = clipboard.set_html(html) {
+            log::debug!("Typing rich text as plain text: {}", e);
+            clipboard.restore()?;
+            return Ok(false);
+        }
+
+        let input = input.lock().unwrap();
+        let simulator = input.simulator()?;
+        let pasted = simulator
+            .simulate_backspace(trigger_length)
+            .and_then(|_| simulator.paste());
+        clipboard.restore()?;
+        pasted?;
+
+        Ok(true)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct ExpansionEvent {
     trigger: String,
+    /// Number of typed characters to erase (Unicode scalar values, not bytes)
     trigger_length: usize,
 }
 
@@ -348,7 +1072,8 @@ impl Drop for TextExpansionEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::infra::DatabaseConnection;
+    use crate::domain::{ContentType, Snippet, SnippetRepository};
+    use crate::infra::{DatabaseConnection, HashMapSnippetRepository};
     use tempfile::TempDir;
 
     async fn create_test_engine() -> (TextExpansionEngine, TempDir) {
@@ -361,29 +1086,47 @@ mod tests {
             buffer_size: 50,
             trigger_timeout_ms: 500,
             expansion_delay_ms: 10,
+            type_delay_ms: 2,
             enabled: true,
             case_sensitive: true,
+            max_expansion_length: DEFAULT_MAX_EXPANSION_LENGTH,
+            require_word_start: false,
+            app_denylist: Vec::new(),
+            app_allowlist: Vec::new(),
+            trigger_prefix: TriggerPrefix::default(),
+            simulate_only: false,
+            privacy_mode: false,
         };
 
         let engine = TextExpansionEngine::new(service, Some(config)).unwrap();
         (engine, temp_dir)
     }
 
+    fn typed_text(engine: &TextExpansionEngine) -> String {
+        engine
+            .input
+            .lock()
+            .unwrap()
+            .simulator()
+            .unwrap()
+            .typed_text()
+    }
+
     #[tokio::test]
     async fn test_text_buffer() {
         let mut buffer = TextBuffer::new(5);
 
         // Test adding characters
-        buffer.add_char('a');
-        buffer.add_char('b');
-        buffer.add_char('c');
+        buffer.add_text("a");
+        buffer.add_text("b");
+        buffer.add_text("c");
 
         assert_eq!(buffer.get_text(), "abc");
 
         // Test buffer overflow
-        buffer.add_char('d');
-        buffer.add_char('e');
-        buffer.add_char('f'); // Should remove 'a'
+        buffer.add_text("d");
+        buffer.add_text("e");
+        buffer.add_text("f"); // Should remove 'a'
 
         assert_eq!(buffer.get_text(), "bcdef");
 
@@ -396,6 +1139,26 @@ mod tests {
         assert_eq!(buffer.get_text(), "");
     }
 
+    #[test]
+    fn test_text_buffer_keeps_multi_byte_graphemes() {
+        let mut buffer = TextBuffer::new(4);
+
+        // An input method committing two characters at once, then an accent
+        // typed after its letter
+        buffer.add_text("日本");
+        buffer.add_text("e");
+        buffer.add_text("\u{301}");
+        assert_eq!(buffer.get_text(), "日本e\u{301}");
+        assert_eq!(buffer.content.len(), 3);
+
+        // The accented letter is one grapheme, removed in one go
+        buffer.remove_chars(1);
+        assert_eq!(buffer.get_text(), "日本");
+
+        buffer.add_text("語!?");
+        assert_eq!(buffer.get_text(), "本語!?");
+    }
+
     #[test]
     fn test_expansion_config() {
         let config = ExpansionConfig::default();
@@ -403,8 +1166,38 @@ mod tests {
         assert_eq!(config.buffer_size, 100);
         assert_eq!(config.trigger_timeout_ms, 1000);
         assert_eq!(config.expansion_delay_ms, 50);
+        assert_eq!(config.type_delay_ms, 0);
         assert!(config.enabled);
         assert!(config.case_sensitive);
+        assert_eq!(config.max_expansion_length, 50_000);
+        assert!(!config.require_word_start);
+        assert!(config.app_denylist.is_empty() && config.app_allowlist.is_empty());
+        assert!(!config.simulate_only);
+    }
+
+    #[test]
+    fn test_allows_app() {
+        assert!(ExpansionConfig::default().allows_app(Some("Terminal")));
+        assert!(ExpansionConfig::default().allows_app(None));
+
+        let config = ExpansionConfig {
+            app_denylist: vec!["1Password".to_string(), "KeePassXC".to_string()],
+            ..ExpansionConfig::default()
+        };
+        assert!(!config.allows_app(Some("1password")));
+        assert!(config.allows_app(Some("Terminal")));
+        assert!(config.allows_app(None));
+
+        // The allowlist wins over the denylist, and shuts out everything else
+        let config = ExpansionConfig {
+            app_allowlist: vec!["Slack".to_string(), "KeePassXC".to_string()],
+            trigger_prefix: TriggerPrefix::default(),
+            ..config
+        };
+        assert!(config.allows_app(Some("SLACK")));
+        assert!(config.allows_app(Some("KeePassXC")));
+        assert!(!config.allows_app(Some("Terminal")));
+        assert!(!config.allows_app(None));
     }
 
     #[tokio::test]
@@ -413,6 +1206,901 @@ mod tests {
         // Engine should be created successfully
     }
 
+    #[tokio::test]
+    async fn test_status_reports_running_and_enabled() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        engine
+            .service
+            .create_snippet(crate::app::dto::CreateSnippetRequest::new("::hi", "Hello"))
+            .await
+            .unwrap();
+
+        let status = engine.status().await.unwrap();
+        assert!(!status.is_running);
+        assert!(status.enabled);
+        assert_eq!(status.snippet_count, 1);
+        assert_eq!(status.uptime_seconds, 0);
+        assert_eq!(status.last_expansion, None);
+
+        engine.start().await.unwrap();
+        assert!(engine.status().await.unwrap().is_running);
+
+        engine.stop();
+        assert!(!engine.status().await.unwrap().is_running);
+    }
+
+    #[tokio::test]
+    async fn test_start_warns_about_trigger_collisions() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        for (trigger, replacement) in [("::a", "A"), ("::ab", "AB"), ("::b", "B")] {
+            engine
+                .service
+                .create_snippet(crate::app::dto::CreateSnippetRequest::new(
+                    trigger,
+                    replacement,
+                ))
+                .await
+                .unwrap();
+        }
+        assert!(engine.status().await.unwrap().warnings.is_empty());
+
+        engine.start().await.unwrap();
+        assert_eq!(
+            engine.status().await.unwrap().warnings,
+            vec!["'::ab' can't be typed: '::a' (from '::a') expands first"]
+        );
+        engine.stop();
+    }
+
+    #[tokio::test]
+    async fn test_start_warns_about_triggers_longer_than_the_buffer() {
+        let (mut engine, _temp_dir) = create_test_engine().await;
+        engine.update_config(ExpansionConfig {
+            buffer_size: 10,
+            ..engine.config.clone()
+        });
+        let trigger = "::signature".to_string();
+        engine
+            .service
+            .create_snippet(crate::app::dto::CreateSnippetRequest::new(
+                trigger.clone(),
+                "long",
+            ))
+            .await
+            .unwrap();
+
+        engine.start().await.unwrap();
+        assert_eq!(
+            engine.status().await.unwrap().warnings,
+            vec![format!(
+                "'{}' can't be typed: it is longer than the 10-character buffer",
+                trigger
+            )]
+        );
+        engine.stop();
+    }
+
+    #[tokio::test]
+    async fn test_engine_without_input_runs_monitor_only() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        let engine = engine.with_input_backend(InputBackend::unavailable("no display"));
+        assert!(engine.is_monitor_only());
+
+        engine.start().await.unwrap();
+        assert!(engine.is_running());
+        assert_eq!(
+            engine.status().await.unwrap().warnings,
+            vec![
+                "Input simulation is unavailable (no display): expansions are detected but not typed"
+            ]
+        );
+
+        assert_eq!(
+            apply_plain_expansion(&engine, false, None).await,
+            ExpansionOutcome::MonitorOnly
+        );
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_disabled_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_connection = DatabaseConnection::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let service = Arc::new(TypelyService::new(db_connection).await);
+        let config = ExpansionConfig {
+            enabled: false,
+            ..ExpansionConfig::default()
+        };
+
+        let engine = TextExpansionEngine::new(service, Some(config)).unwrap();
+        assert!(!engine.status().await.unwrap().enabled);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_expansion_callback_fires_once_per_expansion() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+        engine.set_on_expansion(Box::new(move |info| {
+            sender.lock().unwrap().send(info.clone()).unwrap();
+        }));
+
+        for trigger in ["::one", "::two"] {
+            create_snippet(&engine, trigger).await;
+            handle_event(&engine, trigger).await.unwrap();
+        }
+
+        let timeout = Duration::from_secs(1);
+        let first = receiver.recv_timeout(timeout).unwrap();
+        let second = receiver.recv_timeout(timeout).unwrap();
+        assert_eq!(first.trigger, "::one");
+        assert_eq!(second.trigger, "::two");
+        assert!(first.expanded_length > 0);
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+        let snapshot = engine.metrics().snapshot();
+        assert_eq!(snapshot.expansions_total, 2);
+        assert_eq!(snapshot.expansion_failures_total, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_expansion_over_max_length_is_not_typed() {
+        let (mut engine, _temp_dir) = create_test_engine().await;
+        let mut config = engine.config.clone();
+        config.max_expansion_length = 4;
+        engine.update_config(config);
+
+        create_snippet(&engine, "::long").await;
+        let result = handle_event(&engine, "::long").await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("limit is 4"));
+        assert_eq!(typed_text(&engine), "");
+
+        // Found by the service, then refused by the engine
+        let snapshot = engine.metrics().snapshot();
+        assert_eq!(snapshot.expansions_total, 1);
+        assert_eq!(snapshot.expansion_failures_total, 1);
+    }
+
     // Note: Testing the actual keyboard monitoring and input simulation
     // would require a real GUI environment and is complex to test in unit tests
+
+    /// A cache that hasn't loaded, so the keyboard thread searches the buffer
+    fn unloaded_trigger_cache() -> Arc<TriggerCache> {
+        Arc::new(TriggerCache::new(
+            Arc::new(HashMapSnippetRepository::new()),
+            Arc::new(std::sync::atomic::AtomicU64::new(0)),
+        ))
+    }
+
+    /// A focus channel that never reports a change
+    fn no_focus_changes() -> Receiver<FocusChange> {
+        std::sync::mpsc::channel().1
+    }
+
+    /// Run the keyboard thread over `keys`, one key name or character each
+    fn detect_typed_keys(
+        keys: &[&str],
+        config: ExpansionConfig,
+        trigger_cache: Arc<TriggerCache>,
+    ) -> Vec<ExpansionEvent> {
+        let (key_sender, key_receiver) = std::sync::mpsc::channel();
+        let (expansion_sender, mut expansion_receiver) = tokio_mpsc::channel(64);
+
+        for key in keys {
+            let mut event = KeyboardEvent::new(KeyboardEventType::KeyDown, 0);
+            event.key = key.to_string();
+            key_sender.send(event).unwrap();
+        }
+        drop(key_sender);
+
+        TextExpansionEngine::handle_keyboard_events(
+            key_receiver,
+            no_focus_changes(),
+            Arc::new(Mutex::new(TextBuffer::new(config.buffer_size))),
+            TriggerDetectionService::new(),
+            trigger_cache,
+            Arc::new(Mutex::new(Vec::new())),
+            config,
+            Arc::new(Mutex::new(true)),
+            expansion_sender,
+        );
+
+        let mut events = Vec::new();
+        while let Ok(event) = expansion_receiver.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn detect_typed_triggers(typed: &str, config: ExpansionConfig) -> Vec<String> {
+        let keys: Vec<String> = typed.chars().map(String::from).collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        detect_typed_keys(&keys, config, unloaded_trigger_cache())
+            .into_iter()
+            .map(|event| event.trigger)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_loaded_triggers_are_followed_through_the_trie() {
+        let repository = Arc::new(HashMapSnippetRepository::new());
+        for trigger in ["::hi", "::hello", "::caf\u{e9}"] {
+            let snippet = Snippet::new(trigger.to_string(), "Text".to_string()).unwrap();
+            repository.save(&snippet).await.unwrap();
+        }
+        let trigger_cache = Arc::new(TriggerCache::new(
+            repository,
+            Arc::new(std::sync::atomic::AtomicU64::new(0)),
+        ));
+        trigger_cache.reload().await.unwrap();
+
+        let typed: Vec<String> = "say ::hi x::hello ::hex ::cafe\u{301}"
+            .chars()
+            .map(String::from)
+            .collect();
+        let mut keys: Vec<&str> = typed.iter().map(String::as_str).collect();
+        // Typing "::hx", erasing the x and typing "i" still finds "::hi"
+        keys.extend([" ", ":", ":", "h", "x", "Backspace", "i"]);
+
+        let followed = detect_typed_keys(&keys, ExpansionConfig::default(), trigger_cache);
+        let searched =
+            detect_typed_keys(&keys, ExpansionConfig::default(), unloaded_trigger_cache());
+
+        // The same as searching the buffer, less everything that isn't a trigger
+        let known = ["::hi", "::hello", "::caf\u{e9}"];
+        let summary = |events: Vec<ExpansionEvent>| -> Vec<(String, usize)> {
+            events
+                .into_iter()
+                .filter(|event| known.contains(&event.trigger.as_str()))
+                .map(|event| (event.trigger, event.trigger_length))
+                .collect()
+        };
+        assert!(searched.len() > followed.len());
+        assert_eq!(summary(searched), summary(followed.clone()));
+        assert_eq!(
+            summary(followed),
+            vec![
+                ("::hi".to_string(), 4),
+                ("::hello".to_string(), 7),
+                ("::caf\u{e9}".to_string(), 7),
+                ("::hi".to_string(), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typed_trigger_uses_layout_characters() {
+        // "::hi" typed on AZERTY, where ":" sits on the QWERTY "." key and
+        // rdev reports the physical key alongside the layout's character
+        let (key_sender, key_receiver) = std::sync::mpsc::channel();
+        let (expansion_sender, mut expansion_receiver) = tokio_mpsc::channel(16);
+        for (key, text) in [("Dot", ":"), ("Dot", ":"), ("KeyH", "h"), ("KeyI", "i")] {
+            let mut event = KeyboardEvent::new(KeyboardEventType::KeyDown, 0);
+            event.key = key.to_string();
+            event.text = Some(text.to_string());
+            key_sender.send(event).unwrap();
+        }
+        drop(key_sender);
+
+        TextExpansionEngine::handle_keyboard_events(
+            key_receiver,
+            no_focus_changes(),
+            Arc::new(Mutex::new(TextBuffer::new(50))),
+            TriggerDetectionService::new(),
+            unloaded_trigger_cache(),
+            Arc::new(Mutex::new(Vec::new())),
+            ExpansionConfig::default(),
+            Arc::new(Mutex::new(true)),
+            expansion_sender,
+        );
+
+        let mut triggers = Vec::new();
+        while let Ok(event) = expansion_receiver.try_recv() {
+            triggers.push(event.trigger);
+        }
+        assert_eq!(triggers.last().map(String::as_str), Some("::hi"));
+    }
+
+    #[test]
+    fn test_trigger_typed_through_an_input_method() {
+        // The IME commits "日本" as one event, with no physical key behind it
+        let (key_sender, key_receiver) = std::sync::mpsc::channel();
+        let (expansion_sender, mut expansion_receiver) = tokio_mpsc::channel(16);
+        for (key, text) in [("SemiColon", ":"), ("SemiColon", ":"), ("Unknown", "日本")] {
+            let mut event = KeyboardEvent::new(KeyboardEventType::KeyDown, 0);
+            event.key = key.to_string();
+            event.text = Some(text.to_string());
+            key_sender.send(event).unwrap();
+        }
+        drop(key_sender);
+
+        TextExpansionEngine::handle_keyboard_events(
+            key_receiver,
+            no_focus_changes(),
+            Arc::new(Mutex::new(TextBuffer::new(50))),
+            TriggerDetectionService::new(),
+            unloaded_trigger_cache(),
+            Arc::new(Mutex::new(Vec::new())),
+            ExpansionConfig::default(),
+            Arc::new(Mutex::new(true)),
+            expansion_sender,
+        );
+
+        let event = expansion_receiver.try_recv().unwrap();
+        assert_eq!(event.trigger, "::日本");
+        assert_eq!(event.trigger_length, 4);
+    }
+
+    #[test]
+    fn test_focus_change_clears_the_buffer() {
+        let (key_sender, key_receiver) = std::sync::mpsc::channel();
+        let (focus_sender, focus_receiver) = std::sync::mpsc::channel();
+        let (expansion_sender, mut expansion_receiver) = tokio_mpsc::channel(16);
+        let buffer = Arc::new(Mutex::new(TextBuffer::new(50)));
+
+        let thread_buffer = Arc::clone(&buffer);
+        let keyboard_thread = thread::spawn(move || {
+            TextExpansionEngine::handle_keyboard_events(
+                key_receiver,
+                focus_receiver,
+                thread_buffer,
+                TriggerDetectionService::new(),
+                unloaded_trigger_cache(),
+                Arc::new(Mutex::new(Vec::new())),
+                ExpansionConfig::default(),
+                Arc::new(Mutex::new(true)),
+                expansion_sender,
+            )
+        });
+        let send_key = |key: &str| {
+            let mut event = KeyboardEvent::new(KeyboardEventType::KeyDown, 0);
+            event.key = key.to_string();
+            key_sender.send(event).unwrap();
+        };
+
+        for key in [":", ":", "s", "i"] {
+            send_key(key);
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while buffer.lock().unwrap().get_text() != "::si" {
+            assert!(Instant::now() < deadline, "keys were never buffered");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // "g" is typed in another app, so "::sig" is never complete
+        focus_sender
+            .send(FocusChange {
+                from: Some("Editor".to_string()),
+                to: Some("Browser".to_string()),
+            })
+            .unwrap();
+        send_key("g");
+        drop(key_sender);
+        keyboard_thread.join().unwrap();
+
+        assert_eq!(buffer.lock().unwrap().get_text(), "g");
+        let mut triggers = Vec::new();
+        while let Ok(event) = expansion_receiver.try_recv() {
+            triggers.push(event.trigger);
+        }
+        assert_eq!(triggers, vec!["::s", "::si"]);
+    }
+
+    #[test]
+    fn test_hotkey_expands_without_erasing() {
+        let (key_sender, key_receiver) = std::sync::mpsc::channel();
+        let (expansion_sender, mut expansion_receiver) = tokio_mpsc::channel(16);
+        let presses = [
+            ("ControlLeft", KeyboardEventType::KeyDown),
+            ("Alt", KeyboardEventType::KeyDown),
+            ("Num1", KeyboardEventType::KeyDown),
+            ("Alt", KeyboardEventType::KeyUp),
+            // Ctrl+1 alone is not the hotkey
+            ("Num1", KeyboardEventType::KeyDown),
+        ];
+        for (key, event_type) in presses {
+            let mut event = KeyboardEvent::new(event_type, 0);
+            event.key = key.to_string();
+            key_sender.send(event).unwrap();
+        }
+        drop(key_sender);
+
+        let hotkeys = vec![(Hotkey::parse("Ctrl+Alt+1").unwrap(), "::sig".to_string())];
+        TextExpansionEngine::handle_keyboard_events(
+            key_receiver,
+            no_focus_changes(),
+            Arc::new(Mutex::new(TextBuffer::new(50))),
+            TriggerDetectionService::new(),
+            unloaded_trigger_cache(),
+            Arc::new(Mutex::new(hotkeys)),
+            ExpansionConfig::default(),
+            Arc::new(Mutex::new(true)),
+            expansion_sender,
+        );
+
+        let event = expansion_receiver.try_recv().unwrap();
+        assert_eq!(event.trigger, "::sig");
+        assert_eq!(event.trigger_length, 0);
+        assert!(expansion_receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_start_loads_hotkeys_of_active_snippets() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        let repository = engine.service.snippet_repository();
+        for (trigger, hotkey, active) in [
+            ("::sig", Some("Ctrl+Alt+S"), true),
+            ("::plain", None, true),
+            ("::old", Some("Ctrl+Alt+O"), false),
+        ] {
+            let mut snippet = Snippet::new(trigger.to_string(), "Text".to_string()).unwrap();
+            snippet.set_hotkey(hotkey).unwrap();
+            if !active {
+                snippet.deactivate();
+            }
+            repository.save(&snippet).await.unwrap();
+        }
+
+        assert_eq!(engine.reload_hotkeys().await.unwrap(), 1);
+        let hotkeys = engine.hotkeys.lock().unwrap().clone();
+        assert_eq!(hotkeys[0].0.to_string(), "Ctrl+Alt+S");
+        assert_eq!(hotkeys[0].1, "::sig");
+    }
+
+    #[test]
+    fn test_typed_trigger_is_nfc_normalized() {
+        let triggers = detect_typed_triggers("::cafe\u{301}", ExpansionConfig::default());
+        assert_eq!(triggers.last().map(String::as_str), Some("::caf\u{e9}"));
+    }
+
+    #[test]
+    fn test_trigger_inside_word_expands_by_default() {
+        let triggers = detect_typed_triggers("abc::hi", ExpansionConfig::default());
+        assert_eq!(triggers.last().map(String::as_str), Some("::hi"));
+    }
+
+    #[test]
+    fn test_require_word_start() {
+        let config = ExpansionConfig {
+            require_word_start: true,
+            ..ExpansionConfig::default()
+        };
+
+        let triggers = detect_typed_triggers("say ::hi", config.clone());
+        assert_eq!(triggers.last().map(String::as_str), Some("::hi"));
+
+        let triggers = detect_typed_triggers("abc::hi", config.clone());
+        assert!(triggers.is_empty());
+
+        let triggers = detect_typed_triggers("::hi", config);
+        assert_eq!(triggers.last().map(String::as_str), Some("::hi"));
+    }
+
+    fn confirm_response(text: &str) -> ExpansionResponse {
+        ExpansionResponse {
+            success: true,
+            expanded_text: Some(text.to_string()),
+            error_message: None,
+            content_type: ContentType::Plain,
+            confirm_before_expand: true,
+            keep_trigger: false,
+            type_delay_ms: None,
+            snippet_id: None,
+            usage_count: None,
+        }
+    }
+
+    async fn apply_with_answer(engine: &TextExpansionEngine, answer: bool) -> ExpansionOutcome {
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let asked_in_callback = Arc::clone(&asked);
+        engine.set_confirmation_callback(Box::new(move |request| {
+            asked_in_callback.lock().unwrap().push(request.clone());
+            answer
+        }));
+
+        let event = ExpansionEvent {
+            trigger: "::secret".to_string(),
+            trigger_length: 8,
+        };
+        let outcome = TextExpansionEngine::apply_expansion(
+            event,
+            confirm_response("hunter2"),
+            &engine.input,
+            &engine.config,
+            &engine.on_expansion,
+            &engine.on_confirm,
+        )
+        .await
+        .unwrap();
+
+        let asked = asked.lock().unwrap();
+        assert_eq!(asked.len(), 1);
+        assert_eq!(asked[0].trigger, "::secret");
+        assert_eq!(asked[0].expanded_text, "hunter2");
+        outcome
+    }
+
+    #[tokio::test]
+    async fn test_declined_confirmation_types_nothing() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        let outcome = apply_with_answer(&engine, false).await;
+
+        assert_eq!(outcome, ExpansionOutcome::Declined);
+        assert_eq!(typed_text(&engine), "");
+    }
+
+    #[tokio::test]
+    async fn test_approved_confirmation_types_expansion() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        let outcome = apply_with_answer(&engine, true).await;
+
+        assert_eq!(outcome, ExpansionOutcome::Expanded);
+        assert_eq!(typed_text(&engine), "hunter2");
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_without_handler_declines() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        let event = ExpansionEvent {
+            trigger: "::secret".to_string(),
+            trigger_length: 8,
+        };
+        let outcome = TextExpansionEngine::apply_expansion(
+            event,
+            confirm_response("hunter2"),
+            &engine.input,
+            &engine.config,
+            &engine.on_expansion,
+            &engine.on_confirm,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, ExpansionOutcome::Declined);
+        assert_eq!(typed_text(&engine), "");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_saved_snippet_needing_confirmation_is_not_typed_when_declined() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        engine
+            .service
+            .create_snippet(crate::app::dto::CreateSnippetRequest {
+                confirm_before_expand: Some(true),
+                ..crate::app::dto::CreateSnippetRequest::new("::rmrf", "rm -rf ./build")
+            })
+            .await
+            .unwrap();
+
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let asked_in_callback = Arc::clone(&asked);
+        engine.set_confirmation_callback(Box::new(move |request| {
+            asked_in_callback.lock().unwrap().push(request.clone());
+            false
+        }));
+
+        handle_event(&engine, "::rmrf").await.unwrap();
+
+        let asked = asked.lock().unwrap();
+        assert_eq!(asked.len(), 1);
+        assert_eq!(asked[0].expanded_text, "rm -rf ./build");
+        assert_eq!(typed_text(&engine), "");
+        let backspaces = engine
+            .input
+            .lock()
+            .unwrap()
+            .simulator()
+            .unwrap()
+            .backspace_count();
+        assert_eq!(backspaces, 0);
+    }
+
+    async fn apply_plain_expansion(
+        engine: &TextExpansionEngine,
+        keep_trigger: bool,
+        type_delay_ms: Option<u64>,
+    ) -> ExpansionOutcome {
+        let event = ExpansionEvent {
+            trigger: "::log".to_string(),
+            trigger_length: 5,
+        };
+        let response = ExpansionResponse {
+            success: true,
+            expanded_text: Some(" [logged]".to_string()),
+            error_message: None,
+            content_type: ContentType::Plain,
+            confirm_before_expand: false,
+            keep_trigger,
+            type_delay_ms,
+            snippet_id: None,
+            usage_count: None,
+        };
+
+        TextExpansionEngine::apply_expansion(
+            event,
+            response,
+            &engine.input,
+            &engine.config,
+            &engine.on_expansion,
+            &engine.on_confirm,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_privacy_mode_keeps_triggers_out_of_logs() {
+        let (mut engine, _temp_dir) = create_test_engine().await;
+        engine.update_config(ExpansionConfig {
+            privacy_mode: true,
+            ..engine.config.clone()
+        });
+
+        let logs = crate::test_support::LogCapture::start();
+        assert_eq!(
+            apply_plain_expansion(&engine, false, None).await,
+            ExpansionOutcome::Expanded
+        );
+
+        let messages = logs.messages();
+        assert_eq!(messages, vec!["Expanded a trigger to 9 characters"]);
+        assert!(messages
+            .iter()
+            .all(|message| !message.contains("::log") && !message.contains("[logged]")));
+    }
+
+    #[tokio::test]
+    async fn test_expansions_are_logged_without_privacy_mode() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        let logs = crate::test_support::LogCapture::start();
+        apply_plain_expansion(&engine, false, None).await;
+
+        assert_eq!(logs.messages(), vec!["Expanded '::log' to ' [logged]'"]);
+    }
+
+    #[tokio::test]
+    async fn test_keep_trigger_sends_no_backspaces() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        let outcome = apply_plain_expansion(&engine, true, None).await;
+
+        let input = engine.input.lock().unwrap();
+        let simulator = input.simulator().unwrap();
+        assert_eq!(outcome, ExpansionOutcome::Expanded);
+        assert_eq!(simulator.backspace_count(), 0);
+        assert_eq!(simulator.typed_text(), " [logged]");
+    }
+
+    #[tokio::test]
+    async fn test_replacing_trigger_erases_it() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        apply_plain_expansion(&engine, false, None).await;
+
+        let input = engine.input.lock().unwrap();
+        let simulator = input.simulator().unwrap();
+        assert_eq!(simulator.backspace_count(), 5);
+        assert_eq!(simulator.typed_text(), " [logged]");
+    }
+
+    #[tokio::test]
+    async fn test_rich_text_is_typed_without_a_system_clipboard() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        let event = ExpansionEvent {
+            trigger: "::bold".to_string(),
+            trigger_length: 6,
+        };
+        let response = ExpansionResponse {
+            content_type: ContentType::Markdown,
+            confirm_before_expand: false,
+            ..confirm_response("**bold**")
+        };
+
+        let outcome = TextExpansionEngine::apply_expansion(
+            event,
+            response,
+            &engine.input,
+            &engine.config,
+            &engine.on_expansion,
+            &engine.on_confirm,
+        )
+        .await
+        .unwrap();
+
+        // This build has no clipboard to paste HTML from, so the plain text
+        // is typed rather than pasting whatever the clipboard holds
+        let input = engine.input.lock().unwrap();
+        let simulator = input.simulator().unwrap();
+        assert_eq!(outcome, ExpansionOutcome::Expanded);
+        assert_eq!(simulator.backspace_count(), 6);
+        assert_eq!(simulator.typed_text(), "**bold**");
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_can_be_retried() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_connection = DatabaseConnection::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let pool = db_connection.pool().clone();
+        let service = Arc::new(TypelyService::new(db_connection).await);
+        let engine = TextExpansionEngine::new(service, None).unwrap();
+
+        // Without a snippets table the triggers can't be loaded
+        sqlx::query("ALTER TABLE snippets RENAME TO snippets_away")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(engine.start().await.is_err());
+        assert!(!engine.is_running());
+        assert!(engine.started_at.lock().unwrap().is_none());
+
+        sqlx::query("ALTER TABLE snippets_away RENAME TO snippets")
+            .execute(&pool)
+            .await
+            .unwrap();
+        engine.start().await.unwrap();
+        assert!(engine.is_running());
+        engine.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snippet_type_delay_overrides_config() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        apply_plain_expansion(&engine, false, Some(25)).await;
+
+        let input = engine.input.lock().unwrap();
+        let simulator = input.simulator().unwrap();
+        assert_eq!(simulator.type_delays(), vec![25]);
+        assert_eq!(simulator.typed_text(), " [logged]");
+    }
+
+    #[tokio::test]
+    async fn test_type_delay_falls_back_to_config() {
+        let (engine, _temp_dir) = create_test_engine().await;
+
+        apply_plain_expansion(&engine, false, None).await;
+
+        let input = engine.input.lock().unwrap();
+        let simulator = input.simulator().unwrap();
+        assert_eq!(simulator.type_delays(), vec![engine.config.type_delay_ms]);
+        assert_eq!(engine.config.type_delay_ms, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_simulate_only_types_nothing_but_tells_the_callback() {
+        let (mut engine, _temp_dir) = create_test_engine().await;
+        let mut config = engine.config.clone();
+        config.simulate_only = true;
+        engine.update_config(config);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+        engine.set_on_expansion(Box::new(move |info| {
+            sender.lock().unwrap().send(info.clone()).unwrap();
+        }));
+
+        let outcome = apply_plain_expansion(&engine, false, None).await;
+
+        assert_eq!(outcome, ExpansionOutcome::Simulated);
+        let info = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(info.trigger, "::log");
+        assert_eq!(info.expanded_length, " [logged]".len());
+
+        let input = engine.input.lock().unwrap();
+        let simulator = input.simulator().unwrap();
+        assert_eq!(simulator.backspace_count(), 0);
+        assert_eq!(simulator.typed_text(), "");
+        assert!(simulator.type_delays().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shutdown_drains_queued_expansions() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        for trigger in ["::a", "::b", "::c"] {
+            create_snippet(&engine, trigger).await;
+        }
+        engine.start().await.unwrap();
+
+        {
+            let workers = engine.workers.lock().unwrap();
+            let sender = &workers.as_ref().unwrap().expansion_sender;
+            for trigger in ["::a", "::b", "::c"] {
+                sender
+                    .try_send(ExpansionEvent {
+                        trigger: trigger.to_string(),
+                        trigger_length: 3,
+                    })
+                    .unwrap();
+            }
+        }
+
+        engine.shutdown().await.unwrap();
+
+        assert!(!engine.is_running());
+        {
+            let input = engine.input.lock().unwrap();
+            let simulator = input.simulator().unwrap();
+            assert_eq!(simulator.typed_text(), "HelloHelloHello");
+            assert_eq!(simulator.backspace_count(), 9);
+        }
+
+        // Nothing left to wait for the second time
+        engine.shutdown().await.unwrap();
+    }
+
+    async fn create_snippet(engine: &TextExpansionEngine, trigger: &str) {
+        engine
+            .service
+            .create_snippet(crate::app::dto::CreateSnippetRequest::new(trigger, "Hello"))
+            .await
+            .unwrap();
+    }
+
+    async fn handle_event(engine: &TextExpansionEngine, trigger: &str) -> Result<()> {
+        TextExpansionEngine::handle_expansion_event(
+            ExpansionEvent {
+                trigger: trigger.to_string(),
+                trigger_length: trigger.chars().count(),
+            },
+            &engine.service,
+            &engine.trigger_cache,
+            &engine.input,
+            &engine.config,
+            &engine.on_expansion,
+            &engine.on_confirm,
+        )
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unknown_triggers_are_not_expanded() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        create_snippet(&engine, "::hello").await;
+
+        handle_event(&engine, "::hel").await.unwrap();
+        assert_eq!(typed_text(&engine), "");
+        assert_eq!(engine.metrics.snapshot().expansion_failures_total, 0);
+
+        handle_event(&engine, "::hello").await.unwrap();
+        assert_eq!(typed_text(&engine), "Hello");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_apps_shut_out_by_the_allowlist_get_no_expansion() {
+        let (mut engine, _temp_dir) = create_test_engine().await;
+        create_snippet(&engine, "::hello").await;
+
+        // CLI builds can't tell which app has focus, so only a denylist lets
+        // expansion through
+        engine.config.app_denylist = vec!["1Password".to_string()];
+        handle_event(&engine, "::hello").await.unwrap();
+        assert_eq!(typed_text(&engine), "Hello");
+
+        engine.config.app_allowlist = vec!["Slack".to_string()];
+        handle_event(&engine, "::hello").await.unwrap();
+        assert_eq!(typed_text(&engine), "Hello");
+        assert_eq!(engine.metrics.snapshot().expansion_failures_total, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_new_snippets_reach_the_trigger_cache() {
+        let (engine, _temp_dir) = create_test_engine().await;
+        handle_event(&engine, "::late").await.unwrap();
+
+        create_snippet(&engine, "::late").await;
+        handle_event(&engine, "::late").await.unwrap();
+
+        assert_eq!(typed_text(&engine), "Hello");
+    }
 }
@@ -0,0 +1,323 @@
+use crate::domain::{MatchOptions, SnippetQuery, SnippetRepository, TriggerTrie};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// The triggers and aliases of every active snippet, kept in memory so the
+/// engine can drop typed text that isn't a trigger without asking the
+/// database.
+///
+/// The set is reloaded on the first lookup after `changes` moves on (the
+/// service bumps it whenever snippets change) or after `invalidate`.
+///
+/// Each load also builds a `TriggerTrie` of the set, which the keyboard
+/// thread follows keystroke by keystroke. It can't wait for a reload, so
+/// `trie` gives nothing while the set is out of date.
+pub struct TriggerCache {
+    repository: Arc<dyn SnippetRepository>,
+    changes: Arc<AtomicU64>,
+    loaded: Mutex<Option<LoadedTriggers>>,
+    /// The trie of `loaded` and its generation
+    trie: std::sync::Mutex<Option<(u64, Arc<TriggerTrie>)>>,
+}
+
+struct LoadedTriggers {
+    /// Value of `changes` when the set was read
+    generation: u64,
+    triggers: HashSet<String>,
+}
+
+impl TriggerCache {
+    pub fn new(repository: Arc<dyn SnippetRepository>, changes: Arc<AtomicU64>) -> Self {
+        Self {
+            repository,
+            changes,
+            loaded: Mutex::new(None),
+            trie: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// The loaded triggers as a trie, unless snippets changed since they
+    /// were read or none have been yet. Never touches the database.
+    pub fn trie(&self) -> Option<Arc<TriggerTrie>> {
+        let generation = self.changes.load(Ordering::Acquire);
+        match self.trie.lock().unwrap().as_ref() {
+            Some((loaded, trie)) if *loaded == generation => Some(Arc::clone(trie)),
+            _ => None,
+        }
+    }
+
+    /// Whether `trigger` is the trigger or an alias of an active snippet,
+    /// compared as expansion compares triggers (`MatchOptions::default()`)
+    pub async fn contains(&self, trigger: &str) -> Result<bool> {
+        let mut loaded = self.loaded.lock().await;
+        let generation = self.changes.load(Ordering::Acquire);
+
+        if loaded.as_ref().map(|l| l.generation) != Some(generation) {
+            *loaded = Some(self.load(generation).await?);
+        }
+
+        Ok(loaded.as_ref().is_some_and(|l| {
+            l.triggers
+                .contains(MatchOptions::default().key(trigger).as_ref())
+        }))
+    }
+
+    /// Read the triggers now, returning how many there are
+    pub async fn reload(&self) -> Result<usize> {
+        let generation = self.changes.load(Ordering::Acquire);
+        let triggers = self.load(generation).await?;
+        let count = triggers.triggers.len();
+        *self.loaded.lock().await = Some(triggers);
+        Ok(count)
+    }
+
+    /// Forget the loaded triggers, e.g. after another process changed the
+    /// database, so the next lookup reads them again
+    pub async fn invalidate(&self) {
+        *self.loaded.lock().await = None;
+        *self.trie.lock().unwrap() = None;
+    }
+
+    async fn load(&self, generation: u64) -> Result<LoadedTriggers> {
+        let snippets = self
+            .repository
+            .find_all(&SnippetQuery::new().with_active_only())
+            .await?;
+
+        let triggers: HashSet<String> = snippets
+            .into_iter()
+            .flat_map(|snippet| std::iter::once(snippet.trigger).chain(snippet.aliases))
+            .map(|trigger| MatchOptions::default().key(&trigger).into_owned())
+            .collect();
+
+        let trie = TriggerTrie::new(&triggers)?;
+        *self.trie.lock().unwrap() = Some((generation, Arc::new(trie)));
+
+        Ok(LoadedTriggers {
+            generation,
+            triggers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Snippet, SnippetVersion};
+    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use crate::test_support::snippet;
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use futures::stream::BoxStream;
+    use std::sync::atomic::AtomicUsize;
+    use uuid::Uuid;
+
+    /// Delegates to SQLite, counting the reads the cache is meant to save
+    struct CountingRepository {
+        inner: SqliteSnippetRepository,
+        reads: AtomicUsize,
+    }
+
+    impl CountingRepository {
+        fn reads(&self) -> usize {
+            self.reads.load(Ordering::SeqCst)
+        }
+
+        fn count_read(&self) {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl SnippetRepository for CountingRepository {
+        async fn save(&self, snippet: &Snippet) -> Result<()> {
+            self.inner.save(snippet).await
+        }
+
+        async fn find_by_id(&self, id: &Uuid) -> Result<Option<Snippet>> {
+            self.count_read();
+            self.inner.find_by_id(id).await
+        }
+
+        async fn find_by_trigger(&self, trigger: &str) -> Result<Option<Snippet>> {
+            self.count_read();
+            self.inner.find_by_trigger(trigger).await
+        }
+
+        async fn find_all(&self, query: &SnippetQuery) -> Result<Vec<Snippet>> {
+            self.count_read();
+            self.inner.find_all(query).await
+        }
+
+        fn stream_all(&self, query: &SnippetQuery) -> BoxStream<'_, Result<Snippet>> {
+            self.count_read();
+            self.inner.stream_all(query)
+        }
+
+        async fn update(&self, snippet: &Snippet) -> Result<()> {
+            self.inner.update(snippet).await
+        }
+
+        async fn delete(&self, id: &Uuid) -> Result<bool> {
+            self.inner.delete(id).await
+        }
+
+        async fn count(&self, query: &SnippetQuery) -> Result<u64> {
+            self.count_read();
+            self.inner.count(query).await
+        }
+
+        async fn count_all(&self) -> Result<u64> {
+            self.count_read();
+            self.inner.count_all().await
+        }
+
+        async fn top_by_usage(&self, limit: u32) -> Result<Vec<Snippet>> {
+            self.count_read();
+            self.inner.top_by_usage(limit).await
+        }
+
+        async fn random_active(&self, limit: u32) -> Result<Vec<Snippet>> {
+            self.count_read();
+            self.inner.random_active(limit).await
+        }
+
+        async fn exists_with_trigger(&self, trigger: &str) -> Result<bool> {
+            self.count_read();
+            self.inner.exists_with_trigger(trigger).await
+        }
+
+        async fn set_active_matching(
+            &self,
+            query: &SnippetQuery,
+            is_active: bool,
+        ) -> Result<Vec<(Uuid, String)>> {
+            self.inner.set_active_matching(query, is_active).await
+        }
+
+        async fn reset_usage(&self, id: Option<Uuid>) -> Result<u64> {
+            self.inner.reset_usage(id).await
+        }
+
+        async fn reorder(&self, ordered_ids: &[Uuid]) -> Result<u64> {
+            self.inner.reorder(ordered_ids).await
+        }
+
+        async fn record_version(
+            &self,
+            version: &SnippetVersion,
+            max_versions: usize,
+        ) -> Result<()> {
+            self.inner.record_version(version, max_versions).await
+        }
+
+        async fn history(&self, id: &Uuid) -> Result<Vec<SnippetVersion>> {
+            self.count_read();
+            self.inner.history(id).await
+        }
+
+        async fn edits_since(&self, since: DateTime<Utc>) -> Result<Vec<SnippetVersion>> {
+            self.count_read();
+            self.inner.edits_since(since).await
+        }
+
+        async fn record_usage(&self, id: &Uuid, used_at: DateTime<Utc>) -> Result<()> {
+            self.inner.record_usage(id, used_at).await
+        }
+
+        async fn usage_count_between(
+            &self,
+            id: &Uuid,
+            from: DateTime<Utc>,
+            to: DateTime<Utc>,
+        ) -> Result<u64> {
+            self.count_read();
+            self.inner.usage_count_between(id, from, to).await
+        }
+
+        async fn first_used(&self, id: &Uuid) -> Result<Option<DateTime<Utc>>> {
+            self.count_read();
+            self.inner.first_used(id).await
+        }
+    }
+
+    async fn counting_cache() -> (TriggerCache, Arc<CountingRepository>, Arc<AtomicU64>) {
+        let connection = DatabaseConnection::new_in_memory().await.unwrap();
+        let repository = Arc::new(CountingRepository {
+            inner: SqliteSnippetRepository::new(connection.pool().clone()),
+            reads: AtomicUsize::new(0),
+        });
+
+        let mut hello = snippet("::hello", "Hello!");
+        hello
+            .set_aliases(vec!["::hi".to_string(), "::caf\u{e9}".to_string()])
+            .unwrap();
+        let mut old = snippet("::old", "Old");
+        old.deactivate();
+        repository.save(&hello).await.unwrap();
+        repository.save(&old).await.unwrap();
+
+        let changes = Arc::new(AtomicU64::new(0));
+        let cache = TriggerCache::new(repository.clone(), Arc::clone(&changes));
+        (cache, repository, changes)
+    }
+
+    #[tokio::test]
+    async fn test_lookups_after_the_first_skip_the_repository() {
+        let (cache, repository, _changes) = counting_cache().await;
+
+        assert!(cache.contains("::hello").await.unwrap());
+        assert!(cache.contains("::hi").await.unwrap());
+        assert!(!cache.contains("::hel").await.unwrap());
+        assert!(!cache.contains("::old").await.unwrap());
+        // Typed in decomposed form
+        assert!(cache.contains("::cafe\u{301}").await.unwrap());
+
+        assert_eq!(repository.reads(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_changes_reload_the_triggers() {
+        let (cache, repository, changes) = counting_cache().await;
+        assert_eq!(cache.reload().await.unwrap(), 3);
+
+        repository.save(&snippet("::new", "New")).await.unwrap();
+        // Not visible until the service reports a change
+        assert!(!cache.contains("::new").await.unwrap());
+
+        changes.fetch_add(1, Ordering::Release);
+        assert!(cache.contains("::new").await.unwrap());
+        assert_eq!(repository.reads(), 2);
+
+        repository.save(&snippet("::newer", "Newer")).await.unwrap();
+        cache.invalidate().await;
+        assert!(cache.contains("::newer").await.unwrap());
+        assert_eq!(repository.reads(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_trie_follows_the_loaded_triggers() {
+        let (cache, repository, changes) = counting_cache().await;
+        assert!(cache.trie().is_none());
+
+        cache.reload().await.unwrap();
+        let trie = cache.trie().unwrap();
+        assert_eq!(trie.find_at_end("say ::hi").unwrap().trigger, "::hi");
+        assert!(trie.find_at_end("::old").is_none());
+
+        // Stale once snippets change, without reading them again
+        repository.save(&snippet("::new", "New")).await.unwrap();
+        changes.fetch_add(1, Ordering::Release);
+        assert!(cache.trie().is_none());
+        assert_eq!(repository.reads(), 1);
+
+        assert!(cache.contains("::new").await.unwrap());
+        assert!(cache.trie().unwrap().find_at_end("::new").is_some());
+
+        cache.invalidate().await;
+        assert!(cache.trie().is_none());
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Where the expansion log goes and how much of it is kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionLogConfig {
+    pub path: PathBuf,
+    /// Size at which the log is rotated
+    pub max_bytes: u64,
+    /// Files kept in total, counting the one being written
+    pub max_files: usize,
+}
+
+impl ExpansionLogConfig {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_bytes: 1024 * 1024,
+            max_files: 3,
+        }
+    }
+
+    /// `~/.typely/expansions.log`
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(crate::infra::get_default_database_path()?.with_file_name("expansions.log"))
+    }
+}
+
+/// Appends one line per expansion attempt, rotating by size:
+/// `expansions.log` is the newest, then `expansions.log.1`, `expansions.log.2`, ...
+pub struct ExpansionLogger {
+    config: ExpansionLogConfig,
+    write_lock: Mutex<()>,
+}
+
+impl ExpansionLogger {
+    pub fn new(config: ExpansionLogConfig) -> Self {
+        Self {
+            config,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Log an expansion attempt. Failures are reported as warnings only, so a
+    /// full disk or unwritable file never gets in the way of expanding.
+    pub fn record(&self, trigger: &str, success: bool) {
+        if let Err(e) = self.write_entry(trigger, success) {
+            log::warn!(
+                "Failed to write expansion log {}: {}",
+                self.config.path.display(),
+                e
+            );
+        }
+    }
+
+    fn write_entry(&self, trigger: &str, success: bool) -> Result<()> {
+        let entry = format!(
+            "{}\t{}\t{}\n",
+            Utc::now().to_rfc3339(),
+            trigger,
+            if success { "ok" } else { "failed" }
+        );
+
+        let _guard = self.write_lock.lock().unwrap();
+
+        if let Some(parent) = self.config.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let current_size = fs::metadata(&self.config.path).map_or(0, |m| m.len());
+        if current_size > 0 && current_size + entry.len() as u64 > self.config.max_bytes {
+            self.rotate()?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)?;
+        file.write_all(entry.as_bytes())?;
+        Ok(())
+    }
+
+    /// Shift every file up one place, dropping the oldest
+    fn rotate(&self) -> Result<()> {
+        let backups = self.config.max_files.saturating_sub(1);
+        if backups == 0 {
+            fs::remove_file(&self.config.path)?;
+            return Ok(());
+        }
+
+        let oldest = self.rotated_path(backups);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+
+        for index in (1..backups).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+
+        fs::rename(&self.config.path, self.rotated_path(1))?;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.config.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn logger(temp_dir: &TempDir, max_bytes: u64, max_files: usize) -> ExpansionLogger {
+        ExpansionLogger::new(ExpansionLogConfig {
+            path: temp_dir.path().join("expansions.log"),
+            max_bytes,
+            max_files,
+        })
+    }
+
+    #[test]
+    fn test_log_rotates_and_keeps_latest_entry_newest() {
+        let temp_dir = TempDir::new().unwrap();
+        let logger = logger(&temp_dir, 200, 3);
+
+        for i in 0..100 {
+            logger.record(&format!("::entry{}", i), i % 10 != 0);
+        }
+
+        let mut files: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["expansions.log", "expansions.log.1", "expansions.log.2"]
+        );
+
+        for file in &files {
+            let size = fs::metadata(temp_dir.path().join(file)).unwrap().len();
+            assert!(size <= 200, "{} is {} bytes", file, size);
+        }
+
+        let newest = fs::read_to_string(logger.path()).unwrap();
+        let last_line = newest.lines().last().unwrap();
+        assert!(last_line.ends_with("\t::entry99\tok"), "{}", last_line);
+
+        let older = fs::read_to_string(temp_dir.path().join("expansions.log.1")).unwrap();
+        assert!(!older.contains("::entry99"));
+    }
+
+    #[test]
+    fn test_single_file_log_is_truncated_on_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let logger = logger(&temp_dir, 100, 1);
+
+        for i in 0..20 {
+            logger.record(&format!("::entry{}", i), true);
+        }
+
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+        let contents = fs::read_to_string(logger.path()).unwrap();
+        assert!(contents.contains("::entry19"));
+        assert!(!contents.contains("::entry0\t"));
+    }
+
+    #[test]
+    fn test_unwritable_log_does_not_panic() {
+        let temp_dir = TempDir::new().unwrap();
+        // The log path is a directory, so every write fails
+        let logger = ExpansionLogger::new(ExpansionLogConfig::new(temp_dir.path().to_path_buf()));
+        logger.record("::hello", true);
+    }
+}
@@ -1,12 +1,18 @@
 // Infrastructure layer - Database, repositories, and engine implementations
 
 pub mod database;
+pub mod diagnostics;
+pub mod encryption;
 pub mod engine;
+pub mod expansion_log;
 pub mod repositories;
 pub mod system;
 
 // Re-export
 pub use database::*;
+pub use diagnostics::*;
+pub use encryption::*;
 pub use engine::*;
+pub use expansion_log::*;
 pub use repositories::*;
 pub use system::*;
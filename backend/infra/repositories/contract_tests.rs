@@ -0,0 +1,769 @@
+//! Behaviour every `SnippetRepository` must share, run against each implementation
+
+use chrono::{DateTime, Duration, Utc};
+use futures::TryStreamExt;
+use uuid::Uuid;
+
+use super::{HashMapSnippetRepository, SqliteSnippetRepository};
+use crate::domain::{
+    DuplicateTrigger, PageCursor, SearchField, Snippet, SnippetQuery, SnippetRepository,
+    SnippetVersion, SortBy, SortOrder, TagMatch,
+};
+use crate::infra::DatabaseConnection;
+use crate::test_support::snippet;
+
+/// Exercise `repo`, which must start out empty
+pub(crate) async fn repo_contract_tests<R: SnippetRepository>(repo: R) {
+    save_and_find(&repo).await;
+    update_and_delete(&repo).await;
+    filters_and_counts(&repo).await;
+    active_window(&repo).await;
+    sorting_and_paging(&repo).await;
+    top_by_usage(&repo).await;
+    random_active(&repo).await;
+    reorder(&repo).await;
+    bulk_writes(&repo).await;
+    versions(&repo).await;
+    edits_since(&repo).await;
+    usage_log(&repo).await;
+}
+
+async fn triggers(repo: &impl SnippetRepository, query: &SnippetQuery) -> Vec<String> {
+    let snippets = repo.find_all(query).await.unwrap();
+    snippets
+        .into_iter()
+        .map(|snippet| snippet.trigger)
+        .collect()
+}
+
+async fn clear(repo: &impl SnippetRepository) {
+    for snippet in repo.find_all(&SnippetQuery::new()).await.unwrap() {
+        repo.delete(&snippet.id).await.unwrap();
+    }
+    assert_eq!(repo.count_all().await.unwrap(), 0);
+}
+
+async fn save_and_find(repo: &impl SnippetRepository) {
+    let mut hello = snippet("::hello", "Hello World");
+    hello.set_aliases(vec!["::hi".to_string()]).unwrap();
+    hello.add_tag("greeting".to_string());
+    hello.set_source(Some("pack".to_string()));
+    hello.set_pinned(true);
+    hello.usage_count = 3;
+    repo.save(&hello).await.unwrap();
+
+    assert_eq!(repo.find_by_id(&hello.id).await.unwrap().unwrap(), hello);
+    assert_eq!(
+        repo.find_by_trigger("::hello").await.unwrap().unwrap(),
+        hello
+    );
+    assert_eq!(
+        repo.find_by_trigger("::hi").await.unwrap().unwrap().id,
+        hello.id
+    );
+    assert!(repo.find_by_trigger("::hel").await.unwrap().is_none());
+    assert!(repo.find_by_id(&Uuid::new_v4()).await.unwrap().is_none());
+
+    assert!(repo.exists_with_trigger("::hello").await.unwrap());
+    assert!(repo.exists_with_trigger("::hi").await.unwrap());
+    assert!(!repo.exists_with_trigger("::bye").await.unwrap());
+
+    // Ids and triggers are unique
+    assert!(repo.save(&hello).await.is_err());
+    let error = repo.save(&snippet("::hello", "Again")).await.unwrap_err();
+    assert!(error.is::<DuplicateTrigger>(), "{}", error);
+    assert_eq!(repo.count_all().await.unwrap(), 1);
+
+    clear(repo).await;
+}
+
+async fn update_and_delete(repo: &impl SnippetRepository) {
+    let mut hello = snippet("::hello", "Hello");
+    let bye = snippet("::bye", "Bye");
+    repo.save(&hello).await.unwrap();
+    repo.save(&bye).await.unwrap();
+
+    hello.update_replacement("Hello again".to_string()).unwrap();
+    hello.update_trigger("::hey".to_string()).unwrap();
+    repo.update(&hello).await.unwrap();
+    let stored = repo.find_by_id(&hello.id).await.unwrap().unwrap();
+    assert_eq!(stored.replacement, "Hello again");
+    assert!(repo.find_by_trigger("::hello").await.unwrap().is_none());
+    assert_eq!(
+        repo.find_by_trigger("::hey").await.unwrap().unwrap().id,
+        hello.id
+    );
+
+    // Taking another snippet's trigger fails and changes nothing
+    hello.update_trigger("::bye".to_string()).unwrap();
+    assert!(repo.update(&hello).await.is_err());
+    assert_eq!(
+        repo.find_by_trigger("::bye").await.unwrap().unwrap().id,
+        bye.id
+    );
+
+    // Updating a snippet that was never saved doesn't add it
+    repo.update(&snippet("::ghost", "Boo")).await.unwrap();
+    assert!(repo.find_by_trigger("::ghost").await.unwrap().is_none());
+
+    assert!(repo.delete(&bye.id).await.unwrap());
+    assert!(!repo.delete(&bye.id).await.unwrap());
+    assert!(repo.find_by_id(&bye.id).await.unwrap().is_none());
+    assert_eq!(repo.count_all().await.unwrap(), 1);
+
+    clear(repo).await;
+}
+
+async fn filters_and_counts(repo: &impl SnippetRepository) {
+    for (trigger, replacement, tags, source, active) in [
+        (
+            "::work",
+            "Work email",
+            &["work", "email"][..],
+            Some("pack"),
+            true,
+        ),
+        (
+            "::home",
+            "Home address",
+            &["personal"][..],
+            Some("pack"),
+            true,
+        ),
+        ("::sig", "Regards, Jane", &["email"][..], None, true),
+        ("::old", "Old text", &[][..], None, false),
+    ] {
+        let mut snippet = snippet(trigger, replacement);
+        for tag in tags {
+            snippet.add_tag(tag.to_string());
+        }
+        snippet.set_source(source.map(str::to_string));
+        if !active {
+            snippet.deactivate();
+        }
+        repo.save(&snippet).await.unwrap();
+    }
+
+    let by_trigger = SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Asc);
+    let cases = [
+        (
+            by_trigger.clone(),
+            vec!["::home", "::old", "::sig", "::work"],
+        ),
+        (
+            by_trigger.clone().with_active_only(),
+            vec!["::home", "::sig", "::work"],
+        ),
+        (by_trigger.clone().with_active(false), vec!["::old"]),
+        (
+            by_trigger.clone().with_source("pack".to_string()),
+            vec!["::home", "::work"],
+        ),
+        // Searches ignore case and look at the replacement too
+        (
+            by_trigger.clone().with_search("EMAIL".to_string()),
+            vec!["::work"],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_search_any(vec!["jane".to_string(), "::old".to_string()]),
+            vec!["::old", "::sig"],
+        ),
+        // ...unless told which fields to look in
+        (
+            by_trigger
+                .clone()
+                .with_search("sig".to_string())
+                .with_search_fields(vec![SearchField::Trigger]),
+            vec!["::sig"],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_search("sig".to_string())
+                .with_search_fields(vec![SearchField::Replacement]),
+            vec![],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_search("address".to_string())
+                .with_search_fields(vec![SearchField::Trigger]),
+            vec![],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_search("Address".to_string())
+                .with_search_fields(vec![SearchField::Replacement]),
+            vec!["::home"],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_search("MAIL".to_string())
+                .with_search_fields(vec![SearchField::Tags]),
+            vec!["::sig", "::work"],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_search_any(vec!["jane".to_string(), "::old".to_string()])
+                .with_search_fields(vec![SearchField::Trigger]),
+            vec!["::old"],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_tags(vec!["work".to_string(), "personal".to_string()]),
+            vec!["::home", "::work"],
+        ),
+        (
+            by_trigger
+                .clone()
+                .with_tags(vec!["work".to_string(), "email".to_string()])
+                .with_tag_match(TagMatch::All),
+            vec!["::work"],
+        ),
+    ];
+
+    for (query, expected) in cases {
+        assert_eq!(triggers(repo, &query).await, expected, "{:?}", query);
+        assert_eq!(
+            repo.count(&query).await.unwrap(),
+            expected.len() as u64,
+            "{:?}",
+            query
+        );
+    }
+    assert_eq!(repo.count_all().await.unwrap(), 4);
+
+    clear(repo).await;
+}
+
+async fn active_window(repo: &impl SnippetRepository) {
+    let noon = DateTime::parse_from_rfc3339("2026-06-01T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let hour = Duration::hours(1);
+    for (trigger, from, until) in [
+        ("::always", None, None),
+        ("::ooo", Some(noon - hour), Some(noon + hour)),
+        ("::expired", None, Some(noon - hour)),
+        ("::upcoming", Some(noon + hour), None),
+    ] {
+        let mut snippet = snippet(trigger, "Text");
+        snippet.set_active_window(from, until).unwrap();
+        repo.save(&snippet).await.unwrap();
+    }
+
+    let ooo = repo.find_by_trigger("::ooo").await.unwrap().unwrap();
+    assert_eq!(ooo.active_from, Some(noon - hour));
+    assert_eq!(ooo.active_until, Some(noon + hour));
+
+    let by_trigger = SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Asc);
+    for (time, expected) in [
+        (noon, vec!["::always", "::ooo"]),
+        // The window's start is included and its end isn't
+        (noon - hour, vec!["::always", "::ooo"]),
+        (noon + hour, vec!["::always", "::upcoming"]),
+        (noon - hour * 2, vec!["::always", "::expired"]),
+    ] {
+        let query = by_trigger.clone().with_active_at(time);
+        assert_eq!(triggers(repo, &query).await, expected, "{}", time);
+        assert_eq!(repo.count(&query).await.unwrap(), expected.len() as u64);
+    }
+
+    clear(repo).await;
+}
+
+async fn sorting_and_paging(repo: &impl SnippetRepository) {
+    for (trigger, usage_count, pinned) in [
+        ("::a", 4, false),
+        ("::b", 9, true),
+        ("::c", 1, false),
+        ("::d", 4, false),
+        ("::e", 0, true),
+    ] {
+        let mut snippet = snippet(trigger, "Text");
+        snippet.usage_count = usage_count;
+        snippet.set_pinned(pinned);
+        repo.save(&snippet).await.unwrap();
+    }
+
+    // Pinned snippets come first whatever the order
+    assert_eq!(
+        triggers(
+            repo,
+            &SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Desc)
+        )
+        .await,
+        vec!["::e", "::b", "::d", "::c", "::a"]
+    );
+    let by_usage = triggers(
+        repo,
+        &SnippetQuery::new().with_sort(SortBy::UsageCount, SortOrder::Asc),
+    )
+    .await;
+    assert_eq!(&by_usage[..2], ["::e", "::b"]);
+    assert_eq!(by_usage[2], "::c");
+
+    let by_trigger = SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Asc);
+    assert_eq!(
+        triggers(repo, &by_trigger.clone().with_pagination(2, 1)).await,
+        vec!["::e", "::a"]
+    );
+
+    // Following cursors visits every snippet once, in order, ties included
+    for sort_by in [SortBy::Trigger, SortBy::UsageCount] {
+        for sort_order in [SortOrder::Asc, SortOrder::Desc] {
+            let query = SnippetQuery::new().with_sort(sort_by, sort_order);
+            let expected = triggers(repo, &query).await;
+
+            let mut seen = Vec::new();
+            let mut page = query.clone().with_limit(2);
+            loop {
+                let snippets = repo.find_all(&page).await.unwrap();
+                let Some(last) = snippets.last() else { break };
+                page = page.after(PageCursor::after_snippet(last, sort_by));
+                seen.extend(snippets.into_iter().map(|snippet| snippet.trigger));
+            }
+            assert_eq!(seen, expected, "{:?} {:?}", sort_by, sort_order);
+
+            let streamed: Vec<Snippet> = repo.stream_all(&query).try_collect().await.unwrap();
+            let streamed: Vec<String> = streamed.into_iter().map(|s| s.trigger).collect();
+            assert_eq!(streamed, expected);
+        }
+    }
+
+    clear(repo).await;
+}
+
+async fn top_by_usage(repo: &impl SnippetRepository) {
+    for (trigger, usage_count, active) in [
+        ("::rare", 1, true),
+        ("::busy", 9, true),
+        ("::retired", 20, false),
+        ("::often", 5, true),
+    ] {
+        let mut snippet = snippet(trigger, "Text");
+        snippet.usage_count = usage_count;
+        if !active {
+            snippet.deactivate();
+        }
+        repo.save(&snippet).await.unwrap();
+    }
+
+    let top: Vec<String> = repo
+        .top_by_usage(2)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|snippet| snippet.trigger)
+        .collect();
+    assert_eq!(top, vec!["::busy", "::often"]);
+    assert_eq!(repo.top_by_usage(10).await.unwrap().len(), 3);
+    assert!(repo.top_by_usage(0).await.unwrap().is_empty());
+
+    clear(repo).await;
+}
+
+async fn random_active(repo: &impl SnippetRepository) {
+    assert!(repo.random_active(3).await.unwrap().is_empty());
+
+    for trigger in ["::one", "::two", "::three"] {
+        repo.save(&snippet(trigger, "Text")).await.unwrap();
+    }
+    let mut retired = snippet("::retired", "Text");
+    retired.deactivate();
+    repo.save(&retired).await.unwrap();
+
+    let picked = repo.random_active(2).await.unwrap();
+    assert_eq!(picked.len(), 2);
+    assert_ne!(picked[0].id, picked[1].id);
+    assert!(picked.iter().all(|snippet| snippet.is_active));
+
+    // Asking for more than there are gives every active snippet once
+    let mut all: Vec<String> = repo
+        .random_active(10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|snippet| snippet.trigger)
+        .collect();
+    all.sort();
+    assert_eq!(all, vec!["::one", "::three", "::two"]);
+
+    clear(repo).await;
+}
+
+async fn reorder(repo: &impl SnippetRepository) {
+    let mut ids = Vec::new();
+    for trigger in ["::a", "::b", "::c", "::d"] {
+        let snippet = snippet(trigger, "Text");
+        ids.push(snippet.id);
+        repo.save(&snippet).await.unwrap();
+    }
+    let by_position = SnippetQuery::new().with_sort(SortBy::Position, SortOrder::Asc);
+
+    // Unknown ids and repeats are skipped
+    let placed = repo
+        .reorder(&[ids[2], Uuid::new_v4(), ids[0], ids[2]])
+        .await
+        .unwrap();
+    assert_eq!(placed, 2);
+    let c = repo.find_by_id(&ids[2]).await.unwrap().unwrap();
+    assert_eq!(c.position, Some(0));
+    assert_eq!(
+        repo.find_by_id(&ids[1]).await.unwrap().unwrap().position,
+        None
+    );
+    assert_eq!(&triggers(repo, &by_position).await[..2], ["::c", "::a"]);
+
+    // Placed snippets left out follow the listed ones, in their old order
+    assert_eq!(repo.reorder(&[ids[3], ids[1]]).await.unwrap(), 2);
+    assert_eq!(
+        triggers(repo, &by_position).await,
+        vec!["::d", "::b", "::c", "::a"]
+    );
+    let a = repo.find_by_id(&ids[0]).await.unwrap().unwrap();
+    assert_eq!(a.position, Some(3));
+
+    // Unplaced snippets come last, and cursors page through them too
+    repo.save(&snippet("::new", "Text")).await.unwrap();
+    let expected = vec!["::d", "::b", "::c", "::a", "::new"];
+    assert_eq!(triggers(repo, &by_position).await, expected);
+    let mut seen = Vec::new();
+    let mut page = by_position.clone().with_limit(2);
+    loop {
+        let snippets = repo.find_all(&page).await.unwrap();
+        let Some(last) = snippets.last() else { break };
+        page = page.after(PageCursor::after_snippet(last, SortBy::Position));
+        seen.extend(snippets.into_iter().map(|snippet| snippet.trigger));
+    }
+    assert_eq!(seen, expected);
+
+    clear(repo).await;
+}
+
+async fn bulk_writes(repo: &impl SnippetRepository) {
+    let mut work = snippet("::work", "Work");
+    work.add_tag("work".to_string());
+    work.usage_count = 5;
+    let mut done = snippet("::done", "Done");
+    done.add_tag("work".to_string());
+    done.deactivate();
+    let mut home = snippet("::home", "Home");
+    home.usage_count = 2;
+    for snippet in [&work, &done, &home] {
+        repo.save(snippet).await.unwrap();
+    }
+
+    // Only snippets that change are reported
+    let work_only = SnippetQuery::new().with_tags(vec!["work".to_string()]);
+    let changed = repo.set_active_matching(&work_only, false).await.unwrap();
+    assert_eq!(changed, vec![(work.id, "::work".to_string())]);
+    assert_eq!(
+        repo.count(&SnippetQuery::new().with_active_only())
+            .await
+            .unwrap(),
+        1
+    );
+
+    // Paging is ignored
+    let mut changed = repo
+        .set_active_matching(&SnippetQuery::new().with_limit(1), true)
+        .await
+        .unwrap();
+    changed.sort();
+    let mut expected = vec![
+        (work.id, "::work".to_string()),
+        (done.id, "::done".to_string()),
+    ];
+    expected.sort();
+    assert_eq!(changed, expected);
+
+    assert_eq!(repo.reset_usage(Some(work.id)).await.unwrap(), 1);
+    assert_eq!(
+        repo.find_by_id(&work.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .usage_count,
+        0
+    );
+    assert_eq!(
+        repo.find_by_id(&home.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .usage_count,
+        2
+    );
+    assert_eq!(repo.reset_usage(Some(Uuid::new_v4())).await.unwrap(), 0);
+    assert_eq!(repo.reset_usage(None).await.unwrap(), 3);
+    assert_eq!(
+        repo.find_by_id(&home.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .usage_count,
+        0
+    );
+
+    clear(repo).await;
+}
+
+async fn versions(repo: &impl SnippetRepository) {
+    let mut hello = snippet("::hello", "v0");
+    repo.save(&hello).await.unwrap();
+    assert!(repo.history(&hello.id).await.unwrap().is_empty());
+
+    for i in 1..=4 {
+        repo.record_version(&SnippetVersion::of(&hello), 3)
+            .await
+            .unwrap();
+        hello.update_replacement(format!("v{}", i)).unwrap();
+    }
+
+    // Newest first, the oldest dropped past the limit
+    let history = repo.history(&hello.id).await.unwrap();
+    let replacements: Vec<&str> = history.iter().map(|v| v.replacement.as_str()).collect();
+    assert_eq!(replacements, vec!["v3", "v2", "v1"]);
+    assert!(repo.history(&Uuid::new_v4()).await.unwrap().is_empty());
+
+    clear(repo).await;
+}
+
+async fn edits_since(repo: &impl SnippetRepository) {
+    let hello = snippet("::hello", "Hello");
+    let bye = snippet("::bye", "Bye");
+    repo.save(&hello).await.unwrap();
+    repo.save(&bye).await.unwrap();
+
+    let noon = DateTime::parse_from_rfc3339("2026-06-02T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    // Recorded out of order, with one right on the boundary
+    for (snippet, replacement, changed_at) in [
+        (&hello, "day before", noon - Duration::days(1)),
+        (&bye, "on the boundary", noon),
+        (&hello, "an hour after", noon + Duration::hours(1)),
+        (&hello, "a second before", noon - Duration::seconds(1)),
+        (&bye, "a minute after", noon + Duration::minutes(1)),
+    ] {
+        let version = SnippetVersion {
+            replacement: replacement.to_string(),
+            changed_at,
+            ..SnippetVersion::of(snippet)
+        };
+        repo.record_version(&version, 10).await.unwrap();
+    }
+
+    // Newest first across snippets, and `since` itself included. Versions
+    // outlive their snippets, so earlier steps' are left out.
+    let ids = [hello.id, bye.id];
+    let since = |time| async move {
+        let versions = repo.edits_since(time).await.unwrap();
+        versions
+            .into_iter()
+            .filter(|version| ids.contains(&version.snippet_id))
+            .map(|version| version.replacement)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        since(noon).await,
+        vec!["an hour after", "a minute after", "on the boundary"]
+    );
+    assert_eq!(
+        since(noon + Duration::milliseconds(1)).await,
+        vec!["an hour after", "a minute after"]
+    );
+    assert_eq!(since(noon - Duration::days(2)).await.len(), 5);
+    assert!(since(noon + Duration::days(1)).await.is_empty());
+
+    let latest = repo.edits_since(noon + Duration::hours(1)).await.unwrap();
+    let latest = latest.iter().find(|v| ids.contains(&v.snippet_id)).unwrap();
+    assert_eq!(latest.snippet_id, hello.id);
+    assert_eq!(latest.changed_at, noon + Duration::hours(1));
+
+    clear(repo).await;
+}
+
+async fn usage_log(repo: &impl SnippetRepository) {
+    let hello = snippet("::hello", "Hello");
+    let bye = snippet("::bye", "Bye");
+    repo.save(&hello).await.unwrap();
+    repo.save(&bye).await.unwrap();
+    assert_eq!(repo.first_used(&hello.id).await.unwrap(), None);
+
+    let day = |date: &str| {
+        DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", date))
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+    let june_1 = day("2026-06-01");
+    let june_2 = day("2026-06-02");
+    let june_3 = day("2026-06-03");
+    // Logged out of order, with one right on a day boundary
+    for used_at in [
+        june_2 + Duration::hours(9),
+        june_1 + Duration::hours(23) + Duration::minutes(59),
+        june_2,
+        june_2 + Duration::hours(17),
+        june_3 + Duration::milliseconds(1),
+    ] {
+        repo.record_usage(&hello.id, used_at).await.unwrap();
+    }
+    repo.record_usage(&bye.id, june_2 + Duration::hours(12))
+        .await
+        .unwrap();
+
+    // Windows include their start and exclude their end
+    let count = |from, to| repo.usage_count_between(&hello.id, from, to);
+    assert_eq!(count(june_1, june_2).await.unwrap(), 1);
+    assert_eq!(count(june_2, june_3).await.unwrap(), 3);
+    assert_eq!(count(june_3, day("2026-06-04")).await.unwrap(), 1);
+    assert_eq!(count(june_1, day("2026-06-04")).await.unwrap(), 5);
+    assert_eq!(count(day("2026-05-01"), june_1).await.unwrap(), 0);
+    assert_eq!(
+        repo.first_used(&hello.id).await.unwrap(),
+        Some(june_1 + Duration::hours(23) + Duration::minutes(59))
+    );
+
+    // Resetting one snippet's usage forgets only its log
+    repo.reset_usage(Some(hello.id)).await.unwrap();
+    assert_eq!(count(june_1, day("2026-06-04")).await.unwrap(), 0);
+    assert_eq!(repo.first_used(&hello.id).await.unwrap(), None);
+    assert_eq!(
+        repo.usage_count_between(&bye.id, june_2, june_3)
+            .await
+            .unwrap(),
+        1
+    );
+
+    clear(repo).await;
+}
+
+/// Snippets covering every filter and sort column, with ties
+fn parity_fixture() -> Vec<Snippet> {
+    (0..24)
+        .map(|i| {
+            let mut snippet = snippet(&format!("::s{:02}", (i * 7) % 24), &format!("Text {}", i));
+            snippet.usage_count = (i % 5) as u64;
+            snippet.set_pinned(i % 6 == 0);
+            snippet.set_source((i % 3 == 0).then(|| "pack".to_string()));
+            for (tag, every) in [("work", 2), ("email", 3)] {
+                if i % every == 0 {
+                    snippet.add_tag(tag.to_string());
+                }
+            }
+            if i % 4 == 0 {
+                snippet.deactivate();
+            }
+            snippet
+        })
+        .collect()
+}
+
+/// Load the parity fixture into empty `other` and a fresh SQLite repository,
+/// then check every filter and sort gives the same snippets in the same order
+async fn assert_matches_sqlite(other: &impl SnippetRepository) {
+    let connection = DatabaseConnection::new_in_memory().await.unwrap();
+    let sqlite = SqliteSnippetRepository::new(connection.pool().clone());
+    for snippet in parity_fixture() {
+        sqlite.save(&snippet).await.unwrap();
+        other.save(&snippet).await.unwrap();
+    }
+
+    let filters = [
+        SnippetQuery::new(),
+        SnippetQuery::new().with_active_only(),
+        SnippetQuery::new().with_search("text 1".to_string()),
+        SnippetQuery::new().with_search_any(vec!["s0".to_string(), "Text 2".to_string()]),
+        SnippetQuery::new().with_tags(vec!["work".to_string(), "email".to_string()]),
+        SnippetQuery::new()
+            .with_tags(vec!["work".to_string(), "email".to_string()])
+            .with_tag_match(TagMatch::All)
+            .with_source("pack".to_string()),
+    ];
+    let sorts = [
+        SortBy::Trigger,
+        SortBy::CreatedAt,
+        SortBy::UpdatedAt,
+        SortBy::UsageCount,
+        SortBy::Position,
+    ];
+
+    for filter in filters {
+        assert_eq!(
+            sqlite.count(&filter).await.unwrap(),
+            other.count(&filter).await.unwrap(),
+            "{:?}",
+            filter
+        );
+        for sort_by in sorts {
+            for sort_order in [SortOrder::Asc, SortOrder::Desc] {
+                let sorted = filter.clone().with_sort(sort_by, sort_order);
+                for query in [sorted.clone(), sorted.clone().with_pagination(5, 3)] {
+                    assert_eq!(
+                        triggers(&sqlite, &query).await,
+                        triggers(other, &query).await,
+                        "{:?}",
+                        query
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_hash_map_repository_matches_sqlite() {
+    assert_matches_sqlite(&HashMapSnippetRepository::new()).await;
+}
+
+#[tokio::test]
+async fn test_sqlite_repository_contract() {
+    let connection = DatabaseConnection::new_in_memory().await.unwrap();
+    repo_contract_tests(SqliteSnippetRepository::new(connection.pool().clone())).await;
+}
+
+#[tokio::test]
+async fn test_hash_map_repository_contract() {
+    repo_contract_tests(HashMapSnippetRepository::new()).await;
+}
+
+/// A Postgres database for the tests below, from `TYPELY_TEST_POSTGRES_URL`
+/// and emptied first. `None`, skipping the test, when that isn't set.
+#[cfg(feature = "postgres")]
+async fn empty_postgres() -> Option<super::PostgresSnippetRepository> {
+    let Ok(url) = std::env::var("TYPELY_TEST_POSTGRES_URL") else {
+        eprintln!("TYPELY_TEST_POSTGRES_URL isn't set, skipping");
+        return None;
+    };
+    let pool = sqlx::PgPool::connect(&url).await.unwrap();
+    crate::infra::PostgresMigrationRunner::new(pool.clone())
+        .run_migrations()
+        .await
+        .unwrap();
+    sqlx::query("TRUNCATE snippets, snippet_versions, snippet_usage")
+        .execute(&pool)
+        .await
+        .unwrap();
+    Some(super::PostgresSnippetRepository::new(pool))
+}
+
+// One test, as both halves need the database to themselves
+#[cfg(feature = "postgres")]
+#[tokio::test]
+async fn test_postgres_repository() {
+    let Some(postgres) = empty_postgres().await else {
+        return;
+    };
+    repo_contract_tests(postgres).await;
+
+    let postgres = empty_postgres().await.unwrap();
+    assert_matches_sqlite(&postgres).await;
+}
@@ -0,0 +1,250 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::domain::{Snippet, SnippetQuery, SnippetRepository, SnippetVersion};
+
+/// Wraps a repository so that reads go through and writes don't: each write
+/// is logged and recorded as an operation instead, and reports the result
+/// the real write would have had.
+pub struct DryRunSnippetRepository {
+    inner: Arc<dyn SnippetRepository>,
+    operations: Mutex<Vec<String>>,
+}
+
+impl DryRunSnippetRepository {
+    pub fn new(inner: Arc<dyn SnippetRepository>) -> Self {
+        Self {
+            inner,
+            operations: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The writes skipped so far, in order, e.g. "save '::hello'"
+    pub fn operations(&self) -> Vec<String> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    fn skip(&self, operation: String) {
+        log::info!("Dry run, not performed: {}", operation);
+        self.operations.lock().unwrap().push(operation);
+    }
+}
+
+#[async_trait]
+impl SnippetRepository for DryRunSnippetRepository {
+    async fn save(&self, snippet: &Snippet) -> anyhow::Result<()> {
+        self.skip(format!("save '{}'", snippet.trigger));
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> anyhow::Result<Option<Snippet>> {
+        self.inner.find_by_id(id).await
+    }
+
+    async fn find_by_trigger(&self, trigger: &str) -> anyhow::Result<Option<Snippet>> {
+        self.inner.find_by_trigger(trigger).await
+    }
+
+    async fn find_all(&self, query: &SnippetQuery) -> anyhow::Result<Vec<Snippet>> {
+        self.inner.find_all(query).await
+    }
+
+    fn stream_all(&self, query: &SnippetQuery) -> BoxStream<'_, anyhow::Result<Snippet>> {
+        self.inner.stream_all(query)
+    }
+
+    async fn update(&self, snippet: &Snippet) -> anyhow::Result<()> {
+        self.skip(format!("update '{}'", snippet.trigger));
+        Ok(())
+    }
+
+    async fn delete(&self, id: &Uuid) -> anyhow::Result<bool> {
+        match self.inner.find_by_id(id).await? {
+            Some(snippet) => {
+                self.skip(format!("delete '{}'", snippet.trigger));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn count(&self, query: &SnippetQuery) -> anyhow::Result<u64> {
+        self.inner.count(query).await
+    }
+
+    async fn count_all(&self) -> anyhow::Result<u64> {
+        self.inner.count_all().await
+    }
+
+    async fn top_by_usage(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        self.inner.top_by_usage(limit).await
+    }
+
+    async fn random_active(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        self.inner.random_active(limit).await
+    }
+
+    async fn exists_with_trigger(&self, trigger: &str) -> anyhow::Result<bool> {
+        self.inner.exists_with_trigger(trigger).await
+    }
+
+    async fn set_active_matching(
+        &self,
+        query: &SnippetQuery,
+        is_active: bool,
+    ) -> anyhow::Result<Vec<(Uuid, String)>> {
+        // Same filters, without the paging the real write ignores
+        let query = SnippetQuery {
+            limit: None,
+            offset: None,
+            after: None,
+            ..query.clone()
+        };
+        let changed: Vec<(Uuid, String)> = self
+            .inner
+            .find_all(&query)
+            .await?
+            .into_iter()
+            .filter(|snippet| snippet.is_active != is_active)
+            .map(|snippet| (snippet.id, snippet.trigger))
+            .collect();
+
+        let verb = if is_active { "enable" } else { "disable" };
+        for (_, trigger) in &changed {
+            self.skip(format!("{} '{}'", verb, trigger));
+        }
+        Ok(changed)
+    }
+
+    async fn reset_usage(&self, id: Option<Uuid>) -> anyhow::Result<u64> {
+        let affected = match id {
+            Some(id) => match self.inner.find_by_id(&id).await? {
+                Some(snippet) => {
+                    self.skip(format!("reset usage of '{}'", snippet.trigger));
+                    1
+                }
+                None => 0,
+            },
+            None => {
+                let count = self.inner.count_all().await?;
+                self.skip(format!("reset usage of all {} snippets", count));
+                count
+            }
+        };
+        Ok(affected)
+    }
+
+    async fn reorder(&self, ordered_ids: &[Uuid]) -> anyhow::Result<u64> {
+        let mut found = 0;
+        for (index, id) in ordered_ids.iter().enumerate() {
+            if !ordered_ids[..index].contains(id) && self.inner.find_by_id(id).await?.is_some() {
+                found += 1;
+            }
+        }
+        self.skip(format!("reorder {} snippets", found));
+        Ok(found)
+    }
+
+    async fn record_version(
+        &self,
+        version: &SnippetVersion,
+        _max_versions: usize,
+    ) -> anyhow::Result<()> {
+        self.skip(format!("record a version of '{}'", version.trigger));
+        Ok(())
+    }
+
+    async fn history(&self, id: &Uuid) -> anyhow::Result<Vec<SnippetVersion>> {
+        self.inner.history(id).await
+    }
+
+    async fn edits_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<SnippetVersion>> {
+        self.inner.edits_since(since).await
+    }
+
+    async fn record_usage(&self, id: &Uuid, _used_at: DateTime<Utc>) -> anyhow::Result<()> {
+        self.skip(format!("record a use of snippet {}", id));
+        Ok(())
+    }
+
+    async fn usage_count_between(
+        &self,
+        id: &Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        self.inner.usage_count_between(id, from, to).await
+    }
+
+    async fn first_used(&self, id: &Uuid) -> anyhow::Result<Option<DateTime<Utc>>> {
+        self.inner.first_used(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+    use crate::test_support::snippet;
+
+    async fn dry_run_fixture() -> (DryRunSnippetRepository, Arc<SqliteSnippetRepository>) {
+        let connection = DatabaseConnection::new_in_memory().await.unwrap();
+        let inner = Arc::new(SqliteSnippetRepository::new(connection.pool().clone()));
+        inner.save(&snippet("::hello", "Hello")).await.unwrap();
+        (DryRunSnippetRepository::new(inner.clone()), inner)
+    }
+
+    #[tokio::test]
+    async fn test_writes_leave_the_store_unchanged() {
+        let (repository, inner) = dry_run_fixture().await;
+        let mut hello = inner.find_by_trigger("::hello").await.unwrap().unwrap();
+
+        repository.save(&snippet("::new", "New")).await.unwrap();
+        hello.update_replacement("Changed".to_string()).unwrap();
+        repository.update(&hello).await.unwrap();
+        assert!(repository.delete(&hello.id).await.unwrap());
+        assert!(!repository.delete(&Uuid::new_v4()).await.unwrap());
+
+        assert_eq!(inner.count_all().await.unwrap(), 1);
+        assert!(inner.find_by_trigger("::new").await.unwrap().is_none());
+        let stored = inner.find_by_id(&hello.id).await.unwrap().unwrap();
+        assert_eq!(stored.replacement, "Hello");
+
+        assert_eq!(
+            repository.operations(),
+            vec!["save '::new'", "update '::hello'", "delete '::hello'"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reads_pass_through() {
+        let (repository, _inner) = dry_run_fixture().await;
+
+        let hello = repository
+            .find_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hello.replacement, "Hello");
+        assert!(repository.find_by_id(&hello.id).await.unwrap().is_some());
+        assert!(repository.exists_with_trigger("::hello").await.unwrap());
+        assert_eq!(repository.count(&SnippetQuery::new()).await.unwrap(), 1);
+
+        let changed = repository
+            .set_active_matching(&SnippetQuery::new(), false)
+            .await
+            .unwrap();
+        assert_eq!(changed, vec![(hello.id, "::hello".to_string())]);
+        assert!(
+            repository
+                .find_by_id(&hello.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .is_active
+        );
+    }
+}
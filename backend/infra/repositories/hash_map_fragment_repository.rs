@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use crate::domain::{Fragment, FragmentRepository};
+
+/// Keeps fragments in memory, keyed by name, for use alongside
+/// `HashMapSnippetRepository`
+#[derive(Default)]
+pub struct HashMapFragmentRepository {
+    fragments: RwLock<BTreeMap<String, Fragment>>,
+}
+
+impl HashMapFragmentRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FragmentRepository for HashMapFragmentRepository {
+    async fn save(&self, fragment: &Fragment) -> anyhow::Result<()> {
+        let mut fragments = self.fragments.write().unwrap();
+        match fragments.get_mut(&fragment.name) {
+            // Keeps the stored id and creation time, as the SQL upsert does
+            Some(existing) => {
+                existing.content = fragment.content.clone();
+                existing.updated_at = fragment.updated_at;
+            }
+            None => {
+                fragments.insert(fragment.name.clone(), fragment.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn find_by_name(&self, name: &str) -> anyhow::Result<Option<Fragment>> {
+        Ok(self.fragments.read().unwrap().get(name).cloned())
+    }
+
+    async fn find_all(&self) -> anyhow::Result<Vec<Fragment>> {
+        Ok(self.fragments.read().unwrap().values().cloned().collect())
+    }
+
+    async fn delete(&self, name: &str) -> anyhow::Result<bool> {
+        Ok(self.fragments.write().unwrap().remove(name).is_some())
+    }
+}
@@ -0,0 +1,399 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::domain::{
+    DuplicateTrigger, PageCursor, SearchField, Snippet, SnippetQuery, SnippetRepository,
+    SnippetVersion, SortBy, SortOrder, TagMatch, UNPLACED_POSITION,
+};
+
+/// Keeps snippets in memory, answering queries the way
+/// `SqliteSnippetRepository` does. Nothing is persisted and no database is
+/// needed, which makes it a quick stand-in for SQLite in tests and GUI work.
+#[derive(Default)]
+pub struct HashMapSnippetRepository {
+    snippets: RwLock<HashMap<Uuid, Snippet>>,
+    /// Oldest first, as they were recorded
+    versions: Mutex<HashMap<Uuid, Vec<SnippetVersion>>>,
+    /// When each snippet was expanded, in the order it was logged
+    usage: Mutex<HashMap<Uuid, Vec<DateTime<Utc>>>>,
+}
+
+type Snippets = HashMap<Uuid, Snippet>;
+
+impl HashMapSnippetRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Snippets> {
+        self.snippets.read().unwrap()
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Snippets> {
+        self.snippets.write().unwrap()
+    }
+
+    /// The snippets `find_all` returns: filtered, sorted, then paged
+    fn select(&self, query: &SnippetQuery) -> Vec<Snippet> {
+        let mut snippets: Vec<Snippet> = self
+            .read()
+            .values()
+            .filter(|snippet| matches_filters(query, snippet))
+            .cloned()
+            .collect();
+
+        // A cursor needs a fixed order, so it falls back to the default sort
+        let sort_by = query
+            .sort_by
+            .or_else(|| query.after.as_ref().map(|_| SortBy::default()));
+        let descending = query.sort_order == Some(SortOrder::Desc);
+
+        match sort_by {
+            Some(sort_by) => {
+                if let Some(cursor) = &query.after {
+                    snippets.retain(|s| is_past_cursor(s, cursor, sort_by, descending));
+                }
+                snippets.sort_by(|a, b| {
+                    let order = sort_key(a, sort_by)
+                        .cmp(&sort_key(b, sort_by))
+                        .then(a.id.cmp(&b.id));
+                    let order = if descending { order.reverse() } else { order };
+                    b.pinned.cmp(&a.pinned).then(order)
+                });
+            }
+            None => snippets.sort_by_key(|snippet| Reverse(snippet.pinned)),
+        }
+
+        snippets
+            .into_iter()
+            .skip(query.offset.unwrap_or(0) as usize)
+            .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+}
+
+/// A sort column's value, compared as SQLite compares it: numbers before text
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Number(u64),
+    Text(String),
+}
+
+fn sort_key(snippet: &Snippet, sort_by: SortBy) -> SortKey {
+    match sort_by {
+        SortBy::UsageCount => SortKey::Number(snippet.usage_count),
+        SortBy::Position => SortKey::Number(snippet.position.unwrap_or(UNPLACED_POSITION).into()),
+        _ => SortKey::Text(PageCursor::after_snippet(snippet, sort_by).sort_key),
+    }
+}
+
+fn is_past_cursor(
+    snippet: &Snippet,
+    cursor: &PageCursor,
+    sort_by: SortBy,
+    descending: bool,
+) -> bool {
+    let cursor_key = match sort_by {
+        SortBy::UsageCount | SortBy::Position => cursor
+            .sort_key
+            .parse()
+            .map_or_else(|_| SortKey::Text(cursor.sort_key.clone()), SortKey::Number),
+        _ => SortKey::Text(cursor.sort_key.clone()),
+    };
+    let past = if descending {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    };
+
+    // Pinned snippets come first, so past a pinned one are the rest of the
+    // pinned ones and then every unpinned one
+    (cursor.pinned && !snippet.pinned)
+        || (snippet.pinned == cursor.pinned
+            && (sort_key(snippet, sort_by), snippet.id).cmp(&(cursor_key, cursor.id)) == past)
+}
+
+/// The filters of `query`, matching text like SQL `LIKE` (ignoring ASCII case)
+fn matches_filters(query: &SnippetQuery, snippet: &Snippet) -> bool {
+    let contains = |term: &str| {
+        let term = term.to_ascii_lowercase();
+        let has = |text: &str| text.to_ascii_lowercase().contains(&term);
+        query.search_fields.iter().any(|field| match field {
+            SearchField::Trigger => has(&snippet.trigger),
+            SearchField::Replacement => has(&snippet.replacement),
+            SearchField::Tags => snippet.tags.iter().any(|tag| has(tag)),
+        })
+    };
+    let has_tag = |tag: &String| snippet.tags.contains(tag);
+
+    query
+        .is_active
+        .is_none_or(|is_active| snippet.is_active == is_active)
+        && query
+            .active_at
+            .is_none_or(|time| snippet.in_active_window(time))
+        && query
+            .source
+            .as_ref()
+            .is_none_or(|source| snippet.source.as_ref() == Some(source))
+        && query.search.as_deref().is_none_or(contains)
+        && (query.search_any.is_empty() || query.search_any.iter().any(|term| contains(term)))
+        && (query.tags.is_empty()
+            || match query.tag_match {
+                TagMatch::Any => query.tags.iter().any(has_tag),
+                TagMatch::All => query.tags.iter().all(has_tag),
+            })
+}
+
+fn ensure_trigger_free(snippets: &Snippets, snippet: &Snippet) -> anyhow::Result<()> {
+    let taken = snippets
+        .values()
+        .any(|other| other.id != snippet.id && other.trigger == snippet.trigger);
+    if taken {
+        return Err(DuplicateTrigger(snippet.trigger.clone()).into());
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SnippetRepository for HashMapSnippetRepository {
+    async fn save(&self, snippet: &Snippet) -> anyhow::Result<()> {
+        let mut snippets = self.write();
+        if snippets.contains_key(&snippet.id) {
+            return Err(anyhow::anyhow!("Snippet {} already exists", snippet.id));
+        }
+        ensure_trigger_free(&snippets, snippet)?;
+
+        snippets.insert(snippet.id, snippet.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> anyhow::Result<Option<Snippet>> {
+        Ok(self.read().get(id).cloned())
+    }
+
+    async fn find_by_trigger(&self, trigger: &str) -> anyhow::Result<Option<Snippet>> {
+        let snippets = self.read();
+        let by_trigger = snippets.values().find(|s| s.trigger == trigger);
+        let by_alias = || {
+            snippets
+                .values()
+                .find(|s| s.aliases.iter().any(|alias| alias == trigger))
+        };
+
+        Ok(by_trigger.or_else(by_alias).cloned())
+    }
+
+    async fn find_all(&self, query: &SnippetQuery) -> anyhow::Result<Vec<Snippet>> {
+        Ok(self.select(query))
+    }
+
+    fn stream_all(&self, query: &SnippetQuery) -> BoxStream<'_, anyhow::Result<Snippet>> {
+        Box::pin(futures::stream::iter(
+            self.select(query).into_iter().map(Ok),
+        ))
+    }
+
+    async fn update(&self, snippet: &Snippet) -> anyhow::Result<()> {
+        let mut snippets = self.write();
+        let Some(created_at) = snippets.get(&snippet.id).map(|s| s.created_at) else {
+            return Ok(());
+        };
+        ensure_trigger_free(&snippets, snippet)?;
+
+        // `created_at` is never rewritten, as with the SQL UPDATE
+        let mut updated = snippet.clone();
+        updated.created_at = created_at;
+        snippets.insert(snippet.id, updated);
+        Ok(())
+    }
+
+    async fn delete(&self, id: &Uuid) -> anyhow::Result<bool> {
+        Ok(self.write().remove(id).is_some())
+    }
+
+    async fn count(&self, query: &SnippetQuery) -> anyhow::Result<u64> {
+        let count = self
+            .read()
+            .values()
+            .filter(|snippet| matches_filters(query, snippet))
+            .count();
+        Ok(count as u64)
+    }
+
+    async fn count_all(&self) -> anyhow::Result<u64> {
+        Ok(self.read().len() as u64)
+    }
+
+    async fn top_by_usage(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        let mut snippets: Vec<Snippet> = self
+            .read()
+            .values()
+            .filter(|snippet| snippet.is_active)
+            .cloned()
+            .collect();
+        snippets.sort_by_key(|snippet| Reverse(snippet.usage_count));
+        snippets.truncate(limit as usize);
+        Ok(snippets)
+    }
+
+    async fn random_active(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        let mut snippets: Vec<Snippet> = self
+            .read()
+            .values()
+            .filter(|snippet| snippet.is_active)
+            .cloned()
+            .collect();
+        // Random v4 ids make a shuffle without pulling in a random number crate
+        snippets.sort_by_cached_key(|_| Uuid::new_v4());
+        snippets.truncate(limit as usize);
+        Ok(snippets)
+    }
+
+    async fn exists_with_trigger(&self, trigger: &str) -> anyhow::Result<bool> {
+        Ok(self.read().values().any(|snippet| {
+            snippet.trigger == trigger || snippet.aliases.iter().any(|alias| alias == trigger)
+        }))
+    }
+
+    async fn set_active_matching(
+        &self,
+        query: &SnippetQuery,
+        is_active: bool,
+    ) -> anyhow::Result<Vec<(Uuid, String)>> {
+        let now = Utc::now();
+        let mut changed = Vec::new();
+
+        for snippet in self.write().values_mut() {
+            if snippet.is_active != is_active && matches_filters(query, snippet) {
+                snippet.is_active = is_active;
+                snippet.updated_at = now;
+                changed.push((snippet.id, snippet.trigger.clone()));
+            }
+        }
+
+        Ok(changed)
+    }
+
+    async fn reset_usage(&self, id: Option<Uuid>) -> anyhow::Result<u64> {
+        let mut snippets = self.write();
+        let mut usage = self.usage.lock().unwrap();
+        let affected = match id {
+            Some(id) => match snippets.get_mut(&id) {
+                Some(snippet) => {
+                    snippet.usage_count = 0;
+                    usage.remove(&id);
+                    1
+                }
+                None => 0,
+            },
+            None => {
+                for snippet in snippets.values_mut() {
+                    snippet.usage_count = 0;
+                }
+                usage.clear();
+                snippets.len() as u64
+            }
+        };
+
+        Ok(affected)
+    }
+
+    async fn reorder(&self, ordered_ids: &[Uuid]) -> anyhow::Result<u64> {
+        let mut snippets = self.write();
+        let mut placed: Vec<(u32, Uuid)> = snippets
+            .values()
+            .filter_map(|snippet| snippet.position.map(|position| (position, snippet.id)))
+            .collect();
+        placed.sort();
+
+        let mut listed = HashSet::new();
+        let mut order: Vec<Uuid> = ordered_ids
+            .iter()
+            .filter(|id| snippets.contains_key(*id) && listed.insert(**id))
+            .copied()
+            .collect();
+        let found = order.len() as u64;
+        order.extend(
+            placed
+                .into_iter()
+                .map(|(_, id)| id)
+                .filter(|id| !listed.contains(id)),
+        );
+
+        for (position, id) in order.iter().enumerate() {
+            if let Some(snippet) = snippets.get_mut(id) {
+                snippet.position = Some(position as u32);
+            }
+        }
+
+        Ok(found)
+    }
+
+    async fn record_version(
+        &self,
+        version: &SnippetVersion,
+        max_versions: usize,
+    ) -> anyhow::Result<()> {
+        let mut all_versions = self.versions.lock().unwrap();
+        let versions = all_versions.entry(version.snippet_id).or_default();
+        versions.push(version.clone());
+
+        // Drop the oldest versions beyond the retention limit
+        let excess = versions.len().saturating_sub(max_versions);
+        versions.drain(..excess);
+        Ok(())
+    }
+
+    async fn history(&self, id: &Uuid) -> anyhow::Result<Vec<SnippetVersion>> {
+        let all_versions = self.versions.lock().unwrap();
+        let versions = all_versions.get(id).map(Vec::as_slice).unwrap_or_default();
+        Ok(versions.iter().rev().cloned().collect())
+    }
+
+    async fn edits_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<SnippetVersion>> {
+        let all_versions = self.versions.lock().unwrap();
+        let mut versions: Vec<SnippetVersion> = all_versions
+            .values()
+            .flat_map(|versions| versions.iter().rev())
+            .filter(|version| version.changed_at >= since)
+            .cloned()
+            .collect();
+        versions.sort_by_key(|version| Reverse(version.changed_at));
+        Ok(versions)
+    }
+
+    async fn record_usage(&self, id: &Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()> {
+        self.usage
+            .lock()
+            .unwrap()
+            .entry(*id)
+            .or_default()
+            .push(used_at);
+        Ok(())
+    }
+
+    async fn usage_count_between(
+        &self,
+        id: &Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let usage = self.usage.lock().unwrap();
+        let times = usage.get(id).map(Vec::as_slice).unwrap_or_default();
+        Ok(times
+            .iter()
+            .filter(|time| (from..to).contains(*time))
+            .count() as u64)
+    }
+
+    async fn first_used(&self, id: &Uuid) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let usage = self.usage.lock().unwrap();
+        Ok(usage.get(id).and_then(|times| times.iter().min().copied()))
+    }
+}
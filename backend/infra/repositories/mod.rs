@@ -1,3 +1,18 @@
+pub mod dry_run_snippet_repository;
+pub mod hash_map_fragment_repository;
+pub mod hash_map_snippet_repository;
+#[cfg(feature = "postgres")]
+pub mod postgres_snippet_repository;
+pub mod sqlite_fragment_repository;
 pub mod sqlite_snippet_repository;
 
+#[cfg(test)]
+mod contract_tests;
+
+pub use dry_run_snippet_repository::*;
+pub use hash_map_fragment_repository::*;
+pub use hash_map_snippet_repository::*;
+#[cfg(feature = "postgres")]
+pub use postgres_snippet_repository::*;
+pub use sqlite_fragment_repository::*;
 pub use sqlite_snippet_repository::*;
@@ -0,0 +1,700 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use sqlx::{Pool, Postgres, Row};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::sqlite_snippet_repository::{duplicate_trigger_error, sortable_time};
+use crate::domain::{
+    SearchField, Snippet, SnippetQuery, SnippetRepository, SnippetVersion, SortBy, SortOrder,
+    TagMatch,
+};
+
+const SNIPPET_COLUMNS: &str = r#"
+    id, trigger, replacement, created_at, updated_at,
+    is_active, usage_count, tags, content_type, confirm_before_expand,
+    keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+    active_from, active_until, case_variants, position
+"#;
+
+/// Stores snippets in Postgres, e.g. for a library shared by a team. Queries
+/// mirror `SqliteSnippetRepository`'s, with `$n` placeholders; every dynamic
+/// bind value is text, cast where the column isn't.
+pub struct PostgresSnippetRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PostgresSnippetRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+}
+
+/// Encryption at rest is only implemented for SQLite, so an `encrypted`
+/// snippet is refused rather than stored in the clear
+fn ensure_not_encrypted(snippet: &Snippet) -> anyhow::Result<()> {
+    if snippet.encrypted {
+        return Err(anyhow::anyhow!(
+            "Snippet '{}' is marked encrypted, which Postgres storage doesn't support",
+            snippet.trigger
+        ));
+    }
+    Ok(())
+}
+
+/// Add `value` to `bind_values`, returning its placeholder
+fn param(bind_values: &mut Vec<String>, value: String) -> String {
+    bind_values.push(value);
+    format!("${}", bind_values.len())
+}
+
+/// `term` appears in one of the query's search fields. ILIKE, as SQLite's
+/// LIKE ignores case.
+fn search_condition(query: &SnippetQuery, term: &str, bind_values: &mut Vec<String>) -> String {
+    let pattern = param(bind_values, format!("%{}%", term));
+    let conditions: Vec<String> = query
+        .search_fields
+        .iter()
+        .map(|field| match field {
+            SearchField::Trigger => format!("trigger ILIKE {}", pattern),
+            SearchField::Replacement => format!("replacement ILIKE {}", pattern),
+            SearchField::Tags => format!(
+                "EXISTS (SELECT 1 FROM jsonb_array_elements_text(tags::jsonb) AS tag WHERE tag ILIKE {})",
+                pattern
+            ),
+        })
+        .collect();
+    conditions.join(" OR ")
+}
+
+#[async_trait]
+impl SnippetRepository for PostgresSnippetRepository {
+    async fn save(&self, snippet: &Snippet) -> anyhow::Result<()> {
+        ensure_not_encrypted(snippet)?;
+        let tags_json = serde_json::to_string(&snippet.tags)?;
+        let aliases_json = serde_json::to_string(&snippet.aliases)?;
+
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO snippets ({})
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+                    $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+            "#,
+            SNIPPET_COLUMNS
+        ))
+        .bind(snippet.id.to_string())
+        .bind(snippet.trigger.as_str())
+        .bind(snippet.replacement.as_str())
+        .bind(snippet.created_at.to_rfc3339())
+        .bind(snippet.updated_at.to_rfc3339())
+        .bind(snippet.is_active)
+        .bind(snippet.usage_count as i64)
+        .bind(tags_json)
+        .bind(snippet.content_type.as_str())
+        .bind(snippet.confirm_before_expand)
+        .bind(snippet.keep_trigger)
+        .bind(aliases_json)
+        .bind(snippet.type_delay_ms.map(|ms| ms as i64))
+        .bind(snippet.source.as_deref())
+        .bind(snippet.pinned)
+        .bind(snippet.hotkey.as_deref())
+        .bind(snippet.active_from.as_ref().map(sortable_time))
+        .bind(snippet.active_until.as_ref().map(sortable_time))
+        .bind(snippet.case_variants)
+        .bind(snippet.position.map(i64::from))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| duplicate_trigger_error(e.into(), &snippet.trigger))?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> anyhow::Result<Option<Snippet>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM snippets WHERE id = $1",
+            SNIPPET_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| self.row_to_snippet(row)).transpose()
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn find_by_trigger(&self, trigger: &str) -> anyhow::Result<Option<Snippet>> {
+        let row = sqlx::query(&format!(
+            r#"
+            SELECT {} FROM snippets
+            WHERE trigger = $1 OR aliases::jsonb @> jsonb_build_array($1::text)
+            LIMIT 1
+            "#,
+            SNIPPET_COLUMNS
+        ))
+        .bind(trigger)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| self.row_to_snippet(row)).transpose()
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn find_all(&self, query: &SnippetQuery) -> anyhow::Result<Vec<Snippet>> {
+        let (sql, bind_values) = Self::build_select(query);
+
+        let mut query_builder = sqlx::query(&sql);
+        for value in bind_values {
+            query_builder = query_builder.bind(value);
+        }
+
+        query_builder
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| self.row_to_snippet(row))
+            .collect()
+    }
+
+    fn stream_all(&self, query: &SnippetQuery) -> BoxStream<'_, anyhow::Result<Snippet>> {
+        let (sql, bind_values) = Self::build_select(query);
+
+        Box::pin(async_stream::try_stream! {
+            let mut query_builder = sqlx::query(&sql);
+            for value in bind_values {
+                query_builder = query_builder.bind(value);
+            }
+
+            let mut rows = query_builder.fetch(&self.pool);
+            while let Some(row) = rows.try_next().await? {
+                yield self.row_to_snippet(row)?;
+            }
+        })
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(id = %snippet.id))]
+    async fn update(&self, snippet: &Snippet) -> anyhow::Result<()> {
+        ensure_not_encrypted(snippet)?;
+        let tags_json = serde_json::to_string(&snippet.tags)?;
+        let aliases_json = serde_json::to_string(&snippet.aliases)?;
+
+        sqlx::query(
+            r#"
+            UPDATE snippets SET
+                trigger = $1, replacement = $2, updated_at = $3,
+                is_active = $4, usage_count = $5, tags = $6, content_type = $7,
+                confirm_before_expand = $8, keep_trigger = $9, aliases = $10,
+                type_delay_ms = $11, source = $12, pinned = $13, hotkey = $14,
+                active_from = $15, active_until = $16, case_variants = $17,
+                position = $18
+            WHERE id = $19
+            "#,
+        )
+        .bind(snippet.trigger.as_str())
+        .bind(snippet.replacement.as_str())
+        .bind(snippet.updated_at.to_rfc3339())
+        .bind(snippet.is_active)
+        .bind(snippet.usage_count as i64)
+        .bind(tags_json)
+        .bind(snippet.content_type.as_str())
+        .bind(snippet.confirm_before_expand)
+        .bind(snippet.keep_trigger)
+        .bind(aliases_json)
+        .bind(snippet.type_delay_ms.map(|ms| ms as i64))
+        .bind(snippet.source.as_deref())
+        .bind(snippet.pinned)
+        .bind(snippet.hotkey.as_deref())
+        .bind(snippet.active_from.as_ref().map(sortable_time))
+        .bind(snippet.active_until.as_ref().map(sortable_time))
+        .bind(snippet.case_variants)
+        .bind(snippet.position.map(i64::from))
+        .bind(snippet.id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| duplicate_trigger_error(e.into(), &snippet.trigger))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM snippets WHERE id = $1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn count(&self, query: &SnippetQuery) -> anyhow::Result<u64> {
+        let mut sql = String::from("SELECT COUNT(*) FROM snippets WHERE 1=1");
+        let mut bind_values: Vec<String> = Vec::new();
+        Self::push_filters(query, &mut sql, &mut bind_values);
+
+        let mut query_builder = sqlx::query_scalar::<_, i64>(&sql);
+        for value in bind_values {
+            query_builder = query_builder.bind(value);
+        }
+
+        let count = query_builder.fetch_one(&self.pool).await?;
+        Ok(count as u64)
+    }
+
+    async fn count_all(&self) -> anyhow::Result<u64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM snippets")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count as u64)
+    }
+
+    async fn top_by_usage(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {} FROM snippets
+            WHERE is_active
+            ORDER BY usage_count DESC
+            LIMIT $1
+            "#,
+            SNIPPET_COLUMNS
+        ))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_snippet(row))
+            .collect()
+    }
+
+    async fn random_active(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {} FROM snippets
+            WHERE is_active
+            ORDER BY random()
+            LIMIT $1
+            "#,
+            SNIPPET_COLUMNS
+        ))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_snippet(row))
+            .collect()
+    }
+
+    async fn exists_with_trigger(&self, trigger: &str) -> anyhow::Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM snippets
+                WHERE trigger = $1 OR aliases::jsonb @> jsonb_build_array($1::text)
+            )
+            "#,
+        )
+        .bind(trigger)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    async fn set_active_matching(
+        &self,
+        query: &SnippetQuery,
+        is_active: bool,
+    ) -> anyhow::Result<Vec<(Uuid, String)>> {
+        // Only touch snippets that actually change, so they're the ones returned
+        let mut bind_values = vec![is_active.to_string(), Utc::now().to_rfc3339()];
+        let mut sql = String::from(
+            "UPDATE snippets SET is_active = $1::BOOLEAN, updated_at = $2 \
+             WHERE is_active != $1::BOOLEAN",
+        );
+        Self::push_filters(query, &mut sql, &mut bind_values);
+        sql.push_str(" RETURNING id, trigger");
+
+        let mut query_builder = sqlx::query(&sql);
+        for value in bind_values {
+            query_builder = query_builder.bind(value);
+        }
+
+        query_builder
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| {
+                let id_str: String = row.get("id");
+                Ok((Uuid::parse_str(&id_str)?, row.get("trigger")))
+            })
+            .collect()
+    }
+
+    async fn reset_usage(&self, id: Option<Uuid>) -> anyhow::Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let result = match id {
+            Some(id) => {
+                sqlx::query("DELETE FROM snippet_usage WHERE snippet_id = $1")
+                    .bind(id.to_string())
+                    .execute(&mut *transaction)
+                    .await?;
+                sqlx::query("UPDATE snippets SET usage_count = 0 WHERE id = $1")
+                    .bind(id.to_string())
+                    .execute(&mut *transaction)
+                    .await?
+            }
+            None => {
+                sqlx::query("DELETE FROM snippet_usage")
+                    .execute(&mut *transaction)
+                    .await?;
+                sqlx::query("UPDATE snippets SET usage_count = 0")
+                    .execute(&mut *transaction)
+                    .await?
+            }
+        };
+        transaction.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn reorder(&self, ordered_ids: &[Uuid]) -> anyhow::Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let placed: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM snippets WHERE position IS NOT NULL ORDER BY position, id",
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut listed = HashSet::new();
+        let mut position: i64 = 0;
+        for id in ordered_ids.iter().map(Uuid::to_string) {
+            if !listed.insert(id.clone()) {
+                continue;
+            }
+            let result = sqlx::query("UPDATE snippets SET position = $1 WHERE id = $2")
+                .bind(position)
+                .bind(&id)
+                .execute(&mut *transaction)
+                .await?;
+            position += result.rows_affected() as i64;
+        }
+        let found = position as u64;
+
+        for id in placed.iter().filter(|id| !listed.contains(*id)) {
+            sqlx::query("UPDATE snippets SET position = $1 WHERE id = $2")
+                .bind(position)
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+            position += 1;
+        }
+        transaction.commit().await?;
+
+        Ok(found)
+    }
+
+    async fn record_version(
+        &self,
+        version: &SnippetVersion,
+        max_versions: usize,
+    ) -> anyhow::Result<()> {
+        let snippet_id = version.snippet_id.to_string();
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO snippet_versions (snippet_id, trigger, replacement, changed_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(&snippet_id)
+        .bind(&version.trigger)
+        .bind(&version.replacement)
+        .bind(sortable_time(&version.changed_at))
+        .execute(&mut *transaction)
+        .await?;
+
+        // Drop the oldest versions beyond the retention limit
+        sqlx::query(
+            r#"
+            DELETE FROM snippet_versions
+            WHERE snippet_id = $1 AND id NOT IN (
+                SELECT id FROM snippet_versions
+                WHERE snippet_id = $1
+                ORDER BY id DESC
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(&snippet_id)
+        .bind(max_versions as i64)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn history(&self, id: &Uuid) -> anyhow::Result<Vec<SnippetVersion>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT snippet_id, trigger, replacement, changed_at
+            FROM snippet_versions
+            WHERE snippet_id = $1
+            ORDER BY id DESC
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_version).collect()
+    }
+
+    async fn edits_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<SnippetVersion>> {
+        // The column predates `COLLATE "C"` on time columns
+        let rows = sqlx::query(
+            r#"
+            SELECT snippet_id, trigger, replacement, changed_at
+            FROM snippet_versions
+            WHERE changed_at COLLATE "C" >= $1
+            ORDER BY changed_at COLLATE "C" DESC, id DESC
+            "#,
+        )
+        .bind(sortable_time(&since))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_version).collect()
+    }
+
+    async fn record_usage(&self, id: &Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO snippet_usage (snippet_id, used_at) VALUES ($1, $2)")
+            .bind(id.to_string())
+            .bind(sortable_time(&used_at))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn usage_count_between(
+        &self,
+        id: &Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM snippet_usage
+            WHERE snippet_id = $1 AND used_at >= $2 AND used_at < $3
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(sortable_time(&from))
+        .bind(sortable_time(&to))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as u64)
+    }
+
+    async fn first_used(&self, id: &Uuid) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let first: Option<String> =
+            sqlx::query_scalar("SELECT MIN(used_at) FROM snippet_usage WHERE snippet_id = $1")
+                .bind(id.to_string())
+                .fetch_one(&self.pool)
+                .await?;
+
+        first
+            .map(|time| Ok(DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc)))
+            .transpose()
+    }
+}
+
+impl PostgresSnippetRepository {
+    /// Build the SELECT used by `find_all` and `stream_all`, with its bind values
+    fn build_select(query: &SnippetQuery) -> (String, Vec<String>) {
+        let mut sql = format!("SELECT {} FROM snippets WHERE 1=1", SNIPPET_COLUMNS);
+        let mut bind_values: Vec<String> = Vec::new();
+        Self::push_filters(query, &mut sql, &mut bind_values);
+
+        // A cursor needs a fixed order, so it falls back to the default sort
+        let sort_by = query
+            .sort_by
+            .or_else(|| query.after.as_ref().map(|_| SortBy::default()));
+        let direction = match query.sort_order {
+            Some(SortOrder::Asc) => " ASC",
+            Some(SortOrder::Desc) => " DESC",
+            None => "",
+        };
+
+        if let (Some(sort_by), Some(cursor)) = (sort_by, &query.after) {
+            let comparison = if query.sort_order == Some(SortOrder::Desc) {
+                "<"
+            } else {
+                ">"
+            };
+            let pinned = param(&mut bind_values, cursor.pinned.to_string());
+            let sort_key = param(&mut bind_values, cursor.sort_key.clone());
+            let id = param(&mut bind_values, cursor.id.to_string());
+            // Pinned snippets come first, so past a pinned one are the rest of
+            // the pinned ones and then every unpinned one
+            sql.push_str(&format!(
+                " AND (pinned < {pinned}::BOOLEAN OR (pinned = {pinned}::BOOLEAN \
+                 AND ({column}, id) {comparison} ({sort_key}{cast}, {id})))",
+                column = Self::sort_column(sort_by),
+                cast = Self::sort_cast(sort_by),
+            ));
+        }
+
+        // Add sorting, pinned snippets first and the id breaking ties so
+        // pages never overlap
+        match sort_by {
+            Some(sort_by) => sql.push_str(&format!(
+                " ORDER BY pinned DESC, {}{}, id{}",
+                Self::sort_column(sort_by),
+                direction,
+                direction
+            )),
+            None => sql.push_str(" ORDER BY pinned DESC"),
+        }
+
+        if let Some(limit) = query.limit {
+            let limit = param(&mut bind_values, limit.to_string());
+            sql.push_str(&format!(" LIMIT {}::BIGINT", limit));
+        }
+
+        if let Some(offset) = query.offset {
+            let offset = param(&mut bind_values, offset.to_string());
+            sql.push_str(&format!(" OFFSET {}::BIGINT", offset));
+        }
+
+        (sql, bind_values)
+    }
+
+    fn sort_column(sort_by: SortBy) -> &'static str {
+        match sort_by {
+            SortBy::CreatedAt => "created_at",
+            SortBy::UpdatedAt => "updated_at",
+            SortBy::UsageCount => "usage_count",
+            SortBy::Trigger => "trigger",
+            SortBy::Position => "COALESCE(position, 4294967295)",
+        }
+    }
+
+    /// Cast for a cursor's sort key, which is always text
+    fn sort_cast(sort_by: SortBy) -> &'static str {
+        match sort_by {
+            SortBy::UsageCount | SortBy::Position => "::BIGINT",
+            _ => "",
+        }
+    }
+
+    /// Append the WHERE conditions shared by `find_all`, `count` and
+    /// `set_active_matching`
+    fn push_filters(query: &SnippetQuery, sql: &mut String, bind_values: &mut Vec<String>) {
+        if let Some(is_active) = query.is_active {
+            let is_active = param(bind_values, is_active.to_string());
+            sql.push_str(&format!(" AND is_active = {}::BOOLEAN", is_active));
+        }
+
+        if let Some(ref time) = query.active_at {
+            let time = param(bind_values, sortable_time(time));
+            sql.push_str(&format!(
+                " AND (active_from IS NULL OR active_from <= {time}) \
+                 AND (active_until IS NULL OR active_until > {time})"
+            ));
+        }
+
+        if let Some(ref source) = query.source {
+            let source = param(bind_values, source.clone());
+            sql.push_str(&format!(" AND source = {}", source));
+        }
+
+        if let Some(ref search_term) = query.search {
+            let condition = search_condition(query, search_term, bind_values);
+            sql.push_str(&format!(" AND ({})", condition));
+        }
+
+        if !query.search_any.is_empty() {
+            let conditions: Vec<String> = query
+                .search_any
+                .iter()
+                .map(|term| search_condition(query, term, bind_values))
+                .collect();
+            sql.push_str(&format!(" AND ({})", conditions.join(" OR ")));
+        }
+
+        if !query.tags.is_empty() {
+            let joiner = match query.tag_match {
+                TagMatch::Any => " OR ",
+                TagMatch::All => " AND ",
+            };
+            let conditions: Vec<String> = query
+                .tags
+                .iter()
+                .map(|tag| {
+                    let tag = param(bind_values, tag.clone());
+                    format!("tags::jsonb @> jsonb_build_array({}::text)", tag)
+                })
+                .collect();
+            sql.push_str(&format!(" AND ({})", conditions.join(joiner)));
+        }
+    }
+
+    fn row_to_version(row: sqlx::postgres::PgRow) -> anyhow::Result<SnippetVersion> {
+        let snippet_id_str: String = row.get("snippet_id");
+        let changed_at_str: String = row.get("changed_at");
+        Ok(SnippetVersion {
+            snippet_id: Uuid::parse_str(&snippet_id_str)?,
+            trigger: row.get("trigger"),
+            replacement: row.get("replacement"),
+            changed_at: DateTime::parse_from_rfc3339(&changed_at_str)?.with_timezone(&Utc),
+        })
+    }
+
+    fn row_to_snippet(&self, row: sqlx::postgres::PgRow) -> anyhow::Result<Snippet> {
+        let id_str: String = row.get("id");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+        let tags_json: String = row.get("tags");
+        let aliases_json: String = row.get("aliases");
+        let content_type_str: String = row.get("content_type");
+        let usage_count: i64 = row.get("usage_count");
+        let type_delay_ms: Option<i64> = row.get("type_delay_ms");
+        let position: Option<i64> = row.get("position");
+
+        let parse_time = |column: &str| -> anyhow::Result<Option<DateTime<Utc>>> {
+            let text: Option<String> = row.get(column);
+            text.map(|text| Ok(DateTime::parse_from_rfc3339(&text)?.with_timezone(&Utc)))
+                .transpose()
+        };
+
+        Ok(Snippet {
+            id: Uuid::parse_str(&id_str)?,
+            trigger: row.get("trigger"),
+            replacement: row.get("replacement"),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at_str)?.with_timezone(&Utc),
+            is_active: row.get("is_active"),
+            usage_count: usage_count as u64,
+            tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+            content_type: content_type_str.parse().unwrap_or_default(),
+            confirm_before_expand: row.get("confirm_before_expand"),
+            keep_trigger: row.get("keep_trigger"),
+            aliases: serde_json::from_str(&aliases_json).unwrap_or_default(),
+            type_delay_ms: type_delay_ms.map(|ms| ms as u64),
+            source: row.get("source"),
+            pinned: row.get("pinned"),
+            hotkey: row.get("hotkey"),
+            active_from: parse_time("active_from")?,
+            active_until: parse_time("active_until")?,
+            case_variants: row.get("case_variants"),
+            position: position.map(|position| position as u32),
+            encrypted: false,
+            locked: None,
+        })
+    }
+}
@@ -0,0 +1,138 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Row, Sqlite};
+use uuid::Uuid;
+
+use crate::domain::{Fragment, FragmentRepository};
+
+pub struct SqliteFragmentRepository {
+    pool: Pool<Sqlite>,
+}
+
+impl SqliteFragmentRepository {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    fn row_to_fragment(row: sqlx::sqlite::SqliteRow) -> anyhow::Result<Fragment> {
+        let id_str: String = row.get("id");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        Ok(Fragment {
+            id: Uuid::parse_str(&id_str)?,
+            name: row.get("name"),
+            content: row.get("content"),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)?.with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at_str)?.with_timezone(&Utc),
+        })
+    }
+}
+
+#[async_trait]
+impl FragmentRepository for SqliteFragmentRepository {
+    async fn save(&self, fragment: &Fragment) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO fragments (id, name, content, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO UPDATE SET
+                content = excluded.content,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(fragment.id.to_string())
+        .bind(&fragment.name)
+        .bind(&fragment.content)
+        .bind(fragment.created_at.to_rfc3339())
+        .bind(fragment.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_name(&self, name: &str) -> anyhow::Result<Option<Fragment>> {
+        let row = sqlx::query(
+            "SELECT id, name, content, created_at, updated_at FROM fragments WHERE name = ?",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(Self::row_to_fragment).transpose()
+    }
+
+    async fn find_all(&self) -> anyhow::Result<Vec<Fragment>> {
+        let rows = sqlx::query(
+            "SELECT id, name, content, created_at, updated_at FROM fragments ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_fragment).collect()
+    }
+
+    async fn delete(&self, name: &str) -> anyhow::Result<bool> {
+        let result = sqlx::query("DELETE FROM fragments WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::DatabaseConnection;
+    use tempfile::TempDir;
+
+    async fn create_test_repository() -> (SqliteFragmentRepository, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_connection = DatabaseConnection::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        let repository = SqliteFragmentRepository::new(db_connection.pool().clone());
+        (repository, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_save_find_and_delete() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        assert_eq!(repository.find_by_name("sig").await.unwrap(), None);
+
+        let fragment = Fragment::new("sig".to_string(), "Jane Doe".to_string()).unwrap();
+        repository.save(&fragment).await.unwrap();
+        let found = repository.find_by_name("sig").await.unwrap().unwrap();
+        assert_eq!(found.id, fragment.id);
+        assert_eq!(found.content, "Jane Doe");
+
+        assert!(repository.delete("sig").await.unwrap());
+        assert!(!repository.delete("sig").await.unwrap());
+        assert_eq!(repository.find_by_name("sig").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_save_replaces_content_by_name() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let original = Fragment::new("sig".to_string(), "Jane".to_string()).unwrap();
+        repository.save(&original).await.unwrap();
+        let replacement = Fragment::new("sig".to_string(), "Jane Doe".to_string()).unwrap();
+        repository.save(&replacement).await.unwrap();
+        repository
+            .save(&Fragment::new("addr".to_string(), "1 Main St".to_string()).unwrap())
+            .await
+            .unwrap();
+
+        let all = repository.find_all().await.unwrap();
+        let names: Vec<&str> = all.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["addr", "sig"]);
+
+        let sig = &all[1];
+        assert_eq!(sig.id, original.id);
+        assert_eq!(sig.content, "Jane Doe");
+    }
+}
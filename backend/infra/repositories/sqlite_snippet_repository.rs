@@ -1,44 +1,238 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
 use serde_json;
 use sqlx::{Pool, Row, Sqlite};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
-use crate::domain::{Snippet, SnippetQuery, SnippetRepository, SortBy, SortOrder};
+use crate::domain::{
+    DuplicateTrigger, SearchField, Snippet, SnippetQuery, SnippetRepository, SnippetVersion,
+    SortBy, SortOrder, TagMatch, LOCKED_REPLACEMENT,
+};
+use crate::infra::{EncryptionService, PASSPHRASE_ENV_VAR};
 
 pub struct SqliteSnippetRepository {
     pool: Pool<Sqlite>,
+    retry_policy: RetryPolicy,
+    /// Encrypts the replacements of snippets marked `encrypted`
+    encryption: Option<Arc<EncryptionService>>,
+}
+
+/// How writes are retried when SQLite reports the database as busy or locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(500),
+        }
+    }
 }
 
 impl SqliteSnippetRepository {
     pub fn new(pool: Pool<Sqlite>) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            retry_policy: RetryPolicy::default(),
+            encryption: None,
+        }
+    }
+
+    /// A repository over `pool` that reads and writes encrypted snippets with
+    /// the passphrase in `TYPELY_PASSPHRASE`, if set
+    pub fn from_env(pool: Pool<Sqlite>) -> Self {
+        let repository = Self::new(pool);
+        match EncryptionService::from_env() {
+            Some(encryption) => repository.with_encryption(encryption),
+            None => repository,
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Store the replacements of `encrypted` snippets with `encryption`.
+    /// Without it, reading or writing one is an error.
+    pub fn with_encryption(mut self, encryption: EncryptionService) -> Self {
+        self.encryption = Some(Arc::new(encryption));
+        self
+    }
+
+    fn encryption(&self) -> anyhow::Result<&EncryptionService> {
+        self.encryption.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "This snippet is stored encrypted: set {} to its passphrase",
+                PASSPHRASE_ENV_VAR
+            )
+        })
+    }
+
+    /// `replacement` as it goes in the database
+    fn stored_replacement(&self, replacement: &str, encrypted: bool) -> anyhow::Result<String> {
+        if encrypted {
+            self.encryption()?.encrypt(replacement)
+        } else {
+            Ok(replacement.to_string())
+        }
+    }
+
+    /// `snippet`'s replacement as it goes in the database. A locked one
+    /// still holds what was stored, so it's written back unchanged.
+    fn stored_replacement_of(&self, snippet: &Snippet) -> anyhow::Result<String> {
+        match snippet.locked {
+            Some(_) => Ok(snippet.replacement.clone()),
+            None => self.stored_replacement(&snippet.replacement, snippet.encrypted),
+        }
+    }
+
+    /// `stored` as read back from the database
+    fn read_replacement(&self, stored: String, encrypted: bool) -> anyhow::Result<String> {
+        if encrypted {
+            self.encryption()?.decrypt(&stored)
+        } else {
+            Ok(stored)
+        }
+    }
+
+    /// Run a write, retrying with exponential backoff while the database is
+    /// busy. Any other error is returned straight away.
+    async fn with_retry<T, F, Fut>(&self, operation: &str, mut run: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let mut backoff = self.retry_policy.initial_backoff;
+        let mut attempt = 1;
+
+        loop {
+            match run().await {
+                Ok(value) => return Ok(value),
+                Err(e) if is_transient(&e) && attempt < self.retry_policy.max_attempts => {
+                    tracing::debug!(
+                        "{} hit a busy database (attempt {}), retrying in {:?}",
+                        operation,
+                        attempt,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.retry_policy.max_backoff);
+                    attempt += 1;
+                }
+                Err(e) if is_transient(&e) => {
+                    return Err(anyhow::Error::new(e).context(format!(
+                        "{} gave up after {} attempts: database is busy",
+                        operation, attempt
+                    )));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Active window bounds and usage times are stored at a fixed precision in
+/// UTC, so that SQL can compare them as text
+pub(crate) fn sortable_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// A unique violation on the trigger column as `DuplicateTrigger` (with the
+/// database error as its source), any other error as it is
+pub(crate) fn duplicate_trigger_error(error: anyhow::Error, trigger: &str) -> anyhow::Error {
+    let is_duplicate = match error.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(db_error)) => {
+            db_error.is_unique_violation()
+                && (db_error.message().contains("trigger")
+                    || db_error
+                        .constraint()
+                        .is_some_and(|name| name.contains("trigger")))
+        }
+        _ => false,
+    };
+    if is_duplicate {
+        error.context(DuplicateTrigger(trigger.to_string()))
+    } else {
+        error
     }
 }
 
+/// Whether an error clears up on its own once other writers finish
+/// (`SQLITE_BUSY`, `SQLITE_LOCKED` and their extended codes)
+fn is_transient(error: &sqlx::Error) -> bool {
+    const SQLITE_BUSY: i32 = 5;
+    const SQLITE_LOCKED: i32 = 6;
+
+    let sqlx::Error::Database(db_error) = error else {
+        return false;
+    };
+
+    db_error
+        .code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
 #[async_trait]
 impl SnippetRepository for SqliteSnippetRepository {
     async fn save(&self, snippet: &Snippet) -> anyhow::Result<()> {
         let tags_json = serde_json::to_string(&snippet.tags)?;
+        let aliases_json = serde_json::to_string(&snippet.aliases)?;
+        let id = snippet.id.to_string();
+        let created_at = snippet.created_at.to_rfc3339();
+        let updated_at = snippet.updated_at.to_rfc3339();
+        let replacement = self.stored_replacement_of(snippet)?;
 
-        sqlx::query(
-            r#"
+        self.with_retry("save", || {
+            sqlx::query(
+                r#"
             INSERT INTO snippets (
                 id, trigger, replacement, created_at, updated_at, 
-                is_active, usage_count, tags
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                is_active, usage_count, tags, content_type, confirm_before_expand,
+                keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+                active_from, active_until, case_variants, position, encrypted
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
-        .bind(snippet.id.to_string())
-        .bind(&snippet.trigger)
-        .bind(&snippet.replacement)
-        .bind(snippet.created_at.to_rfc3339())
-        .bind(snippet.updated_at.to_rfc3339())
-        .bind(snippet.is_active as i64)
-        .bind(snippet.usage_count as i64)
-        .bind(tags_json)
-        .execute(&self.pool)
-        .await?;
+            )
+            .bind(id.as_str())
+            .bind(snippet.trigger.as_str())
+            .bind(replacement.as_str())
+            .bind(created_at.as_str())
+            .bind(updated_at.as_str())
+            .bind(snippet.is_active as i64)
+            .bind(snippet.usage_count as i64)
+            .bind(tags_json.as_str())
+            .bind(snippet.content_type.as_str())
+            .bind(snippet.confirm_before_expand as i64)
+            .bind(snippet.keep_trigger as i64)
+            .bind(aliases_json.as_str())
+            .bind(snippet.type_delay_ms.map(|ms| ms as i64))
+            .bind(snippet.source.as_deref())
+            .bind(snippet.pinned as i64)
+            .bind(snippet.hotkey.as_deref())
+            .bind(snippet.active_from.as_ref().map(sortable_time))
+            .bind(snippet.active_until.as_ref().map(sortable_time))
+            .bind(snippet.case_variants as i64)
+            .bind(snippet.position.map(i64::from))
+            .bind(snippet.encrypted as i64)
+            .execute(&self.pool)
+        })
+        .await
+        .map_err(|e| duplicate_trigger_error(e, &snippet.trigger))?;
 
         Ok(())
     }
@@ -47,7 +241,9 @@ impl SnippetRepository for SqliteSnippetRepository {
         let row = sqlx::query(
             r#"
             SELECT id, trigger, replacement, created_at, updated_at, 
-                   is_active, usage_count, tags
+                   is_active, usage_count, tags, content_type, confirm_before_expand,
+                   keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+                active_from, active_until, case_variants, position, encrypted
             FROM snippets 
             WHERE id = ?
             "#,
@@ -65,16 +261,25 @@ impl SnippetRepository for SqliteSnippetRepository {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     async fn find_by_trigger(&self, trigger: &str) -> anyhow::Result<Option<Snippet>> {
         let row = sqlx::query(
             r#"
             SELECT id, trigger, replacement, created_at, updated_at, 
-                   is_active, usage_count, tags
+                   is_active, usage_count, tags, content_type, confirm_before_expand,
+                   keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+                active_from, active_until, case_variants, position, encrypted
             FROM snippets 
-            WHERE trigger = ?
+            WHERE id IN (
+                SELECT id FROM snippets WHERE trigger = ?
+                UNION
+                SELECT id FROM snippets WHERE aliases != '[]'
+                    AND EXISTS (SELECT 1 FROM json_each(snippets.aliases) WHERE value = ?)
+            )
             "#,
         )
         .bind(trigger)
+        .bind(trigger)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -87,58 +292,9 @@ impl SnippetRepository for SqliteSnippetRepository {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     async fn find_all(&self, query: &SnippetQuery) -> anyhow::Result<Vec<Snippet>> {
-        let mut sql = String::from(
-            r#"
-            SELECT id, trigger, replacement, created_at, updated_at, 
-                   is_active, usage_count, tags
-            FROM snippets 
-            WHERE 1=1
-            "#,
-        );
-
-        let mut bind_values: Vec<String> = Vec::new();
-
-        // Add filters
-        if let Some(is_active) = query.is_active {
-            sql.push_str(" AND is_active = ?");
-            bind_values.push((is_active as i64).to_string());
-        }
-
-        if let Some(ref search_term) = query.search {
-            sql.push_str(" AND (trigger LIKE ? OR replacement LIKE ?)");
-            let search_pattern = format!("%{}%", search_term);
-            bind_values.push(search_pattern.clone());
-            bind_values.push(search_pattern);
-        }
-
-        // Add sorting
-        if let Some(sort_by) = query.sort_by {
-            match sort_by {
-                SortBy::CreatedAt => sql.push_str(" ORDER BY created_at"),
-                SortBy::UpdatedAt => sql.push_str(" ORDER BY updated_at"),
-                SortBy::UsageCount => sql.push_str(" ORDER BY usage_count"),
-                SortBy::Trigger => sql.push_str(" ORDER BY trigger"),
-            }
-
-            if let Some(sort_order) = query.sort_order {
-                match sort_order {
-                    SortOrder::Asc => sql.push_str(" ASC"),
-                    SortOrder::Desc => sql.push_str(" DESC"),
-                }
-            }
-        }
-
-        // Add pagination
-        if let Some(limit) = query.limit {
-            sql.push_str(" LIMIT ?");
-            bind_values.push(limit.to_string());
-        }
-
-        if let Some(offset) = query.offset {
-            sql.push_str(" OFFSET ?");
-            bind_values.push(offset.to_string());
-        }
+        let (sql, bind_values) = Self::build_select(query);
 
         let mut query_builder = sqlx::query(&sql);
         for value in bind_values {
@@ -156,34 +312,79 @@ impl SnippetRepository for SqliteSnippetRepository {
         Ok(snippets)
     }
 
+    fn stream_all(&self, query: &SnippetQuery) -> BoxStream<'_, anyhow::Result<Snippet>> {
+        let (sql, bind_values) = Self::build_select(query);
+
+        Box::pin(async_stream::try_stream! {
+            let mut query_builder = sqlx::query(&sql);
+            for value in bind_values {
+                query_builder = query_builder.bind(value);
+            }
+
+            let mut rows = query_builder.fetch(&self.pool);
+            while let Some(row) = rows.try_next().await? {
+                yield self.row_to_snippet(row)?;
+            }
+        })
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(id = %snippet.id))]
     async fn update(&self, snippet: &Snippet) -> anyhow::Result<()> {
         let tags_json = serde_json::to_string(&snippet.tags)?;
+        let aliases_json = serde_json::to_string(&snippet.aliases)?;
+        let id = snippet.id.to_string();
+        let updated_at = snippet.updated_at.to_rfc3339();
+        let replacement = self.stored_replacement_of(snippet)?;
 
-        sqlx::query(
-            r#"
+        self.with_retry("update", || {
+            sqlx::query(
+                r#"
             UPDATE snippets SET 
                 trigger = ?, replacement = ?, updated_at = ?, 
-                is_active = ?, usage_count = ?, tags = ?
+                is_active = ?, usage_count = ?, tags = ?, content_type = ?,
+                confirm_before_expand = ?, keep_trigger = ?, aliases = ?,
+                type_delay_ms = ?, source = ?, pinned = ?, hotkey = ?,
+                active_from = ?, active_until = ?, case_variants = ?, position = ?,
+                encrypted = ?
             WHERE id = ?
             "#,
-        )
-        .bind(&snippet.trigger)
-        .bind(&snippet.replacement)
-        .bind(snippet.updated_at.to_rfc3339())
-        .bind(snippet.is_active as i64)
-        .bind(snippet.usage_count as i64)
-        .bind(tags_json)
-        .bind(snippet.id.to_string())
-        .execute(&self.pool)
-        .await?;
+            )
+            .bind(snippet.trigger.as_str())
+            .bind(replacement.as_str())
+            .bind(updated_at.as_str())
+            .bind(snippet.is_active as i64)
+            .bind(snippet.usage_count as i64)
+            .bind(tags_json.as_str())
+            .bind(snippet.content_type.as_str())
+            .bind(snippet.confirm_before_expand as i64)
+            .bind(snippet.keep_trigger as i64)
+            .bind(aliases_json.as_str())
+            .bind(snippet.type_delay_ms.map(|ms| ms as i64))
+            .bind(snippet.source.as_deref())
+            .bind(snippet.pinned as i64)
+            .bind(snippet.hotkey.as_deref())
+            .bind(snippet.active_from.as_ref().map(sortable_time))
+            .bind(snippet.active_until.as_ref().map(sortable_time))
+            .bind(snippet.case_variants as i64)
+            .bind(snippet.position.map(i64::from))
+            .bind(snippet.encrypted as i64)
+            .bind(id.as_str())
+            .execute(&self.pool)
+        })
+        .await
+        .map_err(|e| duplicate_trigger_error(e, &snippet.trigger))?;
 
         Ok(())
     }
 
     async fn delete(&self, id: &Uuid) -> anyhow::Result<bool> {
-        let result = sqlx::query("DELETE FROM snippets WHERE id = ?")
-            .bind(id.to_string())
-            .execute(&self.pool)
+        let id = id.to_string();
+        let result = self
+            .with_retry("delete", || {
+                sqlx::query("DELETE FROM snippets WHERE id = ?")
+                    .bind(id.as_str())
+                    .execute(&self.pool)
+            })
             .await?;
 
         Ok(result.rows_affected() > 0)
@@ -194,17 +395,7 @@ impl SnippetRepository for SqliteSnippetRepository {
         let mut bind_values: Vec<String> = Vec::new();
 
         // Add filters
-        if let Some(is_active) = query.is_active {
-            sql.push_str(" AND is_active = ?");
-            bind_values.push((is_active as i64).to_string());
-        }
-
-        if let Some(ref search_term) = query.search {
-            sql.push_str(" AND (trigger LIKE ? OR replacement LIKE ?)");
-            let search_pattern = format!("%{}%", search_term);
-            bind_values.push(search_pattern.clone());
-            bind_values.push(search_pattern);
-        }
+        Self::push_filters(query, &mut sql, &mut bind_values);
 
         let mut query_builder = sqlx::query_scalar::<_, i64>(&sql);
         for value in bind_values {
@@ -215,17 +406,485 @@ impl SnippetRepository for SqliteSnippetRepository {
         Ok(count as u64)
     }
 
-    async fn exists_with_trigger(&self, trigger: &str) -> anyhow::Result<bool> {
-        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM snippets WHERE trigger = ?")
-            .bind(trigger)
+    async fn count_all(&self) -> anyhow::Result<u64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM snippets")
             .fetch_one(&self.pool)
             .await?;
+        Ok(count as u64)
+    }
+
+    async fn top_by_usage(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        // Walks idx_snippets_usage_count from the top instead of sorting every row
+        let rows = sqlx::query(
+            r#"
+            SELECT id, trigger, replacement, created_at, updated_at, 
+                   is_active, usage_count, tags, content_type, confirm_before_expand,
+                   keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+                active_from, active_until, case_variants, position, encrypted
+            FROM snippets 
+            WHERE is_active = 1
+            ORDER BY usage_count DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_snippet(row))
+            .collect()
+    }
+
+    async fn random_active(&self, limit: u32) -> anyhow::Result<Vec<Snippet>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, trigger, replacement, created_at, updated_at,
+                   is_active, usage_count, tags, content_type, confirm_before_expand,
+                   keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+                   active_from, active_until, case_variants, position, encrypted
+            FROM snippets
+            WHERE is_active = 1
+            ORDER BY RANDOM()
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_snippet(row))
+            .collect()
+    }
+
+    async fn exists_with_trigger(&self, trigger: &str) -> anyhow::Result<bool> {
+        // Split so the trigger lookup uses its index and only aliased snippets are scanned
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM snippets WHERE trigger = ?)
+                OR EXISTS (
+                    SELECT 1 FROM snippets WHERE aliases != '[]'
+                        AND EXISTS (SELECT 1 FROM json_each(snippets.aliases) WHERE value = ?)
+                )
+            "#,
+        )
+        .bind(trigger)
+        .bind(trigger)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    async fn set_active_matching(
+        &self,
+        query: &SnippetQuery,
+        is_active: bool,
+    ) -> anyhow::Result<Vec<(Uuid, String)>> {
+        // Only touch snippets that actually change, so they're the ones returned
+        let mut sql =
+            String::from("UPDATE snippets SET is_active = ?, updated_at = ? WHERE is_active != ?");
+        let mut bind_values: Vec<String> = Vec::new();
+        Self::push_filters(query, &mut sql, &mut bind_values);
+        sql.push_str(" RETURNING id, trigger");
+
+        let updated_at = Utc::now().to_rfc3339();
+        let rows = self
+            .with_retry("set_active_matching", || {
+                let mut query_builder = sqlx::query(&sql)
+                    .bind(is_active as i64)
+                    .bind(updated_at.as_str())
+                    .bind(is_active as i64);
+                for value in &bind_values {
+                    query_builder = query_builder.bind(value.as_str());
+                }
+                query_builder.fetch_all(&self.pool)
+            })
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let id_str: String = row.get("id");
+                Ok((Uuid::parse_str(&id_str)?, row.get("trigger")))
+            })
+            .collect()
+    }
+
+    async fn reset_usage(&self, id: Option<Uuid>) -> anyhow::Result<u64> {
+        let result = match id {
+            Some(id) => {
+                sqlx::query("DELETE FROM snippet_usage WHERE snippet_id = ?")
+                    .bind(id.to_string())
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query("UPDATE snippets SET usage_count = 0 WHERE id = ?")
+                    .bind(id.to_string())
+                    .execute(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("DELETE FROM snippet_usage")
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query("UPDATE snippets SET usage_count = 0")
+                    .execute(&self.pool)
+                    .await?
+            }
+        };
+
+        Ok(result.rows_affected())
+    }
+
+    async fn reorder(&self, ordered_ids: &[Uuid]) -> anyhow::Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let placed: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM snippets WHERE position IS NOT NULL ORDER BY position, id",
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut listed = HashSet::new();
+        let mut position: i64 = 0;
+        for id in ordered_ids.iter().map(Uuid::to_string) {
+            if !listed.insert(id.clone()) {
+                continue;
+            }
+            let result = sqlx::query("UPDATE snippets SET position = ? WHERE id = ?")
+                .bind(position)
+                .bind(&id)
+                .execute(&mut *transaction)
+                .await?;
+            position += result.rows_affected() as i64;
+        }
+        let found = position as u64;
+
+        for id in placed.iter().filter(|id| !listed.contains(*id)) {
+            sqlx::query("UPDATE snippets SET position = ? WHERE id = ?")
+                .bind(position)
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+            position += 1;
+        }
+        transaction.commit().await?;
+
+        Ok(found)
+    }
+
+    async fn record_version(
+        &self,
+        version: &SnippetVersion,
+        max_versions: usize,
+    ) -> anyhow::Result<()> {
+        let snippet_id = version.snippet_id.to_string();
+
+        // Old replacements of an encrypted snippet are just as sensitive
+        let encrypted: Option<i64> =
+            sqlx::query_scalar("SELECT encrypted FROM snippets WHERE id = ?")
+                .bind(&snippet_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let encrypted = encrypted.is_some_and(|encrypted| encrypted != 0);
+        let replacement = self.stored_replacement(&version.replacement, encrypted)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO snippet_versions (snippet_id, trigger, replacement, changed_at, encrypted)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&snippet_id)
+        .bind(&version.trigger)
+        .bind(&replacement)
+        .bind(sortable_time(&version.changed_at))
+        .bind(encrypted as i64)
+        .execute(&self.pool)
+        .await?;
+
+        // Drop the oldest versions beyond the retention limit
+        sqlx::query(
+            r#"
+            DELETE FROM snippet_versions
+            WHERE snippet_id = ? AND id NOT IN (
+                SELECT id FROM snippet_versions
+                WHERE snippet_id = ?
+                ORDER BY id DESC
+                LIMIT ?
+            )
+            "#,
+        )
+        .bind(&snippet_id)
+        .bind(&snippet_id)
+        .bind(max_versions as i64)
+        .execute(&self.pool)
+        .await?;
 
-        Ok(count > 0)
+        Ok(())
+    }
+
+    async fn history(&self, id: &Uuid) -> anyhow::Result<Vec<SnippetVersion>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT snippet_id, trigger, replacement, changed_at, encrypted
+            FROM snippet_versions
+            WHERE snippet_id = ?
+            ORDER BY id DESC
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_version(row))
+            .collect()
+    }
+
+    async fn edits_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<SnippetVersion>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT snippet_id, trigger, replacement, changed_at, encrypted
+            FROM snippet_versions
+            WHERE changed_at >= ?
+            ORDER BY changed_at DESC, id DESC
+            "#,
+        )
+        .bind(sortable_time(&since))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_version(row))
+            .collect()
+    }
+
+    async fn record_usage(&self, id: &Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()> {
+        let id = id.to_string();
+        let used_at = sortable_time(&used_at);
+        self.with_retry("record usage", || {
+            sqlx::query("INSERT INTO snippet_usage (snippet_id, used_at) VALUES (?, ?)")
+                .bind(id.as_str())
+                .bind(used_at.as_str())
+                .execute(&self.pool)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn usage_count_between(
+        &self,
+        id: &Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM snippet_usage
+            WHERE snippet_id = ? AND used_at >= ? AND used_at < ?
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(sortable_time(&from))
+        .bind(sortable_time(&to))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as u64)
+    }
+
+    async fn first_used(&self, id: &Uuid) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let first: Option<String> =
+            sqlx::query_scalar("SELECT MIN(used_at) FROM snippet_usage WHERE snippet_id = ?")
+                .bind(id.to_string())
+                .fetch_one(&self.pool)
+                .await?;
+
+        first
+            .map(|time| Ok(DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc)))
+            .transpose()
     }
 }
 
 impl SqliteSnippetRepository {
+    /// Build the SELECT used by `find_all` and `stream_all`, with its bind values
+    fn build_select(query: &SnippetQuery) -> (String, Vec<String>) {
+        let mut sql = String::from(
+            r#"
+            SELECT id, trigger, replacement, created_at, updated_at, 
+                   is_active, usage_count, tags, content_type, confirm_before_expand,
+                   keep_trigger, aliases, type_delay_ms, source, pinned, hotkey,
+                active_from, active_until, case_variants, position, encrypted
+            FROM snippets 
+            WHERE 1=1
+            "#,
+        );
+
+        let mut bind_values: Vec<String> = Vec::new();
+
+        // Add filters
+        Self::push_filters(query, &mut sql, &mut bind_values);
+
+        // A cursor needs a fixed order, so it falls back to the default sort
+        let sort_by = query
+            .sort_by
+            .or_else(|| query.after.as_ref().map(|_| SortBy::default()));
+        let direction = match query.sort_order {
+            Some(SortOrder::Asc) => " ASC",
+            Some(SortOrder::Desc) => " DESC",
+            None => "",
+        };
+
+        if let (Some(sort_by), Some(cursor)) = (sort_by, &query.after) {
+            let comparison = if query.sort_order == Some(SortOrder::Desc) {
+                "<"
+            } else {
+                ">"
+            };
+            // Pinned snippets come first, so past a pinned one are the rest of
+            // the pinned ones and then every unpinned one
+            sql.push_str(&format!(
+                " AND (pinned < ? OR (pinned = ? AND ({}, id) {} ({}, ?)))",
+                Self::sort_column(sort_by),
+                comparison,
+                Self::sort_param(sort_by)
+            ));
+            let pinned = (cursor.pinned as i64).to_string();
+            bind_values.push(pinned.clone());
+            bind_values.push(pinned);
+            bind_values.push(cursor.sort_key.clone());
+            bind_values.push(cursor.id.to_string());
+        }
+
+        // Add sorting, pinned snippets first and the id breaking ties so
+        // pages never overlap
+        match sort_by {
+            Some(sort_by) => sql.push_str(&format!(
+                " ORDER BY pinned DESC, {}{}, id{}",
+                Self::sort_column(sort_by),
+                direction,
+                direction
+            )),
+            None => sql.push_str(" ORDER BY pinned DESC"),
+        }
+
+        // Add pagination
+        if let Some(limit) = query.limit {
+            sql.push_str(" LIMIT ?");
+            bind_values.push(limit.to_string());
+        }
+
+        if let Some(offset) = query.offset {
+            sql.push_str(" OFFSET ?");
+            bind_values.push(offset.to_string());
+        }
+
+        (sql, bind_values)
+    }
+
+    fn sort_column(sort_by: SortBy) -> &'static str {
+        match sort_by {
+            SortBy::CreatedAt => "created_at",
+            SortBy::UpdatedAt => "updated_at",
+            SortBy::UsageCount => "usage_count",
+            SortBy::Trigger => "trigger",
+            SortBy::Position => "COALESCE(position, 4294967295)",
+        }
+    }
+
+    /// Placeholder for a cursor's sort key, which is bound as text. Columns
+    /// convert it themselves; an expression has no type to convert it to.
+    fn sort_param(sort_by: SortBy) -> &'static str {
+        match sort_by {
+            SortBy::Position => "CAST(? AS INTEGER)",
+            _ => "?",
+        }
+    }
+
+    /// Append the WHERE conditions shared by `find_all` and `count`
+    fn push_filters(query: &SnippetQuery, sql: &mut String, bind_values: &mut Vec<String>) {
+        if let Some(is_active) = query.is_active {
+            sql.push_str(" AND is_active = ?");
+            bind_values.push((is_active as i64).to_string());
+        }
+
+        if let Some(ref time) = query.active_at {
+            sql.push_str(" AND (active_from IS NULL OR active_from <= ?)");
+            sql.push_str(" AND (active_until IS NULL OR active_until > ?)");
+            let time = sortable_time(time);
+            bind_values.push(time.clone());
+            bind_values.push(time);
+        }
+
+        if let Some(ref source) = query.source {
+            sql.push_str(" AND source = ?");
+            bind_values.push(source.clone());
+        }
+
+        if let Some(ref search_term) = query.search {
+            let condition = Self::search_condition(query, search_term, bind_values);
+            sql.push_str(&format!(" AND ({})", condition));
+        }
+
+        if !query.search_any.is_empty() {
+            let conditions: Vec<String> = query
+                .search_any
+                .iter()
+                .map(|term| Self::search_condition(query, term, bind_values))
+                .collect();
+            sql.push_str(&format!(" AND ({})", conditions.join(" OR ")));
+        }
+
+        if !query.tags.is_empty() {
+            let joiner = match query.tag_match {
+                TagMatch::Any => " OR ",
+                TagMatch::All => " AND ",
+            };
+            let conditions = vec![
+                "EXISTS (SELECT 1 FROM json_each(snippets.tags) WHERE json_each.value = ?)";
+                query.tags.len()
+            ];
+
+            sql.push_str(&format!(" AND ({})", conditions.join(joiner)));
+            bind_values.extend(query.tags.iter().cloned());
+        }
+    }
+
+    /// `term` appears in one of the query's search fields
+    fn search_condition(query: &SnippetQuery, term: &str, bind_values: &mut Vec<String>) -> String {
+        let conditions: Vec<&str> = query
+            .search_fields
+            .iter()
+            .map(|field| match field {
+                SearchField::Trigger => "trigger LIKE ?",
+                // Encrypted replacements can't be searched
+                SearchField::Replacement => "(encrypted = 0 AND replacement LIKE ?)",
+                SearchField::Tags => {
+                    "EXISTS (SELECT 1 FROM json_each(snippets.tags) WHERE json_each.value LIKE ?)"
+                }
+            })
+            .collect();
+        let pattern = format!("%{}%", term);
+        bind_values.extend(std::iter::repeat_n(pattern, conditions.len()));
+        conditions.join(" OR ")
+    }
+
+    fn row_to_version(&self, row: sqlx::sqlite::SqliteRow) -> anyhow::Result<SnippetVersion> {
+        let snippet_id_str: String = row.get("snippet_id");
+        let changed_at_str: String = row.get("changed_at");
+        let encrypted = row.get::<i64, _>("encrypted") != 0;
+
+        Ok(SnippetVersion {
+            snippet_id: Uuid::parse_str(&snippet_id_str)?,
+            trigger: row.get("trigger"),
+            replacement: self
+                .read_replacement(row.get("replacement"), encrypted)
+                .unwrap_or_else(|_| LOCKED_REPLACEMENT.to_string()),
+            changed_at: DateTime::parse_from_rfc3339(&changed_at_str)?.with_timezone(&Utc),
+        })
+    }
+
     fn row_to_snippet(&self, row: sqlx::sqlite::SqliteRow) -> anyhow::Result<Snippet> {
         let id_str: String = row.get("id");
         let id = Uuid::parse_str(&id_str)?;
@@ -245,15 +904,58 @@ impl SqliteSnippetRepository {
         let tags_json: String = row.get("tags");
         let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
 
+        let content_type_str: String = row.get("content_type");
+        let content_type = content_type_str.parse().unwrap_or_default();
+
+        let confirm_before_expand_int: i64 = row.get("confirm_before_expand");
+        let keep_trigger_int: i64 = row.get("keep_trigger");
+        let pinned_int: i64 = row.get("pinned");
+        let case_variants_int: i64 = row.get("case_variants");
+        let encrypted = row.get::<i64, _>("encrypted") != 0;
+        // A snippet that can't be decrypted is still listed, just locked
+        let stored: String = row.get("replacement");
+        let (replacement, locked) = match self.read_replacement(stored.clone(), encrypted) {
+            Ok(replacement) => (replacement, None),
+            Err(e) => (stored, Some(e.to_string())),
+        };
+
+        let type_delay_ms: Option<i64> = row.get("type_delay_ms");
+        let position: Option<i64> = row.get("position");
+
+        let aliases_json: String = row.get("aliases");
+        let aliases: Vec<String> = serde_json::from_str(&aliases_json).unwrap_or_default();
+
+        let parse_time = |column: &str| -> anyhow::Result<Option<DateTime<Utc>>> {
+            let text: Option<String> = row.get(column);
+            text.map(|text| Ok(DateTime::parse_from_rfc3339(&text)?.with_timezone(&Utc)))
+                .transpose()
+        };
+        let active_from = parse_time("active_from")?;
+        let active_until = parse_time("active_until")?;
+
         Ok(Snippet {
             id,
             trigger: row.get("trigger"),
-            replacement: row.get("replacement"),
+            replacement,
             created_at,
             updated_at,
             is_active,
             usage_count,
             tags,
+            content_type,
+            confirm_before_expand: confirm_before_expand_int != 0,
+            keep_trigger: keep_trigger_int != 0,
+            aliases,
+            type_delay_ms: type_delay_ms.map(|ms| ms as u64),
+            source: row.get("source"),
+            pinned: pinned_int != 0,
+            hotkey: row.get("hotkey"),
+            active_from,
+            active_until,
+            case_variants: case_variants_int != 0,
+            position: position.map(|position| position as u32),
+            encrypted,
+            locked,
         })
     }
 }
@@ -261,6 +963,7 @@ impl SqliteSnippetRepository {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::ContentType;
     use crate::infra::DatabaseConnection;
     use tempfile::TempDir;
 
@@ -318,6 +1021,90 @@ mod tests {
         assert_eq!(found.replacement, "Updated");
     }
 
+    #[tokio::test]
+    async fn test_content_type_round_trip() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let mut snippet = Snippet::new("::sig".to_string(), "**Jane**".to_string()).unwrap();
+        snippet.set_content_type(ContentType::Markdown);
+        repository.save(&snippet).await.unwrap();
+
+        let found = repository.find_by_trigger("::sig").await.unwrap().unwrap();
+        assert_eq!(found.content_type, ContentType::Markdown);
+
+        snippet.set_content_type(ContentType::Html);
+        repository.update(&snippet).await.unwrap();
+
+        let found = repository.find_by_id(&snippet.id).await.unwrap().unwrap();
+        assert_eq!(found.content_type, ContentType::Html);
+    }
+
+    #[tokio::test]
+    async fn test_confirm_before_expand_round_trip() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let mut snippet = Snippet::new("::secret".to_string(), "hunter2".to_string()).unwrap();
+        snippet.set_confirm_before_expand(true);
+        repository.save(&snippet).await.unwrap();
+
+        let found = repository
+            .find_by_trigger("::secret")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(found.confirm_before_expand);
+
+        snippet.set_confirm_before_expand(false);
+        repository.update(&snippet).await.unwrap();
+
+        let found = repository.find_by_id(&snippet.id).await.unwrap().unwrap();
+        assert!(!found.confirm_before_expand);
+    }
+
+    #[tokio::test]
+    async fn test_keep_trigger_round_trip() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let mut snippet = Snippet::new("::log".to_string(), " [logged]".to_string()).unwrap();
+        snippet.set_keep_trigger(true);
+        repository.save(&snippet).await.unwrap();
+
+        let found = repository.find_by_trigger("::log").await.unwrap().unwrap();
+        assert!(found.keep_trigger);
+        assert!(!found.confirm_before_expand);
+    }
+
+    #[tokio::test]
+    async fn test_type_delay_round_trip() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let mut snippet = Snippet::new("::slow".to_string(), "Typed slowly".to_string()).unwrap();
+        repository.save(&snippet).await.unwrap();
+        let found = repository.find_by_trigger("::slow").await.unwrap().unwrap();
+        assert_eq!(found.type_delay_ms, None);
+
+        snippet.set_type_delay_ms(Some(25));
+        repository.update(&snippet).await.unwrap();
+        let found = repository.find_by_trigger("::slow").await.unwrap().unwrap();
+        assert_eq!(found.type_delay_ms, Some(25));
+    }
+
+    #[tokio::test]
+    async fn test_hotkey_round_trip() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let mut snippet = Snippet::new("::sig".to_string(), "Signature".to_string()).unwrap();
+        snippet.set_hotkey(Some("ctrl+alt+s")).unwrap();
+        repository.save(&snippet).await.unwrap();
+        let found = repository.find_by_trigger("::sig").await.unwrap().unwrap();
+        assert_eq!(found.hotkey.as_deref(), Some("Ctrl+Alt+S"));
+
+        snippet.set_hotkey(None).unwrap();
+        repository.update(&snippet).await.unwrap();
+        let found = repository.find_by_trigger("::sig").await.unwrap().unwrap();
+        assert_eq!(found.hotkey, None);
+    }
+
     #[tokio::test]
     async fn test_delete() {
         let (repository, _temp_dir) = create_test_repository().await;
@@ -333,4 +1120,375 @@ mod tests {
         let found = repository.find_by_id(&snippet_id).await.unwrap();
         assert!(found.is_none());
     }
+
+    #[tokio::test]
+    async fn test_count_all_tracks_inserts_and_deletes() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        assert_eq!(repository.count_all().await.unwrap(), 0);
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let mut snippet = Snippet::new(format!("::count{}", i), "Counted".to_string()).unwrap();
+            if i % 2 == 0 {
+                snippet.deactivate();
+            }
+            repository.save(&snippet).await.unwrap();
+            ids.push(snippet.id);
+        }
+        assert_eq!(repository.count_all().await.unwrap(), 5);
+        assert_eq!(
+            repository.count(&SnippetQuery::new()).await.unwrap(),
+            repository.count_all().await.unwrap()
+        );
+
+        repository.delete(&ids[0]).await.unwrap();
+        repository.delete(&ids[3]).await.unwrap();
+        assert_eq!(repository.count_all().await.unwrap(), 3);
+    }
+
+    /// A pool that fails immediately instead of waiting on SQLite's busy timeout
+    async fn impatient_pool(db_path: &std::path::Path) -> Pool<Sqlite> {
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(db_path)
+            .busy_timeout(Duration::ZERO);
+        sqlx::SqlitePool::connect_with(options).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_retries_while_database_is_busy() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
+        let pool = impatient_pool(&db_path).await;
+        let repository = SqliteSnippetRepository::new(pool.clone());
+
+        // Another connection holds the write lock for a while
+        let mut locker = db_connection.pool().acquire().await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *locker)
+            .await
+            .unwrap();
+
+        let busy = sqlx::query("DELETE FROM snippets").execute(&pool).await;
+        assert!(is_transient(&busy.unwrap_err()));
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            sqlx::query("COMMIT").execute(&mut *locker).await.unwrap();
+        });
+
+        let snippet = Snippet::new("::busy".to_string(), "Saved anyway".to_string()).unwrap();
+        repository.save(&snippet).await.unwrap();
+        release.await.unwrap();
+
+        let found = repository.find_by_id(&snippet.id).await.unwrap();
+        assert_eq!(found.unwrap().replacement, "Saved anyway");
+    }
+
+    #[tokio::test]
+    async fn test_write_gives_up_when_database_stays_busy() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
+        let repository = SqliteSnippetRepository::new(impatient_pool(&db_path).await)
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(5),
+            });
+
+        let mut locker = db_connection.pool().acquire().await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *locker)
+            .await
+            .unwrap();
+
+        let snippet = Snippet::new("::busy".to_string(), "Never saved".to_string()).unwrap();
+        let error = repository.save(&snippet).await.unwrap_err();
+        assert!(error.to_string().contains("gave up after 3 attempts"));
+    }
+
+    #[tokio::test]
+    async fn test_permanent_write_errors_are_not_retried() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        let snippet = Snippet::new("::dup".to_string(), "Dup".to_string()).unwrap();
+        repository.save(&snippet).await.unwrap();
+
+        let error = repository.save(&snippet).await.unwrap_err();
+        let sqlx_error = error.downcast_ref::<sqlx::Error>().unwrap();
+        assert!(!is_transient(sqlx_error));
+        assert!(!error.to_string().contains("gave up"));
+    }
+
+    async fn save_tagged(repository: &SqliteSnippetRepository, trigger: &str, tags: &[&str]) {
+        let mut snippet = Snippet::new(trigger.to_string(), "Tagged".to_string()).unwrap();
+        for tag in tags {
+            snippet.add_tag(tag.to_string());
+        }
+        repository.save(&snippet).await.unwrap();
+    }
+
+    async fn triggers_matching(
+        repository: &SqliteSnippetRepository,
+        tags: &[&str],
+        tag_match: TagMatch,
+    ) -> Vec<String> {
+        let query = SnippetQuery::new()
+            .with_tags(tags.iter().map(|t| t.to_string()).collect())
+            .with_tag_match(tag_match)
+            .with_sort(SortBy::Trigger, SortOrder::Asc);
+
+        let count = repository.count(&query).await.unwrap();
+        let triggers: Vec<String> = repository
+            .find_all(&query)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snippet| snippet.trigger)
+            .collect();
+        assert_eq!(count, triggers.len() as u64);
+        triggers
+    }
+
+    #[tokio::test]
+    async fn test_tag_match_any_and_all() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        save_tagged(&repository, "::both", &["work", "email"]).await;
+        save_tagged(&repository, "::work", &["work"]).await;
+        save_tagged(&repository, "::email", &["email", "personal"]).await;
+        save_tagged(&repository, "::none", &[]).await;
+
+        assert_eq!(
+            triggers_matching(&repository, &["work", "email"], TagMatch::Any).await,
+            vec!["::both", "::email", "::work"]
+        );
+        assert_eq!(
+            triggers_matching(&repository, &["work", "email"], TagMatch::All).await,
+            vec!["::both"]
+        );
+        assert_eq!(
+            triggers_matching(&repository, &["personal"], TagMatch::All).await,
+            vec!["::email"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_source() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        for (trigger, source) in [
+            ("::pr", Some("dev-pack")),
+            ("::ci", Some("dev-pack")),
+            ("::hi", Some("greetings")),
+            ("::mine", None),
+        ] {
+            let mut snippet = Snippet::new(trigger.to_string(), "Text".to_string()).unwrap();
+            snippet.set_source(source.map(str::to_string));
+            repository.save(&snippet).await.unwrap();
+        }
+
+        let query = SnippetQuery::new()
+            .with_source("dev-pack".to_string())
+            .with_sort(SortBy::Trigger, SortOrder::Asc);
+        let triggers: Vec<String> = repository
+            .find_all(&query)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|snippet| snippet.trigger)
+            .collect();
+
+        assert_eq!(triggers, vec!["::ci", "::pr"]);
+        assert_eq!(repository.count(&query).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_snippets_are_listed_first() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        for (trigger, usage_count, pinned) in [
+            ("::busy", 9, true),
+            ("::rare", 1, false),
+            ("::pinned", 5, true),
+            ("::never", 0, false),
+        ] {
+            let mut snippet = Snippet::new(trigger.to_string(), "Text".to_string()).unwrap();
+            snippet.usage_count = usage_count;
+            snippet.set_pinned(pinned);
+            repository.save(&snippet).await.unwrap();
+        }
+
+        let triggers = |query: SnippetQuery| {
+            let repository = &repository;
+            async move {
+                repository
+                    .find_all(&query)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|snippet| snippet.trigger)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            triggers(SnippetQuery::new().with_sort(SortBy::UsageCount, SortOrder::Asc)).await,
+            vec!["::pinned", "::busy", "::never", "::rare"]
+        );
+        assert_eq!(
+            triggers(SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Desc)).await,
+            vec!["::pinned", "::busy", "::rare", "::never"]
+        );
+
+        let pinned = repository.find_by_trigger("::busy").await.unwrap().unwrap();
+        assert!(pinned.pinned);
+    }
+
+    #[test]
+    fn test_tag_match_defaults_to_any() {
+        assert_eq!(SnippetQuery::new().tag_match, TagMatch::Any);
+    }
+
+    #[tokio::test]
+    async fn test_record_version_retention() {
+        let (repository, _temp_dir) = create_test_repository().await;
+
+        let mut snippet = Snippet::new("::ver".to_string(), "v1".to_string()).unwrap();
+        repository.save(&snippet).await.unwrap();
+
+        for replacement in ["v2", "v3", "v4"] {
+            repository
+                .record_version(&SnippetVersion::of(&snippet), 2)
+                .await
+                .unwrap();
+            snippet.update_replacement(replacement.to_string()).unwrap();
+        }
+
+        let replacements: Vec<String> = repository
+            .history(&snippet.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|version| version.replacement)
+            .collect();
+        assert_eq!(replacements, vec!["v3", "v2"]);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_replacements() {
+        let (plain, _temp_dir) = create_test_repository().await;
+        let pool = plain.pool.clone();
+        let repository = SqliteSnippetRepository::new(pool.clone())
+            .with_encryption(EncryptionService::new("correct horse").unwrap());
+
+        let mut snippet = Snippet::new("::pin".to_string(), "PIN 1234".to_string()).unwrap();
+        snippet.set_encrypted(true);
+        repository.save(&snippet).await.unwrap();
+        repository
+            .record_version(&SnippetVersion::of(&snippet), 5)
+            .await
+            .unwrap();
+        repository
+            .save(&Snippet::new("::hi".to_string(), "Hello".to_string()).unwrap())
+            .await
+            .unwrap();
+
+        // Stored as ciphertext, in the snippet and its history
+        let stored: Vec<String> = sqlx::query_scalar(
+            "SELECT replacement FROM snippets WHERE encrypted = 1 UNION ALL SELECT replacement FROM snippet_versions",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().all(|text| !text.contains("1234")));
+
+        let found = repository.find_by_trigger("::pin").await.unwrap().unwrap();
+        assert!(found.encrypted);
+        assert_eq!(found.replacement, "PIN 1234");
+        assert_eq!(
+            repository.history(&snippet.id).await.unwrap()[0].replacement,
+            "PIN 1234"
+        );
+        let search = SnippetQuery::new().with_search("1234".to_string());
+        assert!(repository.find_all(&search).await.unwrap().is_empty());
+
+        // Plain snippets are readable with no passphrase, encrypted ones are
+        // locked without the right one
+        assert_eq!(
+            plain
+                .find_by_trigger("::hi")
+                .await
+                .unwrap()
+                .unwrap()
+                .replacement,
+            "Hello"
+        );
+        let locked = plain.find_by_trigger("::pin").await.unwrap().unwrap();
+        let error = locked.ensure_unlocked().unwrap_err();
+        assert!(error.to_string().contains(PASSPHRASE_ENV_VAR));
+        let wrong = SqliteSnippetRepository::new(pool)
+            .with_encryption(EncryptionService::new("battery staple").unwrap());
+        let locked = wrong.find_by_trigger("::pin").await.unwrap().unwrap();
+        let error = locked.ensure_unlocked().unwrap_err();
+        assert!(error.to_string().contains("wrong passphrase"));
+        assert_eq!(
+            wrong.history(&snippet.id).await.unwrap()[0].replacement,
+            LOCKED_REPLACEMENT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mixed_library_lists_without_passphrase() {
+        let (plain, _temp_dir) = create_test_repository().await;
+        let repository = SqliteSnippetRepository::new(plain.pool.clone())
+            .with_encryption(EncryptionService::new("correct horse").unwrap());
+
+        let mut pin = Snippet::new("::pin".to_string(), "PIN 1234".to_string()).unwrap();
+        pin.set_encrypted(true);
+        repository.save(&pin).await.unwrap();
+        repository
+            .save(&Snippet::new("::hi".to_string(), "Hello".to_string()).unwrap())
+            .await
+            .unwrap();
+
+        // Every listing still works, with only the encrypted snippet locked
+        let query = SnippetQuery::new().with_sort(SortBy::Trigger, SortOrder::Asc);
+        let listed = plain.find_all(&query).await.unwrap();
+        let streamed: Vec<Snippet> = plain.stream_all(&query).try_collect().await.unwrap();
+        assert_eq!(listed, streamed);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].trigger, "::hi");
+        assert!(listed[0].locked.is_none());
+        assert_eq!(listed[1].trigger, "::pin");
+        assert!(listed[1].locked.is_some());
+
+        // Saving a locked snippet keeps the stored replacement as it was
+        let mut locked = listed[1].clone();
+        locked.add_tag("secret".to_string());
+        plain.update(&locked).await.unwrap();
+        let found = repository.find_by_trigger("::pin").await.unwrap().unwrap();
+        assert_eq!(found.replacement, "PIN 1234");
+        assert_eq!(found.tags, vec!["secret"]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_saves_of_one_trigger() {
+        let (repository, _temp_dir) = create_test_repository().await;
+        let first = Snippet::new("::race".to_string(), "First".to_string()).unwrap();
+        let second = Snippet::new("::race".to_string(), "Second".to_string()).unwrap();
+
+        // Neither checks first, so only the database can stop the second
+        let (a, b) = tokio::join!(repository.save(&first), repository.save(&second));
+        let errors: Vec<anyhow::Error> = [a, b].into_iter().filter_map(Result::err).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].downcast_ref::<DuplicateTrigger>(),
+            Some(&DuplicateTrigger("::race".to_string()))
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "A snippet with trigger '::race' already exists"
+        );
+
+        assert_eq!(repository.count_all().await.unwrap(), 1);
+    }
 }
@@ -62,51 +62,554 @@ impl KeyboardMonitor {
     pub fn stop_monitoring(&self) {
         // No-op for CLI builds
     }
+
+    /// The `KeyboardEvent` for a key press or release from rdev, or `None`
+    /// for mouse events. `key` is rdev's key name (`KeyQ`, `ShiftLeft`),
+    /// `text` the character the layout produced and `timestamp` when the OS
+    /// saw the key, not when it was converted. Only keys rdev doesn't know
+    /// carry a `key_code`; the rest are identified by name.
+    #[cfg(feature = "system-integration")]
+    pub fn convert_event(event: &rdev::Event) -> Option<KeyboardEvent> {
+        let (event_type, key) = match event.event_type {
+            rdev::EventType::KeyPress(key) => (KeyboardEventType::KeyDown, key),
+            rdev::EventType::KeyRelease(key) => (KeyboardEventType::KeyUp, key),
+            _ => return None,
+        };
+        let (key, key_code) = match key {
+            rdev::Key::Unknown(code) => ("Unknown".to_string(), code),
+            key => (format!("{:?}", key), 0),
+        };
+
+        Some(KeyboardEvent {
+            event_type,
+            key_code,
+            key,
+            text: event.name.clone(),
+            timestamp: chrono::DateTime::<chrono::Utc>::from(event.time),
+        })
+    }
+
+    /// The text a key event should contribute to the typing buffer.
+    ///
+    /// The character the OS reports (rdev's `name`) follows the user's
+    /// layout, so it's used whenever present. Only without one do we fall
+    /// back to the US-QWERTY meaning of the physical key, which is wrong on
+    /// AZERTY or QWERTZ keyboards. Whitespace and control characters are left
+    /// to the key name, so Space, Return and Backspace stay recognisable.
+    pub fn key_to_string(event: &KeyboardEvent) -> String {
+        match Self::os_text(event) {
+            Some(text) => text.to_string(),
+            None => Self::physical_key_to_string(&event.key),
+        }
+    }
+
+    /// What a key event types, or `None` for keys such as Backspace that
+    /// only have a name. The OS's text can be several characters at once,
+    /// e.g. a word an input method commits or a letter with its accent.
+    pub fn typed_text(event: &KeyboardEvent) -> Option<String> {
+        if let Some(text) = Self::os_text(event) {
+            return Some(text.to_string());
+        }
+        let key = Self::physical_key_to_string(&event.key);
+        (key.chars().count() == 1).then_some(key)
+    }
+
+    fn os_text(event: &KeyboardEvent) -> Option<&str> {
+        event
+            .text
+            .as_deref()
+            .filter(|text| !text.is_empty())
+            .filter(|text| !text.chars().any(|c| c.is_control() || c.is_whitespace()))
+    }
+
+    /// US-QWERTY character for an rdev key name such as `KeyQ` or `Num1`.
+    /// Names with no character mapping are returned unchanged. Hotkeys are
+    /// matched on these, since the layout's text is unreliable with Ctrl held.
+    pub fn physical_key_to_string(key: &str) -> String {
+        if let Some(letter) = key.strip_prefix("Key") {
+            if letter.len() == 1 {
+                return letter.to_ascii_lowercase();
+            }
+        }
+        if let Some(digit) = key.strip_prefix("Num") {
+            if digit.len() == 1 {
+                return digit.to_string();
+            }
+        }
+
+        let symbol = match key {
+            "Minus" => "-",
+            "Equal" => "=",
+            "LeftBracket" => "[",
+            "RightBracket" => "]",
+            "SemiColon" => ";",
+            "Quote" => "'",
+            "BackQuote" => "`",
+            "BackSlash" | "IntlBackslash" => "\\",
+            "Comma" => ",",
+            "Dot" => ".",
+            "Slash" => "/",
+            other => other,
+        };
+        symbol.to_string()
+    }
+}
+
+/// The application with keyboard focus, by name or bundle id. CLI builds
+/// can't tell, so this is always `None`.
+pub fn active_application() -> Option<String> {
+    None
 }
 
 /// Stub input simulator (no-op for CLI builds)
-pub struct InputSimulator;
+///
+/// Nothing reaches the OS, but typed text is recorded so callers can check
+/// what would have been typed.
+#[derive(Default)]
+pub struct InputSimulator {
+    typed: std::sync::Mutex<String>,
+    backspaces: std::sync::Mutex<usize>,
+    delays: std::sync::Mutex<Vec<u64>>,
+}
 
 impl InputSimulator {
     pub fn new() -> anyhow::Result<Self> {
-        Ok(Self)
+        Ok(Self::default())
     }
 
-    pub fn type_text(&self, _text: &str) -> anyhow::Result<()> {
-        // No-op for CLI builds
+    pub fn type_text(&self, text: &str) -> anyhow::Result<()> {
+        self.typed
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Input simulator lock poisoned"))?
+            .push_str(text);
         Ok(())
     }
 
-    pub fn simulate_backspace(&self, _count: usize) -> anyhow::Result<()> {
+    /// Type `text` one character at a time, pausing `delay_ms` between
+    /// characters for applications that drop fast input
+    pub fn type_text_with_delay(&self, text: &str, delay_ms: u64) -> anyhow::Result<()> {
+        // Nothing is typed for real, so record the delay instead of sleeping
+        self.delays
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Input simulator lock poisoned"))?
+            .push(delay_ms);
+        self.type_text(text)
+    }
+
+    /// Delay of each `type_text_with_delay` call so far, in call order
+    pub fn type_delays(&self) -> Vec<u64> {
+        self.delays
+            .lock()
+            .map(|delays| delays.clone())
+            .unwrap_or_default()
+    }
+
+    /// Everything passed to `type_text` so far
+    pub fn typed_text(&self) -> String {
+        self.typed
+            .lock()
+            .map(|typed| typed.clone())
+            .unwrap_or_default()
+    }
+
+    /// Total backspaces sent so far
+    pub fn backspace_count(&self) -> usize {
+        self.backspaces
+            .lock()
+            .map(|count| *count)
+            .unwrap_or_default()
+    }
+
+    pub fn simulate_backspace(&self, count: usize) -> anyhow::Result<()> {
+        // Nothing reaches the keyboard in CLI builds; just keep count
+        *self
+            .backspaces
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Input simulator lock poisoned"))? += count;
+        Ok(())
+    }
+
+    pub fn paste(&self) -> anyhow::Result<()> {
         // No-op for CLI builds
         Ok(())
     }
 
     pub fn replace_trigger_with_expansion(
         &self,
-        _trigger_length: usize,
-        _expanded_text: &str,
+        trigger_length: usize,
+        expanded_text: &str,
     ) -> anyhow::Result<()> {
-        // No-op for CLI builds
-        Ok(())
+        self.simulate_backspace(trigger_length)?;
+        self.type_text(expanded_text)
     }
 }
 
-/// Stub clipboard manager (no-op for CLI builds)
-pub struct ClipboardManager;
+/// Where the engine types expansions. Input can't be simulated everywhere
+/// (e.g. on Wayland or in a headless session), and then the engine runs
+/// monitor-only: triggers are still detected and expanded, but never typed.
+pub enum InputBackend {
+    Simulator(InputSimulator),
+    Unavailable { reason: String },
+}
+
+impl InputBackend {
+    /// The input simulator, or `Unavailable` with a warning if it can't be created
+    pub fn detect() -> Self {
+        match InputSimulator::new() {
+            Ok(simulator) => Self::Simulator(simulator),
+            Err(e) => {
+                log::warn!(
+                    "Input simulation is unavailable ({}); expansions will be detected but not typed",
+                    e
+                );
+                Self::unavailable(e.to_string())
+            }
+        }
+    }
+
+    pub fn unavailable(reason: impl Into<String>) -> Self {
+        Self::Unavailable {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        matches!(self, Self::Simulator(_))
+    }
+
+    /// Why input can't be simulated, if it can't
+    pub fn unavailable_reason(&self) -> Option<&str> {
+        match self {
+            Self::Simulator(_) => None,
+            Self::Unavailable { reason } => Some(reason),
+        }
+    }
+
+    pub fn simulator(&self) -> anyhow::Result<&InputSimulator> {
+        match self {
+            Self::Simulator(simulator) => Ok(simulator),
+            Self::Unavailable { reason } => Err(anyhow::anyhow!(
+                "Input simulation is unavailable: {}",
+                reason
+            )),
+        }
+    }
+}
+
+/// Clipboard manager, in-memory unless it comes from `system`
+///
+/// In-memory contents live only as long as the manager, so nothing reaches
+/// the system clipboard, but `save`/`restore` sequencing behaves like the
+/// real thing.
+#[derive(Default)]
+pub struct ClipboardManager {
+    contents: std::sync::Mutex<String>,
+    saved: std::sync::Mutex<Option<String>>,
+    /// The system clipboard, which `get_text` and `set_text` use when set
+    #[cfg(feature = "system-integration")]
+    system: Option<std::sync::Mutex<clipboard::ClipboardContext>>,
+    /// Serves the HTML put there by `set_html` until the clipboard changes
+    #[cfg(all(feature = "system-integration", target_os = "linux"))]
+    html: std::sync::Mutex<Option<x11_clipboard::Clipboard>>,
+}
 
 impl ClipboardManager {
     pub fn new() -> anyhow::Result<Self> {
-        Ok(Self)
+        Ok(Self::default())
+    }
+
+    /// A manager that must reach the real system clipboard, for text that has
+    /// to outlive the process (e.g. `expand --copy`). Fails when no clipboard
+    /// can be reached, such as without a display.
+    #[cfg(feature = "system-integration")]
+    pub fn system() -> anyhow::Result<Self> {
+        use clipboard::ClipboardProvider;
+
+        let context = clipboard::ClipboardContext::new()
+            .map_err(|e| anyhow::anyhow!("The system clipboard isn't available: {}", e))?;
+        Ok(Self {
+            system: Some(std::sync::Mutex::new(context)),
+            ..Self::default()
+        })
+    }
+
+    /// CLI-only builds have no system clipboard, so this always fails
+    #[cfg(not(feature = "system-integration"))]
+    pub fn system() -> anyhow::Result<Self> {
+        Err(anyhow::anyhow!(
+            "The system clipboard isn't available in this build; rebuild with `--features system-integration`"
+        ))
     }
 
     pub fn get_text(&self) -> anyhow::Result<String> {
-        // Return empty string for CLI builds
-        Ok(String::new())
+        #[cfg(feature = "system-integration")]
+        if let Some(system) = &self.system {
+            use clipboard::ClipboardProvider;
+
+            return Self::lock_system(system)?
+                .get_contents()
+                .map_err(|e| anyhow::anyhow!("Failed to read the clipboard: {}", e));
+        }
+
+        Ok(self.lock_contents()?.clone())
     }
 
-    pub fn set_text(&self, _text: &str) -> anyhow::Result<()> {
-        // No-op for CLI builds
+    pub fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        #[cfg(feature = "system-integration")]
+        if let Some(system) = &self.system {
+            use clipboard::ClipboardProvider;
+
+            return Self::lock_system(system)?
+                .set_contents(text.to_string())
+                .map_err(|e| anyhow::anyhow!("Failed to write the clipboard: {}", e));
+        }
+
+        *self.lock_contents()? = text.to_string();
         Ok(())
     }
+
+    #[cfg(feature = "system-integration")]
+    fn lock_system(
+        system: &std::sync::Mutex<clipboard::ClipboardContext>,
+    ) -> anyhow::Result<std::sync::MutexGuard<'_, clipboard::ClipboardContext>> {
+        system
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Clipboard lock poisoned"))
+    }
+
+    /// Stash the current contents so they can be put back with `restore`
+    pub fn save(&self) -> anyhow::Result<()> {
+        let current = self.get_text()?;
+        *self.lock_saved()? = Some(current);
+        Ok(())
+    }
+
+    /// Put back the contents stashed by `save`, returning them.
+    ///
+    /// Returns `None` and leaves the clipboard untouched if nothing was saved.
+    pub fn restore(&self) -> anyhow::Result<Option<String>> {
+        let saved = self.lock_saved()?.take();
+        if let Some(text) = &saved {
+            self.set_text(text)?;
+        }
+        Ok(saved)
+    }
+
+    fn lock_contents(&self) -> anyhow::Result<std::sync::MutexGuard<'_, String>> {
+        self.contents
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Clipboard lock poisoned"))
+    }
+
+    fn lock_saved(&self) -> anyhow::Result<std::sync::MutexGuard<'_, Option<String>>> {
+        self.saved
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Clipboard lock poisoned"))
+    }
+
+    /// Put `html` on the system clipboard for rich-text apps to paste. Only
+    /// an X11 clipboard can hold HTML here, so in-memory managers fail and
+    /// callers can fall back to plain text.
+    #[cfg(all(feature = "system-integration", target_os = "linux"))]
+    pub fn set_html(&self, html: &str) -> anyhow::Result<()> {
+        if self.system.is_none() {
+            return Err(anyhow::anyhow!("HTML can't be put on this clipboard"));
+        }
+
+        let clipboard = x11_clipboard::Clipboard::new()
+            .map_err(|e| anyhow::anyhow!("The system clipboard isn't available: {}", e))?;
+        let target = clipboard
+            .setter
+            .get_atom("text/html")
+            .map_err(|e| anyhow::anyhow!("Failed to write HTML to the clipboard: {}", e))?;
+        clipboard
+            .store(clipboard.setter.atoms.clipboard, target, html)
+            .map_err(|e| anyhow::anyhow!("Failed to write HTML to the clipboard: {}", e))?;
+        *self
+            .html
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Clipboard lock poisoned"))? = Some(clipboard);
+        Ok(())
+    }
+
+    /// No clipboard here can hold HTML, so this always fails and callers
+    /// fall back to plain text
+    #[cfg(not(all(feature = "system-integration", target_os = "linux")))]
+    pub fn set_html(&self, _html: &str) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("HTML can't be put on this clipboard"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(key: &str, text: Option<&str>) -> KeyboardEvent {
+        KeyboardEvent {
+            event_type: KeyboardEventType::KeyDown,
+            key_code: 0,
+            key: key.to_string(),
+            text: text.map(str::to_string),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_stub_has_no_system_clipboard() {
+        let error = ClipboardManager::system().err().unwrap();
+        assert!(error.to_string().contains("system-integration"));
+    }
+
+    #[test]
+    fn test_in_memory_clipboard_refuses_html() {
+        let clipboard = ClipboardManager::new().unwrap();
+        clipboard.set_text("original").unwrap();
+        assert!(clipboard.set_html("<b>bold</b>").is_err());
+        assert_eq!(clipboard.get_text().unwrap(), "original");
+    }
+
+    #[test]
+    fn test_key_to_string_prefers_os_character() {
+        // AZERTY: the key in the QWERTY "Q" position types "a"
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("KeyQ", Some("a"))),
+            "a"
+        );
+        // QWERTZ: the QWERTY "Y" position types "z"
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("KeyY", Some("z"))),
+            "z"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("Num2", Some("é"))),
+            "é"
+        );
+    }
+
+    #[test]
+    fn test_typed_text() {
+        assert_eq!(
+            KeyboardMonitor::typed_text(&key_event("Unknown", Some("日本"))).as_deref(),
+            Some("日本")
+        );
+        assert_eq!(
+            KeyboardMonitor::typed_text(&key_event("KeyQ", None)).as_deref(),
+            Some("q")
+        );
+        for event in [
+            key_event("Backspace", Some("\u{8}")),
+            key_event("Space", Some(" ")),
+            key_event("Return", None),
+        ] {
+            assert_eq!(KeyboardMonitor::typed_text(&event), None, "{}", event.key);
+        }
+    }
+
+    #[test]
+    fn test_key_to_string_falls_back_to_physical_key() {
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("KeyQ", None)),
+            "q"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("Num7", None)),
+            "7"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("SemiColon", None)),
+            ";"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("KeyQ", Some(""))),
+            "q"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("Escape", None)),
+            "Escape"
+        );
+    }
+
+    #[test]
+    fn test_key_to_string_keeps_special_key_names() {
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("Space", Some(" "))),
+            "Space"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("Return", Some("\r"))),
+            "Return"
+        );
+        assert_eq!(
+            KeyboardMonitor::key_to_string(&key_event("Backspace", Some("\u{8}"))),
+            "Backspace"
+        );
+    }
+
+    #[cfg(feature = "system-integration")]
+    #[test]
+    fn test_convert_event_fills_text_and_timestamp() {
+        use std::time::{Duration, SystemTime};
+
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let event = KeyboardMonitor::convert_event(&rdev::Event {
+            time,
+            name: Some("a".to_string()),
+            event_type: rdev::EventType::KeyPress(rdev::Key::KeyQ),
+        })
+        .unwrap();
+        assert_eq!(event.event_type, KeyboardEventType::KeyDown);
+        assert_eq!(event.key, "KeyQ");
+        assert_eq!(event.text.as_deref(), Some("a"));
+        assert_eq!(event.timestamp, chrono::DateTime::<chrono::Utc>::from(time));
+        assert_eq!(KeyboardMonitor::key_to_string(&event), "a");
+
+        let release = KeyboardMonitor::convert_event(&rdev::Event {
+            time,
+            name: None,
+            event_type: rdev::EventType::KeyRelease(rdev::Key::Unknown(191)),
+        })
+        .unwrap();
+        assert_eq!(release.event_type, KeyboardEventType::KeyUp);
+        assert_eq!((release.key.as_str(), release.key_code), ("Unknown", 191));
+        assert_eq!(release.text, None);
+
+        let click = rdev::Event {
+            time,
+            name: None,
+            event_type: rdev::EventType::ButtonPress(rdev::Button::Left),
+        };
+        assert!(KeyboardMonitor::convert_event(&click).is_none());
+    }
+
+    #[test]
+    fn test_clipboard_stub_starts_empty() {
+        let clipboard = ClipboardManager::new().unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "");
+        assert_eq!(clipboard.restore().unwrap(), None);
+    }
+
+    #[test]
+    fn test_clipboard_set_and_get() {
+        let clipboard = ClipboardManager::new().unwrap();
+        clipboard.set_text("hello").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_clipboard_save_restore_sequence() {
+        let clipboard = ClipboardManager::new().unwrap();
+        clipboard.set_text("original").unwrap();
+
+        clipboard.save().unwrap();
+        clipboard.set_text("expansion").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "expansion");
+
+        assert_eq!(clipboard.restore().unwrap(), Some("original".to_string()));
+        assert_eq!(clipboard.get_text().unwrap(), "original");
+
+        // A second restore has nothing left to put back
+        clipboard.set_text("later").unwrap();
+        assert_eq!(clipboard.restore().unwrap(), None);
+        assert_eq!(clipboard.get_text().unwrap(), "later");
+    }
 }
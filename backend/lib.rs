@@ -4,6 +4,9 @@ pub mod app;
 pub mod domain;
 pub mod infra;
 
+#[cfg(any(test, feature = "testing"))]
+pub mod test_support;
+
 // Re-export commonly used types
 pub use app::*;
 pub use domain::entities::*;
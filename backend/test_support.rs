@@ -0,0 +1,135 @@
+//! Shared helpers for tests that need a ready-to-use `TypelyService` or
+//! want to look at what was logged
+
+use crate::app::services::TypelyService;
+use crate::domain::entities::Snippet;
+use crate::domain::repositories::SnippetRepository;
+use crate::infra::{DatabaseConnection, SqliteSnippetRepository};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::Once;
+
+/// Build a snippet with a valid trigger, panicking on invalid input
+pub fn snippet(trigger: &str, replacement: &str) -> Snippet {
+    Snippet::new(trigger.to_string(), replacement.to_string()).expect("valid test snippet")
+}
+
+/// An in-memory service pre-populated with `snippets`
+pub async fn seeded_service(snippets: impl IntoIterator<Item = Snippet>) -> TypelyService {
+    let connection = DatabaseConnection::new_in_memory()
+        .await
+        .expect("in-memory database");
+
+    let repository = SqliteSnippetRepository::new(connection.pool().clone());
+    for snippet in snippets {
+        repository.save(&snippet).await.expect("seed snippet");
+    }
+
+    TypelyService::new(connection).await
+}
+
+thread_local! {
+    static CAPTURED_LOGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Hands log records to whichever `LogCapture` is active on their thread
+struct ThreadLogger;
+
+impl log::Log for ThreadLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        CAPTURED_LOGS.with(|captured| {
+            if let Some(messages) = captured.borrow_mut().as_mut() {
+                messages.push(record.args().to_string());
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+static THREAD_LOGGER: ThreadLogger = ThreadLogger;
+
+/// Collects the messages logged on the current thread, at every level, until
+/// dropped. Async tests see their own logs as long as they run on a
+/// current-thread runtime, which `#[tokio::test]` is by default.
+pub struct LogCapture {
+    // Capturing is per thread, so the capture must stay on it
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl LogCapture {
+    pub fn start() -> Self {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&THREAD_LOGGER).expect("no other logger in tests");
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+
+        CAPTURED_LOGS.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+        Self {
+            _thread_bound: PhantomData,
+        }
+    }
+
+    /// Everything logged on this thread since `start`
+    pub fn messages(&self) -> Vec<String> {
+        CAPTURED_LOGS.with(|captured| captured.borrow().clone().unwrap_or_default())
+    }
+}
+
+impl Drop for LogCapture {
+    fn drop(&mut self) {
+        CAPTURED_LOGS.with(|captured| *captured.borrow_mut() = None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_seeded_service_contains_snippets() {
+        let mut inactive = snippet("::old", "Old text");
+        inactive.deactivate();
+
+        let service = seeded_service(vec![snippet("::hello", "Hello!"), inactive]).await;
+
+        let hello = service
+            .get_snippet_by_trigger("::hello")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hello.replacement, "Hello!");
+
+        let old = service
+            .get_snippet_by_trigger("::old")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!old.is_active);
+
+        assert_eq!(service.get_all_active_snippets().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_log_capture_sees_only_its_own_thread() {
+        let capture = LogCapture::start();
+        log::debug!("on this thread");
+        std::thread::spawn(|| log::debug!("on another thread"))
+            .join()
+            .unwrap();
+
+        assert_eq!(capture.messages(), vec!["on this thread"]);
+    }
+
+    #[tokio::test]
+    async fn test_seeded_service_starts_empty() {
+        let service = seeded_service(Vec::new()).await;
+
+        assert!(service.get_all_active_snippets().await.unwrap().is_empty());
+    }
+}
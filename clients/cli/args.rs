@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Utc};
+use clap::{ArgGroup, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(author, version, about = "Typely - Text expansion made easy", long_about = None)]
@@ -13,6 +15,58 @@ pub struct TypelyArgs {
     /// Database file path (optional)
     #[arg(short, long)]
     pub database: Option<String>,
+
+    /// Use a named profile's database (~/.typely/profiles/<NAME>.db)
+    #[arg(short, long, conflicts_with = "database")]
+    pub profile: Option<String>,
+
+    /// Show what add, remove, update, import and other changes would do without saving them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Record expansions in ~/.typely/expansions.log
+    #[arg(long)]
+    pub log_expansions: bool,
+
+    /// Keep triggers out of logs and the expansion log; only counts are recorded
+    #[arg(long)]
+    pub privacy_mode: bool,
+
+    /// Only expand {env:NAME} for these environment variables (comma-separated)
+    #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+    pub allow_env: Option<Vec<String>>,
+
+    /// Let {file:PATH} read files from this directory (off unless set)
+    #[arg(long, value_name = "DIR")]
+    pub file_dir: Option<PathBuf>,
+
+    /// Time zone for date and time placeholders, e.g. Europe/Berlin (defaults to the system's)
+    #[arg(long, value_name = "ZONE")]
+    pub timezone: Option<String>,
+
+    /// Turn straight quotes into curly quotes in every expansion
+    #[arg(long)]
+    pub smart_quotes: bool,
+
+    /// Drop spaces and tabs at the end of each line of every expansion
+    #[arg(long)]
+    pub trim_trailing: bool,
+
+    /// Leave the trigger prefix out when showing snippets (stored triggers are unchanged)
+    #[arg(long)]
+    pub hide_prefix: bool,
+
+    /// Trigger prefix left out by --hide-prefix
+    #[arg(long, value_name = "PREFIX", default_value = "::")]
+    pub trigger_prefix: String,
+
+    /// Log output format (text, json)
+    #[arg(long, default_value = "text")]
+    pub log_format: String,
+
+    /// Print without colors (also the default when NO_COLOR is set or output isn't a terminal)
+    #[arg(long)]
+    pub no_color: bool,
 }
 
 #[derive(Subcommand)]
@@ -22,10 +76,39 @@ pub enum TypelyCommand {
         /// Trigger text (e.g., "::hello")
         trigger: String,
         /// Replacement text
-        replacement: String,
+        #[arg(required_unless_present_any = ["stdin", "editor"], conflicts_with_all = ["stdin", "editor"])]
+        replacement: Option<String>,
+        /// Read the replacement from stdin until EOF, for multi-line text
+        #[arg(long, conflicts_with = "editor")]
+        stdin: bool,
+        /// Write the replacement in $EDITOR
+        #[arg(long)]
+        editor: bool,
         /// Tags (comma-separated)
         #[arg(short, long)]
         tags: Option<String>,
+        /// Content type (plain, html, markdown)
+        #[arg(long)]
+        content_type: Option<String>,
+        /// Ask for confirmation before expanding
+        #[arg(long)]
+        confirm: bool,
+        /// Keep the trigger and type the expansion after it
+        #[arg(long)]
+        keep_trigger: bool,
+        /// Other triggers for the same snippet (comma-separated)
+        #[arg(short, long)]
+        aliases: Option<String>,
+        /// Milliseconds to wait between typed characters, for slow apps
+        #[arg(long, value_name = "MS")]
+        type_delay: Option<u64>,
+        /// Also expand the Title and UPPER case forms of the trigger, in
+        /// that case (::Btw -> "By the way")
+        #[arg(long)]
+        case_variants: bool,
+        /// Store the replacement encrypted, with the passphrase in TYPELY_PASSPHRASE
+        #[arg(long)]
+        encrypt: bool,
     },
 
     /// Remove a snippet by trigger
@@ -34,6 +117,26 @@ pub enum TypelyCommand {
         trigger: String,
     },
 
+    /// Copy a snippet under a new trigger
+    Duplicate {
+        /// Trigger of the snippet to copy
+        trigger: String,
+        /// Trigger for the copy
+        new_trigger: String,
+    },
+
+    /// Pin a snippet so listings show it first
+    Pin {
+        /// Trigger of the snippet to pin
+        trigger: String,
+    },
+
+    /// Unpin a snippet
+    Unpin {
+        /// Trigger of the snippet to unpin
+        trigger: String,
+    },
+
     /// List all snippets
     List {
         /// Search term to filter snippets
@@ -48,9 +151,21 @@ pub enum TypelyCommand {
         /// Filter by tags (comma-separated)
         #[arg(short, long)]
         tags: Option<String>,
-        /// Limit number of results
-        #[arg(short, long)]
+        /// Match snippets having any or all of the tags (any, all)
+        #[arg(long, default_value = "any")]
+        tag_match: String,
+        /// Show only snippets imported from this source (pack or file name)
+        #[arg(long)]
+        source: Option<String>,
+        /// Limit number of results (at least 1)
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
         limit: Option<u32>,
+        /// Skip this many results first, to show a later page
+        #[arg(long, requires = "limit", conflicts_with = "after")]
+        offset: Option<u32>,
+        /// Continue after the cursor printed at the end of the previous page
+        #[arg(long, value_name = "CURSOR", requires = "limit")]
+        after: Option<String>,
         /// Sort by (trigger, created, updated, usage)
         #[arg(long, default_value = "updated")]
         sort: String,
@@ -65,6 +180,16 @@ pub enum TypelyCommand {
         trigger: String,
     },
 
+    /// Show past versions of a snippet, or of every snippet with --since
+    History {
+        /// Trigger text of the snippet
+        #[arg(required_unless_present = "since")]
+        trigger: Option<String>,
+        /// Only versions recorded at or after this time: RFC 3339, a date (YYYY-MM-DD), or a duration ago such as 30m, 12h, 7d or 2w
+        #[arg(long)]
+        since: Option<String>,
+    },
+
     /// Update a snippet
     Update {
         /// Trigger text to update
@@ -84,20 +209,97 @@ pub enum TypelyCommand {
         /// Deactivate the snippet
         #[arg(long)]
         deactivate: bool,
+        /// New content type (plain, html, markdown)
+        #[arg(long)]
+        content_type: Option<String>,
+        /// Ask for confirmation before expanding
+        #[arg(long, conflicts_with = "no_confirm")]
+        confirm: bool,
+        /// Expand without asking for confirmation
+        #[arg(long)]
+        no_confirm: bool,
+        /// Keep the trigger and type the expansion after it
+        #[arg(long, conflicts_with = "replace_trigger")]
+        keep_trigger: bool,
+        /// Replace the trigger with the expansion (the default)
+        #[arg(long)]
+        replace_trigger: bool,
+        /// Replace the aliases (comma-separated; "" removes them all)
+        #[arg(short, long)]
+        aliases: Option<String>,
+        /// Milliseconds to wait between typed characters, for slow apps
+        #[arg(long, value_name = "MS", conflicts_with = "default_type_delay")]
+        type_delay: Option<u64>,
+        /// Go back to the engine's default typing delay
+        #[arg(long)]
+        default_type_delay: bool,
+        /// Expand the snippet when this key combination is pressed, e.g. "Ctrl+Alt+1"
+        #[arg(long, value_name = "COMBO", conflicts_with = "no_hotkey")]
+        hotkey: Option<String>,
+        /// Remove the snippet's hotkey
+        #[arg(long)]
+        no_hotkey: bool,
+        /// Only expand from this time on (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "TIME", conflicts_with = "always_active")]
+        active_from: Option<String>,
+        /// Stop expanding at this time (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "TIME", conflicts_with = "always_active")]
+        active_until: Option<String>,
+        /// Remove the active window, so the snippet expands at any time
+        #[arg(long)]
+        always_active: bool,
+        /// Store the replacement encrypted, with the passphrase in TYPELY_PASSPHRASE
+        #[arg(long, conflicts_with = "decrypt")]
+        encrypt: bool,
+        /// Store the replacement as plain text again
+        #[arg(long)]
+        decrypt: bool,
     },
 
     /// Import snippets from a file
     Import {
-        /// JSON file to import from
-        file: String,
+        /// File to import from
+        #[arg(required_unless_present = "dir")]
+        file: Option<String>,
+        /// Import every .txt file in this directory, named after the file, instead of a file
+        #[arg(long, conflicts_with_all = ["file", "format", "lenient"])]
+        dir: Option<String>,
+        /// Prefix added to the triggers of snippets imported with --dir (e.g. "::")
+        #[arg(long, requires = "dir")]
+        prefix: Option<String>,
         /// Overwrite existing snippets
         #[arg(short, long)]
         overwrite: bool,
+        /// Keep existing snippets, deactivated under a suffixed trigger, and
+        /// import the new ones under the trigger
+        #[arg(long, conflicts_with = "overwrite")]
+        deactivate_old: bool,
+        /// Input format (json, csv)
+        #[arg(short, long, default_value = "json")]
+        format: String,
+        /// Source recorded on imported snippets (defaults to the file or directory name)
+        #[arg(long)]
+        source: Option<String>,
+        /// Import the readable records of a JSON file and report the rest instead of failing
+        #[arg(long)]
+        lenient: bool,
+    },
+
+    /// Import a JSON export and keep the snippets in step with it
+    Sync {
+        /// JSON file to sync from
+        file: String,
+        /// Keep running and sync again whenever the file changes
+        #[arg(short, long)]
+        watch: bool,
+        /// Keep snippets that were removed from the file
+        #[arg(long)]
+        keep_missing: bool,
     },
 
     /// Export snippets to a file
     Export {
-        /// JSON file to export to
+        /// File to export to
         file: String,
         /// Include inactive snippets
         #[arg(short, long)]
@@ -105,12 +307,24 @@ pub enum TypelyCommand {
         /// Export only specific tags (comma-separated)
         #[arg(short, long)]
         tags: Option<String>,
+        /// Output format (json, csv, shell)
+        #[arg(short, long, default_value = "json")]
+        format: String,
     },
 
     /// Test snippet expansion
     Expand {
         /// Trigger text to expand
         trigger: String,
+        /// Put the expansion on the clipboard instead of printing it
+        #[arg(long)]
+        copy: bool,
+    },
+
+    /// Expand all triggers in a piece of text
+    ExpandText {
+        /// Text to expand (reads from stdin when omitted)
+        text: Option<String>,
     },
 
     /// Search for snippets
@@ -120,10 +334,163 @@ pub enum TypelyCommand {
         /// Limit number of results
         #[arg(short, long, default_value = "10")]
         limit: u32,
+        /// Rank results by fuzzy match, tolerating typos
+        #[arg(long)]
+        fuzzy: bool,
+        /// Match the query as a regular expression against replacements
+        #[arg(long, conflicts_with = "fuzzy")]
+        regex: bool,
+        /// Look only in these fields (comma-separated: trigger, replacement, tags)
+        #[arg(
+            long = "in",
+            value_name = "FIELDS",
+            value_delimiter = ',',
+            conflicts_with_all = ["fuzzy", "regex"]
+        )]
+        fields: Vec<String>,
     },
 
     /// Show statistics
-    Stats,
+    Stats {
+        /// Print the statistics as JSON
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+        /// Print trigger,usage_count CSV rows for every snippet
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// Show active snippets picked at random, to try one out
+    Random {
+        /// Number of snippets to show
+        #[arg(short = 'n', long, default_value = "1")]
+        count: u32,
+    },
+
+    /// Activate every snippet matching the filters
+    #[command(group(ArgGroup::new("filter").required(true).multiple(true)))]
+    Enable {
+        /// Snippets with these tags (comma-separated)
+        #[arg(short, long, group = "filter")]
+        tags: Option<String>,
+        /// Match snippets having any or all of the tags (any, all)
+        #[arg(long, default_value = "any")]
+        tag_match: String,
+        /// Snippets whose trigger or replacement contains this text
+        #[arg(short, long, group = "filter")]
+        search: Option<String>,
+    },
+
+    /// Deactivate every snippet matching the filters
+    #[command(group(ArgGroup::new("filter").required(true).multiple(true)))]
+    Disable {
+        /// Snippets with these tags (comma-separated)
+        #[arg(short, long, group = "filter")]
+        tags: Option<String>,
+        /// Match snippets having any or all of the tags (any, all)
+        #[arg(long, default_value = "any")]
+        tag_match: String,
+        /// Snippets whose trigger or replacement contains this text
+        #[arg(short, long, group = "filter")]
+        search: Option<String>,
+    },
+
+    /// Reset usage counts to zero
+    ResetUsage {
+        /// Reset only the snippet with this trigger
+        #[arg(long, conflicts_with = "all", required_unless_present = "all")]
+        trigger: Option<String>,
+        /// Reset every snippet
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Replace text in every snippet's replacement, e.g. an old domain
+    Sed {
+        /// Text to find, matched exactly (case included)
+        find: String,
+        /// Text to put in its place
+        replace: String,
+        /// Only show how many snippets would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check every snippet for problems
+    Validate,
+
+    /// Run pending database migrations
+    Migrate {
+        /// Show applied and pending migrations instead of running them
+        #[arg(long)]
+        status: bool,
+
+        /// Only apply migrations up to and including this version, e.g. 002
+        #[arg(long, value_name = "VERSION", conflicts_with = "status")]
+        to: Option<String>,
+    },
+
+    /// Manage reusable fragments, referenced from snippets as {fragment:NAME}
+    Fragment {
+        #[command(subcommand)]
+        action: FragmentCommand,
+    },
+
+    /// Manage tags across all snippets
+    Tag {
+        #[command(subcommand)]
+        action: TagCommand,
+    },
+
+    /// Check that the database, keyboard access and clipboard work, with hints for fixing them
+    Doctor,
+
+    /// Show the state of the background expansion engine
+    Status {
+        /// Status file written by the engine (defaults to ~/.typely/engine-status.json)
+        #[arg(long)]
+        file: Option<String>,
+    },
+
+    /// Browse snippets interactively
+    #[cfg(feature = "tui")]
+    Tui,
+
+    /// Print the JSON Schema of the request and response types
+    #[cfg(feature = "schema")]
+    Schema {
+        /// Only this type, e.g. SnippetDto
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FragmentCommand {
+    /// Create a fragment, or replace an existing one's content
+    Set {
+        /// Fragment name (letters, digits, '-' and '_')
+        name: String,
+        /// Fragment content
+        content: String,
+    },
+    /// List all fragments
+    List,
+    /// Remove a fragment
+    Remove {
+        /// Fragment name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagCommand {
+    /// Rename a tag on every snippet that has it
+    Rename {
+        /// Current tag name
+        from: String,
+        /// New tag name
+        to: String,
+    },
 }
 
 impl TypelyArgs {
@@ -134,4 +501,64 @@ impl TypelyArgs {
             .filter(|s| !s.is_empty())
             .collect()
     }
+
+    /// An RFC 3339 time such as "2026-07-01T09:00:00+02:00", or a date
+    /// ("2026-07-01") meaning its local midnight
+    pub fn parse_time(text: &str) -> anyhow::Result<DateTime<Utc>> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+            return Ok(time.with_timezone(&Utc));
+        }
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_time(NaiveTime::MIN).and_local_timezone(Local).earliest())
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| anyhow::anyhow!("Invalid time '{}' (expected YYYY-MM-DD or an RFC 3339 time)", text))
+    }
+
+    /// A whole number of seconds, minutes, hours, days or weeks: "90s",
+    /// "30m", "12h", "7d", "2w"
+    pub fn parse_duration(text: &str) -> anyhow::Result<Duration> {
+        let invalid = || anyhow::anyhow!("Invalid duration '{}' (expected a number and s, m, h, d or w, e.g. 7d)", text);
+        let split = text.len().checked_sub(1).filter(|&i| text.is_char_boundary(i)).ok_or_else(invalid)?;
+        let (amount, unit) = text.split_at(split);
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+        let duration = match unit {
+            "s" => Duration::try_seconds(amount),
+            "m" => Duration::try_minutes(amount),
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            "w" => Duration::try_weeks(amount),
+            _ => None,
+        };
+        duration.filter(|duration| *duration >= Duration::zero()).ok_or_else(invalid)
+    }
+
+    /// A time for `--since`: a duration back from `now`, or else any time
+    /// `parse_time` reads
+    pub fn parse_since(text: &str, now: DateTime<Utc>) -> anyhow::Result<DateTime<Utc>> {
+        if text.ends_with(|c: char| c.is_ascii_alphabetic()) && text.starts_with(|c: char| c.is_ascii_digit()) && !text.contains(['-', ':']) {
+            return Ok(now - Self::parse_duration(text)?);
+        }
+        Self::parse_time(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2026-06-08T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(TypelyArgs::parse_since("7d", now).unwrap(), now - Duration::days(7));
+        assert_eq!(TypelyArgs::parse_since("2w", now).unwrap(), now - Duration::weeks(2));
+        assert_eq!(TypelyArgs::parse_since("90s", now).unwrap(), now - Duration::seconds(90));
+        assert_eq!(TypelyArgs::parse_since("2026-06-01T09:00:00+02:00", now).unwrap().to_rfc3339(), "2026-06-01T07:00:00+00:00");
+
+        let error = TypelyArgs::parse_since("7y", now).unwrap_err();
+        assert!(error.to_string().contains("Invalid duration"), "{}", error);
+        for text in ["", "d", "-7d", "7", "yesterday", "9999999999999w"] {
+            assert!(TypelyArgs::parse_since(text, now).is_err(), "{}", text);
+        }
+    }
 }
\ No newline at end of file
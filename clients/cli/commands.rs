@@ -1,13 +1,18 @@
 use typely::app::services::TypelyService;
 use typely::app::dto::*;
-use crate::args::{TypelyCommand, TypelyArgs};
+use typely::domain::{ContentType, IssueSeverity, SearchField, SnippetQuery, TagMatch};
+use typely::infra::{get_default_status_path, run_diagnostics, CheckStatus, ClipboardManager, EngineStatus};
+use crate::args::{FragmentCommand, TagCommand, TypelyCommand, TypelyArgs};
 use anyhow::Result;
-use console::{style, Term};
+use console::{StyledObject, Term};
 use std::fs;
+use std::io::{BufWriter, Read, Write};
 
 pub struct TypelyCliHandler {
     service: TypelyService,
     term: Term,
+    trigger_display: TriggerDisplay,
+    colors_enabled: bool,
 }
 
 impl TypelyCliHandler {
@@ -15,51 +20,190 @@ impl TypelyCliHandler {
         Self {
             service,
             term: Term::stdout(),
+            trigger_display: TriggerDisplay::default(),
+            colors_enabled: console::colors_enabled(),
         }
     }
 
+    /// Print with or without colors, whatever the terminal supports
+    pub fn with_colors(mut self, enabled: bool) -> Self {
+        self.colors_enabled = enabled;
+        self
+    }
+
+    /// `console::style`, plain when colors are off
+    fn style<D>(&self, value: D) -> StyledObject<D> {
+        console::style(value).force_styling(self.colors_enabled)
+    }
+
+    /// Show triggers according to `display`, e.g. without their prefix
+    pub fn with_trigger_display(mut self, display: TriggerDisplay) -> Self {
+        self.trigger_display = display;
+        self
+    }
+
+    /// The trigger as printed; the snippet itself is left untouched
+    fn display_trigger<'a>(&self, trigger: &'a str) -> &'a str {
+        self.trigger_display.render(trigger)
+    }
+
     pub async fn handle_command(&self, command: TypelyCommand, verbose: bool) -> Result<()> {
         match command {
-            TypelyCommand::Add { trigger, replacement, tags } => {
-                self.handle_add(trigger, replacement, tags, verbose).await
+            TypelyCommand::Add { trigger, replacement, stdin: _, editor, tags, content_type, confirm, keep_trigger, aliases, type_delay, case_variants, encrypt } => {
+                let replacement = match replacement {
+                    Some(replacement) => replacement,
+                    None if editor => Self::replacement_from_editor()?,
+                    None => Self::replacement_from(std::io::stdin().lock())?,
+                };
+                self.handle_add(trigger, replacement, tags, content_type, confirm, keep_trigger, aliases, type_delay, case_variants, encrypt, verbose).await
             }
             TypelyCommand::Remove { trigger } => {
                 self.handle_remove(trigger, verbose).await
             }
-            TypelyCommand::List { search, active, inactive, tags, limit, sort, order } => {
-                self.handle_list(search, active, inactive, tags, limit, sort, order, verbose).await
+            TypelyCommand::Duplicate { trigger, new_trigger } => {
+                self.handle_duplicate(trigger, new_trigger, verbose).await
+            }
+            TypelyCommand::Pin { trigger } => {
+                self.handle_pin(trigger, true).await
+            }
+            TypelyCommand::Unpin { trigger } => {
+                self.handle_pin(trigger, false).await
+            }
+            TypelyCommand::List { search, active, inactive, tags, tag_match, source, limit, offset, after, sort, order } => {
+                self.handle_list(search, active, inactive, tags, tag_match, source, limit, offset, after, sort, order, verbose).await
             }
             TypelyCommand::Show { trigger } => {
                 self.handle_show(trigger).await
             }
-            TypelyCommand::Update { trigger, replacement, new_trigger, tags, activate, deactivate } => {
-                self.handle_update(trigger, replacement, new_trigger, tags, activate, deactivate, verbose).await
+            TypelyCommand::History { trigger, since } => {
+                self.handle_history(trigger, since).await
+            }
+            TypelyCommand::Update { trigger, replacement, new_trigger, tags, activate, deactivate, content_type, confirm, no_confirm, keep_trigger, replace_trigger, aliases, type_delay, default_type_delay, hotkey, no_hotkey, active_from, active_until, always_active, encrypt, decrypt } => {
+                self.handle_update(trigger, replacement, new_trigger, tags, activate, deactivate, content_type, confirm, no_confirm, keep_trigger, replace_trigger, aliases, type_delay, default_type_delay, hotkey, no_hotkey, active_from, active_until, always_active, encrypt, decrypt, verbose).await
+            }
+            TypelyCommand::Import { file, dir, prefix, overwrite, deactivate_old, format, source, lenient } => {
+                let strategy = if deactivate_old { ConflictStrategy::DeactivateOld } else { overwrite.into() };
+                match (file, dir) {
+                    (_, Some(dir)) => self.handle_import_dir(dir, strategy, prefix, source, verbose).await,
+                    (Some(file), None) => self.handle_import(file, strategy, format, source, lenient, verbose).await,
+                    (None, None) => Err(anyhow::anyhow!("Pass a file or --dir to import from")),
+                }
+            }
+            TypelyCommand::Sync { file, watch, keep_missing } => {
+                self.handle_sync(file, watch, keep_missing).await
+            }
+            TypelyCommand::Export { file, inactive, tags, format } => {
+                self.handle_export(file, inactive, tags, format, verbose).await
+            }
+            TypelyCommand::Expand { trigger, copy } => {
+                self.handle_expand(trigger, copy).await
+            }
+            TypelyCommand::ExpandText { text } => {
+                self.handle_expand_text(text).await
+            }
+            TypelyCommand::Search { query, limit, fuzzy, regex, fields } => {
+                self.handle_search(query, limit, fuzzy, regex, fields, verbose).await
+            }
+            TypelyCommand::Stats { json, csv } => {
+                self.handle_stats(json, csv, verbose).await
+            }
+            TypelyCommand::Random { count } => {
+                self.handle_random(count, verbose).await
+            }
+            TypelyCommand::Enable { tags, tag_match, search } => {
+                self.handle_set_active(tags, tag_match, search, true).await
             }
-            TypelyCommand::Import { file, overwrite } => {
-                self.handle_import(file, overwrite, verbose).await
+            TypelyCommand::Disable { tags, tag_match, search } => {
+                self.handle_set_active(tags, tag_match, search, false).await
             }
-            TypelyCommand::Export { file, inactive, tags } => {
-                self.handle_export(file, inactive, tags, verbose).await
+            TypelyCommand::Sed { find, replace, dry_run } => {
+                self.handle_sed(find, replace, dry_run).await
             }
-            TypelyCommand::Expand { trigger } => {
-                self.handle_expand(trigger).await
+            TypelyCommand::ResetUsage { trigger, all: _ } => {
+                self.handle_reset_usage(trigger).await
             }
-            TypelyCommand::Search { query, limit } => {
-                self.handle_search(query, limit, verbose).await
+            TypelyCommand::Validate => {
+                self.handle_validate().await
             }
-            TypelyCommand::Stats => {
-                self.handle_stats(verbose).await
+            TypelyCommand::Migrate { status, to } => {
+                self.handle_migrate(status, to).await
             }
+            TypelyCommand::Fragment { action } => {
+                self.handle_fragment(action).await
+            }
+            TypelyCommand::Tag { action } => {
+                self.handle_tag(action).await
+            }
+            // Handled in main, before the database is opened
+            TypelyCommand::Doctor => Ok(()),
+            TypelyCommand::Status { file } => {
+                self.handle_status(file)
+            }
+            #[cfg(feature = "tui")]
+            TypelyCommand::Tui => {
+                crate::tui::run(&self.service).await
+            }
+            // Handled in main, as it needs no database
+            #[cfg(feature = "schema")]
+            TypelyCommand::Schema { .. } => Ok(()),
         }
     }
 
-    async fn handle_add(&self, trigger: String, replacement: String, tags: Option<String>, verbose: bool) -> Result<()> {
+    /// A replacement read to EOF, without the final newline that `echo` or an
+    /// editor adds
+    fn replacement_from(mut reader: impl Read) -> Result<String> {
+        let mut replacement = String::new();
+        reader.read_to_string(&mut replacement)?;
+        let trimmed = replacement
+            .strip_suffix('\n')
+            .map(|rest| rest.strip_suffix('\r').unwrap_or(rest))
+            .unwrap_or(&replacement);
+        if trimmed.is_empty() {
+            anyhow::bail!("The replacement is empty, nothing was added");
+        }
+        Ok(trimmed.to_string())
+    }
+
+    /// A replacement written in `$EDITOR`, which may carry arguments such as
+    /// "code --wait"
+    fn replacement_from_editor() -> Result<String> {
+        let editor = std::env::var("EDITOR").unwrap_or_default();
+        let mut words = editor.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Set $EDITOR to write the replacement with --editor"))?;
+
+        let path = std::env::temp_dir().join(format!("typely-{}.txt", uuid::Uuid::new_v4()));
+        fs::write(&path, "")?;
+        let status = std::process::Command::new(program).args(words).arg(&path).status();
+        let replacement = match status {
+            Ok(status) if status.success() => fs::File::open(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(Self::replacement_from),
+            Ok(status) => Err(anyhow::anyhow!("{} exited with {}, nothing was added", program, status)),
+            Err(e) => Err(anyhow::anyhow!("Can't run {}: {}", program, e)),
+        };
+        let _ = fs::remove_file(&path);
+        replacement
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_add(&self, trigger: String, replacement: String, tags: Option<String>, content_type: Option<String>, confirm: bool, keep_trigger: bool, aliases: Option<String>, type_delay: Option<u64>, case_variants: bool, encrypt: bool, verbose: bool) -> Result<()> {
         let tags = tags.map(|t| TypelyArgs::parse_tags(&t));
-        
+        let aliases = aliases.map(|a| TypelyArgs::parse_tags(&a));
+        let content_type = content_type.map(|c| c.parse::<ContentType>()).transpose()?;
+
         let request = CreateSnippetRequest {
             trigger: trigger.clone(),
             replacement: replacement.clone(),
             tags,
+            content_type,
+            confirm_before_expand: Some(confirm),
+            keep_trigger: Some(keep_trigger),
+            aliases,
+            type_delay_ms: type_delay,
+            generate_case_variants: case_variants,
+            encrypted: encrypt,
         };
 
         match self.service.create_snippet(request).await {
@@ -104,7 +248,60 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_list(&self, search: Option<String>, active: bool, inactive: bool, tags: Option<String>, limit: Option<u32>, sort: String, order: String, verbose: bool) -> Result<()> {
+    async fn handle_duplicate(&self, trigger: String, new_trigger: String, verbose: bool) -> Result<()> {
+        let Some(snippet) = self.service.get_snippet_by_trigger(&trigger).await? else {
+            self.print_error(&format!("✗ Snippet '{}' not found", trigger))?;
+            return Ok(());
+        };
+
+        match self.service.duplicate_snippet(snippet.id, new_trigger).await {
+            Ok(copy) => {
+                self.print_success(&format!("✓ Copied '{}' to '{}'", snippet.trigger, copy.trigger))?;
+                if verbose {
+                    self.print_snippet_details(&copy)?;
+                }
+            }
+            Err(e) => {
+                self.print_error(&format!("✗ Failed to duplicate snippet: {}", e))?;
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_pin(&self, trigger: String, pinned: bool) -> Result<()> {
+        let Some(snippet) = self.service.get_snippet_by_trigger(&trigger).await? else {
+            self.print_error(&format!("✗ Snippet '{}' not found", trigger))?;
+            return Ok(());
+        };
+
+        let request = UpdateSnippetRequest {
+            id: snippet.id,
+            trigger: None,
+            replacement: None,
+            tags: None,
+            is_active: None,
+            content_type: None,
+            confirm_before_expand: None,
+            keep_trigger: None,
+            aliases: None,
+            type_delay_ms: None,
+            pinned: Some(pinned),
+            hotkey: None,
+            active_from: None,
+            active_until: None,
+            encrypted: None,
+        };
+        self.service.update_snippet(request).await?;
+
+        let action = if pinned { "Pinned" } else { "Unpinned" };
+        self.print_success(&format!("✓ {} snippet '{}'", action, snippet.trigger))?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_list(&self, search: Option<String>, active: bool, inactive: bool, tags: Option<String>, tag_match: String, source: Option<String>, limit: Option<u32>, offset: Option<u32>, after: Option<String>, sort: String, order: String, verbose: bool) -> Result<()> {
         let tags_filter = tags.map(|t| TypelyArgs::parse_tags(&t));
         
         let is_active = if inactive {
@@ -117,12 +314,16 @@ impl TypelyCliHandler {
 
         let request = SnippetListRequest {
             search_term: search,
+            search_fields: None,
             tags: tags_filter,
             is_active,
             limit,
-            offset: None,
+            offset,
             sort_by: Some(sort),
             sort_order: Some(order),
+            tag_match: Some(tag_match),
+            source,
+            after,
         };
 
         let response = self.service.list_snippets(request).await?;
@@ -137,6 +338,7 @@ impl TypelyCliHandler {
 
         let total_count = response.total_count;
         let snippets_len = response.snippets.len();
+        let page_info = response.page_info;
         
         for snippet in response.snippets {
             self.print_snippet_summary(&snippet, verbose)?;
@@ -148,6 +350,13 @@ impl TypelyCliHandler {
         if total_count > snippets_len as u64 {
             self.print_info(&format!("Showing {} of {} total snippets", snippets_len, total_count))?;
         }
+        // Pages fetched by cursor don't know their number
+        if limit.is_some() && page_info.current_page > 0 {
+            self.print_info(&format!("Page {} of {}", page_info.current_page, page_info.total_pages))?;
+        }
+        if let Some(cursor) = page_info.next_cursor {
+            self.print_info(&format!("Next page: --after '{}'", cursor))?;
+        }
 
         Ok(())
     }
@@ -165,7 +374,63 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_update(&self, trigger: String, replacement: Option<String>, new_trigger: Option<String>, tags: Option<String>, activate: bool, deactivate: bool, verbose: bool) -> Result<()> {
+    async fn handle_history(&self, trigger: Option<String>, since: Option<String>) -> Result<()> {
+        let since = since.map(|s| TypelyArgs::parse_since(&s, chrono::Utc::now())).transpose()?;
+        let since_text = |since: chrono::DateTime<chrono::Utc>| {
+            since.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()
+        };
+
+        let Some(trigger) = trigger else {
+            // Every snippet's edits; clap makes sure there's a --since
+            let since = since.ok_or_else(|| anyhow::anyhow!("Pass a trigger or --since"))?;
+            let versions = self.service.edits_since(since).await?;
+            if versions.is_empty() {
+                self.print_info(&format!("No edits since {}", since_text(since)))?;
+                return Ok(());
+            }
+            self.print_info(&format!("{} edit(s) since {}:", versions.len(), since_text(since)))?;
+            return self.print_versions(versions);
+        };
+
+        let snippet = match self.service.get_snippet_by_trigger(&trigger).await? {
+            Some(snippet) => snippet,
+            None => {
+                self.print_error(&format!("✗ Snippet '{}' not found", trigger))?;
+                return Ok(());
+            }
+        };
+
+        let mut versions = self.service.snippet_history(snippet.id).await?;
+        if let Some(since) = since {
+            versions.retain(|version| version.changed_at >= since);
+        }
+        if versions.is_empty() {
+            match since {
+                Some(since) => self.print_info(&format!("No previous versions of '{}' since {}", trigger, since_text(since)))?,
+                None => self.print_info(&format!("No previous versions of '{}'", trigger))?,
+            }
+            return Ok(());
+        }
+
+        self.print_info(&format!("{} previous version(s) of '{}':", versions.len(), trigger))?;
+        self.print_versions(versions)
+    }
+
+    fn print_versions(&self, versions: Vec<SnippetVersionDto>) -> Result<()> {
+        for version in versions {
+            self.term.write_line("")?;
+            self.term.write_line(&format!("{}  {}",
+                self.style(version.changed_at.format("%Y-%m-%d %H:%M:%S")).dim(),
+                self.style(&version.trigger).cyan()
+            ))?;
+            self.term.write_line(&format!("  {}", version.replacement))?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_update(&self, trigger: String, replacement: Option<String>, new_trigger: Option<String>, tags: Option<String>, activate: bool, deactivate: bool, content_type: Option<String>, confirm: bool, no_confirm: bool, keep_trigger: bool, replace_trigger: bool, aliases: Option<String>, type_delay: Option<u64>, default_type_delay: bool, hotkey: Option<String>, no_hotkey: bool, active_from: Option<String>, active_until: Option<String>, always_active: bool, encrypt: bool, decrypt: bool, verbose: bool) -> Result<()> {
         // First find the snippet
         let snippet = match self.service.get_snippet_by_trigger(&trigger).await? {
             Some(snippet) => snippet,
@@ -176,6 +441,8 @@ impl TypelyCliHandler {
         };
 
         let tags = tags.map(|t| TypelyArgs::parse_tags(&t));
+        let aliases = aliases.map(|a| TypelyArgs::parse_tags(&a));
+        let content_type = content_type.map(|c| c.parse::<ContentType>()).transpose()?;
         let is_active = if activate {
             Some(true)
         } else if deactivate {
@@ -183,6 +450,38 @@ impl TypelyCliHandler {
         } else {
             None
         };
+        let confirm_before_expand = if confirm {
+            Some(true)
+        } else if no_confirm {
+            Some(false)
+        } else {
+            None
+        };
+        let keep_trigger = if keep_trigger {
+            Some(true)
+        } else if replace_trigger {
+            Some(false)
+        } else {
+            None
+        };
+        let type_delay_ms = if default_type_delay {
+            Some(None)
+        } else {
+            type_delay.map(Some)
+        };
+        let hotkey = if no_hotkey {
+            Some(None)
+        } else {
+            hotkey.map(Some)
+        };
+        let (active_from, active_until) = if always_active {
+            (Some(None), Some(None))
+        } else {
+            (
+                active_from.map(|t| TypelyArgs::parse_time(&t)).transpose()?.map(Some),
+                active_until.map(|t| TypelyArgs::parse_time(&t)).transpose()?.map(Some),
+            )
+        };
 
         let request = UpdateSnippetRequest {
             id: snippet.id,
@@ -190,6 +489,22 @@ impl TypelyCliHandler {
             replacement: replacement.clone(),
             tags,
             is_active,
+            content_type,
+            confirm_before_expand,
+            keep_trigger,
+            aliases,
+            type_delay_ms,
+            pinned: None,
+            hotkey,
+            active_from,
+            active_until,
+            encrypted: if encrypt {
+                Some(true)
+            } else if decrypt {
+                Some(false)
+            } else {
+                None
+            },
         };
 
         match self.service.update_snippet(request).await {
@@ -208,13 +523,43 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_import(&self, file: String, overwrite: bool, verbose: bool) -> Result<()> {
-        let json_data = fs::read_to_string(&file)
+    async fn handle_import(&self, file: String, strategy: ConflictStrategy, format: String, source: Option<String>, lenient: bool, verbose: bool) -> Result<()> {
+        let format = format.parse::<ImportFormat>()?;
+        let data = fs::read_to_string(&file)
             .map_err(|e| anyhow::anyhow!("Failed to read file '{}': {}", file, e))?;
 
-        match self.service.import_from_json(&json_data, overwrite).await {
+        // Name the source after the file unless told otherwise
+        let source = source.or_else(|| {
+            std::path::Path::new(&file)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        });
+
+        let result = match format {
+            ImportFormat::Json if lenient => self.service.import_from_json_lenient(&data, strategy, source).await,
+            ImportFormat::Json => self.service.import_from_json_with_strategy(&data, strategy, source).await,
+            ImportFormat::Csv => self.service.import_from_csv_with_strategy(&data, strategy, source).await,
+        };
+
+        self.print_import_result(&file, result, verbose)
+    }
+
+    async fn handle_import_dir(&self, dir: String, strategy: ConflictStrategy, prefix: Option<String>, source: Option<String>, verbose: bool) -> Result<()> {
+        let path = std::path::Path::new(&dir);
+        // Name the source after the directory unless told otherwise
+        let source = source.or_else(|| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        });
+
+        let result = self.service.import_from_directory(path, strategy, prefix.as_deref(), source).await;
+        self.print_import_result(&dir, result, verbose)
+    }
+
+    fn print_import_result(&self, from: &str, result: Result<ImportResult>, verbose: bool) -> Result<()> {
+        match result {
             Ok(result) => {
-                self.print_success(&format!("✓ Import completed from '{}'", file))?;
+                self.print_success(&format!("✓ Import completed from '{}'", from))?;
                 self.term.write_line(&format!("  Imported: {}", result.imported_count))?;
                 self.term.write_line(&format!("  Skipped:  {}", result.skipped_count))?;
                 self.term.write_line(&format!("  Errors:   {}", result.error_count))?;
@@ -236,35 +581,130 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_export(&self, file: String, include_inactive: bool, tags: Option<String>, verbose: bool) -> Result<()> {
+    async fn handle_sync(&self, file: String, watch: bool, keep_missing: bool) -> Result<()> {
+        let path = std::path::PathBuf::from(&file);
+        let delete_missing = !keep_missing;
+
+        if !watch {
+            let result = self.service.sync_from_file(&path, delete_missing).await?;
+            return self.print_sync_result(&file, &result);
+        }
+
+        // Watch the directory rather than the file: editors often save by
+        // replacing the file, which would end a watch on the file itself
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let _ = tx.send(event);
+        })?;
+        let watched_dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => std::path::PathBuf::from("."),
+        };
+        notify::Watcher::watch(&mut watcher, &watched_dir, notify::RecursiveMode::NonRecursive)?;
+        let file_name = path.file_name().map(|name| name.to_os_string());
+
+        self.print_info(&format!("Watching '{}' for changes (Ctrl+C to stop)", file))?;
+        self.sync_once(&file, &path, delete_missing).await?;
+
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                event = rx.recv() => {
+                    let Some(event) = event else { break };
+                    let touches_file = match event {
+                        Ok(event) => {
+                            !event.kind.is_access()
+                                && event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+                        }
+                        Err(e) => {
+                            self.print_error(&format!("✗ Watch error: {}", e))?;
+                            false
+                        }
+                    };
+                    if !touches_file {
+                        continue;
+                    }
+
+                    // A save usually arrives as several events; let them settle
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                    while rx.try_recv().is_ok() {}
+                    self.sync_once(&file, &path, delete_missing).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sync and report the outcome, treating a bad file as something to
+    /// report rather than a reason to stop watching
+    async fn sync_once(&self, file: &str, path: &std::path::Path, delete_missing: bool) -> Result<()> {
+        match self.service.sync_from_file(path, delete_missing).await {
+            Ok(result) => self.print_sync_result(file, &result),
+            Err(e) => self.print_error(&format!("✗ Sync failed: {:#}", e)),
+        }
+    }
+
+    /// Report the writes a dry run skipped
+    pub fn report_dry_run(&self, operations: &[String]) -> Result<()> {
+        self.term.write_line("")?;
+        if operations.is_empty() {
+            self.print_info("Dry run: nothing would change")?;
+            return Ok(());
+        }
+
+        self.print_info(&format!("Dry run: {} change(s) not saved:", operations.len()))?;
+        for operation in operations {
+            self.term.write_line(&format!("  would {}", operation))?;
+        }
+        Ok(())
+    }
+
+    fn print_sync_result(&self, file: &str, result: &SyncResult) -> Result<()> {
+        self.print_success(&format!("✓ Synced from '{}'", file))?;
+        self.term.write_line(&format!("  Imported: {}", result.import.imported_count))?;
+        self.term.write_line(&format!("  Deleted:  {}", result.deleted.len()))?;
+        self.term.write_line(&format!("  Errors:   {}", result.import.error_count))?;
+        for error in &result.import.errors {
+            self.term.write_line(&format!("  {}", error))?;
+        }
+        for trigger in &result.deleted {
+            self.term.write_line(&format!("  - {}", trigger))?;
+        }
+        Ok(())
+    }
+
+    async fn handle_export(&self, file: String, include_inactive: bool, tags: Option<String>, format: String, verbose: bool) -> Result<()> {
         let tags_filter = tags.map(|t| TypelyArgs::parse_tags(&t));
+        let format = format.parse::<ExportFormat>()?;
 
         let request = ExportSnippetsRequest {
             include_inactive,
-            tags_filter: tags_filter.clone(),
+            tags_filter,
+        };
+
+        if format == ExportFormat::ShellAliases {
+            return self.handle_export_shell_aliases(file, request).await;
+        }
+
+        let output = fs::File::create(&file)
+            .map_err(|e| anyhow::anyhow!("Failed to write file '{}': {}", file, e))?;
+        let mut writer = BufWriter::new(output);
+
+        let result = match format {
+            ExportFormat::Csv => self.service.export_to_csv(request, &mut writer).await,
+            _ => self.service.export_to_writer(request, &mut writer).await,
         };
 
-        match self.service.export_to_json(request).await {
-            Ok(json_data) => {
-                fs::write(&file, json_data)
+        match result {
+            Ok(count) => {
+                writer.flush()
                     .map_err(|e| anyhow::anyhow!("Failed to write file '{}': {}", file, e))?;
 
                 self.print_success(&format!("✓ Exported snippets to '{}'", file))?;
 
                 if verbose {
-                    // Count exported snippets
-                    let count_request = SnippetListRequest {
-                        search_term: None,
-                        tags: tags_filter,
-                        is_active: if include_inactive { None } else { Some(true) },
-                        limit: None,
-                        offset: None,
-                        sort_by: None,
-                        sort_order: None,
-                    };
-
-                    let response = self.service.list_snippets(count_request).await?;
-                    self.term.write_line(&format!("  Exported {} snippet(s)", response.total_count))?;
+                    self.term.write_line(&format!("  Exported {} snippet(s)", count))?;
                 }
             }
             Err(e) => {
@@ -276,7 +716,54 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_expand(&self, trigger: String) -> Result<()> {
+    async fn handle_export_shell_aliases(&self, file: String, request: ExportSnippetsRequest) -> Result<()> {
+        let output = fs::File::create(&file)
+            .map_err(|e| anyhow::anyhow!("Failed to write file '{}': {}", file, e))?;
+        let mut writer = BufWriter::new(output);
+
+        let report = match self.service.export_shell_aliases(request, &mut writer).await {
+            Ok(report) => report,
+            Err(e) => {
+                self.print_error(&format!("✗ Export failed: {}", e))?;
+                return Err(e);
+            }
+        };
+        writer.flush()
+            .map_err(|e| anyhow::anyhow!("Failed to write file '{}': {}", file, e))?;
+
+        self.print_success(&format!("✓ Exported {} alias(es) to '{}'", report.exported, file))?;
+
+        if !report.skipped.is_empty() {
+            self.print_info(&format!("Skipped {} snippet(s):", report.skipped.len()))?;
+            for skipped in &report.skipped {
+                self.term.write_line(&format!("  {} - {}", skipped.trigger, skipped.reason))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_expand(&self, trigger: String, copy: bool) -> Result<()> {
+        // Fail before expanding, so a missing clipboard doesn't count as a use
+        let clipboard = if copy {
+            match ClipboardManager::system() {
+                Ok(clipboard) => Some(clipboard),
+                Err(e) => {
+                    self.print_error(&format!("✗ Can't copy: {}", e))?;
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Some(snippet) = self.service.get_snippet_by_trigger(&trigger).await? {
+            if snippet.confirm_before_expand && !self.confirm(&format!("Expand '{}'?", trigger))? {
+                self.print_info("Expansion cancelled")?;
+                return Ok(());
+            }
+        }
+
         let request = ExpansionRequest {
             trigger: trigger.clone(),
             context: None,
@@ -286,8 +773,13 @@ impl TypelyCliHandler {
             Ok(response) => {
                 if response.success {
                     if let Some(expanded) = response.expanded_text {
-                        self.print_success(&format!("✓ '{}' expands to:", trigger))?;
-                        self.term.write_line(&format!("  {}", expanded))?;
+                        if let Some(clipboard) = clipboard {
+                            clipboard.set_text(&expanded)?;
+                            self.print_success(&format!("✓ Copied the expansion of '{}' to the clipboard", trigger))?;
+                        } else {
+                            self.print_success(&format!("✓ '{}' expands to:", trigger))?;
+                            self.term.write_line(&format!("  {}", expanded))?;
+                        }
                     }
                 } else {
                     let error = response.error_message.unwrap_or_else(|| "Unknown error".to_string());
@@ -303,8 +795,43 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_search(&self, query: String, limit: u32, verbose: bool) -> Result<()> {
-        let snippets = self.service.search_snippets(&query).await?;
+    async fn handle_expand_text(&self, text: Option<String>) -> Result<()> {
+        let input = match text {
+            Some(text) => text,
+            None => {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)?;
+                input
+            }
+        };
+
+        let expanded = self.service.expand_text(&input).await?;
+
+        // Print verbatim so the output can be piped into other tools
+        print!("{}", expanded);
+        if !expanded.ends_with('\n') {
+            println!();
+        }
+
+        Ok(())
+    }
+
+    async fn handle_search(&self, query: String, limit: u32, fuzzy: bool, regex: bool, fields: Vec<String>, verbose: bool) -> Result<()> {
+        if fuzzy {
+            return self.handle_fuzzy_search(query, limit, verbose).await;
+        }
+
+        let fields = fields
+            .iter()
+            .map(|field| field.parse())
+            .collect::<Result<Vec<SearchField>>>()?;
+        let snippets = if regex {
+            self.service.search_by_regex(&query).await?
+        } else if !fields.is_empty() {
+            self.service.search_snippets_in(&query, &fields).await?
+        } else {
+            self.service.search_snippets(&query).await?
+        };
         let snippets = if snippets.len() > limit as usize {
             &snippets[..limit as usize]
         } else {
@@ -329,65 +856,255 @@ impl TypelyCliHandler {
         Ok(())
     }
 
-    async fn handle_stats(&self, verbose: bool) -> Result<()> {
-        // Get all snippets
-        let all_request = SnippetListRequest {
-            search_term: None,
-            tags: None,
-            is_active: None,
-            limit: None,
-            offset: None,
-            sort_by: None,
-            sort_order: None,
-        };
-        let all_response = self.service.list_snippets(all_request).await?;
+    async fn handle_fuzzy_search(&self, query: String, limit: u32, verbose: bool) -> Result<()> {
+        let results = self.service.fuzzy_search(&query, limit).await?;
 
-        // Get active snippets
-        let active_request = SnippetListRequest {
-            search_term: None,
-            tags: None,
-            is_active: Some(true),
-            limit: None,
-            offset: None,
-            sort_by: None,
-            sort_order: None,
-        };
-        let active_response = self.service.list_snippets(active_request).await?;
+        if results.is_empty() {
+            self.print_info(&format!("No snippets found matching '{}'", query))?;
+            return Ok(());
+        }
+
+        self.print_info(&format!("Found {} snippet(s) matching '{}':", results.len(), query))?;
+        self.term.write_line("")?;
 
-        // Calculate stats
-        let total_usage: u64 = all_response.snippets.iter().map(|s| s.usage_count).sum();
-        let most_used = self.service.get_most_used_snippets(5).await?;
+        for (snippet, score) in &results {
+            self.print_snippet_summary(snippet, verbose)?;
+            if verbose {
+                self.term.write_line(&format!("    Score: {}", score))?;
+                self.term.write_line("")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_random(&self, count: u32, verbose: bool) -> Result<()> {
+        let snippets = self.service.random_snippets(count).await?;
+
+        if snippets.is_empty() {
+            self.print_info("No active snippets to pick from")?;
+            return Ok(());
+        }
+
+        if snippets.len() == 1 {
+            self.print_info("Try this snippet:")?;
+        } else {
+            self.print_info(&format!("Try these {} snippets:", snippets.len()))?;
+        }
+        self.term.write_line("")?;
+
+        for snippet in &snippets {
+            self.print_snippet_summary(snippet, verbose)?;
+            if verbose {
+                self.term.write_line("")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_stats(&self, json: bool, csv: bool, verbose: bool) -> Result<()> {
+        if csv {
+            // Buffered, since the export has to be able to move between threads
+            let mut rows = Vec::new();
+            self.service.export_usage_csv(&mut rows).await?;
+            std::io::stdout().write_all(&rows)?;
+            return Ok(());
+        }
+
+        let stats = self.service.statistics(5).await?;
+        if json {
+            self.term.write_line(&serde_json::to_string_pretty(&stats)?)?;
+            return Ok(());
+        }
 
         self.print_info("Typely Statistics")?;
         self.term.write_line("==================")?;
-        self.term.write_line(&format!("Total snippets:  {}", all_response.total_count))?;
-        self.term.write_line(&format!("Active snippets: {}", active_response.total_count))?;
-        self.term.write_line(&format!("Total usage:     {}", total_usage))?;
+        self.term.write_line(&format!("Total snippets:  {}", stats.total_snippets))?;
+        self.term.write_line(&format!("Active snippets: {}", stats.active_snippets))?;
+        self.term.write_line(&format!("Total usage:     {}", stats.total_usage))?;
 
-        if !most_used.is_empty() {
+        if !stats.most_used.is_empty() {
             self.term.write_line("")?;
             self.print_info("Most used snippets:")?;
-            for (i, snippet) in most_used.iter().enumerate() {
-                self.term.write_line(&format!("  {}. {} (used {} times)", i + 1, snippet.trigger, snippet.usage_count))?;
+            for (i, snippet) in stats.most_used.iter().enumerate() {
+                let used_today = match self.service.snippet_usage(snippet.id).await? {
+                    Some(usage) => usage.used_today,
+                    None => 0,
+                };
+                self.term.write_line(&format!("  {}. {} (used {} times, {} today)", i + 1, self.display_trigger(&snippet.trigger), snippet.usage_count, used_today))?;
+            }
+        }
+
+        if verbose && !stats.tags.is_empty() {
+            self.term.write_line("")?;
+            self.print_info(&format!("Tags ({}):", stats.tags.len()))?;
+            for tag in &stats.tags {
+                self.term.write_line(&format!("  {}", tag))?;
             }
         }
 
-        if verbose && !all_response.snippets.is_empty() {
-            // Collect unique tags
-            let mut all_tags = std::collections::HashSet::new();
-            for snippet in &all_response.snippets {
-                for tag in &snippet.tags {
-                    all_tags.insert(tag);
+        Ok(())
+    }
+
+    async fn handle_set_active(&self, tags: Option<String>, tag_match: String, search: Option<String>, is_active: bool) -> Result<()> {
+        let mut query = SnippetQuery::new();
+        if let Some(tags) = tags {
+            query = query.with_tags(TypelyArgs::parse_tags(&tags));
+        }
+        query = match tag_match.as_str() {
+            "any" => query.with_tag_match(TagMatch::Any),
+            "all" => query.with_tag_match(TagMatch::All),
+            other => return Err(anyhow::anyhow!("Unknown tag match '{}' (expected any or all)", other)),
+        };
+        if let Some(search) = search {
+            query = query.with_search(search);
+        }
+
+        let changed = self.service.set_active_for_matching(&query, is_active).await?;
+        let verb = if is_active { "Activated" } else { "Deactivated" };
+        self.print_success(&format!("✓ {} {} snippet(s)", verb, changed))?;
+
+        Ok(())
+    }
+
+    async fn handle_reset_usage(&self, trigger: Option<String>) -> Result<()> {
+        let id = match trigger {
+            Some(trigger) => match self.service.get_snippet_by_trigger(&trigger).await? {
+                Some(snippet) => Some(snippet.id),
+                None => {
+                    self.print_error(&format!("✗ Snippet '{}' not found", trigger))?;
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+
+        let reset = self.service.reset_usage(id).await?;
+        self.print_success(&format!("✓ Reset usage count for {} snippet(s)", reset))?;
+
+        Ok(())
+    }
+
+    async fn handle_validate(&self) -> Result<()> {
+        let issues = self.service.validate_all().await?;
+        if issues.is_empty() {
+            self.print_success("✓ No problems found")?;
+            return Ok(());
+        }
+
+        for issue in &issues {
+            let severity = match issue.severity {
+                IssueSeverity::Error => self.style(format!("{:<7}", issue.severity)).red(),
+                IssueSeverity::Warning => self.style(format!("{:<7}", issue.severity)).yellow(),
+            };
+            self.term.write_line(&format!("{} {}: {}", severity, self.style(&issue.trigger).bold(), issue.message))?;
+        }
+
+        let errors = issues.iter().filter(|issue| issue.severity == IssueSeverity::Error).count();
+        let warnings = issues.len() - errors;
+        self.term.write_line("")?;
+        self.print_info(&format!("{} error(s), {} warning(s)", errors, warnings))?;
+
+        if errors > 0 {
+            return Err(anyhow::anyhow!("Validation found {} error(s)", errors));
+        }
+        Ok(())
+    }
+
+    async fn handle_migrate(&self, status: bool, to: Option<String>) -> Result<()> {
+        if let Some(version) = to {
+            self.service.run_migrations_to(&version).await?;
+            self.print_success(&format!("✓ Migrated up to {}", version))?;
+            return Ok(());
+        }
+
+        if !status {
+            self.service.run_migrations().await?;
+            self.print_success("✓ Database is up to date")?;
+            return Ok(());
+        }
+
+        let applied = self.service.migration_status().await?;
+        let pending = self.service.pending_migrations().await?;
+
+        self.print_info("Applied migrations:")?;
+        if applied.is_empty() {
+            self.term.write_line("  (none)")?;
+        }
+        for migration in &applied {
+            self.term.write_line(&format!("  {} (applied {})", migration.name, migration.applied_at.format("%Y-%m-%d %H:%M:%S")))?;
+        }
+
+        self.term.write_line("")?;
+        self.print_info("Pending migrations:")?;
+        if pending.is_empty() {
+            self.term.write_line("  (none)")?;
+        }
+        for name in pending {
+            self.term.write_line(&format!("  {}", name))?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_fragment(&self, action: FragmentCommand) -> Result<()> {
+        match action {
+            FragmentCommand::Set { name, content } => {
+                self.service.save_fragment(&name, &content).await?;
+                self.print_success(&format!("✓ Saved fragment '{}'", name))?;
+            }
+            FragmentCommand::List => {
+                let fragments = self.service.list_fragments().await?;
+                if fragments.is_empty() {
+                    self.print_info("No fragments found")?;
+                    return Ok(());
+                }
+
+                self.print_info(&format!("Found {} fragment(s):", fragments.len()))?;
+                for fragment in &fragments {
+                    let preview = fragment.content.replace('\n', "\\n");
+                    let preview = if preview.chars().count() > 50 {
+                        format!("{}...", preview.chars().take(47).collect::<String>())
+                    } else {
+                        preview
+                    };
+                    self.term.write_line(&format!("  {} -> {}", self.style(&fragment.name).bold(), preview))?;
                 }
             }
+            FragmentCommand::Remove { name } => {
+                if self.service.delete_fragment(&name).await? {
+                    self.print_success(&format!("✓ Removed fragment '{}'", name))?;
+                } else {
+                    self.print_error(&format!("✗ Fragment '{}' not found", name))?;
+                }
+            }
+        }
 
-            if !all_tags.is_empty() {
-                self.term.write_line("")?;
-                self.print_info(&format!("Tags ({}):", all_tags.len()))?;
-                let mut sorted_tags: Vec<_> = all_tags.into_iter().collect();
-                sorted_tags.sort();
-                for tag in sorted_tags {
-                    self.term.write_line(&format!("  {}", tag))?;
+        Ok(())
+    }
+
+    async fn handle_sed(&self, find: String, replace: String, dry_run: bool) -> Result<()> {
+        let changed = self.service.replace_in_replacements(&find, &replace, dry_run).await?;
+        if changed == 0 {
+            self.print_info(&format!("No snippets changed (none contain '{}')", find))?;
+        } else if dry_run {
+            self.print_info(&format!("Would replace '{}' with '{}' in {} snippet(s)", find, replace, changed))?;
+        } else {
+            self.print_success(&format!("✓ Replaced '{}' with '{}' in {} snippet(s)", find, replace, changed))?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_tag(&self, action: TagCommand) -> Result<()> {
+        match action {
+            TagCommand::Rename { from, to } => {
+                let renamed = self.service.rename_tag(&from, &to).await?;
+                if renamed == 0 {
+                    self.print_info(&format!("No snippets changed (none are tagged '{}')", from))?;
+                } else {
+                    self.print_success(&format!("✓ Renamed tag '{}' to '{}' on {} snippet(s)", from, to, renamed))?;
                 }
             }
         }
@@ -395,21 +1112,64 @@ impl TypelyCliHandler {
         Ok(())
     }
 
+    fn handle_status(&self, file: Option<String>) -> Result<()> {
+        let path = match file {
+            Some(file) => std::path::PathBuf::from(file),
+            None => get_default_status_path()?,
+        };
+
+        let Some(status) = EngineStatus::read_from(&path)? else {
+            self.print_info("Engine is not running")?;
+            self.term.write_line(&format!("No status file at {}", path.display()))?;
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now();
+        let state = if status.is_live(now) {
+            self.style("running").green()
+        } else if status.is_running {
+            self.style("not responding").yellow()
+        } else {
+            self.style("stopped").red()
+        };
+        let expansion = if status.enabled { "enabled" } else { "disabled" };
+        let last_expansion = status
+            .last_expansion
+            .map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "never".to_string());
+
+        self.print_info("Typely Engine Status")?;
+        self.term.write_line("====================")?;
+        self.term.write_line(&format!("State:          {} (pid {})", state, status.pid))?;
+        self.term.write_line(&format!("Expansion:      {}", expansion))?;
+        self.term.write_line(&format!("Snippets:       {}", status.snippet_count))?;
+        self.term.write_line(&format!("Uptime:         {}", format_uptime(status.uptime_seconds)))?;
+        self.term.write_line(&format!("Last expansion: {}", last_expansion))?;
+        self.term.write_line(&format!("Last update:    {}", status.updated_at.format("%Y-%m-%d %H:%M:%S UTC")))?;
+
+        for warning in &status.warnings {
+            self.term.write_line(&format!("{} {}", self.style("warning").yellow(), warning))?;
+        }
+
+        Ok(())
+    }
+
     fn print_snippet_summary(&self, snippet: &SnippetDto, verbose: bool) -> Result<()> {
         let status = if snippet.is_active { 
-            style("●").green() 
+            self.style("●").green() 
         } else { 
-            style("●").red() 
+            self.style("●").red() 
         };
         
-        let trigger = style(&snippet.trigger).cyan().bold();
-        let replacement = if snippet.replacement.len() > 50 && !verbose {
-            format!("{}...", &snippet.replacement[..47])
-        } else {
+        let trigger = self.style(self.display_trigger(&snippet.trigger)).cyan().bold();
+        let replacement = if verbose {
             snippet.replacement.clone()
+        } else {
+            replacement_preview(&snippet.replacement, DEFAULT_PREVIEW_LENGTH)
         };
 
-        self.term.write_line(&format!("{} {} -> {}", status, trigger, replacement))?;
+        let pin = if snippet.pinned { " (pinned)" } else { "" };
+        self.term.write_line(&format!("{} {}{} -> {}", status, trigger, pin, replacement))?;
 
         if verbose {
             self.term.write_line(&format!("    ID: {}", snippet.id))?;
@@ -426,15 +1186,49 @@ impl TypelyCliHandler {
 
     fn print_snippet_details(&self, snippet: &SnippetDto) -> Result<()> {
         let status = if snippet.is_active { 
-            style("Active").green() 
+            self.style("Active").green() 
         } else { 
-            style("Inactive").red() 
+            self.style("Inactive").red() 
         };
 
-        self.term.write_line(&format!("Snippet: {}", style(&snippet.trigger).cyan().bold()))?;
+        self.term.write_line(&format!("Snippet: {}", self.style(self.display_trigger(&snippet.trigger)).cyan().bold()))?;
         self.term.write_line(&format!("Status:  {}", status))?;
         self.term.write_line(&format!("ID:      {}", snippet.id))?;
         self.term.write_line(&format!("Usage:   {} times", snippet.usage_count))?;
+        self.term.write_line(&format!("Type:    {}", snippet.content_type))?;
+        if !snippet.aliases.is_empty() {
+            self.term.write_line(&format!("Aliases: {}", snippet.aliases.join(", ")))?;
+        }
+        if snippet.case_variants {
+            self.term.write_line("Case:    Title and UPPER case aliases expand in their case")?;
+        }
+        if snippet.encrypted {
+            self.term.write_line("Stored:  encrypted")?;
+        }
+        if snippet.confirm_before_expand {
+            self.term.write_line("Confirm: required before expanding")?;
+        }
+        if snippet.keep_trigger {
+            self.term.write_line("Trigger: kept, expansion typed after it")?;
+        }
+        if let Some(type_delay_ms) = snippet.type_delay_ms {
+            self.term.write_line(&format!("Type delay: {} ms per character", type_delay_ms))?;
+        }
+        if let Some(ref source) = snippet.source {
+            self.term.write_line(&format!("Source: {}", source))?;
+        }
+        if snippet.pinned {
+            self.term.write_line("Pinned:  listed first")?;
+        }
+        if let Some(ref hotkey) = snippet.hotkey {
+            self.term.write_line(&format!("Hotkey:  {}", hotkey))?;
+        }
+        if snippet.active_from.is_some() || snippet.active_until.is_some() {
+            self.term.write_line(&format!("Active:  from {} until {}",
+                snippet.active_from.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string()).unwrap_or_else(|| "any time".to_string()),
+                snippet.active_until.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string()).unwrap_or_else(|| "further notice".to_string())
+            ))?;
+        }
         self.term.write_line("")?;
         self.term.write_line("Replacement:")?;
         self.term.write_line(&format!("  {}", snippet.replacement))?;
@@ -451,45 +1245,122 @@ impl TypelyCliHandler {
         Ok(())
     }
 
+    fn confirm(&self, prompt: &str) -> Result<bool> {
+        self.term.write_str(&format!("{} [y/N] ", prompt))?;
+        let answer = self.term.read_line()?;
+        Ok(answer.trim().eq_ignore_ascii_case("y"))
+    }
+
     fn print_success(&self, message: &str) -> Result<()> {
-        self.term.write_line(&style(message).green().to_string())?;
+        self.term.write_line(&self.style(message).green().to_string())?;
         Ok(())
     }
 
     fn print_error(&self, message: &str) -> Result<()> {
-        self.term.write_line(&style(message).red().to_string())?;
+        self.term.write_line(&self.style(message).red().to_string())?;
         Ok(())
     }
 
     fn print_info(&self, message: &str) -> Result<()> {
-        self.term.write_line(&style(message).cyan().to_string())?;
+        self.term.write_line(&self.style(message).cyan().to_string())?;
         Ok(())
     }
 }
 
+/// Print the JSON Schema of the DTO called `name`, or an object with every
+/// DTO's schema by name
+#[cfg(feature = "schema")]
+pub fn print_schema(name: Option<&str>) -> Result<()> {
+    let output = match name {
+        Some(name) => {
+            let schema = typely::app::dto::dto_schema(name)
+                .ok_or_else(|| anyhow::anyhow!("No type named '{}'", name))?;
+            serde_json::to_string_pretty(&schema)?
+        }
+        None => {
+            let schemas: serde_json::Map<String, serde_json::Value> = typely::app::dto::dto_schemas()
+                .into_iter()
+                .map(|(name, schema)| Ok((name.to_string(), serde_json::to_value(schema)?)))
+                .collect::<Result<_>>()?;
+            serde_json::to_string_pretty(&schemas)?
+        }
+    };
+    println!("{}", output);
+    Ok(())
+}
+
+/// Run the environment checks against `db_path` and print each result, failing
+/// if any check failed
+pub fn run_doctor(db_path: &std::path::Path, colors_enabled: bool) -> Result<()> {
+    let term = Term::stdout();
+    let style = |text: &str| console::style(text.to_string()).force_styling(colors_enabled);
+    let checks = run_diagnostics(db_path);
+
+    term.write_line(&style("Typely Doctor").cyan().to_string())?;
+    term.write_line("=============")?;
+    for check in &checks {
+        let mark = match check.status {
+            CheckStatus::Pass => style("✓").green(),
+            CheckStatus::Warn => style("!").yellow(),
+            CheckStatus::Fail => style("✗").red(),
+        };
+        term.write_line(&format!("{} {:<19} {}", mark, check.name, check.detail))?;
+        if let Some(ref hint) = check.hint {
+            term.write_line(&format!("  {:<19} {}", "", style(hint).dim()))?;
+        }
+    }
+
+    let failed = checks.iter().filter(|check| check.status == CheckStatus::Fail).count();
+    term.write_line("")?;
+    if failed > 0 {
+        term.write_line(&style(&format!("✗ {} check(s) failed", failed)).red().to_string())?;
+        return Err(anyhow::anyhow!("{} doctor check(s) failed", failed));
+    }
+    let warned = checks.iter().filter(|check| check.status == CheckStatus::Warn).count();
+    let summary = if warned > 0 {
+        format!("✓ No failures, {} warning(s)", warned)
+    } else {
+        "✓ All checks passed".to_string()
+    };
+    term.write_line(&style(&summary).green().to_string())?;
+    Ok(())
+}
+
+fn format_uptime(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::infra::DatabaseConnection;
-    use tempfile::TempDir;
 
-    async fn create_test_handler() -> (TypelyCliHandler, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let db_connection = DatabaseConnection::new(&db_path).await.unwrap();
-        let service = TypelyService::new(db_connection).await;
-        let handler = TypelyCliHandler::new(service);
-        (handler, temp_dir)
+    async fn create_test_handler() -> TypelyCliHandler {
+        let service = TypelyService::new_in_memory().await.unwrap();
+        TypelyCliHandler::new(service)
     }
 
     #[tokio::test]
     async fn test_add_command() {
-        let (handler, _temp_dir) = create_test_handler().await;
+        let handler = create_test_handler().await;
 
         let result = handler.handle_add(
             "::test".to_string(),
             "Test snippet".to_string(),
             Some("test,cli".to_string()),
+            None,
+            false,
+            false,
+            None,
+            None,
+            false,
+            false,
             false,
         ).await;
 
@@ -507,10 +1378,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_remove_command() {
-        let (handler, _temp_dir) = create_test_handler().await;
+        let handler = create_test_handler().await;
 
         // First add a snippet
-        handler.handle_add("::test".to_string(), "Test".to_string(), None, false).await.unwrap();
+        handler.handle_add("::test".to_string(), "Test".to_string(), None, None, false, false, None, None, false, false, false).await.unwrap();
 
         // Then remove it
         let result = handler.handle_remove("::test".to_string(), false).await;
@@ -520,4 +1391,31 @@ mod tests {
         let snippet = handler.service.get_snippet_by_trigger("::test").await.unwrap();
         assert!(snippet.is_none());
     }
+
+    #[tokio::test]
+    async fn test_hidden_prefix_only_changes_the_rendered_trigger() {
+        let handler = create_test_handler().await.with_trigger_display(TriggerDisplay {
+            display_prefix: false,
+            prefix: "::".to_string(),
+        });
+        handler.handle_add("::hello".to_string(), "Hello".to_string(), None, None, false, false, None, None, false, false, false).await.unwrap();
+
+        let snippet = handler.service.get_snippet_by_trigger("::hello").await.unwrap().unwrap();
+        assert_eq!(handler.display_trigger(&snippet.trigger), "hello");
+        assert_eq!(snippet.trigger, "::hello");
+
+        // Only the prefix, or a different prefix, is shown as stored
+        assert_eq!(handler.display_trigger("::"), "::");
+        assert_eq!(handler.display_trigger(";sig"), ";sig");
+
+        let snippet = snippet.with_trigger_display(&handler.trigger_display);
+        assert_eq!(snippet.display_trigger, "hello");
+        assert_eq!(snippet.trigger, "::hello");
+    }
+
+    #[tokio::test]
+    async fn test_prefix_is_shown_by_default() {
+        let handler = create_test_handler().await;
+        assert_eq!(handler.display_trigger("::hello"), "::hello");
+    }
 }
\ No newline at end of file
@@ -2,6 +2,8 @@
 
 pub mod args;
 pub mod commands;
+#[cfg(feature = "tui")]
+pub mod tui;
 
 pub use args::*;
 pub use commands::*;
\ No newline at end of file
@@ -1,13 +1,22 @@
+use typely::app::dto::TriggerDisplay;
+use typely::domain::{ExpansionRules, FileAccess, parse_timezone};
 use typely::app::services::TypelyService;
-use typely::infra::{DatabaseConnection, get_default_database_path};
+use typely::infra::{
+    DatabaseConnection, DryRunSnippetRepository, ExpansionLogConfig, ExpansionLogger,
+    SqliteSnippetRepository, get_default_database_path, get_profile_database_path,
+};
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
+use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
 
 mod args;
 mod commands;
+#[cfg(feature = "tui")]
+mod tui;
 
-use args::TypelyArgs;
+use args::{TypelyArgs, TypelyCommand};
 use commands::TypelyCliHandler;
 
 #[tokio::main]
@@ -15,15 +24,13 @@ async fn main() -> Result<()> {
     let args = TypelyArgs::parse();
 
     // Initialize logging based on verbosity
-    if args.verbose {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
-    } else {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
-    }
+    init_logging(args.verbose, &args.log_format)?;
 
     // Determine database path
     let db_path = if let Some(db_path) = args.database {
         PathBuf::from(db_path)
+    } else if let Some(profile) = &args.profile {
+        get_profile_database_path(profile)?
     } else {
         get_default_database_path()?
     };
@@ -32,17 +39,94 @@ async fn main() -> Result<()> {
         eprintln!("Using database: {}", db_path.display());
     }
 
-    // Initialize database connection
-    let db_connection = DatabaseConnection::new(&db_path).await?;
+    // The doctor checks the database path itself, so it runs before opening it
+    if let TypelyCommand::Doctor = args.command {
+        return commands::run_doctor(&db_path, colors_enabled(args.no_color));
+    }
+
+    #[cfg(feature = "schema")]
+    if let TypelyCommand::Schema { name } = &args.command {
+        return commands::print_schema(name.as_deref());
+    }
+
+    // Initialize database connection; `migrate` applies migrations itself
+    let db_connection = if let TypelyCommand::Migrate { .. } = args.command {
+        DatabaseConnection::new_unmigrated(&db_path).await?
+    } else {
+        DatabaseConnection::new(&db_path).await?
+    };
 
-    // Create service
-    let service = TypelyService::new(db_connection).await;
+    // Create service, with writes to snippets intercepted for --dry-run
+    let dry_run = args.dry_run.then(|| {
+        let repository = SqliteSnippetRepository::from_env(db_connection.pool().clone());
+        Arc::new(DryRunSnippetRepository::new(Arc::new(repository)))
+    });
+    let mut service = match &dry_run {
+        Some(repository) => TypelyService::with_snippet_repository(db_connection, repository.clone()),
+        None => TypelyService::new(db_connection).await,
+    };
+    service = service.with_privacy_mode(args.privacy_mode);
+    if args.log_expansions {
+        let config = ExpansionLogConfig::new(ExpansionLogConfig::default_path()?);
+        service = service.with_expansion_logger(Arc::new(ExpansionLogger::new(config)));
+    }
+
+    if let Some(names) = args.allow_env {
+        service = service.with_env_allowlist(names);
+    }
+
+    if let Some(dir) = args.file_dir {
+        service = service.with_file_access(FileAccess::new(dir));
+    }
+
+    if let Some(name) = &args.timezone {
+        service = service.with_timezone(parse_timezone(name)?);
+    }
+
+    service = service.with_expansion_rules(ExpansionRules {
+        smart_quotes: args.smart_quotes,
+        trim_trailing: args.trim_trailing,
+    });
 
     // Create CLI handler
-    let handler = TypelyCliHandler::new(service);
+    let handler = TypelyCliHandler::new(service)
+        .with_trigger_display(TriggerDisplay {
+            display_prefix: !args.hide_prefix,
+            prefix: args.trigger_prefix,
+        })
+        .with_colors(colors_enabled(args.no_color));
 
     // Handle command
     handler.handle_command(args.command, args.verbose).await?;
 
+    if let Some(repository) = dry_run {
+        handler.report_dry_run(&repository.operations())?;
+    }
+
+    Ok(())
+}
+
+/// Colors are off with --no-color, when NO_COLOR is set (https://no-color.org)
+/// or when stdout isn't a terminal that supports them
+fn colors_enabled(no_color: bool) -> bool {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && !no_color_env && console::colors_enabled()
+}
+
+fn init_logging(verbose: bool, log_format: &str) -> Result<()> {
+    let default_filter = if verbose { "debug" } else { "warn" };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+
+    // Logs go to stderr so command output stays pipeable
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+
+    match log_format {
+        "text" => builder.init(),
+        "json" => builder.json().with_current_span(true).init(),
+        other => return Err(anyhow::anyhow!("Unknown log format '{}' (expected text or json)", other)),
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
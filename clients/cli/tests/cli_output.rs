@@ -0,0 +1,252 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// Run the CLI against `db_path` with stdout captured through a pipe
+fn run_piped(db_path: &Path, args: &[&str], env: &[(&str, &str)]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(db_path)
+        .args(args)
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .envs(env.iter().copied())
+        .output()
+        .expect("Failed to run typely-cli");
+
+    assert!(
+        output.status.success(),
+        "typely-cli {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn has_ansi_escapes(output: &str) -> bool {
+    output.contains('\u{1b}')
+}
+
+#[test]
+fn test_piped_output_has_no_colors() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let added = run_piped(&db_path, &["add", "::hello", "Hello!"], &[]);
+    assert!(added.contains("::hello"));
+    assert!(!has_ansi_escapes(&added), "{:?}", added);
+
+    for args in [&["list"][..], &["show", "::hello"][..], &["stats"][..]] {
+        let output = run_piped(&db_path, args, &[]);
+        assert!(!has_ansi_escapes(&output), "{:?}: {:?}", args, output);
+    }
+}
+
+#[test]
+fn test_no_color_overrides_forced_colors() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    run_piped(&db_path, &["add", "::hello", "Hello!"], &[]);
+
+    // CLICOLOR_FORCE makes console color even a pipe
+    let forced = [("CLICOLOR_FORCE", "1")];
+    assert!(has_ansi_escapes(&run_piped(&db_path, &["list"], &forced)));
+
+    let output = run_piped(&db_path, &["--no-color", "list"], &forced);
+    assert!(!has_ansi_escapes(&output), "{:?}", output);
+
+    let output = run_piped(
+        &db_path,
+        &["list"],
+        &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")],
+    );
+    assert!(!has_ansi_escapes(&output), "{:?}", output);
+}
+
+#[test]
+fn test_stats_as_json_and_csv() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    run_piped(
+        &db_path,
+        &["add", "::hello", "Hello!", "--tags", "greeting"],
+        &[],
+    );
+    run_piped(&db_path, &["add", "::bye", "Goodbye, then"], &[]);
+    run_piped(&db_path, &["expand", "::hello"], &[]);
+
+    let output = run_piped(&db_path, &["stats", "--json"], &[]);
+    let stats: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(stats["total_snippets"], 2);
+    assert_eq!(stats["active_snippets"], 2);
+    assert_eq!(stats["total_usage"], 1);
+    assert_eq!(stats["tags"], serde_json::json!(["greeting"]));
+    assert_eq!(stats["most_used"][0]["trigger"], "::hello");
+
+    let output = run_piped(&db_path, &["stats", "--csv"], &[]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines, vec!["trigger,usage_count", "::bye,0", "::hello,1"]);
+}
+
+#[test]
+fn test_list_pages_with_limit_and_offset() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    for trigger in ["::a", "::b", "::c", "::d", "::e"] {
+        run_piped(&db_path, &["add", trigger, "text"], &[]);
+    }
+
+    let args = [
+        "list", "--sort", "trigger", "--order", "asc", "--limit", "2", "--offset", "2",
+    ];
+    let output = run_piped(&db_path, &args, &[]);
+    let listed: Vec<&str> = ["::a", "::b", "::c", "::d", "::e"]
+        .into_iter()
+        .filter(|trigger| output.contains(&format!("{} ->", trigger)))
+        .collect();
+    assert_eq!(listed, vec!["::c", "::d"], "{}", output);
+    assert!(output.contains("Page 2 of 3"), "{}", output);
+
+    let rejected = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(&db_path)
+        .args(["list", "--limit", "0"])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+}
+
+#[test]
+fn test_add_reads_a_multi_line_replacement_from_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(&db_path)
+        .args(["add", "::sig", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Best regards,\n\nJane Doe\nTypely Team\n")
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    // The final newline is dropped, the ones inside are kept
+    let output = run_piped(&db_path, &["expand-text", "::sig"], &[]);
+    assert_eq!(output, "Best regards,\n\nJane Doe\nTypely Team\n");
+
+    let both = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(&db_path)
+        .args(["add", "::other", "text", "--stdin"])
+        .output()
+        .unwrap();
+    assert!(!both.status.success());
+}
+
+#[test]
+fn test_expand_text_expands_every_trigger_piped_in() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    run_piped(&db_path, &["add", "::hello", "Hello"], &[]);
+    run_piped(&db_path, &["add", "::name", "Typely"], &[]);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(&db_path)
+        .arg("expand-text")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Say ::hello to ::name.\n::hello again, ::unknown!\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Say Hello to Typely.\nHello again, ::unknown!\n"
+    );
+}
+
+#[test]
+fn test_dry_run_reads_encrypted_snippets() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let passphrase = [("TYPELY_PASSPHRASE", "correct horse")];
+    run_piped(
+        &db_path,
+        &["add", "::secret", "Hidden text", "--encrypt"],
+        &passphrase,
+    );
+
+    let output = run_piped(
+        &db_path,
+        &["--dry-run", "expand-text", "::secret"],
+        &passphrase,
+    );
+    assert!(output.starts_with("Hidden text\n"), "{}", output);
+}
+
+#[test]
+fn test_list_without_passphrase_locks_only_encrypted_snippets() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    run_piped(
+        &db_path,
+        &["add", "::secret", "Hidden text", "--encrypt"],
+        &[("TYPELY_PASSPHRASE", "correct horse")],
+    );
+    run_piped(&db_path, &["add", "::hi", "Hello"], &[]);
+
+    let output = run_piped(&db_path, &["list"], &[]);
+    assert!(output.contains("::hi -> Hello"), "{}", output);
+    assert!(output.contains("::secret -> [encrypted]"), "{}", output);
+    assert!(!output.contains("Hidden text"), "{}", output);
+    run_piped(&db_path, &["stats"], &[]);
+
+    let expanded = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(&db_path)
+        .args(["expand-text", "::secret"])
+        .env_remove("TYPELY_PASSPHRASE")
+        .output()
+        .unwrap();
+    assert!(!expanded.status.success());
+    assert!(String::from_utf8_lossy(&expanded.stderr).contains("TYPELY_PASSPHRASE"));
+}
+
+#[test]
+fn test_migrate_to_stops_at_version() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    run_piped(&db_path, &["migrate", "--to", "002"], &[]);
+
+    let output = run_piped(&db_path, &["migrate", "--status"], &[]);
+    let (applied, pending) = output.split_once("Pending migrations:").unwrap();
+    assert!(applied.contains("001_create_snippets (applied"), "{}", output);
+    assert!(applied.contains("002_add_indexes (applied"), "{}", output);
+    assert!(!applied.contains("003_"), "{}", output);
+    assert!(pending.contains("003_create_events"), "{}", output);
+
+    let rejected = Command::new(env!("CARGO_BIN_EXE_typely-cli"))
+        .arg("--database")
+        .arg(&db_path)
+        .args(["migrate", "--to", "001"])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+}
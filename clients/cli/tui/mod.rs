@@ -0,0 +1,124 @@
+// Interactive snippet browser, built with the `tui` feature
+
+mod state;
+mod ui;
+
+use state::{BrowserState, Command, Key};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use std::io::{stdout, Stdout};
+use typely::app::dto::{SnippetDto, SnippetListRequest};
+use typely::app::services::TypelyService;
+
+type TuiTerminal = Terminal<CrosstermBackend<Stdout>>;
+
+/// Run the browser until the user quits, restoring the terminal afterwards
+pub async fn run(service: &TypelyService) -> Result<()> {
+    let mut terminal = setup_terminal()?;
+    let result = event_loop(&mut terminal, service).await;
+    restore_terminal(&mut terminal)?;
+    result
+}
+
+async fn event_loop(terminal: &mut TuiTerminal, service: &TypelyService) -> Result<()> {
+    let mut state = BrowserState::new(load_snippets(service).await?);
+
+    loop {
+        terminal.draw(|frame| ui::draw(frame, &state))?;
+
+        let Event::Key(key_event) = event::read()? else {
+            continue;
+        };
+        if key_event.kind != KeyEventKind::Press {
+            continue;
+        }
+        let Some(key) = map_key(key_event.code) else {
+            continue;
+        };
+
+        match state.handle_key(key) {
+            Some(Command::Quit) => return Ok(()),
+            Some(command) => {
+                let status = match execute(service, &command).await {
+                    Ok(status) => status,
+                    Err(e) => format!("Error: {}", e),
+                };
+                state.set_snippets(load_snippets(service).await?);
+                state.set_status(status);
+            }
+            None => {}
+        }
+    }
+}
+
+async fn execute(service: &TypelyService, command: &Command) -> Result<String> {
+    match command {
+        Command::SetActive(id, true) => {
+            let snippet = service.activate_snippet(*id).await?;
+            Ok(format!("Activated '{}'", snippet.trigger))
+        }
+        Command::SetActive(id, false) => {
+            let snippet = service.deactivate_snippet(*id).await?;
+            Ok(format!("Deactivated '{}'", snippet.trigger))
+        }
+        Command::Delete(id) => {
+            if service.delete_snippet(*id).await? {
+                Ok("Snippet deleted".to_string())
+            } else {
+                Ok("Snippet was already gone".to_string())
+            }
+        }
+        Command::Quit => Ok(String::new()),
+    }
+}
+
+/// Every snippet, active or not, so inactive ones can be re-activated from the list
+async fn load_snippets(service: &TypelyService) -> Result<Vec<SnippetDto>> {
+    let request = SnippetListRequest {
+        search_term: None,
+        search_fields: None,
+        tags: None,
+        is_active: None,
+        limit: None,
+        offset: None,
+        sort_by: Some("alphabetical".to_string()),
+        sort_order: Some("asc".to_string()),
+        tag_match: None,
+        source: None,
+        after: None,
+    };
+    Ok(service.list_snippets(request).await?.snippets)
+}
+
+fn map_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Char(c) => Some(Key::Char(c)),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Esc => Some(Key::Esc),
+        KeyCode::Backspace => Some(Key::Backspace),
+        KeyCode::Delete => Some(Key::Delete),
+        _ => None,
+    }
+}
+
+fn setup_terminal() -> Result<TuiTerminal> {
+    enable_raw_mode()?;
+    stdout().execute(EnterAlternateScreen)?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout()))?)
+}
+
+fn restore_terminal(terminal: &mut TuiTerminal) -> Result<()> {
+    disable_raw_mode()?;
+    terminal.backend_mut().execute(LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
@@ -0,0 +1,337 @@
+use typely::app::dto::SnippetDto;
+use uuid::Uuid;
+
+/// Terminal-independent key presses the browser reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Enter,
+    Esc,
+    Backspace,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Moving through the list
+    Browse,
+    /// Typing into the search box
+    Search,
+    /// Waiting for y/n before deleting the selected snippet
+    ConfirmDelete,
+}
+
+/// Work the state asks the caller to do against the service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    SetActive(Uuid, bool),
+    Delete(Uuid),
+    Quit,
+}
+
+/// Snippet list, search query and selection, kept free of any terminal code
+pub struct BrowserState {
+    snippets: Vec<SnippetDto>,
+    query: String,
+    /// Indices into `snippets` that match `query`
+    visible: Vec<usize>,
+    /// Position within `visible`
+    selected: usize,
+    mode: Mode,
+    status: Option<String>,
+}
+
+impl BrowserState {
+    pub fn new(snippets: Vec<SnippetDto>) -> Self {
+        let mut state = Self {
+            snippets,
+            query: String::new(),
+            visible: Vec::new(),
+            selected: 0,
+            mode: Mode::Browse,
+            status: None,
+        };
+        state.refilter();
+        state
+    }
+
+    /// Swap in a fresh snippet list, keeping the selection on the same snippet when it still exists
+    pub fn set_snippets(&mut self, snippets: Vec<SnippetDto>) {
+        let selected_id = self.selected_snippet().map(|snippet| snippet.id);
+        self.snippets = snippets;
+        self.refilter();
+
+        if let Some(id) = selected_id {
+            let position = self.visible_snippets().position(|s| s.id == id);
+            if let Some(position) = position {
+                self.selected = position;
+            }
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// Position of the selection within the visible list, if anything is visible
+    pub fn selected_index(&self) -> Option<usize> {
+        (!self.visible.is_empty()).then_some(self.selected)
+    }
+
+    pub fn selected_snippet(&self) -> Option<&SnippetDto> {
+        self.visible
+            .get(self.selected)
+            .map(|&index| &self.snippets[index])
+    }
+
+    pub fn visible_snippets(&self) -> impl Iterator<Item = &SnippetDto> {
+        self.visible.iter().map(move |&index| &self.snippets[index])
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> Option<Command> {
+        match self.mode {
+            Mode::Browse => self.handle_browse_key(key),
+            Mode::Search => {
+                self.handle_search_key(key);
+                None
+            }
+            Mode::ConfirmDelete => self.handle_confirm_key(key),
+        }
+    }
+
+    fn handle_browse_key(&mut self, key: Key) -> Option<Command> {
+        self.status = None;
+        match key {
+            Key::Char('q') | Key::Esc => return Some(Command::Quit),
+            Key::Up | Key::Char('k') => self.select_previous(),
+            Key::Down | Key::Char('j') => self.select_next(),
+            Key::Char('/') => self.mode = Mode::Search,
+            Key::Char('a') => return self.set_selected_active(true),
+            Key::Char('d') => return self.set_selected_active(false),
+            Key::Char('x') | Key::Delete if self.selected_snippet().is_some() => {
+                self.mode = Mode::ConfirmDelete;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_search_key(&mut self, key: Key) {
+        match key {
+            Key::Char(c) => {
+                self.query.push(c);
+                self.refilter();
+            }
+            Key::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            Key::Esc => {
+                self.query.clear();
+                self.refilter();
+                self.mode = Mode::Browse;
+            }
+            Key::Enter => self.mode = Mode::Browse,
+            Key::Up => self.select_previous(),
+            Key::Down => self.select_next(),
+            Key::Delete => {}
+        }
+    }
+
+    fn handle_confirm_key(&mut self, key: Key) -> Option<Command> {
+        self.mode = Mode::Browse;
+        match key {
+            Key::Char('y') | Key::Char('Y') => {
+                self.selected_snippet().map(|s| Command::Delete(s.id))
+            }
+            _ => {
+                self.status = Some("Delete cancelled".to_string());
+                None
+            }
+        }
+    }
+
+    fn set_selected_active(&self, active: bool) -> Option<Command> {
+        self.selected_snippet()
+            .filter(|snippet| snippet.is_active != active)
+            .map(|snippet| Command::SetActive(snippet.id, active))
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn select_next(&mut self) {
+        if self.selected + 1 < self.visible.len() {
+            self.selected += 1;
+        }
+    }
+
+    fn refilter(&mut self) {
+        let query = self.query.to_lowercase();
+        self.visible = self
+            .snippets
+            .iter()
+            .enumerate()
+            .filter(|(_, snippet)| query.is_empty() || Self::matches(snippet, &query))
+            .map(|(index, _)| index)
+            .collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn matches(snippet: &SnippetDto, query: &str) -> bool {
+        snippet.trigger.to_lowercase().contains(query)
+            || snippet.replacement.to_lowercase().contains(query)
+            || snippet
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typely::domain::Snippet;
+
+    fn snippet(trigger: &str, replacement: &str, tags: &[&str]) -> SnippetDto {
+        let mut snippet = Snippet::new(trigger.to_string(), replacement.to_string()).unwrap();
+        for tag in tags {
+            snippet.add_tag(tag.to_string());
+        }
+        SnippetDto::from(snippet)
+    }
+
+    fn sample_state() -> BrowserState {
+        BrowserState::new(vec![
+            snippet("::email", "jane@example.com", &["work"]),
+            snippet("::addr", "1 Main Street", &["home"]),
+            snippet("::sig", "Best regards", &["work", "email"]),
+        ])
+    }
+
+    fn type_query(state: &mut BrowserState, query: &str) {
+        state.handle_key(Key::Char('/'));
+        for c in query.chars() {
+            state.handle_key(Key::Char(c));
+        }
+    }
+
+    fn visible_triggers(state: &BrowserState) -> Vec<&str> {
+        state
+            .visible_snippets()
+            .map(|s| s.trigger.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_search_filters_by_trigger_replacement_and_tag() {
+        let mut state = sample_state();
+
+        type_query(&mut state, "EMAIL");
+        assert_eq!(state.mode(), Mode::Search);
+        assert_eq!(visible_triggers(&state), vec!["::email", "::sig"]);
+
+        state.handle_key(Key::Backspace);
+        assert_eq!(state.query(), "EMAI");
+
+        state.handle_key(Key::Esc);
+        assert_eq!(state.mode(), Mode::Browse);
+        assert_eq!(state.query(), "");
+        assert_eq!(visible_triggers(&state).len(), 3);
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut state = sample_state();
+        assert_eq!(state.selected_index(), Some(0));
+
+        state.handle_key(Key::Up);
+        assert_eq!(state.selected_index(), Some(0));
+
+        for _ in 0..5 {
+            state.handle_key(Key::Char('j'));
+        }
+        assert_eq!(state.selected_index(), Some(2));
+
+        // Narrowing the list pulls the selection back inside it
+        type_query(&mut state, "street");
+        assert_eq!(state.selected_index(), Some(0));
+        assert_eq!(state.selected_snippet().unwrap().trigger, "::addr");
+
+        state.handle_key(Key::Enter);
+        assert_eq!(state.mode(), Mode::Browse);
+        type_query(&mut state, "zzz");
+        assert_eq!(state.selected_index(), None);
+        assert!(state.selected_snippet().is_none());
+    }
+
+    #[test]
+    fn test_toggle_active_commands() {
+        let mut state = sample_state();
+        let id = state.selected_snippet().unwrap().id;
+
+        // Already active, so there is nothing to do
+        assert_eq!(state.handle_key(Key::Char('a')), None);
+        assert_eq!(
+            state.handle_key(Key::Char('d')),
+            Some(Command::SetActive(id, false))
+        );
+    }
+
+    #[test]
+    fn test_delete_requires_confirmation() {
+        let mut state = sample_state();
+        state.handle_key(Key::Down);
+        let id = state.selected_snippet().unwrap().id;
+
+        assert_eq!(state.handle_key(Key::Char('x')), None);
+        assert_eq!(state.mode(), Mode::ConfirmDelete);
+        assert_eq!(state.handle_key(Key::Char('n')), None);
+        assert_eq!(state.mode(), Mode::Browse);
+        assert_eq!(state.status(), Some("Delete cancelled"));
+
+        state.handle_key(Key::Delete);
+        assert_eq!(state.handle_key(Key::Char('y')), Some(Command::Delete(id)));
+    }
+
+    #[test]
+    fn test_set_snippets_keeps_selected_snippet() {
+        let mut state = sample_state();
+        state.handle_key(Key::Down);
+        state.handle_key(Key::Down);
+        let selected = state.selected_snippet().unwrap().clone();
+
+        let mut snippets: Vec<SnippetDto> = state.visible_snippets().cloned().collect();
+        snippets.remove(0);
+        state.set_snippets(snippets);
+
+        assert_eq!(state.selected_index(), Some(1));
+        assert_eq!(state.selected_snippet().unwrap().id, selected.id);
+    }
+
+    #[test]
+    fn test_quit() {
+        let mut state = sample_state();
+        assert_eq!(state.handle_key(Key::Char('q')), Some(Command::Quit));
+
+        // While searching, 'q' is just part of the query
+        state.handle_key(Key::Char('/'));
+        assert_eq!(state.handle_key(Key::Char('q')), None);
+        assert_eq!(state.query(), "q");
+    }
+}
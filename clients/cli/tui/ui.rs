@@ -0,0 +1,96 @@
+use super::state::{BrowserState, Mode};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+pub fn draw(frame: &mut Frame, state: &BrowserState) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[1]);
+
+    let search_style = if state.mode() == Mode::Search {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    let search = Paragraph::new(state.query())
+        .style(search_style)
+        .block(Block::default().borders(Borders::ALL).title("Search (/)"));
+    frame.render_widget(search, rows[0]);
+
+    let items: Vec<ListItem> = state
+        .visible_snippets()
+        .map(|snippet| {
+            let trigger_style = if snippet.is_active {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            ListItem::new(Line::from(Span::styled(
+                snippet.trigger.clone(),
+                trigger_style,
+            )))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Snippets"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default();
+    list_state.select(state.selected_index());
+    frame.render_stateful_widget(list, columns[0], &mut list_state);
+
+    let details = match state.selected_snippet() {
+        Some(snippet) => {
+            let mut lines = vec![
+                Line::from(format!("Trigger: {}", snippet.trigger)),
+                Line::from(format!(
+                    "Status:  {}",
+                    if snippet.is_active {
+                        "Active"
+                    } else {
+                        "Inactive"
+                    }
+                )),
+                Line::from(format!("Usage:   {} times", snippet.usage_count)),
+                Line::from(format!("Type:    {}", snippet.content_type)),
+            ];
+            if !snippet.tags.is_empty() {
+                lines.push(Line::from(format!("Tags:    {}", snippet.tags.join(", "))));
+            }
+            lines.push(Line::from(""));
+            lines.extend(
+                snippet
+                    .replacement
+                    .lines()
+                    .map(|line| Line::from(line.to_string())),
+            );
+            lines
+        }
+        None => vec![Line::from("No matching snippets")],
+    };
+    let details = Paragraph::new(details)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Details"));
+    frame.render_widget(details, columns[1]);
+
+    let footer = match (state.mode(), state.status()) {
+        (Mode::ConfirmDelete, _) => "Delete this snippet? (y/n)".to_string(),
+        (Mode::Search, _) => "Type to filter  Enter: done  Esc: clear".to_string(),
+        (Mode::Browse, Some(status)) => status.to_string(),
+        (Mode::Browse, None) => {
+            "j/k: move  /: search  a: activate  d: deactivate  x: delete  q: quit".to_string()
+        }
+    };
+    frame.render_widget(Paragraph::new(footer), rows[2]);
+}
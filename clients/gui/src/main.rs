@@ -13,7 +13,8 @@ use tauri::{
 use tokio::sync::Mutex;
 use typely::app::dto::*;
 use typely::app::services::TypelyService;
-use typely::infra::{DatabaseConnection, get_default_database_path};
+use typely::domain::ContentType;
+use typely::infra::{ClipboardManager, DatabaseConnection, get_default_database_path};
 
 #[derive(serde::Serialize)]
 struct CliStatus {
@@ -22,24 +23,46 @@ struct CliStatus {
     path: Option<String>,
 }
 
+#[derive(serde::Serialize)]
+struct SnippetPreview {
+    snippet: SnippetDto,
+    preview: String,
+}
+
 // Application state
 struct AppState {
-    service: Arc<Mutex<TypelyService>>,
+    // TypelyService is Sync and its database pool handles concurrent
+    // commands, so it is shared without a lock
+    service: Arc<TypelyService>,
+    /// The system clipboard, if one could be reached at startup
+    clipboard: Option<ClipboardManager>,
     #[allow(dead_code)]
     window: Arc<Mutex<Option<tauri::Window>>>,
 }
 
 impl AppState {
     async fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        // Initialize database connection using persistent storage
-        let db_path = get_default_database_path()?;
-        log::info!("Using database: {}", db_path.display());
+        let service = if std::env::var_os("TYPELY_GUI_IN_MEMORY").is_some() {
+            // For GUI development: snippets live in memory and vanish on exit
+            log::info!("Using in-memory snippets (TYPELY_GUI_IN_MEMORY is set)");
+            TypelyService::new_without_database()
+        } else {
+            // Initialize database connection using persistent storage
+            let db_path = get_default_database_path()?;
+            log::info!("Using database: {}", db_path.display());
+
+            let db_connection = DatabaseConnection::new(&db_path).await?;
+            TypelyService::new(db_connection).await
+        };
 
-        let db_connection = DatabaseConnection::new(&db_path).await?;
-        let service = TypelyService::new(db_connection).await;
+        // The app still works without a clipboard, only copying fails
+        let clipboard = ClipboardManager::system()
+            .map_err(|e| log::warn!("{}", e))
+            .ok();
 
         Ok(Self {
-            service: Arc::new(Mutex::new(service)),
+            service: Arc::new(service),
+            clipboard,
             window: Arc::new(Mutex::new(None)),
         })
     }
@@ -47,23 +70,76 @@ impl AppState {
 
 // Tauri commands
 #[tauri::command]
-async fn get_snippets(state: State<'_, AppState>) -> Result<Vec<SnippetDto>, String> {
+async fn get_snippets(
+    state: State<'_, AppState>,
+    display_prefix: Option<bool>,
+) -> Result<Vec<SnippetDto>, String> {
     let request = SnippetListRequest {
         search_term: None,
+        search_fields: None,
         tags: None,
         is_active: Some(true),
         limit: None,
         offset: None,
         sort_by: Some("updated".to_string()),
         sort_order: Some("desc".to_string()),
+        tag_match: None,
+        source: None,
+        after: None,
     };
 
-    let service = state.service.lock().await;
+    let service = &state.service;
     let response = service
         .list_snippets(request)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(response.snippets)
+
+    let display = TriggerDisplay {
+        display_prefix: display_prefix.unwrap_or(true),
+        ..TriggerDisplay::default()
+    };
+    Ok(response
+        .snippets
+        .into_iter()
+        .map(|snippet| snippet.with_trigger_display(&display))
+        .collect())
+}
+
+/// Active snippets for the list view, with previews instead of full replacements
+#[tauri::command]
+async fn get_snippet_summaries(
+    state: State<'_, AppState>,
+    display_prefix: Option<bool>,
+) -> Result<Vec<SnippetSummaryDto>, String> {
+    let request = SnippetListRequest {
+        search_term: None,
+        search_fields: None,
+        tags: None,
+        is_active: Some(true),
+        limit: None,
+        offset: None,
+        sort_by: Some("updated".to_string()),
+        sort_order: Some("desc".to_string()),
+        tag_match: None,
+        source: None,
+        after: None,
+    };
+
+    let response = state
+        .service
+        .list_summaries(request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let display = TriggerDisplay {
+        display_prefix: display_prefix.unwrap_or(true),
+        ..TriggerDisplay::default()
+    };
+    Ok(response
+        .summaries
+        .into_iter()
+        .map(|summary| summary.with_trigger_display(&display))
+        .collect())
 }
 
 #[tauri::command]
@@ -71,15 +147,28 @@ async fn create_snippet(
     trigger: String,
     replacement: String,
     tags: Option<Vec<String>>,
+    content_type: Option<ContentType>,
+    confirm_before_expand: Option<bool>,
+    keep_trigger: Option<bool>,
+    aliases: Option<Vec<String>>,
+    type_delay_ms: Option<u64>,
+    generate_case_variants: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<SnippetDto, String> {
     let request = CreateSnippetRequest {
         trigger,
         replacement,
         tags,
+        content_type,
+        confirm_before_expand,
+        keep_trigger,
+        aliases,
+        type_delay_ms,
+        generate_case_variants: generate_case_variants.unwrap_or(false),
+        encrypted: false,
     };
 
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .create_snippet(request)
         .await
@@ -93,6 +182,11 @@ async fn update_snippet(
     replacement: Option<String>,
     tags: Option<Vec<String>>,
     is_active: Option<bool>,
+    content_type: Option<ContentType>,
+    confirm_before_expand: Option<bool>,
+    keep_trigger: Option<bool>,
+    aliases: Option<Vec<String>>,
+    type_delay_ms: Option<u64>,
     state: State<'_, AppState>,
 ) -> Result<SnippetDto, String> {
     let snippet_id = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
@@ -102,26 +196,66 @@ async fn update_snippet(
         replacement,
         tags,
         is_active,
+        content_type,
+        confirm_before_expand,
+        keep_trigger,
+        aliases,
+        type_delay_ms: type_delay_ms.map(Some),
+        pinned: None,
+        hotkey: None,
+        active_from: None,
+        active_until: None,
+        encrypted: None,
     };
 
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .update_snippet(request)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn duplicate_snippet(
+    id: String,
+    new_trigger: String,
+    state: State<'_, AppState>,
+) -> Result<SnippetDto, String> {
+    let snippet_id = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+
+    let service = &state.service;
+    service
+        .duplicate_snippet(snippet_id, new_trigger)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn delete_snippet(id: String, state: State<'_, AppState>) -> Result<bool, String> {
     let snippet_id = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
 
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .delete_snippet(snippet_id)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn get_snippet_preview(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<SnippetPreview>, String> {
+    let snippet_id = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+
+    let service = &state.service;
+    let found = service
+        .get_snippet_preview(snippet_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(found.map(|(snippet, preview)| SnippetPreview { snippet, preview }))
+}
+
 #[tauri::command]
 async fn expand_snippet(
     trigger: String,
@@ -132,19 +266,38 @@ async fn expand_snippet(
         context: None,
     };
 
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .expand_snippet(request)
         .await
         .map_err(|e| e.to_string())
 }
 
+fn system_clipboard(state: &AppState) -> Result<&ClipboardManager, String> {
+    state
+        .clipboard
+        .as_ref()
+        .ok_or_else(|| "The system clipboard isn't available".to_string())
+}
+
+#[tauri::command]
+async fn get_clipboard(state: State<'_, AppState>) -> Result<String, String> {
+    system_clipboard(&state)?.get_text().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_clipboard(text: String, state: State<'_, AppState>) -> Result<(), String> {
+    system_clipboard(&state)?
+        .set_text(&text)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn search_snippets(
     query: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<SnippetDto>, String> {
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .search_snippets(&query)
         .await
@@ -152,37 +305,45 @@ async fn search_snippets(
 }
 
 #[tauri::command]
-async fn get_statistics(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
-    let all_snippets = {
-        let service = state.service.lock().await;
-        service
-            .get_all_active_snippets()
-            .await
-            .map_err(|e| e.to_string())?
-    };
+async fn search_highlighted(
+    query: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<MatchedSnippetDto>, String> {
+    let service = &state.service;
+    service
+        .search_highlighted(&query)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    let total_snippets = all_snippets.len();
-    let total_usage: u64 = all_snippets.iter().map(|s| s.usage_count).sum();
+/// An active snippet for a "try this snippet" tip, or `None` when there are none
+#[tauri::command]
+async fn get_random_snippet(state: State<'_, AppState>) -> Result<Option<SnippetDto>, String> {
+    let service = &state.service;
+    service.random_snippet().await.map_err(|e| e.to_string())
+}
 
-    // Get most used snippets
-    let most_used = {
-        let service = state.service.lock().await;
-        service
-            .get_most_used_snippets(10)
-            .await
-            .map_err(|e| e.to_string())?
-    };
+/// Save a hand-arranged order: `ids` come first, in this order, when listing
+/// with the `position` sort. Returns how many of the ids were snippets.
+#[tauri::command]
+async fn reorder_snippets(ids: Vec<String>, state: State<'_, AppState>) -> Result<u64, String> {
+    let ordered_ids = ids
+        .iter()
+        .map(|id| uuid::Uuid::parse_str(id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
 
-    // Create statistics object
-    let stats = serde_json::json!({
-        "total_snippets": total_snippets,
-        "active_snippets": total_snippets,
-        "inactive_snippets": 0,
-        "total_usage": total_usage,
-        "most_used": most_used,
-    });
+    let service = &state.service;
+    service
+        .reorder_snippets(ordered_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    Ok(stats)
+#[tauri::command]
+async fn get_statistics(state: State<'_, AppState>) -> Result<StatisticsDto, String> {
+    let service = &state.service;
+    service.statistics(10).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -192,20 +353,21 @@ async fn export_snippets(state: State<'_, AppState>) -> Result<String, String> {
         tags_filter: None,
     };
 
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .export_to_json(request)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// The counts, plus each record's outcome in `items` for the review table
 #[tauri::command]
 async fn import_snippets(
     json_data: String,
     overwrite: bool,
     state: State<'_, AppState>,
 ) -> Result<ImportResult, String> {
-    let service = state.service.lock().await;
+    let service = &state.service;
     service
         .import_from_json(&json_data, overwrite)
         .await
@@ -549,12 +711,20 @@ async fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             get_snippets,
+            get_snippet_summaries,
             create_snippet,
             update_snippet,
+            duplicate_snippet,
             delete_snippet,
+            get_snippet_preview,
             expand_snippet,
+            get_clipboard,
+            set_clipboard,
             search_snippets,
+            search_highlighted,
             get_statistics,
+            get_random_snippet,
+            reorder_snippets,
             export_snippets,
             import_snippets,
             check_cli_status,